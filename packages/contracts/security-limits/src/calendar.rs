@@ -0,0 +1,120 @@
+//! Security Limits — calendar boundaries
+//!
+//! Limits in [`WindowMode::Calendar`](crate::WindowMode::Calendar) reset on
+//! UTC calendar boundaries rather than a fixed length after `last_reset`:
+//! hourly limits on the hour, daily limits at midnight, weekly limits at
+//! Monday midnight and monthly limits on the 1st.  `Custom` windows reset at
+//! multiples of their length since the Unix epoch.
+//!
+//! Ledger timestamps are Unix seconds, so months are derived from the day
+//! count with the proleptic Gregorian calendar (leap years included).
+//!
+//! [`WindowMode::Scheduled`](crate::WindowMode::Scheduled) limits shift the
+//! clock by their schedule's UTC offset and anchor, then find the period the
+//! same way.
+
+use crate::types::{LimitType, ResetSchedule};
+
+const HOUR: u64 = 3600;
+const DAY: u64 = 86_400;
+const WEEK: u64 = 7 * DAY;
+
+/// Start of the calendar period containing `now`, or `None` for
+/// `PerTransaction` limits (and zero-length `Custom` ones).
+pub fn period_start(limit_type: &LimitType, now: u64) -> Option<u64> {
+    let day = now / DAY;
+    match limit_type {
+        LimitType::PerHour => Some(now - now % HOUR),
+        LimitType::Daily => Some(day * DAY),
+        // 1970-01-01 was a Thursday, three days after a Monday
+        LimitType::Weekly => Some(day.saturating_sub((day + 3) % 7) * DAY),
+        LimitType::Monthly => {
+            let (year, month) = civil_from_days(day);
+            Some(days_from_civil(year, month) * DAY)
+        }
+        LimitType::Custom(window) if *window > 0 => Some(now - now % window),
+        _ => None,
+    }
+}
+
+/// First calendar boundary after `now`, i.e. when the period containing
+/// `now` ends.
+pub fn next_boundary(limit_type: &LimitType, now: u64) -> Option<u64> {
+    let start = period_start(limit_type, now)?;
+    match limit_type {
+        LimitType::PerHour => Some(start + HOUR),
+        LimitType::Daily => Some(start + DAY),
+        LimitType::Weekly => Some(start + WEEK),
+        LimitType::Monthly => {
+            let (year, month) = civil_from_days(start / DAY);
+            let next = if month == 12 {
+                days_from_civil(year + 1, 1)
+            } else {
+                days_from_civil(year, month + 1)
+            };
+            Some(next * DAY)
+        }
+        LimitType::Custom(window) => Some(start + window),
+        LimitType::PerTransaction => None,
+    }
+}
+
+/// Start of the `schedule` period containing `now`, or `None` when the
+/// schedule has no periods for `limit_type`.
+pub fn scheduled_start(schedule: &ResetSchedule, limit_type: &LimitType, now: u64) -> Option<u64> {
+    let local = to_schedule_clock(schedule, now);
+    let start = if schedule.interval > 0 {
+        local - local % schedule.interval
+    } else {
+        period_start(limit_type, local)?
+    };
+    from_schedule_clock(schedule, start)
+}
+
+/// First `schedule` boundary after `now`.
+pub fn scheduled_next(schedule: &ResetSchedule, limit_type: &LimitType, now: u64) -> Option<u64> {
+    let local = to_schedule_clock(schedule, now);
+    let next = if schedule.interval > 0 {
+        local - local % schedule.interval + schedule.interval
+    } else {
+        next_boundary(limit_type, local)?
+    };
+    from_schedule_clock(schedule, next)
+}
+
+/// Shift a UTC timestamp so the schedule's periods start on plain calendar
+/// (or interval) boundaries.
+fn to_schedule_clock(schedule: &ResetSchedule, ts: u64) -> u64 {
+    let shifted = i128::from(ts) + i128::from(schedule.utc_offset) - i128::from(schedule.anchor);
+    u64::try_from(shifted.max(0)).unwrap_or(u64::MAX)
+}
+
+fn from_schedule_clock(schedule: &ResetSchedule, ts: u64) -> Option<u64> {
+    let utc = i128::from(ts) - i128::from(schedule.utc_offset) + i128::from(schedule.anchor);
+    u64::try_from(utc).ok()
+}
+
+/// `(year, month)` of the day `days` after 1970-01-01.
+fn civil_from_days(days: u64) -> (i64, i64) {
+    // Shift to an era starting on 0000-03-01 so leap days fall at the end
+    // of each year
+    let z = days as i64 + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month)
+}
+
+/// Days from 1970-01-01 to the 1st of `month` in `year`.
+fn days_from_civil(year: i64, month: i64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let yoe = year - era * 400;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    (era * 146_097 + doe - 719_468) as u64
+}
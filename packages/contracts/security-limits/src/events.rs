@@ -0,0 +1,448 @@
+//! Security Limits — event emission
+//!
+//! Every state change publishes an event so compliance dashboards and
+//! alerting systems can subscribe instead of polling storage.  Topics are
+//! `(name, owner)` so subscribers can filter on a single account.
+
+use soroban_sdk::{symbol_short, Address, Env, String, Symbol, Vec};
+
+use crate::types::{
+    AuditEntry, ManagerGrant, Proposal, ReportCommitment, RiskLevel, SecurityLimit, SoftLimit,
+    TimelockedOp,
+};
+
+// ---------------------------------------------------------------------------
+// Event topic symbols  (≤ 9 ASCII chars for symbol_short!)
+// ---------------------------------------------------------------------------
+
+const EVT_LIMIT_NEW: Symbol = symbol_short!("lim_new");
+const EVT_LIMIT_UPD: Symbol = symbol_short!("lim_upd");
+const EVT_LIMIT_DEL: Symbol = symbol_short!("lim_del");
+const EVT_LIMIT_MODE: Symbol = symbol_short!("lim_mode");
+const EVT_LIMIT_SCOPE: Symbol = symbol_short!("lim_scope");
+const EVT_LIMIT_PAIR: Symbol = symbol_short!("lim_pair");
+const EVT_LIMIT_DENOM: Symbol = symbol_short!("lim_denom");
+const EVT_SOFT: Symbol = symbol_short!("lim_soft");
+const EVT_LIMIT_BURST: Symbol = symbol_short!("lim_burst");
+const EVT_PROFILE: Symbol = symbol_short!("profile");
+const EVT_TX: Symbol = symbol_short!("tx_rec");
+const EVT_INFLOW: Symbol = symbol_short!("inflow");
+const EVT_BREACH: Symbol = symbol_short!("breach");
+const EVT_BLOCKED: Symbol = symbol_short!("blocked");
+const EVT_THRESHOLD: Symbol = symbol_short!("threshold");
+const EVT_ALERTS: Symbol = symbol_short!("alerts");
+const EVT_AUDIT: Symbol = symbol_short!("audit");
+const EVT_EXC_USED: Symbol = symbol_short!("exc_used");
+const EVT_FROZEN: Symbol = symbol_short!("frozen");
+const EVT_UNFROZEN: Symbol = symbol_short!("unfrozen");
+const EVT_COSIGNER: Symbol = symbol_short!("cosigner");
+const EVT_PROPOSED: Symbol = symbol_short!("proposed");
+const EVT_APPROVED: Symbol = symbol_short!("approved");
+const EVT_CANCELLED: Symbol = symbol_short!("cancelled");
+const EVT_ALLOW_ADD: Symbol = symbol_short!("allow_add");
+const EVT_ALLOW_REM: Symbol = symbol_short!("allow_rem");
+const EVT_BLOCK_ADD: Symbol = symbol_short!("block_add");
+const EVT_BLOCK_REM: Symbol = symbol_short!("block_rem");
+const EVT_WL_ADD: Symbol = symbol_short!("wl_add");
+const EVT_WL_REM: Symbol = symbol_short!("wl_rem");
+const EVT_WL_MODE: Symbol = symbol_short!("wl_mode");
+const EVT_COOLDOWN: Symbol = symbol_short!("cooldown");
+const EVT_VELOCITY: Symbol = symbol_short!("velocity");
+const EVT_ANOMALY: Symbol = symbol_short!("anomaly");
+const EVT_GRP_ADD: Symbol = symbol_short!("grp_add");
+const EVT_GRP_REM: Symbol = symbol_short!("grp_rem");
+const EVT_REP_ADD: Symbol = symbol_short!("rep_add");
+const EVT_REP_REM: Symbol = symbol_short!("rep_rem");
+const EVT_SOFT_HIT: Symbol = symbol_short!("soft_hit");
+const EVT_BURST: Symbol = symbol_short!("burst");
+const EVT_REPORT: Symbol = symbol_short!("report");
+const EVT_MGR_SET: Symbol = symbol_short!("mgr_set");
+const EVT_MGR_REV: Symbol = symbol_short!("mgr_rev");
+const EVT_QUEUED: Symbol = symbol_short!("tl_queued");
+const EVT_TL_CANCEL: Symbol = symbol_short!("tl_cancel");
+const EVT_TPL_APPLY: Symbol = symbol_short!("tpl_apply");
+const EVT_TPL_UNSUB: Symbol = symbol_short!("tpl_unsub");
+
+/// `("lim_new", owner)` → `(limit_id, asset, max_amount)`
+pub fn limit_created(env: &Env, limit: &SecurityLimit) {
+    env.events().publish(
+        (EVT_LIMIT_NEW, limit.owner.clone()),
+        (limit.id, limit.asset.clone(), limit.max_amount),
+    );
+}
+
+/// `("lim_upd", owner)` → `(limit_id, max_amount, time_window, is_active)`
+pub fn limit_updated(env: &Env, limit: &SecurityLimit) {
+    env.events().publish(
+        (EVT_LIMIT_UPD, limit.owner.clone()),
+        (
+            limit.id,
+            limit.max_amount,
+            limit.time_window,
+            limit.is_active,
+        ),
+    );
+}
+
+/// `("lim_mode", owner)` → `(limit_id, window_mode)`
+pub fn window_mode_set(env: &Env, limit: &SecurityLimit) {
+    env.events().publish(
+        (EVT_LIMIT_MODE, limit.owner.clone()),
+        (limit.id, limit.window_mode),
+    );
+}
+
+/// `("lim_scope", owner)` → `(limit_id, scope)`
+pub fn scope_set(env: &Env, limit: &SecurityLimit) {
+    env.events().publish(
+        (EVT_LIMIT_SCOPE, limit.owner.clone()),
+        (limit.id, limit.scope.clone()),
+    );
+}
+
+/// `("lim_pair", owner)` → `(limit_id, pair_asset)`
+pub fn pair_set(env: &Env, limit: &SecurityLimit) {
+    env.events().publish(
+        (EVT_LIMIT_PAIR, limit.owner.clone()),
+        (limit.id, limit.pair_asset.clone()),
+    );
+}
+
+/// `("lim_denom", owner)` → `(limit_id, denomination)`
+pub fn denomination_set(env: &Env, limit: &SecurityLimit) {
+    env.events().publish(
+        (EVT_LIMIT_DENOM, limit.owner.clone()),
+        (limit.id, limit.denomination.clone()),
+    );
+}
+
+/// `("lim_del", owner)` → `limit_id`
+pub fn limit_deleted(env: &Env, owner: &Address, limit_id: u64) {
+    env.events()
+        .publish((EVT_LIMIT_DEL, owner.clone()), limit_id);
+}
+
+/// `("profile", owner)` → `risk_level`
+pub fn risk_profile_set(env: &Env, owner: &Address, risk_level: &RiskLevel) {
+    env.events()
+        .publish((EVT_PROFILE, owner.clone()), risk_level.clone());
+}
+
+/// `("tx_rec", owner)` → `(tx_id, asset, amount)`
+pub fn transaction_recorded(env: &Env, owner: &Address, tx_id: u64, asset: &Symbol, amount: i128) {
+    env.events()
+        .publish((EVT_TX, owner.clone()), (tx_id, asset.clone(), amount));
+}
+
+/// `("inflow", owner)` → `(asset, amount)`
+pub fn inflow_recorded(env: &Env, owner: &Address, asset: &Symbol, amount: i128) {
+    env.events()
+        .publish((EVT_INFLOW, owner.clone()), (asset.clone(), amount));
+}
+
+/// `("breach", owner)` → `(limit_id, asset, usage, max_amount)`
+///
+/// Published when recorded usage pushes a limit past its `max_amount`.  For
+/// per-destination limits `usage` is the offending destination's counter.
+pub fn limit_breached(env: &Env, limit: &SecurityLimit, usage: i128) {
+    env.events().publish(
+        (EVT_BREACH, limit.owner.clone()),
+        (limit.id, limit.asset.clone(), usage, limit.max_amount),
+    );
+}
+
+/// `("blocked", owner)` → `(limit_id, asset, amount, usage, max_amount)`
+///
+/// Published when a check is denied because of a limit.  `asset` and
+/// `amount` are the attempted transaction's; `usage` is what the limit's
+/// usage would have become, in the limit's own units.  Events of a failing
+/// call are rolled back with it, so only the `check_*` entrypoints leave this
+/// event on-ledger.
+pub fn limit_blocked(env: &Env, limit: &SecurityLimit, asset: &Symbol, amount: i128, usage: i128) {
+    env.events().publish(
+        (EVT_BLOCKED, limit.owner.clone()),
+        (limit.id, asset.clone(), amount, usage, limit.max_amount),
+    );
+}
+
+/// `("threshold", owner)` → `(limit_id, asset, threshold_bps, usage, max_amount)`
+///
+/// Published when recorded usage crosses one of the owner's alert
+/// thresholds.
+pub fn threshold_crossed(env: &Env, limit: &SecurityLimit, threshold_bps: u32, usage: i128) {
+    env.events().publish(
+        (EVT_THRESHOLD, limit.owner.clone()),
+        (
+            limit.id,
+            limit.asset.clone(),
+            threshold_bps,
+            usage,
+            limit.max_amount,
+        ),
+    );
+}
+
+/// `("alerts", owner)` → `thresholds`
+pub fn alert_thresholds_set(env: &Env, owner: &Address, thresholds: &Vec<u32>) {
+    env.events()
+        .publish((EVT_ALERTS, owner.clone()), thresholds.clone());
+}
+
+/// `("frozen", owner)` → `(frozen_by, reason)`
+pub fn account_frozen(env: &Env, owner: &Address, frozen_by: &Address, reason: &String) {
+    env.events().publish(
+        (EVT_FROZEN, owner.clone()),
+        (frozen_by.clone(), reason.clone()),
+    );
+}
+
+/// `("unfrozen", owner)` → `unfrozen_by`
+pub fn account_unfrozen(env: &Env, owner: &Address, unfrozen_by: &Address) {
+    env.events()
+        .publish((EVT_UNFROZEN, owner.clone()), unfrozen_by.clone());
+}
+
+/// `("cosigner", owner)` → `Option<co_signer>`
+pub fn co_signer_set(env: &Env, owner: &Address, co_signer: &Option<Address>) {
+    env.events()
+        .publish((EVT_COSIGNER, owner.clone()), co_signer.clone());
+}
+
+/// `("proposed", owner)` → `(proposal_id, expires_at)`
+pub fn change_proposed(env: &Env, proposal: &Proposal) {
+    env.events().publish(
+        (EVT_PROPOSED, proposal.owner.clone()),
+        (proposal.id, proposal.expires_at),
+    );
+}
+
+/// `("approved", owner)` → `proposal_id`
+pub fn change_approved(env: &Env, owner: &Address, proposal_id: u64) {
+    env.events()
+        .publish((EVT_APPROVED, owner.clone()), proposal_id);
+}
+
+/// `("cancelled", owner)` → `proposal_id`
+pub fn change_cancelled(env: &Env, owner: &Address, proposal_id: u64) {
+    env.events()
+        .publish((EVT_CANCELLED, owner.clone()), proposal_id);
+}
+
+/// `("allow_add", owner)` → `asset`
+pub fn allowed_asset_added(env: &Env, owner: &Address, asset: &Symbol) {
+    env.events()
+        .publish((EVT_ALLOW_ADD, owner.clone()), asset.clone());
+}
+
+/// `("allow_rem", owner)` → `asset`
+pub fn allowed_asset_removed(env: &Env, owner: &Address, asset: &Symbol) {
+    env.events()
+        .publish((EVT_ALLOW_REM, owner.clone()), asset.clone());
+}
+
+/// `("block_add", owner)` → `asset`
+pub fn blacklisted_asset_added(env: &Env, owner: &Address, asset: &Symbol) {
+    env.events()
+        .publish((EVT_BLOCK_ADD, owner.clone()), asset.clone());
+}
+
+/// `("block_rem", owner)` → `asset`
+pub fn blacklisted_asset_removed(env: &Env, owner: &Address, asset: &Symbol) {
+    env.events()
+        .publish((EVT_BLOCK_REM, owner.clone()), asset.clone());
+}
+
+/// `("wl_add", owner)` → `destination`
+pub fn destination_whitelisted(env: &Env, owner: &Address, destination: &Address) {
+    env.events()
+        .publish((EVT_WL_ADD, owner.clone()), destination.clone());
+}
+
+/// `("wl_rem", owner)` → `destination`
+pub fn destination_unlisted(env: &Env, owner: &Address, destination: &Address) {
+    env.events()
+        .publish((EVT_WL_REM, owner.clone()), destination.clone());
+}
+
+/// `("wl_mode", owner)` → `enabled`
+pub fn whitelist_mode_set(env: &Env, owner: &Address, enabled: bool) {
+    env.events().publish((EVT_WL_MODE, owner.clone()), enabled);
+}
+
+/// `("cooldown", owner)` → `(asset, interval)`
+///
+/// An `interval` of `0` means the cooldown was removed.
+pub fn cooldown_set(env: &Env, owner: &Address, asset: &Symbol, interval: u64) {
+    env.events()
+        .publish((EVT_COOLDOWN, owner.clone()), (asset.clone(), interval));
+}
+
+/// `("velocity", owner)` → `(asset, multiplier, require_approval)`
+///
+/// A `multiplier` of `0` means the rule was removed.
+pub fn velocity_rule_set(
+    env: &Env,
+    owner: &Address,
+    asset: &Symbol,
+    multiplier: u32,
+    require_approval: bool,
+) {
+    env.events().publish(
+        (EVT_VELOCITY, owner.clone()),
+        (asset.clone(), multiplier, require_approval),
+    );
+}
+
+/// `("anomaly", owner)` → `(asset, amount, today_volume, baseline)`
+///
+/// `today_volume` excludes `amount`; `baseline` is the owner's average daily
+/// volume over the six days before.
+pub fn velocity_anomaly(
+    env: &Env,
+    owner: &Address,
+    asset: &Symbol,
+    amount: i128,
+    today_volume: i128,
+    baseline: i128,
+) {
+    env.events().publish(
+        (EVT_ANOMALY, owner.clone()),
+        (asset.clone(), amount, today_volume, baseline),
+    );
+}
+
+/// `("grp_add", group)` → `member`
+pub fn group_member_added(env: &Env, group: &Address, member: &Address) {
+    env.events()
+        .publish((EVT_GRP_ADD, group.clone()), member.clone());
+}
+
+/// `("rep_add", owner)` → `reporter`
+pub fn reporter_added(env: &Env, owner: &Address, reporter: &Address) {
+    env.events()
+        .publish((EVT_REP_ADD, owner.clone()), reporter.clone());
+}
+
+/// `("rep_rem", owner)` → `reporter`
+pub fn reporter_removed(env: &Env, owner: &Address, reporter: &Address) {
+    env.events()
+        .publish((EVT_REP_REM, owner.clone()), reporter.clone());
+}
+
+/// `("grp_rem", group)` → `member`
+pub fn group_member_removed(env: &Env, group: &Address, member: &Address) {
+    env.events()
+        .publish((EVT_GRP_REM, group.clone()), member.clone());
+}
+
+/// `("lim_soft", owner)` → `(limit_id, soft_limit)`
+pub fn soft_limit_set(env: &Env, owner: &Address, limit_id: u64, soft: &Option<SoftLimit>) {
+    env.events()
+        .publish((EVT_SOFT, owner.clone()), (limit_id, soft.clone()));
+}
+
+/// `("soft_hit", owner)` → `(limit_id, asset, usage, soft_amount)`
+///
+/// Published when recorded usage goes above a limit's soft limit.
+pub fn soft_limit_crossed(env: &Env, limit: &SecurityLimit, usage: i128, soft_amount: i128) {
+    env.events().publish(
+        (EVT_SOFT_HIT, limit.owner.clone()),
+        (limit.id, limit.asset.clone(), usage, soft_amount),
+    );
+}
+
+/// `("lim_burst", owner)` → `(limit_id, extra_bps, refill_windows)`
+///
+/// An `extra_bps` of `0` means the burst allowance was removed.
+pub fn burst_allowance_set(
+    env: &Env,
+    owner: &Address,
+    limit_id: u64,
+    extra_bps: u32,
+    refill_windows: u32,
+) {
+    env.events().publish(
+        (EVT_LIMIT_BURST, owner.clone()),
+        (limit_id, extra_bps, refill_windows),
+    );
+}
+
+/// `("burst", owner)` → `(limit_id, asset, drawn, remaining)`
+///
+/// Published when recorded usage above `max_amount` draws on the limit's
+/// burst budget.
+pub fn burst_drawn(env: &Env, limit: &SecurityLimit, drawn: i128, remaining: i128) {
+    env.events().publish(
+        (EVT_BURST, limit.owner.clone()),
+        (limit.id, limit.asset.clone(), drawn, remaining),
+    );
+}
+
+/// `("report", owner)` → `(from_ts, to_ts, digest)`
+pub fn report_committed(env: &Env, owner: &Address, report: &ReportCommitment) {
+    env.events().publish(
+        (EVT_REPORT, owner.clone()),
+        (report.from_ts, report.to_ts, report.digest.clone()),
+    );
+}
+
+/// `("mgr_set", owner)` → `(manager, caps)`
+pub fn manager_set(env: &Env, owner: &Address, grant: &ManagerGrant) {
+    env.events().publish(
+        (EVT_MGR_SET, owner.clone()),
+        (grant.manager.clone(), grant.caps.clone()),
+    );
+}
+
+/// `("mgr_rev", owner)` → `manager`
+pub fn manager_revoked(env: &Env, owner: &Address, manager: &Address) {
+    env.events()
+        .publish((EVT_MGR_REV, owner.clone()), manager.clone());
+}
+
+/// `("tl_queued", owner)` → `(change_id, change, executable_at)`
+pub fn change_queued(env: &Env, op: &TimelockedOp) {
+    env.events().publish(
+        (EVT_QUEUED, op.owner.clone()),
+        (op.id, op.change.clone(), op.executable_at),
+    );
+}
+
+/// `("tl_cancel", owner)` → `change_id`
+pub fn timelock_cancelled(env: &Env, owner: &Address, change_id: u64) {
+    env.events()
+        .publish((EVT_TL_CANCEL, owner.clone()), change_id);
+}
+
+/// `("tpl_apply", owner)` → `(template_id, version)`
+pub fn template_applied(env: &Env, owner: &Address, template_id: &Symbol, version: u32) {
+    env.events().publish(
+        (EVT_TPL_APPLY, owner.clone()),
+        (template_id.clone(), version),
+    );
+}
+
+/// `("tpl_unsub", owner)` → `template_id`
+pub fn template_unsubscribed(env: &Env, owner: &Address, template_id: &Symbol) {
+    env.events()
+        .publish((EVT_TPL_UNSUB, owner.clone()), template_id.clone());
+}
+
+/// `("exc_used", owner)` → `(asset, amount)`
+///
+/// Published when an approved exception lets a transaction through.
+pub fn exception_used(env: &Env, owner: &Address, asset: &Symbol, amount: i128) {
+    env.events()
+        .publish((EVT_EXC_USED, owner.clone()), (asset.clone(), amount));
+}
+
+/// `("audit", admin)` → `(entry_id, action)`
+///
+/// Mirrors every audit log append, so the topic is the acting admin rather
+/// than an owner.
+pub fn audit_recorded(env: &Env, entry: &AuditEntry) {
+    env.events().publish(
+        (EVT_AUDIT, entry.admin.clone()),
+        (entry.id, entry.action.clone()),
+    );
+}
@@ -1,81 +1,263 @@
 //! Security Limits Contract for Galaxy DevKit
-//! 
+//!
 //! This contract implements security limits and risk management
 //! for automated trading operations in the Stellar ecosystem.
+//!
+//! ## Errors
+//! Fallible entrypoints return [`LimitsError`] so integrating contracts can
+//! branch on the failure reason (`try_*` client methods) instead of parsing
+//! host panics.
+//!
+//! ## Amounts
+//! Amounts are `i128`, like Soroban token amounts.  Negative amounts are
+//! rejected and usage arithmetic is checked, failing with
+//! [`LimitsError::Overflow`] instead of wrapping.
+//!
+//! ## Upgrades
+//! The compliance admin can `upgrade` the contract to new WASM in place and
+//! then `migrate` its storage to the new [`SCHEMA_VERSION`]; limits,
+//! profiles and records carry over.
+//!
+//! ## Storage layout
+//! Limits are persistent entries keyed by `(owner, id)` with a per-owner
+//! index (see `storage.rs`).  Transaction records are persistent entries
+//! keyed by `(owner, position)`, so history queries read only one owner's
+//! records.  Risk profiles remain in instance storage.
+//!
+//! ## Window modes
+//! Each limit is either `Fixed` (usage resets once its window elapses) or
+//! `Rolling` (usage is summed over a ring buffer of recent time buckets, see
+//! `window.rs`).  New limits start in `Fixed` mode.  A fixed window lasts the
+//! limit type's length, or `time_window` when that is shorter, and its reset
+//! is stored by whichever check or record first crosses the boundary.
+//! `Calendar` mode resets at UTC calendar boundaries instead (midnight for
+//! `Daily`, Monday for `Weekly`, the 1st for `Monthly`, see `calendar.rs`),
+//! as most compliance policies specify.  `Scheduled` mode resets on the
+//! limit's own [`ResetSchedule`] — an interval and anchor in a local time
+//! zone — so regional businesses can follow their local business day.
+//! `PerTransaction` limits cap each transaction on its own.
+//!
+//! ## Soft limits
+//! A limit can carry a soft limit below its `max_amount`.  Usage going above
+//! it publishes a `soft_hit` warning while the hard limit still blocks.  With
+//! `require_confirmation` set, crossing it also fails with
+//! [`LimitsError::SoftLimitUnconfirmed`] unless the transaction goes through
+//! [`SecurityLimitsContract::authorize_confirmed_or_fail`].
+//!
+//! ## Burst allowances
+//! A limit can carry a burst budget, a share of `max_amount` that usage may
+//! go above it by, refilled only every `refill_windows` windows.
+//!
+//! ## Destinations
+//! Transaction entrypoints take an optional `destination`.  A limit's
+//! [`LimitScope`] decides whether it counts every transfer (`Aggregate`, the
+//! default), each counterparty separately (`PerDestination`) or only
+//! transfers to one address (`Destination`).
+//!
+//! ## Net flow
+//! A limit scoped to `LimitScope::NetFlow` caps net outflow rather than
+//! gross spend: inflows a registered reporter confirms through
+//! [`SecurityLimitsContract::record_inflow`] credit its window's usage back,
+//! so rebalancing in and out does not eat the same cap as pure withdrawals.
+//! Credits stop at zero usage, and other limits ignore inflows.
+//!
+//! ## Spending categories
+//! The `*_categorized_*` entrypoints tag a transaction with a category symbol
+//! (e.g. `trading`, `transfer`, `defi`).  A limit scoped to
+//! `LimitScope::Category` counts only transactions with its tag, while other
+//! limits count tagged and untagged transactions alike, so a policy like
+//! "internal transfers unlimited, at most 1,000 a day into DeFi" is a single
+//! category limit.
+//!
+//! ## Calling contracts
+//! Protocols that authorize on an owner's behalf can go through
+//! [`SecurityLimitsContract::authorize_from_caller_or_fail`], which takes the
+//! calling contract's auth as proof of the invoker.  Limits scoped to
+//! `LimitScope::Caller` count only that contract's transactions, e.g. "the
+//! swap router may spend at most 500 a day, the lending adapter 2,000".
+//!
+//! ## Asset pairs
+//! Swaps go through the `*_swap_*` entrypoints, which also name the asset
+//! received.  A limit with a `pair_asset` only counts swaps from its asset
+//! into that asset (e.g. "max 5,000 XLM→USDC per day"); limits without one
+//! count swaps like any other spend of the source asset.
+//!
+//! ## Reference-currency limits
+//! A limit with a [`Denomination::Reference`] is expressed in its `asset`
+//! (e.g. `USD`) and counts every asset, converted with the configured price
+//! oracle; stale or missing prices fail with [`LimitsError::PriceStale`] or
+//! [`LimitsError::PriceUnavailable`] instead of letting the transfer through.
+//! `create_portfolio_limit` sets up such a portfolio-wide cap in one call
+//! and `get_portfolio_breakdown` shows which assets its usage came from.
+//!
+//! ## Compliance reports
+//! An owner can commit to the usage report of a closed period: the contract
+//! sums the period's transactions per asset and stores the SHA-256 digest of
+//! those totals, so an exported report can later be checked against the
+//! on-chain commitment.
+//!
+//! ## Alerts
+//! Besides the `breach` event for usage recorded past a limit, a denied check
+//! publishes a `blocked` event naming the limit responsible, and owners can
+//! set usage thresholds (in basis points of each limit, e.g. `8000` for 80%)
+//! that publish a `threshold` event as recorded usage crosses them, so
+//! monitoring can warn before transfers start failing.
+//!
+//! ## Limit groups
+//! A group address can enrol other addresses as members, e.g. the hot
+//! wallets of one team treasury.  Each member's transactions count against
+//! the group's limits as well as their own, so all members draw from one
+//! budget.  Joining needs both parties' auth; members cannot leave on their
+//! own, and removing one needs the group's co-signer too when it has one.
+//!
+//! ## Reporters
+//! Recorded usage counts against an owner's limits, so only the owner, or a
+//! reporter they register (e.g. the wallet or exchange contract executing
+//! their transfers), can record it; anyone else could otherwise exhaust the
+//! owner's limits.  Reporters go through
+//! [`SecurityLimitsContract::report_transaction`] with their own auth, and
+//! are the only ones who can credit inflows back to `NetFlow` limits.
+//!
+//! ## Limit managers
+//! An owner can let a manager address (e.g. a treasury team or custodian)
+//! create and update their limits.  Each grant caps the `max_amount` the
+//! manager may set per asset; managers cannot deactivate or delete limits.
+//! While the owner has a co-signer, granting a manager needs its auth too.
+//!
+//! ## Destination whitelist
+//! With whitelist mode on, transfers authorised through the contract must
+//! name a destination on the owner's whitelist.  Additions, and turning the
+//! mode off, are queued for [`TIMELOCK_DELAY`] seconds before they can be
+//! executed, so a stolen key cannot immediately whitelist an attacker; the
+//! owner or their co-signer can cancel a queued change meanwhile.  Swaps
+//! return funds to the owner and are not subject to the whitelist.
+//!
+//! ## Cooldowns
+//! An owner can require a minimum interval between transactions in an asset
+//! to throttle runaway bots and drain attempts.  Lengthening a cooldown
+//! applies at once; shortening or removing one goes through the timelock.
+//!
+//! ## Velocity anomalies
+//! A velocity rule flags transactions that would take an owner's daily
+//! volume in an asset above a multiple of their trailing daily average — the
+//! burst a drained key produces while each transfer still fits its limits.
+//! Flagged transactions publish an `anomaly` event; with `require_approval`
+//! set they also fail with [`LimitsError::VelocityAnomaly`] unless the
+//! compliance admin has approved an exception.  Like cooldowns, rules tighten
+//! at once and loosen through the timelock.
+//!
+//! ## Risk levels
+//! A risk profile's `max_single_transaction` and `max_daily_volume` cap each
+//! asset separately (daily volume per UTC day, `0` meaning uncapped) and are
+//! enforced by the checks and authorization hooks alongside limits.  The
+//! compliance admin can attach default limits to each [`RiskLevel`]; setting
+//! a profile installs them, creating missing ones and lowering matching
+//! limits set above them, so changing level never loosens protection.
+//!
+//! ## Default limits
+//! Owners need not define a limit for every asset.  When an owner has no
+//! limit of their own on an asset, a transaction in it is held to their risk
+//! level's defaults for that asset, or failing those to the deployment-wide
+//! defaults the compliance admin sets.  The resolved defaults are installed
+//! as the owner's limits with the first recorded transaction, so usage is
+//! tracked from then on and the owner can adjust them like any other limit.
+//!
+//! ## Templates
+//! The compliance admin can publish named risk templates bundling a risk
+//! profile with default limits.  `apply_template` instantiates one for an
+//! owner and subscribes them, so a later `set_template(.., propagate)` moves
+//! subscribed owners to the new version.  Applying a template counts as a
+//! loosening change when it would raise or remove template-created limits.
+//!
+//! ## Compliance admin
+//! An optional compliance admin, set at initialisation, can freeze accounts,
+//! force-lower limits and approve one-off exceptions for institutional
+//! oversight.  Each of these actions is appended to an audit log that can be
+//! read page by page and is never rewritten.
+//!
+//! ## Global limits
+//! Besides per-owner limits, the compliance admin can cap the volume of an
+//! asset the whole deployment authorizes per rolling window, as a circuit
+//! breaker for integrating contracts during incidents.  Transactions over a
+//! global limit fail with [`LimitsError::GlobalLimitExceeded`].
+//!
+//! ## Freezes
+//! An owner can freeze their own account (e.g. after a key compromise) and
+//! lift that freeze; the compliance admin can freeze or unfreeze any account,
+//! and an admin freeze can only be lifted by the admin.
+//!
+//! ## Two-party approval
+//! An owner may name a co-signer.  While one is set, changes that loosen
+//! protection — raising, shortening, deactivating or deleting a limit,
+//! changing how it counts usage, enlarging its burst allowance, or raising a
+//! risk profile cap or widening its asset lists — fail with
+//! [`LimitsError::CoSignRequired`] and must instead be proposed by the owner
+//! and approved by the co-signer.
 
 #![no_std]
 
+mod calendar;
+mod events;
+mod stats;
+mod storage;
+mod types;
+mod valuation;
+mod window;
+pub use types::{
+    AssetStats, AuditAction, AuditEntry, BurstAllowance, Cooldown, Denomination, FreezeInfo,
+    GlobalLimit, LimitBudget, LimitCheck, LimitException, LimitScope, LimitType, LimitsError,
+    ManagerGrant, OraclePrice, PeriodTotals, Proposal, ProposedChange, ReportCommitment,
+    ResetSchedule, RiskLevel, RiskProfile, RiskProfileParams, RiskTemplate, SecurityLimit,
+    SoftLimit, TemplateLimit, TemplateSubscription, TimelockedChange, TimelockedOp,
+    TransactionRecord, UsageBucket, UsageStats, Valuation, VelocityRule, WindowMode,
+};
+pub use valuation::MAX_ORACLE_DECIMALS;
+pub use window::ROLLING_BUCKETS;
+
 use soroban_sdk::{
-    contract, contractimpl, contracttype, symbol_short, vec, Address, BytesN, Env, Map, Symbol,
-    Vec, String as SorobanString,
+    contract, contractimpl, symbol_short, xdr::ToXdr, Address, BytesN, Env, Map, String, Symbol,
+    Vec,
 };
 
-/// Contract type definitions
-#[contracttype]
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub struct SecurityLimit {
-    pub id: u64,
-    pub owner: Address,
-    pub limit_type: LimitType,
-    pub asset: Symbol,
-    pub max_amount: u64,
-    pub time_window: u64,
-    pub current_usage: u64,
-    pub last_reset: u64,
-    pub is_active: bool,
-    pub created_at: u64,
-}
+/// Contract storage keys
+const RISK_PROFILES: Symbol = symbol_short!("PROFILES");
+const NEXT_LIMIT_ID: Symbol = symbol_short!("NEXT_LIM");
+const NEXT_TX_ID: Symbol = symbol_short!("NEXT_TX");
+const COMPLIANCE_ADMIN: Symbol = symbol_short!("CADMIN");
+const NEXT_PROPOSAL_ID: Symbol = symbol_short!("NEXT_PROP");
+const NEXT_TIMELOCK_ID: Symbol = symbol_short!("NEXT_TL");
+const SCHEMA: Symbol = symbol_short!("SCHEMA");
 
-#[contracttype]
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub enum LimitType {
-    Daily,
-    Weekly,
-    Monthly,
-    PerTransaction,
-    PerHour,
-    Custom(u64),
-}
+/// Largest page returned by paginated views.
+pub const MAX_PAGE_SIZE: u32 = 100;
 
-#[contracttype]
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub struct TransactionRecord {
-    pub id: u64,
-    pub owner: Address,
-    pub asset: Symbol,
-    pub amount: u64,
-    pub timestamp: u64,
-    pub transaction_hash: BytesN<32>,
-}
+/// Seconds a timelocked change waits before it can be executed.
+pub const TIMELOCK_DELAY: u64 = 86_400;
 
-#[contracttype]
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub struct RiskProfile {
-    pub owner: Address,
-    pub risk_level: RiskLevel,
-    pub max_daily_volume: u64,
-    pub max_single_transaction: u64,
-    pub allowed_assets: Vec<Symbol>,
-    pub blacklisted_assets: Vec<Symbol>,
-    pub created_at: u64,
-    pub updated_at: u64,
-}
+/// Largest UTC offset a reset schedule accepts, in seconds.
+const MAX_UTC_OFFSET: u32 = 14 * 3600;
+
+/// Storage schema written by this build.  Deployments from before the
+/// version was stored are treated as `1`; they already use this layout, so
+/// migrating them only records the version.
+pub const SCHEMA_VERSION: u32 = 2;
 
-#[contracttype]
-#[derive(Clone, Debug, Eq, PartialEq)]
-pub enum RiskLevel {
-    Low,
-    Medium,
-    High,
-    Restricted,
+/// Optional dimensions a transaction is tagged with, which scoped limits
+/// match on
+#[derive(Clone, Default)]
+struct Tags {
+    category: Option<Symbol>,
+    caller: Option<Address>,
 }
 
-/// Contract storage keys
-const SECURITY_LIMITS: Symbol = symbol_short!("SEC_LIMITS");
-const TRANSACTION_RECORDS: Symbol = symbol_short!("TX_RECORDS");
-const RISK_PROFILES: Symbol = symbol_short!("RISK_PROFILES");
-const NEXT_LIMIT_ID: Symbol = symbol_short!("NEXT_LIMIT_ID");
-const NEXT_TX_ID: Symbol = symbol_short!("NEXT_TX_ID");
+impl Tags {
+    fn category(category: Symbol) -> Self {
+        Tags {
+            category: Some(category),
+            caller: None,
+        }
+    }
+}
 
 /// Security Limits Contract
 #[contract]
@@ -84,48 +266,125 @@ pub struct SecurityLimitsContract;
 /// Contract implementation
 #[contractimpl]
 impl SecurityLimitsContract {
-    /// Initialize the contract
-    pub fn initialize(env: &Env) {
+    /// Initialize the contract, optionally with a compliance admin
+    pub fn initialize(env: &Env, compliance_admin: Option<Address>) -> Result<(), LimitsError> {
         let storage = env.storage().instance();
+        if storage.has(&NEXT_LIMIT_ID) {
+            return Err(LimitsError::AlreadyInitialized);
+        }
         storage.set(&NEXT_LIMIT_ID, &1u64);
         storage.set(&NEXT_TX_ID, &1u64);
+        storage.set(&SCHEMA, &SCHEMA_VERSION);
+
+        if let Some(admin) = compliance_admin {
+            storage.set(&COMPLIANCE_ADMIN, &admin);
+            Self::audit(env, &admin, AuditAction::AdminChanged(Some(admin.clone())));
+        }
+        Ok(())
+    }
+
+    /// Replace the contract's code with an uploaded WASM, keeping all of its
+    /// storage. Requires the compliance admin's auth, so a deployment without
+    /// one cannot be upgraded.
+    ///
+    /// Call [`Self::migrate`] afterwards so the new code can bring the
+    /// storage schema up to date.
+    pub fn upgrade(env: &Env, new_wasm_hash: BytesN<32>) -> Result<(), LimitsError> {
+        let admin = Self::require_compliance_admin(env)?;
+        Self::audit(env, &admin, AuditAction::Upgraded(new_wasm_hash.clone()));
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+        Ok(())
+    }
+
+    /// Bring stored data up to [`SCHEMA_VERSION`] and return it. Requires the
+    /// compliance admin's auth. Running it on an up-to-date deployment does
+    /// nothing.
+    pub fn migrate(env: &Env) -> Result<u32, LimitsError> {
+        let admin = Self::require_compliance_admin(env)?;
+        let from = Self::get_schema_version(env);
+        if from > SCHEMA_VERSION {
+            return Err(LimitsError::UnsupportedSchema);
+        }
+        if from == SCHEMA_VERSION {
+            return Ok(from);
+        }
+
+        env.storage().instance().set(&SCHEMA, &SCHEMA_VERSION);
+        Self::audit(env, &admin, AuditAction::Migrated(from, SCHEMA_VERSION));
+        Ok(SCHEMA_VERSION)
+    }
+
+    /// Storage schema version of the deployed data
+    pub fn get_schema_version(env: &Env) -> u32 {
+        env.storage().instance().get(&SCHEMA).unwrap_or(1)
     }
 
-    /// Create a new security limit
+    /// Create a new security limit. Requires `owner` auth.
     pub fn create_security_limit(
         env: &Env,
         owner: Address,
         limit_type: LimitType,
         asset: Symbol,
-        max_amount: u64,
+        max_amount: i128,
         time_window: u64,
-    ) -> u64 {
-        let storage = env.storage().instance();
-        let mut next_id: u64 = storage.get(&NEXT_LIMIT_ID).unwrap_or(1);
-        
-        let limit = SecurityLimit {
-            id: next_id,
-            owner: owner.clone(),
+    ) -> Result<u64, LimitsError> {
+        owner.require_auth();
+
+        if max_amount <= 0 {
+            return Err(LimitsError::InvalidAmount);
+        }
+
+        Ok(Self::insert_limit(
+            env,
+            &owner,
             limit_type,
             asset,
             max_amount,
             time_window,
-            current_usage: 0,
-            last_reset: env.ledger().timestamp(),
-            is_active: true,
-            created_at: env.ledger().timestamp(),
-        };
+        ))
+    }
 
-        // Store the limit
-        let mut limits: Map<u64, SecurityLimit> = storage.get(&SECURITY_LIMITS).unwrap_or(Map::new(&env));
-        limits.set(next_id, limit);
-        storage.set(&SECURITY_LIMITS, &limits);
-        
-        // Increment next ID
-        next_id += 1;
-        storage.set(&NEXT_LIMIT_ID, &next_id);
+    /// Create a portfolio-wide limit: `max_amount` of `reference` (e.g. `USD`)
+    /// across every asset the owner moves, valued through `valuation`'s
+    /// oracle. Requires `owner` auth.
+    ///
+    /// Shorthand for [`Self::create_security_limit`] followed by
+    /// [`Self::set_limit_denomination`] with a reference denomination.
+    pub fn create_portfolio_limit(
+        env: &Env,
+        owner: Address,
+        limit_type: LimitType,
+        reference: Symbol,
+        max_amount: i128,
+        time_window: u64,
+        valuation: Valuation,
+    ) -> Result<u64, LimitsError> {
+        owner.require_auth();
+
+        if max_amount <= 0 {
+            return Err(LimitsError::InvalidAmount);
+        }
+        Self::validate_valuation(&valuation)?;
+
+        let limit_id =
+            Self::insert_limit(env, &owner, limit_type, reference, max_amount, time_window);
+        let mut limit = storage::get_limit(env, &owner, limit_id).unwrap();
+        limit.denomination = Denomination::Reference(valuation);
+        storage::set_limit(env, &limit);
 
-        next_id - 1
+        events::denomination_set(env, &limit);
+        Ok(limit_id)
+    }
+
+    /// How much of a reference-currency limit's current usage each asset
+    /// accounts for, in reference units
+    pub fn get_portfolio_breakdown(
+        env: &Env,
+        owner: Address,
+        limit_id: u64,
+    ) -> Result<Map<Symbol, i128>, LimitsError> {
+        let limit = storage::get_limit(env, &owner, limit_id).ok_or(LimitsError::LimitNotFound)?;
+        Ok(window::asset_usage(env, &limit, env.ledger().timestamp()))
     }
 
     /// Check if a transaction is allowed within security limits
@@ -133,218 +392,3275 @@ impl SecurityLimitsContract {
         env: &Env,
         owner: Address,
         asset: Symbol,
-        amount: u64,
+        amount: i128,
+        destination: Option<Address>,
     ) -> bool {
-        let storage = env.storage().instance();
-        let limits: Map<u64, SecurityLimit> = storage.get(&SECURITY_LIMITS).unwrap_or(Map::new(&env));
-        
-        let current_time = env.ledger().timestamp();
-        
-        for (_, limit) in limits.iter() {
-            if limit.owner == owner && limit.asset == asset && limit.is_active {
-                // Check if limit applies to this time window
-                if Self::is_limit_applicable(&limit, current_time) {
-                    // Reset usage if time window has passed
-                    let mut updated_limit = limit.clone();
-                    if current_time - limit.last_reset > limit.time_window {
-                        updated_limit.current_usage = 0;
-                        updated_limit.last_reset = current_time;
-                    }
-                    
-                    // Check if transaction would exceed limit
-                    if updated_limit.current_usage + amount > limit.max_amount {
-                        return false;
-                    }
+        !storage::is_frozen(env, &owner)
+            && Self::destination_allowed(env, &owner, &destination)
+            && Self::cooldown_elapsed(env, &owner, &asset)
+            && Self::within_global_limit(env, &asset, amount)
+            && Self::check_limits_or_exception(
+                env,
+                &owner,
+                &asset,
+                amount,
+                &destination,
+                &None,
+                &Tags::default(),
+            )
+            .is_ok()
+    }
+
+    /// Validate a transaction against the owner's asset lists and limits.
+    ///
+    /// Returns [`LimitsError::AccountFrozen`], [`LimitsError::AssetBlocked`],
+    /// [`LimitsError::DestinationNotAllowed`], [`LimitsError::CooldownActive`],
+    /// [`LimitsError::LimitExceeded`] or [`LimitsError::SoftLimitUnconfirmed`]
+    /// so callers can tell the denials apart.
+    pub fn validate_transaction(
+        env: &Env,
+        owner: Address,
+        asset: Symbol,
+        amount: i128,
+        destination: Option<Address>,
+    ) -> Result<(), LimitsError> {
+        Self::validate(
+            env,
+            &owner,
+            &asset,
+            amount,
+            &destination,
+            &None,
+            &Tags::default(),
+            false,
+        )
+        .map(|_| ())
+    }
+
+    /// Record a transaction against the owner's limits. Requires `owner` auth
+    /// so third parties cannot inject usage; see [`Self::report_transaction`]
+    /// for registered reporters.
+    pub fn record_transaction(
+        env: &Env,
+        owner: Address,
+        asset: Symbol,
+        amount: i128,
+        destination: Option<Address>,
+        transaction_hash: BytesN<32>,
+    ) -> Result<u64, LimitsError> {
+        owner.require_auth();
+
+        if amount <= 0 {
+            return Err(LimitsError::InvalidAmount);
+        }
+
+        Self::store_transaction(
+            env,
+            &owner,
+            &asset,
+            amount,
+            destination,
+            None,
+            Tags::default(),
+            transaction_hash,
+        )
+    }
+
+    /// Record an inflow of `amount` of `asset` to `owner`, crediting it back
+    /// to the `NetFlow` limits that cover the asset, including shared group
+    /// limits. Requires `reporter` auth, and fails with
+    /// [`LimitsError::NotReporter`] unless the owner registered it.
+    ///
+    /// Credits free spent budget, so the owner's own word is not enough:
+    /// inflows come from the contract that received the funds.  Inflows are
+    /// not transaction records and count toward no other limit or statistic.
+    pub fn record_inflow(
+        env: &Env,
+        reporter: Address,
+        owner: Address,
+        asset: Symbol,
+        amount: i128,
+    ) -> Result<(), LimitsError> {
+        reporter.require_auth();
+
+        if !storage::reporters(env, &owner).contains(&reporter) {
+            return Err(LimitsError::NotReporter);
+        }
+        if amount <= 0 {
+            return Err(LimitsError::InvalidAmount);
+        }
+
+        let now = env.ledger().timestamp();
+        for holder in Self::limit_holders(env, &owner).iter() {
+            for mut limit in storage::owner_limits(env, &holder).iter() {
+                if limit.scope != LimitScope::NetFlow
+                    || !limit.is_active
+                    || !Self::covers_asset(&limit, &asset)
+                    || window::window_length(&limit) == 0
+                {
+                    continue;
                 }
+                let credit = Self::counted_amount(env, &limit, &asset, amount)?;
+                if limit.window_mode == WindowMode::Rolling {
+                    limit.current_usage = window::credit_rolling_usage(env, &limit, now, credit);
+                } else {
+                    window::roll_fixed(&mut limit, now);
+                    limit.current_usage = (limit.current_usage - credit).max(0);
+                }
+                storage::set_limit(env, &limit);
             }
         }
-        
-        true
+
+        events::inflow_recorded(env, &owner, &asset, amount);
+        Ok(())
     }
 
-    /// Record a transaction
-    pub fn record_transaction(
+    /// Authorization hook for protected contracts.
+    ///
+    /// Designed to be invoked cross-contract by smart-swap, wallets or dApps
+    /// inside their own transaction flow: any limit or risk-profile violation
+    /// is returned as an error, which fails the caller's whole transaction.
+    /// On success the amount is recorded against the owner's limits and the
+    /// new transaction record ID is returned.  Requires `owner` auth.
+    ///
+    /// The stored `transaction_hash` is an authorization receipt derived from
+    /// the call arguments and ledger sequence, since a contract cannot observe
+    /// the enclosing transaction's hash.
+    pub fn authorize_or_fail(
+        env: &Env,
+        owner: Address,
+        asset: Symbol,
+        amount: i128,
+        destination: Option<Address>,
+    ) -> Result<u64, LimitsError> {
+        owner.require_auth();
+        Self::authorize(
+            env,
+            &owner,
+            &asset,
+            amount,
+            destination,
+            None,
+            Tags::default(),
+            false,
+        )
+    }
+
+    /// [`Self::authorize_or_fail`] with the caller confirming the transaction
+    /// may cross soft limits that require confirmation, e.g. after the wallet
+    /// asked the user to acknowledge a warning.
+    pub fn authorize_confirmed_or_fail(
+        env: &Env,
+        owner: Address,
+        asset: Symbol,
+        amount: i128,
+        destination: Option<Address>,
+    ) -> Result<u64, LimitsError> {
+        owner.require_auth();
+        Self::authorize(
+            env,
+            &owner,
+            &asset,
+            amount,
+            destination,
+            None,
+            Tags::default(),
+            true,
+        )
+    }
+
+    /// [`Self::authorize_or_fail`] for a transaction a protocol contract
+    /// authorizes on the owner's behalf: limits scoped to `caller` apply in
+    /// addition to plain limits. Requires `owner` and `caller` auth.
+    ///
+    /// A contract calling this directly satisfies its own auth, so `caller`
+    /// identifies the invoking contract.
+    pub fn authorize_from_caller_or_fail(
         env: &Env,
         owner: Address,
+        caller: Address,
         asset: Symbol,
-        amount: u64,
+        amount: i128,
+        destination: Option<Address>,
+    ) -> Result<u64, LimitsError> {
+        owner.require_auth();
+        caller.require_auth();
+        let tags = Tags {
+            category: None,
+            caller: Some(caller),
+        };
+        Self::authorize(env, &owner, &asset, amount, destination, None, tags, false)
+    }
+
+    /// [`Self::check_transaction_allowed`] for a transaction tagged with a
+    /// spending `category`, which category limits count as well
+    pub fn check_categorized_allowed(
+        env: &Env,
+        owner: Address,
+        asset: Symbol,
+        amount: i128,
+        destination: Option<Address>,
+        category: Symbol,
+    ) -> bool {
+        !storage::is_frozen(env, &owner)
+            && Self::destination_allowed(env, &owner, &destination)
+            && Self::cooldown_elapsed(env, &owner, &asset)
+            && Self::within_global_limit(env, &asset, amount)
+            && Self::check_limits_or_exception(
+                env,
+                &owner,
+                &asset,
+                amount,
+                &destination,
+                &None,
+                &Tags::category(category),
+            )
+            .is_ok()
+    }
+
+    /// [`Self::authorize_or_fail`] for a transaction tagged with a spending
+    /// `category` such as `trading`, `transfer` or `defi`: limits scoped to
+    /// that category apply in addition to plain limits. Requires `owner`
+    /// auth.
+    pub fn authorize_categorized_or_fail(
+        env: &Env,
+        owner: Address,
+        asset: Symbol,
+        amount: i128,
+        destination: Option<Address>,
+        category: Symbol,
+    ) -> Result<u64, LimitsError> {
+        owner.require_auth();
+        Self::authorize(
+            env,
+            &owner,
+            &asset,
+            amount,
+            destination,
+            None,
+            Tags::category(category),
+            false,
+        )
+    }
+
+    /// Record a transaction tagged with a spending `category` against the
+    /// owner's limits. Requires `owner` auth.
+    pub fn record_categorized_transaction(
+        env: &Env,
+        owner: Address,
+        asset: Symbol,
+        amount: i128,
+        destination: Option<Address>,
+        category: Symbol,
         transaction_hash: BytesN<32>,
-    ) -> u64 {
-        let storage = env.storage().instance();
-        let mut next_tx_id: u64 = storage.get(&NEXT_TX_ID).unwrap_or(1);
-        
-        let record = TransactionRecord {
-            id: next_tx_id,
-            owner: owner.clone(),
-            asset,
+    ) -> Result<u64, LimitsError> {
+        owner.require_auth();
+
+        if amount <= 0 {
+            return Err(LimitsError::InvalidAmount);
+        }
+
+        Self::store_transaction(
+            env,
+            &owner,
+            &asset,
             amount,
-            timestamp: env.ledger().timestamp(),
+            destination,
+            None,
+            Tags::category(category),
             transaction_hash,
-        };
+        )
+    }
 
-        // Store the transaction record
-        let mut records: Vec<TransactionRecord> = storage.get(&TRANSACTION_RECORDS).unwrap_or(Vec::new(&env));
-        records.push_back(record.clone());
-        storage.set(&TRANSACTION_RECORDS, &records);
-        
-        // Update security limits usage
-        Self::update_limit_usage(env, &owner, &asset, amount);
-        
-        // Increment next ID
-        next_tx_id += 1;
-        storage.set(&NEXT_TX_ID, &next_tx_id);
+    /// Check whether swapping `amount` of `source_asset` into `dest_asset`
+    /// is allowed, counting pair limits as well as plain limits
+    pub fn check_swap_allowed(
+        env: &Env,
+        owner: Address,
+        source_asset: Symbol,
+        dest_asset: Symbol,
+        amount: i128,
+    ) -> bool {
+        !storage::is_frozen(env, &owner)
+            && Self::cooldown_elapsed(env, &owner, &source_asset)
+            && Self::within_global_limit(env, &source_asset, amount)
+            && Self::check_limits_or_exception(
+                env,
+                &owner,
+                &source_asset,
+                amount,
+                &None,
+                &Some(dest_asset),
+                &Tags::default(),
+            )
+            .is_ok()
+    }
 
-        next_tx_id - 1
+    /// Swap variant of [`Self::authorize_or_fail`] for market contracts such
+    /// as smart-swap: pair limits on `source_asset → dest_asset` apply in
+    /// addition to plain limits on `source_asset`. Requires `owner` auth.
+    pub fn authorize_swap_or_fail(
+        env: &Env,
+        owner: Address,
+        source_asset: Symbol,
+        dest_asset: Symbol,
+        amount: i128,
+    ) -> Result<u64, LimitsError> {
+        owner.require_auth();
+        Self::authorize(
+            env,
+            &owner,
+            &source_asset,
+            amount,
+            None,
+            Some(dest_asset),
+            Tags::default(),
+            false,
+        )
     }
 
-    /// Get security limits for an owner
-    pub fn get_security_limits(env: &Env, owner: Address) -> Vec<SecurityLimit> {
-        let storage = env.storage().instance();
-        let limits: Map<u64, SecurityLimit> = storage.get(&SECURITY_LIMITS).unwrap_or(Map::new(&env));
-        
-        let mut owner_limits = Vec::new(&env);
-        
-        for (_, limit) in limits.iter() {
-            if limit.owner == owner {
-                owner_limits.push_back(limit);
+    /// Record a completed swap against the owner's limits. Requires `owner`
+    /// auth.
+    pub fn record_swap(
+        env: &Env,
+        owner: Address,
+        source_asset: Symbol,
+        dest_asset: Symbol,
+        amount: i128,
+        transaction_hash: BytesN<32>,
+    ) -> Result<u64, LimitsError> {
+        owner.require_auth();
+
+        if amount <= 0 {
+            return Err(LimitsError::InvalidAmount);
+        }
+
+        Self::store_transaction(
+            env,
+            &owner,
+            &source_asset,
+            amount,
+            None,
+            Some(dest_asset),
+            Tags::default(),
+            transaction_hash,
+        )
+    }
+
+    /// Get an owner's transactions, oldest first, optionally filtered by
+    /// asset, with `from_ts <= timestamp <= to_ts`.
+    ///
+    /// `offset` skips that many matching records and `limit` is capped at
+    /// [`MAX_PAGE_SIZE`].  The time range is located by binary search, so
+    /// only records inside it are read.
+    pub fn get_transactions(
+        env: &Env,
+        owner: Address,
+        asset: Option<Symbol>,
+        from_ts: u64,
+        to_ts: u64,
+        offset: u32,
+        limit: u32,
+    ) -> Vec<TransactionRecord> {
+        let mut page = Vec::new(env);
+        let limit = limit.min(MAX_PAGE_SIZE);
+        if limit == 0 || from_ts > to_ts {
+            return page;
+        }
+
+        let count = storage::owner_tx_count(env, &owner);
+        let first = storage::first_owner_tx_from(env, &owner, from_ts);
+
+        let mut skipped = 0u32;
+        for index in first..count {
+            let Some(record) = storage::get_owner_tx(env, &owner, index) else {
+                continue;
+            };
+            if record.timestamp > to_ts {
+                break;
+            }
+            if asset.as_ref().is_some_and(|asset| *asset != record.asset) {
+                continue;
+            }
+            if skipped < offset {
+                skipped += 1;
+                continue;
+            }
+            page.push_back(record);
+            if page.len() == limit {
+                break;
             }
         }
-        
-        owner_limits
+        page
     }
 
-    /// Update a security limit
-    pub fn update_security_limit(
+    /// Aggregated usage of `asset` by `owner`: today's and 7-day volume,
+    /// largest single transaction and lifetime totals
+    pub fn get_usage_stats(env: &Env, owner: Address, asset: Symbol) -> UsageStats {
+        stats::usage(env, &owner, &asset)
+    }
+
+    /// Per-asset totals of the owner's transactions with
+    /// `from_ts <= timestamp <= to_ts`, as hashed by [`Self::commit_report`]
+    pub fn get_period_totals(
         env: &Env,
-        limit_id: u64,
         owner: Address,
-        max_amount: u64,
-        time_window: u64,
-        is_active: bool,
-    ) {
-        let storage = env.storage().instance();
-        let mut limits: Map<u64, SecurityLimit> = storage.get(&SECURITY_LIMITS).unwrap_or(Map::new(&env));
-        
-        let mut limit = limits.get(limit_id).unwrap();
-        
-        // Check ownership
-        if limit.owner != owner {
-            panic!("Not authorized");
-        }
-        
-        // Update limit
-        limit.max_amount = max_amount;
-        limit.time_window = time_window;
-        limit.is_active = is_active;
-        
-        limits.set(limit_id, limit);
-        storage.set(&SECURITY_LIMITS, &limits);
+        from_ts: u64,
+        to_ts: u64,
+    ) -> Map<Symbol, PeriodTotals> {
+        stats::period(env, &owner, from_ts, to_ts)
     }
 
-    /// Delete a security limit
-    pub fn delete_security_limit(env: &Env, limit_id: u64, owner: Address) {
-        let storage = env.storage().instance();
-        let mut limits: Map<u64, SecurityLimit> = storage.get(&SECURITY_LIMITS).unwrap_or(Map::new(&env));
-        
-        let limit = limits.get(limit_id).unwrap();
-        
-        // Check ownership
-        if limit.owner != owner {
-            panic!("Not authorized");
+    /// Commit to the owner's usage report for the closed period
+    /// `from_ts..=to_ts` by storing and publishing the digest of its
+    /// [`Self::get_period_totals`]. Requires `owner` auth.
+    ///
+    /// Auditors recompute the digest from an exported report and compare it
+    /// with [`Self::get_report`].  A period can be committed once, and only
+    /// after `to_ts` has passed so no transaction can still fall inside it.
+    pub fn commit_report(
+        env: &Env,
+        owner: Address,
+        from_ts: u64,
+        to_ts: u64,
+    ) -> Result<BytesN<32>, LimitsError> {
+        owner.require_auth();
+
+        if from_ts > to_ts || to_ts >= env.ledger().timestamp() {
+            return Err(LimitsError::InvalidPeriod);
         }
-        
-        // Remove limit
-        limits.remove(limit_id);
-        storage.set(&SECURITY_LIMITS, &limits);
+        if storage::get_report(env, &owner, from_ts, to_ts).is_some() {
+            return Err(LimitsError::ReportExists);
+        }
+
+        let totals = stats::period(env, &owner, from_ts, to_ts);
+        let digest: BytesN<32> = env
+            .crypto()
+            .sha256(&(owner.clone(), from_ts, to_ts, totals).to_xdr(env))
+            .into();
+        let report = ReportCommitment {
+            from_ts,
+            to_ts,
+            digest: digest.clone(),
+            committed_at: env.ledger().timestamp(),
+        };
+        storage::set_report(env, &owner, &report);
+        events::report_committed(env, &owner, &report);
+        Ok(digest)
     }
 
-    /// Create or update risk profile
-    pub fn set_risk_profile(
+    /// The owner's committed report for `from_ts..=to_ts`, if any
+    pub fn get_report(
         env: &Env,
         owner: Address,
-        risk_level: RiskLevel,
-        max_daily_volume: u64,
-        max_single_transaction: u64,
-        allowed_assets: Vec<Symbol>,
-        blacklisted_assets: Vec<Symbol>,
-    ) {
-        let storage = env.storage().instance();
-        let mut profiles: Map<Address, RiskProfile> = storage.get(&RISK_PROFILES).unwrap_or(Map::new(&env));
-        
-        let profile = RiskProfile {
-            owner: owner.clone(),
-            risk_level,
-            max_daily_volume,
-            max_single_transaction,
-            allowed_assets,
-            blacklisted_assets,
-            created_at: profiles.get(owner.clone()).map(|p| p.created_at).unwrap_or(env.ledger().timestamp()),
-            updated_at: env.ledger().timestamp(),
-        };
-        
-        profiles.set(owner, profile);
-        storage.set(&RISK_PROFILES, &profiles);
+        from_ts: u64,
+        to_ts: u64,
+    ) -> Option<ReportCommitment> {
+        storage::get_report(env, &owner, from_ts, to_ts)
     }
 
-    /// Get risk profile for an owner
-    pub fn get_risk_profile(env: &Env, owner: Address) -> Option<RiskProfile> {
-        let storage = env.storage().instance();
-        let profiles: Map<Address, RiskProfile> = storage.get(&RISK_PROFILES).unwrap_or(Map::new(&env));
-        profiles.get(owner)
+    /// Remaining budget on each of the owner's active limits for `asset`.
+    ///
+    /// `PerDestination` limits are omitted since their budget depends on the
+    /// counterparty; see [`Self::get_destination_usage`].
+    pub fn get_remaining_budgets(env: &Env, owner: Address, asset: Symbol) -> Vec<LimitBudget> {
+        let now = env.ledger().timestamp();
+        let mut budgets = Vec::new(env);
+        for limit in storage::owner_limits(env, &owner).iter() {
+            if limit.asset != asset || !limit.is_active || limit.scope == LimitScope::PerDestination
+            {
+                continue;
+            }
+            let usage = Self::effective_usage(env, &limit, now);
+            budgets.push_back(LimitBudget {
+                limit_id: limit.id,
+                max_amount: limit.max_amount,
+                usage,
+                remaining: limit.max_amount.saturating_sub(usage).max(0),
+            });
+        }
+        budgets
     }
 
-    /// Check if asset is allowed for owner
-    pub fn is_asset_allowed(env: &Env, owner: Address, asset: Symbol) -> bool {
-        if let Some(profile) = Self::get_risk_profile(env, owner) {
-            // Check if asset is blacklisted
-            for blacklisted_asset in profile.blacklisted_assets.iter() {
-                if *blacklisted_asset == asset {
-                    return false;
-                }
+    /// `(limit_id, remaining, resets_at)` for each budget
+    /// [`Self::get_remaining_budgets`] reports, so wallets can show how much
+    /// can still be spent before a transfer is blocked.
+    ///
+    /// `resets_at` is when counted usage next drops: the end of a fixed
+    /// window, or when the oldest bucket leaves a rolling one.  It is the
+    /// current time when nothing is counted.
+    pub fn remaining_allowance(env: &Env, owner: Address, asset: Symbol) -> Vec<(u64, i128, u64)> {
+        let now = env.ledger().timestamp();
+        let mut allowances = Vec::new(env);
+        for budget in Self::get_remaining_budgets(env, owner.clone(), asset).iter() {
+            let resets_at = match storage::get_limit(env, &owner, budget.limit_id) {
+                Some(limit) if budget.usage > 0 => Self::usage_resets_at(env, &limit, now),
+                _ => now,
+            };
+            allowances.push_back((budget.limit_id, budget.remaining, resets_at));
+        }
+        allowances
+    }
+
+    /// Explain how each of the owner's limits, and their group's, would judge
+    /// transferring `amount` of `asset`, without recording anything.
+    ///
+    /// Covers the active limits that count a plain transfer without a
+    /// destination, so wallets can show which limits block it, how much room
+    /// each has left and when that room grows.  Freezes, asset lists,
+    /// cooldowns and risk profile caps are reported by
+    /// [`Self::validate_transaction`] instead.
+    pub fn simulate_check(
+        env: &Env,
+        owner: Address,
+        asset: Symbol,
+        amount: i128,
+    ) -> Result<Vec<LimitCheck>, LimitsError> {
+        if amount < 0 {
+            return Err(LimitsError::InvalidAmount);
+        }
+        let now = env.ledger().timestamp();
+        let mut limits = Vec::new(env);
+        for holder in Self::limit_holders(env, &owner).iter() {
+            limits.append(&storage::owner_limits(env, &holder));
+        }
+        let mut checks = Vec::new(env);
+        for limit in limits.iter() {
+            if !Self::covers_asset(&limit, &asset)
+                || !limit.is_active
+                || !Self::counts_transfer(&limit, &None, &None, &Tags::default())
+            {
+                continue;
             }
-            
-            // Check if asset is in allowed list (if allowed list is not empty)
-            if profile.allowed_assets.len() > 0 {
-                for allowed_asset in profile.allowed_assets.iter() {
-                    if *allowed_asset == asset {
-                        return true;
-                    }
-                }
-                return false;
+            let counted = Self::counted_amount(env, &limit, &asset, amount)?;
+            let usage = Self::effective_usage(env, &limit, now);
+            let resets_at = if usage > 0 {
+                Self::usage_resets_at(env, &limit, now)
+            } else {
+                now
+            };
+            let projected = usage.checked_add(counted).ok_or(LimitsError::Overflow)?;
+            checks.push_back(LimitCheck {
+                limit_id: limit.id,
+                blocks: Self::over_limit(env, &limit, projected, counted, now),
+                headroom: limit.max_amount.saturating_sub(usage).max(0),
+                resets_at,
+            });
+        }
+        Ok(checks)
+    }
+
+    /// Number of transactions recorded for an owner
+    pub fn get_transaction_count(env: &Env, owner: Address) -> u32 {
+        storage::owner_tx_count(env, &owner)
+    }
+
+    /// Get a page of security limits for an owner, in creation order.
+    ///
+    /// `limit` is capped at [`MAX_PAGE_SIZE`].
+    pub fn get_security_limits(
+        env: &Env,
+        owner: Address,
+        offset: u32,
+        limit: u32,
+    ) -> Vec<SecurityLimit> {
+        let ids = storage::owner_limit_ids(env, &owner);
+        let end = offset
+            .saturating_add(limit.min(MAX_PAGE_SIZE))
+            .min(ids.len());
+
+        let mut page = Vec::new(env);
+        for i in offset..end {
+            if let Some(found) = storage::get_limit(env, &owner, ids.get(i).unwrap()) {
+                page.push_back(found);
             }
         }
-        
-        true // Default to allowed if no profile exists
+        page
+    }
+
+    /// Get the number of security limits an owner has
+    pub fn get_security_limit_count(env: &Env, owner: Address) -> u32 {
+        storage::owner_limit_ids(env, &owner).len()
+    }
+
+    /// Get a single security limit
+    pub fn get_security_limit(
+        env: &Env,
+        owner: Address,
+        limit_id: u64,
+    ) -> Result<SecurityLimit, LimitsError> {
+        storage::get_limit(env, &owner, limit_id).ok_or(LimitsError::LimitNotFound)
     }
 
-    /// Helper function to check if limit is applicable
-    fn is_limit_applicable(limit: &SecurityLimit, current_time: u64) -> bool {
-        match limit.limit_type {
-            LimitType::Daily => current_time - limit.last_reset < 86400, // 24 hours
-            LimitType::Weekly => current_time - limit.last_reset < 604800, // 7 days
-            LimitType::Monthly => current_time - limit.last_reset < 2592000, // 30 days
-            LimitType::PerTransaction => true,
-            LimitType::PerHour => current_time - limit.last_reset < 3600, // 1 hour
-            LimitType::Custom(window) => current_time - limit.last_reset < window,
+    /// Update a security limit. Requires `owner` auth.
+    pub fn update_security_limit(
+        env: &Env,
+        limit_id: u64,
+        owner: Address,
+        max_amount: i128,
+        time_window: u64,
+        is_active: bool,
+    ) -> Result<(), LimitsError> {
+        owner.require_auth();
+
+        if max_amount <= 0 {
+            return Err(LimitsError::InvalidAmount);
+        }
+
+        // Limits are namespaced by owner, so another owner's ID is not found
+        let limit = storage::get_limit(env, &owner, limit_id).ok_or(LimitsError::LimitNotFound)?;
+        let loosens = max_amount > limit.max_amount
+            || Self::shortens_window(&limit, time_window)
+            || (limit.is_active && !is_active);
+        if loosens && storage::get_co_signer(env, &owner).is_some() {
+            return Err(LimitsError::CoSignRequired);
         }
+
+        Self::apply_limit_update(env, limit, max_amount, time_window, is_active);
+        Ok(())
     }
 
-    /// Helper function to update limit usage
-    fn update_limit_usage(env: &Env, owner: &Address, asset: &Symbol, amount: u64) {
-        let storage = env.storage().instance();
-        let mut limits: Map<u64, SecurityLimit> = storage.get(&SECURITY_LIMITS).unwrap_or(Map::new(&env));
-        
-        for (id, mut limit) in limits.iter() {
-            if limit.owner == *owner && limit.asset == *asset && limit.is_active {
-                limit.current_usage += amount;
-                limits.set(id, limit);
-            }
+    /// Switch a limit between fixed, rolling, calendar and scheduled window
+    /// accounting. Requires `owner` auth.
+    ///
+    /// Usage restarts from zero in the new mode.  Schedules are rejected with
+    /// [`LimitsError::InvalidSchedule`] on per-transaction limits.
+    ///
+    /// Because the usage reset frees budget, this fails with
+    /// [`LimitsError::CoSignRequired`] while the owner has a co-signer;
+    /// propose a [`ProposedChange::SetWindowMode`] instead.
+    pub fn set_limit_window_mode(
+        env: &Env,
+        limit_id: u64,
+        owner: Address,
+        window_mode: WindowMode,
+    ) -> Result<(), LimitsError> {
+        owner.require_auth();
+
+        let limit = storage::get_limit(env, &owner, limit_id).ok_or(LimitsError::LimitNotFound)?;
+        Self::check_window_mode(&limit, &window_mode)?;
+        if storage::get_co_signer(env, &owner).is_some() {
+            return Err(LimitsError::CoSignRequired);
         }
-        
-        storage.set(&SECURITY_LIMITS, &limits);
+
+        Self::apply_window_mode(env, limit, window_mode);
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod test;
+    /// Change which transfers a limit counts. Requires `owner` auth.
+    ///
+    /// Usage restarts from zero under the new scope, so this fails with
+    /// [`LimitsError::CoSignRequired`] while the owner has a co-signer;
+    /// propose a [`ProposedChange::SetScope`] instead.
+    pub fn set_limit_scope(
+        env: &Env,
+        limit_id: u64,
+        owner: Address,
+        scope: LimitScope,
+    ) -> Result<(), LimitsError> {
+        owner.require_auth();
 
+        let limit = storage::get_limit(env, &owner, limit_id).ok_or(LimitsError::LimitNotFound)?;
+        if storage::get_co_signer(env, &owner).is_some() {
+            return Err(LimitsError::CoSignRequired);
+        }
+
+        Self::apply_scope(env, limit, scope);
+        Ok(())
+    }
+
+    /// Restrict a limit to swaps into `pair_asset`, or lift the restriction
+    /// with `None`. Requires `owner` auth.
+    ///
+    /// Usage restarts from zero, so this fails with
+    /// [`LimitsError::CoSignRequired`] while the owner has a co-signer;
+    /// propose a [`ProposedChange::SetPair`] instead.
+    pub fn set_limit_pair(
+        env: &Env,
+        limit_id: u64,
+        owner: Address,
+        pair_asset: Option<Symbol>,
+    ) -> Result<(), LimitsError> {
+        owner.require_auth();
+
+        let limit = storage::get_limit(env, &owner, limit_id).ok_or(LimitsError::LimitNotFound)?;
+        if storage::get_co_signer(env, &owner).is_some() {
+            return Err(LimitsError::CoSignRequired);
+        }
+
+        Self::apply_pair(env, limit, pair_asset);
+        Ok(())
+    }
+
+    /// Denominate a limit in a reference currency, or revert it to a plain
+    /// single-asset limit. Requires `owner` auth.
+    ///
+    /// With a reference denomination the limit's `asset` names the reference
+    /// currency and every asset counts towards it.  Usage restarts from zero,
+    /// so this fails with [`LimitsError::CoSignRequired`] while the owner has
+    /// a co-signer; propose a [`ProposedChange::SetDenomination`] instead.
+    pub fn set_limit_denomination(
+        env: &Env,
+        limit_id: u64,
+        owner: Address,
+        denomination: Denomination,
+    ) -> Result<(), LimitsError> {
+        owner.require_auth();
+
+        if let Denomination::Reference(valuation) = &denomination {
+            Self::validate_valuation(valuation)?;
+        }
+
+        let limit = storage::get_limit(env, &owner, limit_id).ok_or(LimitsError::LimitNotFound)?;
+        if storage::get_co_signer(env, &owner).is_some() {
+            return Err(LimitsError::CoSignRequired);
+        }
+
+        Self::apply_denomination(env, limit, denomination);
+        Ok(())
+    }
+
+    /// Set a soft limit of `amount` on a limit, or remove it with `0`.
+    /// Requires `owner` auth.
+    ///
+    /// Returns [`LimitsError::InvalidAmount`] unless
+    /// `0 <= amount < max_amount`.  Fails with
+    /// [`LimitsError::CoSignRequired`] if this raises or removes the soft
+    /// limit, or drops its confirmation, while the owner has a co-signer;
+    /// propose a [`ProposedChange::SetSoftLimit`] instead.
+    pub fn set_soft_limit(
+        env: &Env,
+        limit_id: u64,
+        owner: Address,
+        amount: i128,
+        require_confirmation: bool,
+    ) -> Result<(), LimitsError> {
+        owner.require_auth();
+
+        let limit = storage::get_limit(env, &owner, limit_id).ok_or(LimitsError::LimitNotFound)?;
+        Self::check_soft_limit(&limit, amount)?;
+        let loosens = storage::get_soft_limit(env, &owner, limit_id).is_some_and(|soft| {
+            amount == 0
+                || amount > soft.amount
+                || (soft.require_confirmation && !require_confirmation)
+        });
+        if loosens && storage::get_co_signer(env, &owner).is_some() {
+            return Err(LimitsError::CoSignRequired);
+        }
+
+        Self::apply_soft_limit(env, &owner, limit_id, amount, require_confirmation);
+        Ok(())
+    }
+
+    /// Let transactions take a limit's usage up to `extra_bps` basis points
+    /// of `max_amount` above it, refilled every `refill_windows` windows; an
+    /// `extra_bps` of `0` removes the allowance. Requires `owner` auth.
+    ///
+    /// Fails with [`LimitsError::CoSignRequired`] if this enlarges the
+    /// allowance while the owner has a co-signer; propose a
+    /// [`ProposedChange::SetBurstAllowance`] instead.
+    pub fn set_burst_allowance(
+        env: &Env,
+        limit_id: u64,
+        owner: Address,
+        extra_bps: u32,
+        refill_windows: u32,
+    ) -> Result<(), LimitsError> {
+        owner.require_auth();
+
+        let limit = storage::get_limit(env, &owner, limit_id).ok_or(LimitsError::LimitNotFound)?;
+        Self::check_burst_allowance(&limit, extra_bps, refill_windows)?;
+        let loosens = extra_bps > 0
+            && storage::get_burst(env, &owner, limit_id).is_none_or(|burst| {
+                extra_bps > burst.extra_bps || refill_windows < burst.refill_windows
+            });
+        if loosens && storage::get_co_signer(env, &owner).is_some() {
+            return Err(LimitsError::CoSignRequired);
+        }
+
+        Self::apply_burst_allowance(env, &owner, limit_id, extra_bps, refill_windows);
+        Ok(())
+    }
+
+    /// A limit's burst allowance, if any
+    pub fn get_burst_allowance(env: &Env, owner: Address, limit_id: u64) -> Option<BurstAllowance> {
+        storage::get_burst(env, &owner, limit_id)
+    }
+
+    /// A limit's soft limit, if any
+    pub fn get_soft_limit(env: &Env, owner: Address, limit_id: u64) -> Option<SoftLimit> {
+        storage::get_soft_limit(env, &owner, limit_id)
+    }
+
+    /// Current usage of a `PerDestination` limit towards one destination.
+    pub fn get_destination_usage(
+        env: &Env,
+        owner: Address,
+        limit_id: u64,
+        destination: Address,
+    ) -> Result<i128, LimitsError> {
+        let limit = storage::get_limit(env, &owner, limit_id).ok_or(LimitsError::LimitNotFound)?;
+        Ok(window::destination_usage(
+            env,
+            &limit,
+            &destination,
+            env.ledger().timestamp(),
+        ))
+    }
+
+    /// Delete a security limit. Requires `owner` auth.
+    pub fn delete_security_limit(
+        env: &Env,
+        limit_id: u64,
+        owner: Address,
+    ) -> Result<(), LimitsError> {
+        owner.require_auth();
+
+        if storage::get_limit(env, &owner, limit_id).is_none() {
+            return Err(LimitsError::LimitNotFound);
+        }
+        if storage::get_co_signer(env, &owner).is_some() {
+            return Err(LimitsError::CoSignRequired);
+        }
+
+        Self::apply_limit_delete(env, &owner, limit_id);
+        Ok(())
+    }
+
+    /// Create or update risk profile. Requires `owner` auth.
+    ///
+    /// Fails with [`LimitsError::CoSignRequired`] if this loosens the current
+    /// profile while the owner has a co-signer; propose a
+    /// [`ProposedChange::SetRiskProfile`] instead.
+    pub fn set_risk_profile(
+        env: &Env,
+        owner: Address,
+        risk_level: RiskLevel,
+        max_daily_volume: i128,
+        max_single_transaction: i128,
+        allowed_assets: Vec<Symbol>,
+        blacklisted_assets: Vec<Symbol>,
+    ) -> Result<(), LimitsError> {
+        owner.require_auth();
+
+        if max_daily_volume < 0 || max_single_transaction < 0 {
+            return Err(LimitsError::InvalidAmount);
+        }
+        let params = RiskProfileParams {
+            risk_level,
+            max_daily_volume,
+            max_single_transaction,
+            allowed_assets,
+            blacklisted_assets,
+        };
+        if storage::get_co_signer(env, &owner).is_some()
+            && Self::profile_loosens(env, &owner, &params)
+        {
+            return Err(LimitsError::CoSignRequired);
+        }
+
+        Self::apply_risk_profile(env, &owner, params);
+        Ok(())
+    }
+
+    /// Get risk profile for an owner
+    pub fn get_risk_profile(env: &Env, owner: Address) -> Option<RiskProfile> {
+        Self::load_profiles(env).get(owner)
+    }
+
+    /// Check if asset is allowed for owner
+    pub fn is_asset_allowed(env: &Env, owner: Address, asset: Symbol) -> bool {
+        if let Some(profile) = Self::get_risk_profile(env, owner) {
+            // Check if asset is blacklisted
+            for blacklisted_asset in profile.blacklisted_assets.iter() {
+                if blacklisted_asset == asset {
+                    return false;
+                }
+            }
+
+            // Check if asset is in allowed list (if allowed list is not empty)
+            if !profile.allowed_assets.is_empty() {
+                for allowed_asset in profile.allowed_assets.iter() {
+                    if allowed_asset == asset {
+                        return true;
+                    }
+                }
+                return false;
+            }
+        }
+
+        true // Default to allowed if no profile exists
+    }
+
+    /// Add `asset` to the owner's allow-list. Requires `owner` auth.
+    ///
+    /// Unlike [`Self::set_risk_profile`] this edits the list in place, so
+    /// concurrent updates do not overwrite each other.  An owner without a
+    /// risk profile gets an uncapped `Low` one.
+    ///
+    /// Adding to a non-empty allow-list widens it, which fails with
+    /// [`LimitsError::CoSignRequired`] while the owner has a co-signer.
+    pub fn add_allowed_asset(env: &Env, owner: Address, asset: Symbol) -> Result<(), LimitsError> {
+        owner.require_auth();
+        let allowed = Self::allowed_assets(env, &owner);
+        if !allowed.is_empty()
+            && !allowed.contains(&asset)
+            && storage::get_co_signer(env, &owner).is_some()
+        {
+            return Err(LimitsError::CoSignRequired);
+        }
+        if Self::edit_asset_list(env, &owner, false, &asset, true) {
+            events::allowed_asset_added(env, &owner, &asset);
+        }
+        Ok(())
+    }
+
+    /// Remove `asset` from the owner's allow-list. Requires `owner` auth.
+    ///
+    /// Removing the last asset empties the list, which allows every asset,
+    /// so that fails with [`LimitsError::CoSignRequired`] while the owner
+    /// has a co-signer.
+    pub fn remove_allowed_asset(
+        env: &Env,
+        owner: Address,
+        asset: Symbol,
+    ) -> Result<(), LimitsError> {
+        owner.require_auth();
+        let allowed = Self::allowed_assets(env, &owner);
+        if allowed.len() == 1
+            && allowed.contains(&asset)
+            && storage::get_co_signer(env, &owner).is_some()
+        {
+            return Err(LimitsError::CoSignRequired);
+        }
+        if Self::edit_asset_list(env, &owner, false, &asset, false) {
+            events::allowed_asset_removed(env, &owner, &asset);
+        }
+        Ok(())
+    }
+
+    /// Add `asset` to the owner's blacklist. Requires `owner` auth.
+    pub fn add_blacklisted_asset(env: &Env, owner: Address, asset: Symbol) {
+        owner.require_auth();
+        if Self::edit_asset_list(env, &owner, true, &asset, true) {
+            events::blacklisted_asset_added(env, &owner, &asset);
+        }
+    }
+
+    /// Remove `asset` from the owner's blacklist. Requires `owner` auth.
+    ///
+    /// Fails with [`LimitsError::CoSignRequired`] while the owner has a
+    /// co-signer; propose a [`ProposedChange::SetRiskProfile`] instead.
+    pub fn remove_blacklisted_asset(
+        env: &Env,
+        owner: Address,
+        asset: Symbol,
+    ) -> Result<(), LimitsError> {
+        owner.require_auth();
+        if storage::get_co_signer(env, &owner).is_some() {
+            return Err(LimitsError::CoSignRequired);
+        }
+        if Self::edit_asset_list(env, &owner, true, &asset, false) {
+            events::blacklisted_asset_removed(env, &owner, &asset);
+        }
+        Ok(())
+    }
+
+    // -----------------------------------------------------------------------
+    // Two-party approval
+    // -----------------------------------------------------------------------
+
+    /// Set or clear the owner's co-signer. Requires `owner` auth, plus the
+    /// current co-signer's auth when one is already set.
+    pub fn set_co_signer(env: &Env, owner: Address, co_signer: Option<Address>) {
+        owner.require_auth();
+        if let Some(current) = storage::get_co_signer(env, &owner) {
+            current.require_auth();
+        }
+
+        storage::set_co_signer(env, &owner, &co_signer);
+        events::co_signer_set(env, &owner, &co_signer);
+    }
+
+    /// The owner's co-signer, if any
+    pub fn get_co_signer(env: &Env, owner: Address) -> Option<Address> {
+        storage::get_co_signer(env, &owner)
+    }
+
+    /// Propose a change for the co-signer to approve before `expires_at`.
+    /// Requires `owner` auth. Returns the proposal ID.
+    pub fn propose_change(
+        env: &Env,
+        owner: Address,
+        change: ProposedChange,
+        expires_at: u64,
+    ) -> Result<u64, LimitsError> {
+        owner.require_auth();
+
+        let now = env.ledger().timestamp();
+        if expires_at <= now {
+            return Err(LimitsError::InvalidExpiry);
+        }
+
+        let instance = env.storage().instance();
+        let id: u64 = instance.get(&NEXT_PROPOSAL_ID).unwrap_or(1);
+        instance.set(&NEXT_PROPOSAL_ID, &(id + 1));
+
+        let proposal = Proposal {
+            id,
+            owner,
+            change,
+            proposed_at: now,
+            expires_at,
+        };
+        storage::set_proposal(env, &proposal);
+
+        events::change_proposed(env, &proposal);
+        Ok(id)
+    }
+
+    /// Approve and apply a pending proposal. Requires the co-signer's auth.
+    pub fn approve_change(env: &Env, owner: Address, proposal_id: u64) -> Result<(), LimitsError> {
+        let co_signer = storage::get_co_signer(env, &owner).ok_or(LimitsError::NotAuthorized)?;
+        co_signer.require_auth();
+
+        let proposal =
+            storage::get_proposal(env, &owner, proposal_id).ok_or(LimitsError::ProposalNotFound)?;
+        if env.ledger().timestamp() >= proposal.expires_at {
+            return Err(LimitsError::ProposalExpired);
+        }
+
+        match proposal.change {
+            ProposedChange::UpdateLimit(limit_id, max_amount, time_window, is_active) => {
+                if max_amount <= 0 {
+                    return Err(LimitsError::InvalidAmount);
+                }
+                let limit =
+                    storage::get_limit(env, &owner, limit_id).ok_or(LimitsError::LimitNotFound)?;
+                Self::apply_limit_update(env, limit, max_amount, time_window, is_active);
+            }
+            ProposedChange::SetWindowMode(limit_id, window_mode) => {
+                let limit =
+                    storage::get_limit(env, &owner, limit_id).ok_or(LimitsError::LimitNotFound)?;
+                Self::check_window_mode(&limit, &window_mode)?;
+                Self::apply_window_mode(env, limit, window_mode);
+            }
+            ProposedChange::SetScope(limit_id, scope) => {
+                let limit =
+                    storage::get_limit(env, &owner, limit_id).ok_or(LimitsError::LimitNotFound)?;
+                Self::apply_scope(env, limit, scope);
+            }
+            ProposedChange::SetPair(limit_id, pair_asset) => {
+                let limit =
+                    storage::get_limit(env, &owner, limit_id).ok_or(LimitsError::LimitNotFound)?;
+                Self::apply_pair(env, limit, pair_asset);
+            }
+            ProposedChange::SetDenomination(limit_id, denomination) => {
+                if let Denomination::Reference(valuation) = &denomination {
+                    Self::validate_valuation(valuation)?;
+                }
+                let limit =
+                    storage::get_limit(env, &owner, limit_id).ok_or(LimitsError::LimitNotFound)?;
+                Self::apply_denomination(env, limit, denomination);
+            }
+            ProposedChange::SetBurstAllowance(limit_id, extra_bps, refill_windows) => {
+                let limit =
+                    storage::get_limit(env, &owner, limit_id).ok_or(LimitsError::LimitNotFound)?;
+                Self::check_burst_allowance(&limit, extra_bps, refill_windows)?;
+                Self::apply_burst_allowance(env, &owner, limit_id, extra_bps, refill_windows);
+            }
+            ProposedChange::SetSoftLimit(limit_id, amount, require_confirmation) => {
+                let limit =
+                    storage::get_limit(env, &owner, limit_id).ok_or(LimitsError::LimitNotFound)?;
+                Self::check_soft_limit(&limit, amount)?;
+                Self::apply_soft_limit(env, &owner, limit_id, amount, require_confirmation);
+            }
+            ProposedChange::DeleteLimit(limit_id) => {
+                if storage::get_limit(env, &owner, limit_id).is_none() {
+                    return Err(LimitsError::LimitNotFound);
+                }
+                Self::apply_limit_delete(env, &owner, limit_id);
+            }
+            ProposedChange::SetRiskProfile(params) => {
+                if params.max_daily_volume < 0 || params.max_single_transaction < 0 {
+                    return Err(LimitsError::InvalidAmount);
+                }
+                Self::apply_risk_profile(env, &owner, params);
+            }
+            ProposedChange::ApplyTemplate(template_id) => {
+                let template = storage::get_template(env, &template_id)
+                    .ok_or(LimitsError::TemplateNotFound)?;
+                Self::instantiate_template(env, &owner, &template);
+            }
+        }
+
+        storage::remove_proposal(env, &owner, proposal_id);
+        events::change_approved(env, &owner, proposal_id);
+        Ok(())
+    }
+
+    /// Withdraw a pending proposal. Requires auth from `caller`, who must be
+    /// the owner or their co-signer. Expired proposals can be cancelled too.
+    pub fn cancel_change(
+        env: &Env,
+        caller: Address,
+        owner: Address,
+        proposal_id: u64,
+    ) -> Result<(), LimitsError> {
+        caller.require_auth();
+        if caller != owner && storage::get_co_signer(env, &owner) != Some(caller) {
+            return Err(LimitsError::NotAuthorized);
+        }
+        if storage::get_proposal(env, &owner, proposal_id).is_none() {
+            return Err(LimitsError::ProposalNotFound);
+        }
+
+        storage::remove_proposal(env, &owner, proposal_id);
+        events::change_cancelled(env, &owner, proposal_id);
+        Ok(())
+    }
+
+    /// Get a pending proposal
+    pub fn get_proposal(env: &Env, owner: Address, proposal_id: u64) -> Option<Proposal> {
+        storage::get_proposal(env, &owner, proposal_id)
+    }
+
+    // -----------------------------------------------------------------------
+    // Limit groups
+    // -----------------------------------------------------------------------
+
+    /// Enrol `member` in `group`, so the member's transactions also count
+    /// against the group's limits. Requires `group` and `member` auth.
+    ///
+    /// An address can belong to one group at a time and groups cannot be
+    /// nested; either case fails with [`LimitsError::AlreadyInGroup`].
+    pub fn add_group_member(env: &Env, group: Address, member: Address) -> Result<(), LimitsError> {
+        group.require_auth();
+        member.require_auth();
+
+        if member == group
+            || storage::group_of(env, &member).is_some()
+            || storage::group_of(env, &group).is_some()
+            || !storage::group_members(env, &member).is_empty()
+        {
+            return Err(LimitsError::AlreadyInGroup);
+        }
+
+        let mut members = storage::group_members(env, &group);
+        members.push_back(member.clone());
+        storage::set_group_members(env, &group, &members);
+        storage::set_group_of(env, &member, &Some(group.clone()));
+        events::group_member_added(env, &group, &member);
+        Ok(())
+    }
+
+    /// Remove `member` from `group`. Requires `group` auth, plus the group's
+    /// co-signer's auth when one is set.
+    pub fn remove_group_member(
+        env: &Env,
+        group: Address,
+        member: Address,
+    ) -> Result<(), LimitsError> {
+        group.require_auth();
+        if let Some(co_signer) = storage::get_co_signer(env, &group) {
+            co_signer.require_auth();
+        }
+
+        let mut members = storage::group_members(env, &group);
+        let index = members
+            .first_index_of(&member)
+            .ok_or(LimitsError::NotGroupMember)?;
+        members.remove(index);
+        storage::set_group_members(env, &group, &members);
+        storage::set_group_of(env, &member, &None);
+        events::group_member_removed(env, &group, &member);
+        Ok(())
+    }
+
+    /// Members of `group`, in enrolment order
+    pub fn get_group_members(env: &Env, group: Address) -> Vec<Address> {
+        storage::group_members(env, &group)
+    }
+
+    /// The group `member` belongs to, if any
+    pub fn get_group(env: &Env, member: Address) -> Option<Address> {
+        storage::group_of(env, &member)
+    }
+
+    // -----------------------------------------------------------------------
+    // Reporters
+    // -----------------------------------------------------------------------
+
+    /// Let `reporter` record transactions and inflows against the owner's
+    /// limits. Requires `owner` auth, plus the co-signer's auth when one is
+    /// set, as reported inflows free budget; registering a reporter twice is
+    /// a no-op.
+    pub fn add_reporter(env: &Env, owner: Address, reporter: Address) {
+        owner.require_auth();
+        if let Some(co_signer) = storage::get_co_signer(env, &owner) {
+            co_signer.require_auth();
+        }
+
+        let mut reporters = storage::reporters(env, &owner);
+        if reporters.contains(&reporter) {
+            return;
+        }
+        reporters.push_back(reporter.clone());
+        storage::set_reporters(env, &owner, &reporters);
+        events::reporter_added(env, &owner, &reporter);
+    }
+
+    /// Revoke a reporter. Requires `owner` auth.
+    pub fn remove_reporter(
+        env: &Env,
+        owner: Address,
+        reporter: Address,
+    ) -> Result<(), LimitsError> {
+        owner.require_auth();
+
+        let mut reporters = storage::reporters(env, &owner);
+        let index = reporters
+            .first_index_of(&reporter)
+            .ok_or(LimitsError::NotReporter)?;
+        reporters.remove(index);
+        storage::set_reporters(env, &owner, &reporters);
+        events::reporter_removed(env, &owner, &reporter);
+        Ok(())
+    }
+
+    /// Reporters registered for `owner`, in registration order
+    pub fn get_reporters(env: &Env, owner: Address) -> Vec<Address> {
+        storage::reporters(env, &owner)
+    }
+
+    /// Record a transaction against the owner's limits on their behalf.
+    /// Requires `reporter` auth, and fails with [`LimitsError::NotReporter`]
+    /// unless the owner registered it.
+    pub fn report_transaction(
+        env: &Env,
+        reporter: Address,
+        owner: Address,
+        asset: Symbol,
+        amount: i128,
+        destination: Option<Address>,
+        transaction_hash: BytesN<32>,
+    ) -> Result<u64, LimitsError> {
+        reporter.require_auth();
+
+        if !storage::reporters(env, &owner).contains(&reporter) {
+            return Err(LimitsError::NotReporter);
+        }
+        if amount <= 0 {
+            return Err(LimitsError::InvalidAmount);
+        }
+
+        Self::store_transaction(
+            env,
+            &owner,
+            &asset,
+            amount,
+            destination,
+            None,
+            Tags::default(),
+            transaction_hash,
+        )
+    }
+
+    // -----------------------------------------------------------------------
+    // Limit managers
+    // -----------------------------------------------------------------------
+
+    /// Let `manager` create and update the owner's limits up to `caps`, or
+    /// replace an existing grant. Requires `owner` auth, plus the co-signer's
+    /// auth when one is set.
+    pub fn set_limit_manager(env: &Env, owner: Address, manager: Address, caps: Map<Symbol, i128>) {
+        owner.require_auth();
+        if let Some(co_signer) = storage::get_co_signer(env, &owner) {
+            co_signer.require_auth();
+        }
+
+        let grant = ManagerGrant {
+            manager,
+            caps,
+            granted_at: env.ledger().timestamp(),
+        };
+        storage::set_manager(env, &owner, &grant);
+        events::manager_set(env, &owner, &grant);
+    }
+
+    /// Withdraw a manager's grant. Requires `owner` auth.
+    pub fn revoke_limit_manager(
+        env: &Env,
+        owner: Address,
+        manager: Address,
+    ) -> Result<(), LimitsError> {
+        owner.require_auth();
+        if storage::get_manager(env, &owner, &manager).is_none() {
+            return Err(LimitsError::NotAuthorized);
+        }
+
+        storage::remove_manager(env, &owner, &manager);
+        events::manager_revoked(env, &owner, &manager);
+        Ok(())
+    }
+
+    /// A manager's grant from `owner`, if any
+    pub fn get_limit_manager(env: &Env, owner: Address, manager: Address) -> Option<ManagerGrant> {
+        storage::get_manager(env, &owner, &manager)
+    }
+
+    /// Create a limit on the owner's behalf. Requires `manager` auth.
+    pub fn manager_create_limit(
+        env: &Env,
+        manager: Address,
+        owner: Address,
+        limit_type: LimitType,
+        asset: Symbol,
+        max_amount: i128,
+        time_window: u64,
+    ) -> Result<u64, LimitsError> {
+        manager.require_auth();
+
+        if max_amount <= 0 {
+            return Err(LimitsError::InvalidAmount);
+        }
+        Self::check_manager_cap(env, &owner, &manager, &asset, max_amount)?;
+
+        Ok(Self::insert_limit(
+            env,
+            &owner,
+            limit_type,
+            asset,
+            max_amount,
+            time_window,
+        ))
+    }
+
+    /// Update one of the owner's limits on their behalf. Requires `manager`
+    /// auth. Managers cannot deactivate a limit or shorten its window, which
+    /// would let the capped amount be spent more often.
+    pub fn manager_update_limit(
+        env: &Env,
+        manager: Address,
+        owner: Address,
+        limit_id: u64,
+        max_amount: i128,
+        time_window: u64,
+        is_active: bool,
+    ) -> Result<(), LimitsError> {
+        manager.require_auth();
+
+        if max_amount <= 0 {
+            return Err(LimitsError::InvalidAmount);
+        }
+        let limit = storage::get_limit(env, &owner, limit_id).ok_or(LimitsError::LimitNotFound)?;
+        Self::check_manager_cap(env, &owner, &manager, &limit.asset, max_amount)?;
+        if (limit.is_active && !is_active) || Self::shortens_window(&limit, time_window) {
+            return Err(LimitsError::NotAuthorized);
+        }
+
+        Self::apply_limit_update(env, limit, max_amount, time_window, is_active);
+        Ok(())
+    }
+
+    // -----------------------------------------------------------------------
+    // Destination whitelist
+    // -----------------------------------------------------------------------
+
+    /// Add `destination` to the owner's whitelist. Requires `owner` auth.
+    ///
+    /// While whitelist mode is on the addition is queued behind the timelock
+    /// and its change ID is returned; otherwise it applies at once and `None`
+    /// is returned.
+    pub fn add_whitelisted_destination(
+        env: &Env,
+        owner: Address,
+        destination: Address,
+    ) -> Option<u64> {
+        owner.require_auth();
+
+        let change = TimelockedChange::AddDestination(destination);
+        if storage::whitelist_enabled(env, &owner) {
+            return Some(Self::queue_timelocked(env, &owner, change));
+        }
+        Self::apply_timelocked(env, &owner, change);
+        None
+    }
+
+    /// Remove `destination` from the owner's whitelist. Requires `owner`
+    /// auth. Takes effect immediately.
+    pub fn remove_whitelisted_destination(env: &Env, owner: Address, destination: Address) {
+        owner.require_auth();
+
+        let mut list = storage::whitelist(env, &owner);
+        if let Some(pos) = list.first_index_of(&destination) {
+            list.remove(pos);
+            storage::set_whitelist(env, &owner, &list);
+            events::destination_unlisted(env, &owner, &destination);
+        }
+    }
+
+    /// Turn whitelist mode on or off. Requires `owner` auth.
+    ///
+    /// Turning it on applies at once.  Turning it off is queued behind the
+    /// timelock and returns the change ID.
+    pub fn set_whitelist_mode(env: &Env, owner: Address, enabled: bool) -> Option<u64> {
+        owner.require_auth();
+
+        let current = storage::whitelist_enabled(env, &owner);
+        if current == enabled {
+            return None;
+        }
+        if !enabled {
+            return Some(Self::queue_timelocked(
+                env,
+                &owner,
+                TimelockedChange::DisableWhitelist,
+            ));
+        }
+
+        storage::set_whitelist_enabled(env, &owner, true);
+        events::whitelist_mode_set(env, &owner, true);
+        None
+    }
+
+    /// The owner's whitelisted destinations
+    pub fn get_whitelist(env: &Env, owner: Address) -> Vec<Address> {
+        storage::whitelist(env, &owner)
+    }
+
+    /// Whether the owner's whitelist mode is on
+    pub fn is_whitelist_enabled(env: &Env, owner: Address) -> bool {
+        storage::whitelist_enabled(env, &owner)
+    }
+
+    /// Require at least `interval` seconds between the owner's transactions
+    /// in `asset`. Requires `owner` auth.
+    ///
+    /// A longer interval applies at once.  A shorter one, or `0` to remove
+    /// the cooldown, is queued behind the timelock and returns the change ID.
+    pub fn set_cooldown(env: &Env, owner: Address, asset: Symbol, interval: u64) -> Option<u64> {
+        owner.require_auth();
+
+        let current = storage::get_cooldown(env, &owner, &asset).map_or(0, |c| c.interval);
+        let change = TimelockedChange::SetCooldown(asset, interval);
+        if interval < current {
+            return Some(Self::queue_timelocked(env, &owner, change));
+        }
+        Self::apply_timelocked(env, &owner, change);
+        None
+    }
+
+    /// The owner's cooldown for `asset`, if any
+    pub fn get_cooldown(env: &Env, owner: Address, asset: Symbol) -> Option<Cooldown> {
+        storage::get_cooldown(env, &owner, &asset)
+    }
+
+    /// Flag the owner's transactions in `asset` that would take the day's
+    /// volume above `multiplier` times their trailing daily average, and with
+    /// `require_approval` hold them for an exception. Requires `owner` auth.
+    ///
+    /// A lower multiplier or turning approval on applies at once.  A higher
+    /// multiplier, turning approval off, or `0` to remove the rule is queued
+    /// behind the timelock and returns the change ID.
+    pub fn set_velocity_rule(
+        env: &Env,
+        owner: Address,
+        asset: Symbol,
+        multiplier: u32,
+        require_approval: bool,
+    ) -> Option<u64> {
+        owner.require_auth();
+
+        let looser = storage::get_velocity_rule(env, &owner, &asset).is_some_and(|rule| {
+            multiplier == 0
+                || multiplier > rule.multiplier
+                || (rule.require_approval && !require_approval)
+        });
+        let change = TimelockedChange::SetVelocityRule(asset, multiplier, require_approval);
+        if looser {
+            return Some(Self::queue_timelocked(env, &owner, change));
+        }
+        Self::apply_timelocked(env, &owner, change);
+        None
+    }
+
+    /// The owner's velocity rule for `asset`, if any
+    pub fn get_velocity_rule(env: &Env, owner: Address, asset: Symbol) -> Option<VelocityRule> {
+        storage::get_velocity_rule(env, &owner, &asset)
+    }
+
+    /// Set the usage alert thresholds applied to all of the owner's limits,
+    /// in ascending basis points of each limit's `max_amount`; an empty list
+    /// turns alerts off. Requires `owner` auth.
+    pub fn set_alert_thresholds(
+        env: &Env,
+        owner: Address,
+        thresholds: Vec<u32>,
+    ) -> Result<(), LimitsError> {
+        owner.require_auth();
+
+        let mut previous = 0;
+        for threshold in thresholds.iter() {
+            if threshold <= previous || threshold > 10_000 {
+                return Err(LimitsError::InvalidThreshold);
+            }
+            previous = threshold;
+        }
+
+        storage::set_alert_thresholds(env, &owner, &thresholds);
+        events::alert_thresholds_set(env, &owner, &thresholds);
+        Ok(())
+    }
+
+    /// The owner's usage alert thresholds, in basis points
+    pub fn get_alert_thresholds(env: &Env, owner: Address) -> Vec<u32> {
+        storage::alert_thresholds(env, &owner)
+    }
+
+    /// Apply a queued change once its timelock has passed. Requires `owner`
+    /// auth.
+    pub fn execute_timelocked(
+        env: &Env,
+        owner: Address,
+        change_id: u64,
+    ) -> Result<(), LimitsError> {
+        owner.require_auth();
+
+        let op =
+            storage::get_timelocked(env, &owner, change_id).ok_or(LimitsError::TimelockNotFound)?;
+        if env.ledger().timestamp() < op.executable_at {
+            return Err(LimitsError::TimelockNotElapsed);
+        }
+
+        storage::remove_timelocked(env, &owner, change_id);
+        Self::apply_timelocked(env, &owner, op.change);
+        Ok(())
+    }
+
+    /// Drop a queued change. Requires auth from `caller`, who must be the
+    /// owner or their co-signer.
+    pub fn cancel_timelocked(
+        env: &Env,
+        caller: Address,
+        owner: Address,
+        change_id: u64,
+    ) -> Result<(), LimitsError> {
+        caller.require_auth();
+        if caller != owner && storage::get_co_signer(env, &owner) != Some(caller) {
+            return Err(LimitsError::NotAuthorized);
+        }
+        if storage::get_timelocked(env, &owner, change_id).is_none() {
+            return Err(LimitsError::TimelockNotFound);
+        }
+
+        storage::remove_timelocked(env, &owner, change_id);
+        events::timelock_cancelled(env, &owner, change_id);
+        Ok(())
+    }
+
+    /// Get a queued change
+    pub fn get_timelocked(env: &Env, owner: Address, change_id: u64) -> Option<TimelockedOp> {
+        storage::get_timelocked(env, &owner, change_id)
+    }
+
+    // -----------------------------------------------------------------------
+    // Risk levels
+    // -----------------------------------------------------------------------
+
+    /// Set the default limits installed for owners whose profile is at
+    /// `risk_level`; an empty list removes them. Requires the compliance
+    /// admin's auth.
+    ///
+    /// Owners already at that level receive the defaults the next time
+    /// their profile is set.
+    pub fn set_risk_level_defaults(
+        env: &Env,
+        risk_level: RiskLevel,
+        limits: Vec<TemplateLimit>,
+    ) -> Result<(), LimitsError> {
+        let admin = Self::require_compliance_admin(env)?;
+        if limits.iter().any(|limit| limit.max_amount <= 0) {
+            return Err(LimitsError::InvalidAmount);
+        }
+
+        storage::set_level_defaults(env, &risk_level, &limits);
+        Self::audit(
+            env,
+            &admin,
+            AuditAction::LevelDefaultsSet(risk_level, limits.len()),
+        );
+        Ok(())
+    }
+
+    /// Default limits installed for owners at `risk_level`
+    pub fn get_risk_level_defaults(env: &Env, risk_level: RiskLevel) -> Vec<TemplateLimit> {
+        storage::level_defaults(env, &risk_level)
+    }
+
+    /// Set the deployment-wide default limits, applied to owners with
+    /// neither a limit of their own nor a risk level default for an asset;
+    /// an empty list removes them. Requires the compliance admin's auth.
+    pub fn set_default_limits(env: &Env, limits: Vec<TemplateLimit>) -> Result<(), LimitsError> {
+        let admin = Self::require_compliance_admin(env)?;
+        if limits.iter().any(|limit| limit.max_amount <= 0) {
+            return Err(LimitsError::InvalidAmount);
+        }
+
+        storage::set_default_limits(env, &limits);
+        Self::audit(env, &admin, AuditAction::DefaultLimitsSet(limits.len()));
+        Ok(())
+    }
+
+    /// Deployment-wide default limits
+    pub fn get_default_limits(env: &Env) -> Vec<TemplateLimit> {
+        storage::default_limits(env)
+    }
+
+    /// Limits that would apply to the owner's next transaction in `asset`
+    /// in place of limits of their own, following the resolution order
+    /// described under "Default limits"; empty once they have one
+    pub fn resolve_default_limits(env: &Env, owner: Address, asset: Symbol) -> Vec<TemplateLimit> {
+        Self::resolve_defaults(env, &owner, &asset)
+    }
+
+    // -----------------------------------------------------------------------
+    // Templates
+    // -----------------------------------------------------------------------
+
+    /// Create or replace a risk profile template. Requires the compliance
+    /// admin's auth. Returns the template's new version.
+    ///
+    /// With `propagate`, every subscribed owner is moved to the new version,
+    /// except owners with a co-signer whose settings it would loosen; they
+    /// stay on their current version until they re-apply the template.
+    pub fn set_template(
+        env: &Env,
+        template_id: Symbol,
+        profile: RiskProfileParams,
+        limits: Vec<TemplateLimit>,
+        propagate: bool,
+    ) -> Result<u32, LimitsError> {
+        let admin = Self::require_compliance_admin(env)?;
+        if limits.iter().any(|limit| limit.max_amount <= 0)
+            || profile.max_daily_volume < 0
+            || profile.max_single_transaction < 0
+        {
+            return Err(LimitsError::InvalidAmount);
+        }
+
+        let version = storage::get_template(env, &template_id).map_or(1, |t| t.version + 1);
+        let template = RiskTemplate {
+            id: template_id.clone(),
+            profile,
+            limits,
+            version,
+            updated_at: env.ledger().timestamp(),
+        };
+        storage::set_template(env, &template);
+        Self::audit(
+            env,
+            &admin,
+            AuditAction::TemplateSet(template_id.clone(), version),
+        );
+
+        if propagate {
+            for owner in storage::template_subscribers(env, &template_id).iter() {
+                let gated = storage::get_co_signer(env, &owner).is_some()
+                    && Self::template_loosens(env, &owner, &template);
+                if !gated {
+                    Self::instantiate_template(env, &owner, &template);
+                }
+            }
+        }
+        Ok(version)
+    }
+
+    /// Delete a template. Requires the compliance admin's auth.
+    ///
+    /// Owners keep the profile and limits it gave them but receive no
+    /// further updates.
+    pub fn remove_template(env: &Env, template_id: Symbol) -> Result<(), LimitsError> {
+        let admin = Self::require_compliance_admin(env)?;
+        if storage::get_template(env, &template_id).is_none() {
+            return Err(LimitsError::TemplateNotFound);
+        }
+
+        storage::remove_template(env, &template_id);
+        Self::audit(env, &admin, AuditAction::TemplateRemoved(template_id));
+        Ok(())
+    }
+
+    /// Get a template
+    pub fn get_template(env: &Env, template_id: Symbol) -> Option<RiskTemplate> {
+        storage::get_template(env, &template_id)
+    }
+
+    /// IDs of every template, in creation order
+    pub fn get_template_ids(env: &Env) -> Vec<Symbol> {
+        storage::template_ids(env)
+    }
+
+    /// Apply a template's risk profile and limits to `owner` and subscribe
+    /// them to its updates. Requires `owner` auth. Returns the IDs of the
+    /// limits the template manages for the owner.
+    ///
+    /// Re-applying a template, or switching to another one, updates or
+    /// replaces the limits created by the previous application; limits the
+    /// owner created themselves are left alone.
+    pub fn apply_template(
+        env: &Env,
+        owner: Address,
+        template_id: Symbol,
+    ) -> Result<Vec<u64>, LimitsError> {
+        owner.require_auth();
+
+        let template =
+            storage::get_template(env, &template_id).ok_or(LimitsError::TemplateNotFound)?;
+        if storage::get_co_signer(env, &owner).is_some()
+            && Self::template_loosens(env, &owner, &template)
+        {
+            return Err(LimitsError::CoSignRequired);
+        }
+
+        Ok(Self::instantiate_template(env, &owner, &template))
+    }
+
+    /// Stop receiving template updates. Requires `owner` auth.
+    ///
+    /// Limits created by the template stay in place as ordinary limits.
+    pub fn unsubscribe_template(env: &Env, owner: Address) -> Result<(), LimitsError> {
+        owner.require_auth();
+
+        let sub = storage::get_subscription(env, &owner).ok_or(LimitsError::TemplateNotFound)?;
+        storage::remove_subscription(env, &owner);
+
+        events::template_unsubscribed(env, &owner, &sub.template_id);
+        Ok(())
+    }
+
+    /// The template an owner is subscribed to, if any
+    pub fn get_template_subscription(env: &Env, owner: Address) -> Option<TemplateSubscription> {
+        storage::get_subscription(env, &owner)
+    }
+
+    // -----------------------------------------------------------------------
+    // Compliance admin
+    // -----------------------------------------------------------------------
+
+    /// Hand the compliance admin role to `new_admin`, or renounce it with
+    /// `None`. Requires the current compliance admin's auth.
+    pub fn set_compliance_admin(env: &Env, new_admin: Option<Address>) -> Result<(), LimitsError> {
+        let admin = Self::require_compliance_admin(env)?;
+
+        match &new_admin {
+            Some(next) => env.storage().instance().set(&COMPLIANCE_ADMIN, next),
+            None => env.storage().instance().remove(&COMPLIANCE_ADMIN),
+        }
+
+        Self::audit(env, &admin, AuditAction::AdminChanged(new_admin));
+        Ok(())
+    }
+
+    /// Current compliance admin, if one is configured
+    pub fn get_compliance_admin(env: &Env) -> Option<Address> {
+        env.storage().instance().get(&COMPLIANCE_ADMIN)
+    }
+
+    /// Cap the volume of `asset` the deployment authorizes across all owners
+    /// to `max_amount` per rolling `time_window`; `0` halts the asset.
+    /// Requires the compliance admin's auth.
+    ///
+    /// Meant as a circuit breaker during incidents: exceptions do not lift
+    /// it.  Changing an existing global limit keeps its recorded usage.
+    pub fn set_global_limit(
+        env: &Env,
+        asset: Symbol,
+        max_amount: i128,
+        time_window: u64,
+    ) -> Result<(), LimitsError> {
+        let admin = Self::require_compliance_admin(env)?;
+        if max_amount < 0 || time_window == 0 {
+            return Err(LimitsError::InvalidAmount);
+        }
+
+        let usage = match storage::get_global_limit(env, &asset) {
+            Some(global) if global.time_window == time_window => global.usage,
+            _ => Vec::new(env),
+        };
+        storage::set_global_limit(
+            env,
+            &GlobalLimit {
+                asset: asset.clone(),
+                max_amount,
+                time_window,
+                usage,
+            },
+        );
+        Self::audit(
+            env,
+            &admin,
+            AuditAction::GlobalLimitSet(asset, max_amount, time_window),
+        );
+        Ok(())
+    }
+
+    /// Remove the global limit on `asset`. Requires the compliance admin's
+    /// auth.
+    pub fn remove_global_limit(env: &Env, asset: Symbol) -> Result<(), LimitsError> {
+        let admin = Self::require_compliance_admin(env)?;
+        if storage::get_global_limit(env, &asset).is_none() {
+            return Err(LimitsError::LimitNotFound);
+        }
+
+        storage::remove_global_limit(env, &asset);
+        Self::audit(env, &admin, AuditAction::GlobalLimitRemoved(asset));
+        Ok(())
+    }
+
+    /// Global limit on `asset`, if any
+    pub fn get_global_limit(env: &Env, asset: Symbol) -> Option<GlobalLimit> {
+        storage::get_global_limit(env, &asset)
+    }
+
+    /// Deployment-wide volume of `asset` inside its global limit's window
+    pub fn get_global_usage(env: &Env, asset: Symbol) -> i128 {
+        storage::get_global_limit(env, &asset).map_or(0, |global| {
+            window::global_usage(&global, env.ledger().timestamp())
+        })
+    }
+
+    /// Freeze an owner's account so every transaction check fails until it
+    /// is unfrozen. Requires auth from `caller`, who must be the owner or the
+    /// compliance admin.
+    ///
+    /// The admin may re-freeze an already frozen account to take over the
+    /// freeze; anyone else gets [`LimitsError::AccountFrozen`].
+    pub fn freeze_account(
+        env: &Env,
+        caller: Address,
+        owner: Address,
+        reason: String,
+    ) -> Result<(), LimitsError> {
+        caller.require_auth();
+        let is_admin = Self::is_compliance_admin(env, &caller);
+        if !is_admin && caller != owner {
+            return Err(LimitsError::NotAuthorized);
+        }
+        if !is_admin && storage::is_frozen(env, &owner) {
+            return Err(LimitsError::AccountFrozen);
+        }
+
+        storage::set_freeze(
+            env,
+            &owner,
+            &FreezeInfo {
+                reason: reason.clone(),
+                frozen_by: caller.clone(),
+                frozen_at: env.ledger().timestamp(),
+            },
+        );
+
+        events::account_frozen(env, &owner, &caller, &reason);
+        if is_admin {
+            Self::audit(env, &caller, AuditAction::AccountFrozen(owner, reason));
+        }
+        Ok(())
+    }
+
+    /// Lift a freeze. Requires auth from `caller`: the compliance admin can
+    /// lift any freeze, the owner only one they placed themselves.
+    pub fn unfreeze_account(env: &Env, caller: Address, owner: Address) -> Result<(), LimitsError> {
+        caller.require_auth();
+        let freeze = storage::get_freeze(env, &owner).ok_or(LimitsError::AccountNotFrozen)?;
+        let is_admin = Self::is_compliance_admin(env, &caller);
+        if !is_admin && (caller != owner || freeze.frozen_by != owner) {
+            return Err(LimitsError::NotAuthorized);
+        }
+
+        storage::remove_freeze(env, &owner);
+
+        events::account_unfrozen(env, &owner, &caller);
+        if is_admin {
+            Self::audit(env, &caller, AuditAction::AccountUnfrozen(owner));
+        }
+        Ok(())
+    }
+
+    /// Whether an owner's account is frozen
+    pub fn is_account_frozen(env: &Env, owner: Address) -> bool {
+        storage::is_frozen(env, &owner)
+    }
+
+    /// Reason, author and time of an owner's freeze, if frozen
+    pub fn get_freeze_info(env: &Env, owner: Address) -> Option<FreezeInfo> {
+        storage::get_freeze(env, &owner)
+    }
+
+    /// Lower an owner's limit below its current maximum without the owner's
+    /// consent. Requires compliance admin auth.
+    ///
+    /// Returns [`LimitsError::InvalidAmount`] unless
+    /// `0 < new_max_amount < max_amount`.
+    pub fn force_lower_limit(
+        env: &Env,
+        owner: Address,
+        limit_id: u64,
+        new_max_amount: i128,
+    ) -> Result<(), LimitsError> {
+        let admin = Self::require_compliance_admin(env)?;
+
+        let mut limit =
+            storage::get_limit(env, &owner, limit_id).ok_or(LimitsError::LimitNotFound)?;
+        if new_max_amount <= 0 || new_max_amount >= limit.max_amount {
+            return Err(LimitsError::InvalidAmount);
+        }
+
+        let old_max_amount = limit.max_amount;
+        limit.max_amount = new_max_amount;
+        storage::set_limit(env, &limit);
+
+        events::limit_updated(env, &limit);
+        Self::audit(
+            env,
+            &admin,
+            AuditAction::LimitLowered(owner, limit_id, old_max_amount, new_max_amount),
+        );
+        Ok(())
+    }
+
+    /// Approve a single transaction of up to `max_amount` of `asset` that
+    /// would otherwise exceed the owner's limits. Requires compliance admin
+    /// auth.
+    ///
+    /// The exception is consumed by the next [`Self::authorize_or_fail`] call
+    /// that needs it and lapses at `expires_at`.  Asset lists, account
+    /// freezes and global limits still apply.  Approving again replaces any pending exception.
+    pub fn approve_exception(
+        env: &Env,
+        owner: Address,
+        asset: Symbol,
+        max_amount: i128,
+        expires_at: u64,
+    ) -> Result<(), LimitsError> {
+        let admin = Self::require_compliance_admin(env)?;
+
+        if max_amount <= 0 {
+            return Err(LimitsError::InvalidAmount);
+        }
+        let now = env.ledger().timestamp();
+        if expires_at <= now {
+            return Err(LimitsError::InvalidExpiry);
+        }
+
+        storage::set_exception(
+            env,
+            &LimitException {
+                owner: owner.clone(),
+                asset: asset.clone(),
+                max_amount,
+                expires_at,
+                approved_by: admin.clone(),
+                approved_at: now,
+            },
+        );
+
+        Self::audit(
+            env,
+            &admin,
+            AuditAction::ExceptionApproved(owner, asset, max_amount, expires_at),
+        );
+        Ok(())
+    }
+
+    /// Withdraw a pending exception. Requires compliance admin auth.
+    pub fn revoke_exception(env: &Env, owner: Address, asset: Symbol) -> Result<(), LimitsError> {
+        let admin = Self::require_compliance_admin(env)?;
+
+        if storage::get_exception(env, &owner, &asset).is_none() {
+            return Err(LimitsError::ExceptionNotFound);
+        }
+        storage::remove_exception(env, &owner, &asset);
+
+        Self::audit(env, &admin, AuditAction::ExceptionRevoked(owner, asset));
+        Ok(())
+    }
+
+    /// Pending exception for an owner and asset, if any
+    pub fn get_exception(env: &Env, owner: Address, asset: Symbol) -> Option<LimitException> {
+        storage::get_exception(env, &owner, &asset)
+    }
+
+    /// Get a page of the audit log, oldest first.
+    ///
+    /// `limit` is capped at [`MAX_PAGE_SIZE`].
+    pub fn get_audit_log(env: &Env, offset: u64, limit: u32) -> Vec<AuditEntry> {
+        let end = offset
+            .saturating_add(limit.min(MAX_PAGE_SIZE) as u64)
+            .min(storage::audit_count(env));
+
+        let mut page = Vec::new(env);
+        for id in (offset + 1)..=end {
+            if let Some(entry) = storage::get_audit(env, id) {
+                page.push_back(entry);
+            }
+        }
+        page
+    }
+
+    /// Number of entries in the audit log
+    pub fn get_audit_log_count(env: &Env) -> u64 {
+        storage::audit_count(env)
+    }
+
+    /// Helper function to require the compliance admin's auth
+    fn require_compliance_admin(env: &Env) -> Result<Address, LimitsError> {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&COMPLIANCE_ADMIN)
+            .ok_or(LimitsError::NotAuthorized)?;
+        admin.require_auth();
+        Ok(admin)
+    }
+
+    /// Helper function to check whether `caller` is the compliance admin
+    fn is_compliance_admin(env: &Env, caller: &Address) -> bool {
+        let admin: Option<Address> = env.storage().instance().get(&COMPLIANCE_ADMIN);
+        admin.as_ref() == Some(caller)
+    }
+
+    /// Helper function to append an admin action to the audit log
+    fn audit(env: &Env, admin: &Address, action: AuditAction) {
+        let entry = AuditEntry {
+            id: storage::audit_count(env) + 1,
+            admin: admin.clone(),
+            action,
+            timestamp: env.ledger().timestamp(),
+            ledger: env.ledger().sequence(),
+        };
+        storage::append_audit(env, &entry);
+        events::audit_recorded(env, &entry);
+    }
+
+    /// Helper function to run every transaction check.
+    ///
+    /// Returns whether a pending exception was needed to pass the limits.
+    #[allow(clippy::too_many_arguments)]
+    fn validate(
+        env: &Env,
+        owner: &Address,
+        asset: &Symbol,
+        amount: i128,
+        destination: &Option<Address>,
+        counter_asset: &Option<Symbol>,
+        tags: &Tags,
+        confirmed: bool,
+    ) -> Result<bool, LimitsError> {
+        if storage::is_frozen(env, owner) {
+            return Err(LimitsError::AccountFrozen);
+        }
+        if !Self::is_asset_allowed(env, owner.clone(), asset.clone()) {
+            return Err(LimitsError::AssetBlocked);
+        }
+        match counter_asset {
+            Some(counter_asset) => {
+                if !Self::is_asset_allowed(env, owner.clone(), counter_asset.clone()) {
+                    return Err(LimitsError::AssetBlocked);
+                }
+            }
+            None => {
+                if !Self::destination_allowed(env, owner, destination) {
+                    return Err(LimitsError::DestinationNotAllowed);
+                }
+            }
+        }
+        if !Self::cooldown_elapsed(env, owner, asset) {
+            return Err(LimitsError::CooldownActive);
+        }
+        if !Self::within_global_limit(env, asset, amount) {
+            return Err(LimitsError::GlobalLimitExceeded);
+        }
+        let needs_exception = Self::check_limits_or_exception(
+            env,
+            owner,
+            asset,
+            amount,
+            destination,
+            counter_asset,
+            tags,
+        )?;
+        if !confirmed
+            && Self::crosses_soft_limit(
+                env,
+                owner,
+                asset,
+                amount,
+                destination,
+                counter_asset,
+                tags,
+            )?
+        {
+            return Err(LimitsError::SoftLimitUnconfirmed);
+        }
+        Ok(needs_exception)
+    }
+
+    /// Helper function to check whether `amount` would take usage above a
+    /// soft limit that requires confirmation
+    fn crosses_soft_limit(
+        env: &Env,
+        owner: &Address,
+        asset: &Symbol,
+        amount: i128,
+        destination: &Option<Address>,
+        counter_asset: &Option<Symbol>,
+        tags: &Tags,
+    ) -> Result<bool, LimitsError> {
+        let now = env.ledger().timestamp();
+        for holder in Self::limit_holders(env, owner).iter() {
+            for limit in storage::owner_limits(env, &holder).iter() {
+                if !Self::covers_asset(&limit, asset)
+                    || !limit.is_active
+                    || !Self::counts_transfer(&limit, destination, counter_asset, tags)
+                {
+                    continue;
+                }
+                let Some(soft) = storage::get_soft_limit(env, &holder, limit.id) else {
+                    continue;
+                };
+                if !soft.require_confirmation {
+                    continue;
+                }
+                let usage = match (&limit.scope, destination) {
+                    (LimitScope::PerDestination, Some(dest)) => {
+                        window::destination_usage(env, &limit, dest, now)
+                    }
+                    _ => Self::effective_usage(env, &limit, now),
+                };
+                let counted = Self::counted_amount(env, &limit, asset, amount)?;
+                if usage.checked_add(counted).ok_or(LimitsError::Overflow)? > soft.amount {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Helper function shared by the authorization hooks: validate, consume
+    /// any exception needed, then record the transaction
+    #[allow(clippy::too_many_arguments)]
+    fn authorize(
+        env: &Env,
+        owner: &Address,
+        asset: &Symbol,
+        amount: i128,
+        destination: Option<Address>,
+        counter_asset: Option<Symbol>,
+        tags: Tags,
+        confirmed: bool,
+    ) -> Result<u64, LimitsError> {
+        if amount <= 0 {
+            return Err(LimitsError::InvalidAmount);
+        }
+
+        if Self::validate(
+            env,
+            owner,
+            asset,
+            amount,
+            &destination,
+            &counter_asset,
+            &tags,
+            confirmed,
+        )? {
+            // Exceptions are single-use
+            storage::remove_exception(env, owner, asset);
+            events::exception_used(env, owner, asset, amount);
+        }
+
+        let receipt = env
+            .crypto()
+            .sha256(
+                &(
+                    owner.clone(),
+                    asset.clone(),
+                    amount,
+                    destination.clone(),
+                    counter_asset.clone(),
+                    env.ledger().sequence(),
+                )
+                    .to_xdr(env),
+            )
+            .into();
+        Self::store_transaction(
+            env,
+            owner,
+            asset,
+            amount,
+            destination,
+            counter_asset,
+            tags,
+            receipt,
+        )
+    }
+
+    /// Helper function to check limits, falling back to a pending exception.
+    ///
+    /// Returns whether the exception was needed.
+    fn check_limits_or_exception(
+        env: &Env,
+        owner: &Address,
+        asset: &Symbol,
+        amount: i128,
+        destination: &Option<Address>,
+        counter_asset: &Option<Symbol>,
+        tags: &Tags,
+    ) -> Result<bool, LimitsError> {
+        if amount < 0 {
+            return Err(LimitsError::InvalidAmount);
+        }
+        let mut breach = None;
+        for holder in Self::limit_holders(env, owner).iter() {
+            breach = Self::check_limits(
+                env,
+                &holder,
+                asset,
+                amount,
+                destination,
+                counter_asset,
+                tags,
+            )?;
+            if breach.is_some() {
+                break;
+            }
+        }
+        if breach.is_none() {
+            // Defaults are not yet installed, so nothing counts against them
+            breach = Self::resolve_defaults(env, owner, asset)
+                .iter()
+                .find(|spec| amount > spec.max_amount)
+                .map(|spec| (Self::new_limit(env, 0, owner, spec), amount));
+        }
+        let over = breach.is_some() || Self::exceeds_risk_profile(env, owner, asset, amount);
+        let held = Self::velocity_anomaly(env, owner, asset, amount)
+            .is_some_and(|(rule, ..)| rule.require_approval);
+        if !over && !held {
+            return Ok(false);
+        }
+        match storage::get_exception(env, owner, asset) {
+            Some(exception)
+                if amount <= exception.max_amount
+                    && env.ledger().timestamp() < exception.expires_at =>
+            {
+                Ok(true)
+            }
+            _ => {
+                if let Some((limit, usage)) = breach {
+                    events::limit_blocked(env, &limit, asset, amount, usage);
+                }
+                if over {
+                    Err(LimitsError::LimitExceeded)
+                } else {
+                    Err(LimitsError::VelocityAnomaly)
+                }
+            }
+        }
+    }
+
+    /// Helper function to store a new limit with default settings and
+    /// return its ID
+    fn insert_limit(
+        env: &Env,
+        owner: &Address,
+        limit_type: LimitType,
+        asset: Symbol,
+        max_amount: i128,
+        time_window: u64,
+    ) -> u64 {
+        let instance = env.storage().instance();
+        let id: u64 = instance.get(&NEXT_LIMIT_ID).unwrap_or(1);
+        instance.set(&NEXT_LIMIT_ID, &(id + 1));
+
+        let spec = TemplateLimit {
+            limit_type,
+            asset,
+            max_amount,
+            time_window,
+        };
+        let limit = Self::new_limit(env, id, owner, spec);
+
+        storage::set_limit(env, &limit);
+        storage::index_limit(env, owner, id);
+
+        events::limit_created(env, &limit);
+        id
+    }
+
+    /// Helper function to build a limit with default settings from `spec`
+    fn new_limit(env: &Env, id: u64, owner: &Address, spec: TemplateLimit) -> SecurityLimit {
+        SecurityLimit {
+            id,
+            owner: owner.clone(),
+            limit_type: spec.limit_type,
+            asset: spec.asset,
+            max_amount: spec.max_amount,
+            time_window: spec.time_window,
+            current_usage: 0,
+            last_reset: env.ledger().timestamp(),
+            is_active: true,
+            created_at: env.ledger().timestamp(),
+            window_mode: WindowMode::Fixed,
+            scope: LimitScope::Aggregate,
+            pair_asset: None,
+            denomination: Denomination::Asset,
+        }
+    }
+
+    /// Helper function to reject out-of-range oracle settings
+    fn validate_valuation(valuation: &Valuation) -> Result<(), LimitsError> {
+        if valuation.decimals > MAX_ORACLE_DECIMALS || valuation.max_age == 0 {
+            return Err(LimitsError::InvalidValuation);
+        }
+        Ok(())
+    }
+
+    /// Helper function to write an updated limit
+    fn apply_limit_update(
+        env: &Env,
+        mut limit: SecurityLimit,
+        max_amount: i128,
+        time_window: u64,
+        is_active: bool,
+    ) {
+        limit.max_amount = max_amount;
+        limit.time_window = time_window;
+        limit.is_active = is_active;
+
+        storage::set_limit(env, &limit);
+
+        events::limit_updated(env, &limit);
+    }
+
+    /// Helper function to tell whether a new `time_window` makes a limit's
+    /// fixed windows shorter, letting its budget come back sooner
+    fn shortens_window(limit: &SecurityLimit, time_window: u64) -> bool {
+        let updated = SecurityLimit {
+            time_window,
+            ..limit.clone()
+        };
+        match (window::fixed_period(limit), window::fixed_period(&updated)) {
+            (Some(current), Some(new)) => new < current,
+            _ => false,
+        }
+    }
+
+    /// Helper function to check a window mode fits a limit: schedules need a
+    /// window and a UTC offset of at most [`MAX_UTC_OFFSET`]
+    fn check_window_mode(
+        limit: &SecurityLimit,
+        window_mode: &WindowMode,
+    ) -> Result<(), LimitsError> {
+        if let WindowMode::Scheduled(schedule) = window_mode {
+            if schedule.utc_offset.unsigned_abs() > MAX_UTC_OFFSET
+                || window::window_length(limit) == 0
+            {
+                return Err(LimitsError::InvalidSchedule);
+            }
+        }
+        Ok(())
+    }
+
+    /// Helper function to switch a limit's window mode, restarting its usage
+    fn apply_window_mode(env: &Env, mut limit: SecurityLimit, window_mode: WindowMode) {
+        limit.window_mode = window_mode;
+        Self::restart_usage(env, &mut limit);
+
+        events::window_mode_set(env, &limit);
+    }
+
+    /// Helper function to change a limit's scope, restarting its usage
+    fn apply_scope(env: &Env, mut limit: SecurityLimit, scope: LimitScope) {
+        limit.scope = scope;
+        Self::restart_usage(env, &mut limit);
+
+        events::scope_set(env, &limit);
+    }
+
+    /// Helper function to restrict a limit to one swap pair, restarting its
+    /// usage
+    fn apply_pair(env: &Env, mut limit: SecurityLimit, pair_asset: Option<Symbol>) {
+        limit.pair_asset = pair_asset;
+        Self::restart_usage(env, &mut limit);
+
+        events::pair_set(env, &limit);
+    }
+
+    /// Helper function to change what a limit is denominated in, restarting
+    /// its usage
+    fn apply_denomination(env: &Env, mut limit: SecurityLimit, denomination: Denomination) {
+        limit.denomination = denomination;
+        Self::restart_usage(env, &mut limit);
+
+        events::denomination_set(env, &limit);
+    }
+
+    /// Helper function to zero a limit's usage from now and store it
+    fn restart_usage(env: &Env, limit: &mut SecurityLimit) {
+        limit.current_usage = 0;
+        limit.last_reset = env.ledger().timestamp();
+
+        storage::set_limit(env, limit);
+        storage::clear_usage(env, &limit.owner, limit.id);
+    }
+
+    /// Helper function to check burst allowance settings: at most 100% extra,
+    /// refilled every one or more windows of a limit that has windows
+    fn check_burst_allowance(
+        limit: &SecurityLimit,
+        extra_bps: u32,
+        refill_windows: u32,
+    ) -> Result<(), LimitsError> {
+        if extra_bps == 0 {
+            return Ok(());
+        }
+        if extra_bps > 10_000 || refill_windows == 0 || window::fixed_period(limit).is_none() {
+            return Err(LimitsError::InvalidAmount);
+        }
+        Ok(())
+    }
+
+    /// Helper function to set or remove a burst allowance, keeping the budget
+    /// already drawn in the current refill period
+    fn apply_burst_allowance(
+        env: &Env,
+        owner: &Address,
+        limit_id: u64,
+        extra_bps: u32,
+        refill_windows: u32,
+    ) {
+        let burst = (extra_bps > 0).then(|| match storage::get_burst(env, owner, limit_id) {
+            Some(burst) => BurstAllowance {
+                extra_bps,
+                refill_windows,
+                ..burst
+            },
+            None => BurstAllowance {
+                extra_bps,
+                refill_windows,
+                used: 0,
+                refilled_at: env.ledger().timestamp(),
+            },
+        });
+        storage::set_burst(env, owner, limit_id, &burst);
+        events::burst_allowance_set(env, owner, limit_id, extra_bps, refill_windows);
+    }
+
+    /// Helper function to check a soft limit sits below the hard limit
+    fn check_soft_limit(limit: &SecurityLimit, amount: i128) -> Result<(), LimitsError> {
+        if amount < 0 || amount >= limit.max_amount {
+            return Err(LimitsError::InvalidAmount);
+        }
+        Ok(())
+    }
+
+    /// Helper function to set a soft limit, or remove it with an `amount`
+    /// of `0`
+    fn apply_soft_limit(
+        env: &Env,
+        owner: &Address,
+        limit_id: u64,
+        amount: i128,
+        require_confirmation: bool,
+    ) {
+        let soft = (amount > 0).then_some(SoftLimit {
+            amount,
+            require_confirmation,
+        });
+        storage::set_soft_limit(env, owner, limit_id, &soft);
+        events::soft_limit_set(env, owner, limit_id, &soft);
+    }
+
+    /// Helper function to remove a limit with its index entry and counters
+    fn apply_limit_delete(env: &Env, owner: &Address, limit_id: u64) {
+        storage::remove_limit(env, owner, limit_id);
+        storage::unindex_limit(env, owner, limit_id);
+        storage::clear_usage(env, owner, limit_id);
+        storage::set_soft_limit(env, owner, limit_id, &None);
+        storage::set_burst(env, owner, limit_id, &None);
+
+        events::limit_deleted(env, owner, limit_id);
+    }
+
+    /// Helper function to read every risk profile
+    fn load_profiles(env: &Env) -> Map<Address, RiskProfile> {
+        env.storage()
+            .instance()
+            .get(&RISK_PROFILES)
+            .unwrap_or(Map::new(env))
+    }
+
+    /// Helper function to store a risk profile, keeping its creation time
+    fn apply_risk_profile(env: &Env, owner: &Address, params: RiskProfileParams) {
+        let mut profiles = Self::load_profiles(env);
+
+        let profile = RiskProfile {
+            owner: owner.clone(),
+            risk_level: params.risk_level,
+            max_daily_volume: params.max_daily_volume,
+            max_single_transaction: params.max_single_transaction,
+            allowed_assets: params.allowed_assets,
+            blacklisted_assets: params.blacklisted_assets,
+            created_at: profiles
+                .get(owner.clone())
+                .map(|p| p.created_at)
+                .unwrap_or(env.ledger().timestamp()),
+            updated_at: env.ledger().timestamp(),
+        };
+
+        profiles.set(owner.clone(), profile.clone());
+        env.storage().instance().set(&RISK_PROFILES, &profiles);
+
+        events::risk_profile_set(env, owner, &profile.risk_level);
+        Self::install_level_defaults(env, owner, &profile.risk_level);
+    }
+
+    /// Helper function to install a risk level's default limits: a default
+    /// without a matching plain limit is created, and matching active limits
+    /// above the default are lowered to it
+    fn install_level_defaults(env: &Env, owner: &Address, risk_level: &RiskLevel) {
+        let defaults = storage::level_defaults(env, risk_level);
+        if defaults.is_empty() {
+            return;
+        }
+        let limits = storage::owner_limits(env, owner);
+        for spec in defaults.iter() {
+            let mut covered = false;
+            for limit in limits.iter() {
+                let matches = limit.is_active
+                    && limit.asset == spec.asset
+                    && limit.limit_type == spec.limit_type
+                    && limit.scope == LimitScope::Aggregate
+                    && limit.pair_asset.is_none()
+                    && limit.denomination == Denomination::Asset;
+                if !matches {
+                    continue;
+                }
+                covered = true;
+                if limit.max_amount > spec.max_amount {
+                    let time_window = limit.time_window;
+                    Self::apply_limit_update(env, limit, spec.max_amount, time_window, true);
+                }
+            }
+            if !covered {
+                Self::insert_limit(
+                    env,
+                    owner,
+                    spec.limit_type,
+                    spec.asset,
+                    spec.max_amount,
+                    spec.time_window,
+                );
+            }
+        }
+    }
+
+    /// Helper function to resolve the default limits for the owner's
+    /// transactions in `asset`: none if they have a limit of their own on
+    /// it, else their risk level's defaults for it, else the global ones
+    fn resolve_defaults(env: &Env, owner: &Address, asset: &Symbol) -> Vec<TemplateLimit> {
+        let for_asset = |defaults: Vec<TemplateLimit>| {
+            let mut matching = Vec::new(env);
+            for spec in defaults.iter() {
+                if spec.asset == *asset {
+                    matching.push_back(spec);
+                }
+            }
+            matching
+        };
+
+        let has_own = storage::owner_limits(env, owner)
+            .iter()
+            .any(|limit| limit.asset == *asset && limit.denomination == Denomination::Asset);
+        if has_own {
+            return Vec::new(env);
+        }
+        if let Some(profile) = Self::load_profiles(env).get(owner.clone()) {
+            let level = for_asset(storage::level_defaults(env, &profile.risk_level));
+            if !level.is_empty() {
+                return level;
+            }
+        }
+        for_asset(storage::default_limits(env))
+    }
+
+    /// Helper function to check `amount` of `asset` against the owner's risk
+    /// profile caps
+    fn exceeds_risk_profile(env: &Env, owner: &Address, asset: &Symbol, amount: i128) -> bool {
+        let Some(profile) = Self::load_profiles(env).get(owner.clone()) else {
+            return false;
+        };
+        if profile.max_single_transaction > 0 && amount > profile.max_single_transaction {
+            return true;
+        }
+        profile.max_daily_volume > 0
+            && stats::usage(env, owner, asset)
+                .today_volume
+                .saturating_add(amount)
+                > profile.max_daily_volume
+    }
+
+    /// Helper function to list the addresses whose limits apply to the
+    /// owner's transactions: the owner, then their group if any
+    fn limit_holders(env: &Env, owner: &Address) -> Vec<Address> {
+        let mut holders = Vec::from_array(env, [owner.clone()]);
+        if let Some(group) = storage::group_of(env, owner) {
+            holders.push_back(group);
+        }
+        holders
+    }
+
+    /// Helper function to apply the owner's velocity rule for `asset` to
+    /// `amount`, returning the rule, today's volume and the baseline if the
+    /// transaction is anomalous
+    fn velocity_anomaly(
+        env: &Env,
+        owner: &Address,
+        asset: &Symbol,
+        amount: i128,
+    ) -> Option<(VelocityRule, i128, i128)> {
+        let rule = storage::get_velocity_rule(env, owner, asset)?;
+        let (today, baseline) = stats::velocity(env, owner, asset);
+        let anomalous = baseline > 0
+            && today.saturating_add(amount) > baseline.saturating_mul(i128::from(rule.multiplier));
+        anomalous.then_some((rule, today, baseline))
+    }
+
+    /// Helper function to check that `manager` may set a `max_amount` on the
+    /// owner's limits for `asset`
+    fn check_manager_cap(
+        env: &Env,
+        owner: &Address,
+        manager: &Address,
+        asset: &Symbol,
+        max_amount: i128,
+    ) -> Result<(), LimitsError> {
+        let grant = storage::get_manager(env, owner, manager).ok_or(LimitsError::NotAuthorized)?;
+        match grant.caps.get(asset.clone()) {
+            Some(cap) if max_amount <= cap => Ok(()),
+            Some(_) => Err(LimitsError::ManagerCapExceeded),
+            None => Err(LimitsError::NotAuthorized),
+        }
+    }
+
+    /// Helper function to queue a change behind the timelock and return its ID
+    fn queue_timelocked(env: &Env, owner: &Address, change: TimelockedChange) -> u64 {
+        let instance = env.storage().instance();
+        let id: u64 = instance.get(&NEXT_TIMELOCK_ID).unwrap_or(1);
+        instance.set(&NEXT_TIMELOCK_ID, &(id + 1));
+
+        let now = env.ledger().timestamp();
+        let op = TimelockedOp {
+            id,
+            owner: owner.clone(),
+            change,
+            queued_at: now,
+            executable_at: now + TIMELOCK_DELAY,
+        };
+        storage::set_timelocked(env, &op);
+
+        events::change_queued(env, &op);
+        id
+    }
+
+    /// Helper function to apply a timelocked change
+    fn apply_timelocked(env: &Env, owner: &Address, change: TimelockedChange) {
+        match change {
+            TimelockedChange::AddDestination(destination) => {
+                let mut list = storage::whitelist(env, owner);
+                if !list.contains(&destination) {
+                    list.push_back(destination.clone());
+                    storage::set_whitelist(env, owner, &list);
+                }
+                events::destination_whitelisted(env, owner, &destination);
+            }
+            TimelockedChange::DisableWhitelist => {
+                storage::set_whitelist_enabled(env, owner, false);
+                events::whitelist_mode_set(env, owner, false);
+            }
+            TimelockedChange::SetCooldown(asset, interval) => {
+                match (interval, storage::get_cooldown(env, owner, &asset)) {
+                    (0, _) => storage::remove_cooldown(env, owner, &asset),
+                    (_, Some(mut cooldown)) => {
+                        cooldown.interval = interval;
+                        storage::set_cooldown(env, owner, &asset, &cooldown);
+                    }
+                    (_, None) => storage::set_cooldown(
+                        env,
+                        owner,
+                        &asset,
+                        &Cooldown {
+                            interval,
+                            last_transaction: None,
+                        },
+                    ),
+                }
+                events::cooldown_set(env, owner, &asset, interval);
+            }
+            TimelockedChange::SetVelocityRule(asset, multiplier, require_approval) => {
+                if multiplier == 0 {
+                    storage::remove_velocity_rule(env, owner, &asset);
+                } else {
+                    storage::set_velocity_rule(
+                        env,
+                        owner,
+                        &asset,
+                        &VelocityRule {
+                            multiplier,
+                            require_approval,
+                        },
+                    );
+                }
+                events::velocity_rule_set(env, owner, &asset, multiplier, require_approval);
+            }
+        }
+    }
+
+    /// Helper function to check `amount` of `asset` against its global limit,
+    /// if any
+    fn within_global_limit(env: &Env, asset: &Symbol, amount: i128) -> bool {
+        match storage::get_global_limit(env, asset) {
+            Some(global) => window::global_usage(&global, env.ledger().timestamp())
+                .checked_add(amount)
+                .is_some_and(|usage| usage <= global.max_amount),
+            None => true,
+        }
+    }
+
+    /// Helper function to check whether the owner's cooldown for `asset`, if
+    /// any, has passed since their last transaction
+    fn cooldown_elapsed(env: &Env, owner: &Address, asset: &Symbol) -> bool {
+        match storage::get_cooldown(env, owner, asset).and_then(|c| {
+            c.last_transaction
+                .map(|last| last.saturating_add(c.interval))
+        }) {
+            Some(next_allowed) => env.ledger().timestamp() >= next_allowed,
+            None => true,
+        }
+    }
+
+    /// Helper function to check a transfer's destination against the
+    /// owner's whitelist, if whitelist mode is on
+    fn destination_allowed(env: &Env, owner: &Address, destination: &Option<Address>) -> bool {
+        if !storage::whitelist_enabled(env, owner) {
+            return true;
+        }
+        match destination {
+            Some(destination) => storage::whitelist(env, owner).contains(destination),
+            None => false,
+        }
+    }
+
+    /// Helper function to bring an owner's profile and template-managed
+    /// limits in line with `template`.
+    ///
+    /// A previously created limit is updated in place when the template's
+    /// limit at the same position has the same asset and type, so its usage
+    /// carries over; any other previous limit is deleted.
+    fn instantiate_template(env: &Env, owner: &Address, template: &RiskTemplate) -> Vec<u64> {
+        let previous = storage::get_subscription(env, owner)
+            .map(|sub| sub.limit_ids)
+            .unwrap_or(Vec::new(env));
+
+        let mut limit_ids = Vec::new(env);
+        for (position, spec) in template.limits.iter().enumerate() {
+            let existing = previous
+                .get(position as u32)
+                .and_then(|id| storage::get_limit(env, owner, id))
+                .filter(|limit| limit.asset == spec.asset && limit.limit_type == spec.limit_type);
+            let id = match existing {
+                Some(limit) => {
+                    let id = limit.id;
+                    Self::apply_limit_update(env, limit, spec.max_amount, spec.time_window, true);
+                    id
+                }
+                None => Self::insert_limit(
+                    env,
+                    owner,
+                    spec.limit_type,
+                    spec.asset,
+                    spec.max_amount,
+                    spec.time_window,
+                ),
+            };
+            limit_ids.push_back(id);
+        }
+        for id in previous.iter() {
+            if !limit_ids.contains(id) && storage::get_limit(env, owner, id).is_some() {
+                Self::apply_limit_delete(env, owner, id);
+            }
+        }
+
+        Self::apply_risk_profile(env, owner, template.profile.clone());
+        storage::set_subscription(
+            env,
+            owner,
+            &TemplateSubscription {
+                template_id: template.id.clone(),
+                version: template.version,
+                limit_ids: limit_ids.clone(),
+            },
+        );
+
+        events::template_applied(env, owner, &template.id, template.version);
+        limit_ids
+    }
+
+    /// Helper function to check whether applying `template` would loosen the
+    /// owner's risk profile, or raise or delete an active limit created by
+    /// the owner's previous template
+    fn template_loosens(env: &Env, owner: &Address, template: &RiskTemplate) -> bool {
+        if Self::profile_loosens(env, owner, &template.profile) {
+            return true;
+        }
+        let previous = match storage::get_subscription(env, owner) {
+            Some(sub) => sub.limit_ids,
+            None => return false,
+        };
+
+        previous.iter().enumerate().any(|(position, id)| {
+            let limit = match storage::get_limit(env, owner, id) {
+                Some(limit) if limit.is_active => limit,
+                _ => return false,
+            };
+            match template.limits.get(position as u32) {
+                Some(spec) if spec.asset == limit.asset && spec.limit_type == limit.limit_type => {
+                    spec.max_amount > limit.max_amount
+                }
+                _ => true,
+            }
+        })
+    }
+
+    /// Helper function to check whether `params` would loosen the owner's
+    /// current profile: raise or lift (set to `0`) either cap, allow an asset
+    /// the allow-list excludes, or drop an asset from the blacklist
+    fn profile_loosens(env: &Env, owner: &Address, params: &RiskProfileParams) -> bool {
+        let Some(profile) = Self::get_risk_profile(env, owner.clone()) else {
+            return false;
+        };
+        let raises = |current: i128, new: i128| current > 0 && (new == 0 || new > current);
+        // An empty allow-list allows every asset
+        let widens_allowed = !profile.allowed_assets.is_empty()
+            && (params.allowed_assets.is_empty()
+                || params
+                    .allowed_assets
+                    .iter()
+                    .any(|asset| !profile.allowed_assets.contains(&asset)));
+
+        raises(profile.max_daily_volume, params.max_daily_volume)
+            || raises(
+                profile.max_single_transaction,
+                params.max_single_transaction,
+            )
+            || widens_allowed
+            || profile
+                .blacklisted_assets
+                .iter()
+                .any(|asset| !params.blacklisted_assets.contains(&asset))
+    }
+
+    /// Helper function to read the owner's allow-list, empty without a profile
+    fn allowed_assets(env: &Env, owner: &Address) -> Vec<Symbol> {
+        match Self::get_risk_profile(env, owner.clone()) {
+            Some(profile) => profile.allowed_assets,
+            None => Vec::new(env),
+        }
+    }
+
+    /// Helper function to add `asset` to or remove it from one of the owner's
+    /// asset lists, returning whether the list changed
+    fn edit_asset_list(
+        env: &Env,
+        owner: &Address,
+        blacklist: bool,
+        asset: &Symbol,
+        add: bool,
+    ) -> bool {
+        let mut profiles = Self::load_profiles(env);
+        let now = env.ledger().timestamp();
+        let mut profile = profiles.get(owner.clone()).unwrap_or(RiskProfile {
+            owner: owner.clone(),
+            risk_level: RiskLevel::Low,
+            max_daily_volume: 0,
+            max_single_transaction: 0,
+            allowed_assets: Vec::new(env),
+            blacklisted_assets: Vec::new(env),
+            created_at: now,
+            updated_at: now,
+        });
+
+        let list = if blacklist {
+            &mut profile.blacklisted_assets
+        } else {
+            &mut profile.allowed_assets
+        };
+        match (add, list.first_index_of(asset)) {
+            (true, None) => list.push_back(asset.clone()),
+            (false, Some(index)) => {
+                list.remove(index);
+            }
+            _ => return false,
+        }
+
+        profile.updated_at = now;
+        profiles.set(owner.clone(), profile);
+        env.storage().instance().set(&RISK_PROFILES, &profiles);
+        true
+    }
+
+    /// Helper function to persist a transaction record and apply its usage
+    #[allow(clippy::too_many_arguments)]
+    fn store_transaction(
+        env: &Env,
+        owner: &Address,
+        asset: &Symbol,
+        amount: i128,
+        destination: Option<Address>,
+        counter_asset: Option<Symbol>,
+        tags: Tags,
+        transaction_hash: BytesN<32>,
+    ) -> Result<u64, LimitsError> {
+        let storage = env.storage().instance();
+        let mut next_tx_id: u64 = storage.get(&NEXT_TX_ID).unwrap_or(1);
+
+        let record = TransactionRecord {
+            id: next_tx_id,
+            owner: owner.clone(),
+            asset: asset.clone(),
+            amount,
+            destination: destination.clone(),
+            counter_asset: counter_asset.clone(),
+            timestamp: env.ledger().timestamp(),
+            transaction_hash,
+        };
+
+        if let Some((_, today, baseline)) = Self::velocity_anomaly(env, owner, asset, amount) {
+            events::velocity_anomaly(env, owner, asset, amount, today, baseline);
+        }
+
+        // Store the transaction record
+        storage::push_owner_tx(env, &record);
+        stats::record(env, owner, asset, amount);
+        if let Some(mut global) = storage::get_global_limit(env, asset) {
+            window::add_global_usage(env, &mut global, record.timestamp, amount);
+            storage::set_global_limit(env, &global);
+        }
+        if let Some(mut cooldown) = storage::get_cooldown(env, owner, asset) {
+            cooldown.last_transaction = Some(record.timestamp);
+            storage::set_cooldown(env, owner, asset, &cooldown);
+        }
+
+        events::transaction_recorded(env, owner, next_tx_id, asset, amount);
+
+        // Install any defaults that resolved for this asset so the amount
+        // counts against them from here on
+        for spec in Self::resolve_defaults(env, owner, asset).iter() {
+            Self::insert_limit(
+                env,
+                owner,
+                spec.limit_type,
+                spec.asset,
+                spec.max_amount,
+                spec.time_window,
+            );
+        }
+
+        // Update security limits usage, including any shared group limits
+        for holder in Self::limit_holders(env, owner).iter() {
+            Self::update_limit_usage(
+                env,
+                &holder,
+                asset,
+                amount,
+                &destination,
+                &counter_asset,
+                &tags,
+            )?;
+        }
+
+        // Increment next ID
+        next_tx_id += 1;
+        storage.set(&NEXT_TX_ID, &next_tx_id);
+
+        Ok(next_tx_id - 1)
+    }
+
+    /// Helper function to check an amount against every active limit
+    fn check_limits(
+        env: &Env,
+        owner: &Address,
+        asset: &Symbol,
+        amount: i128,
+        destination: &Option<Address>,
+        counter_asset: &Option<Symbol>,
+        tags: &Tags,
+    ) -> Result<Option<(SecurityLimit, i128)>, LimitsError> {
+        let current_time = env.ledger().timestamp();
+
+        for limit in storage::owner_limits(env, owner).iter() {
+            if Self::covers_asset(&limit, asset)
+                && limit.is_active
+                && Self::counts_transfer(&limit, destination, counter_asset, tags)
+            {
+                let amount = Self::counted_amount(env, &limit, asset, amount)?;
+
+                if let (LimitScope::PerDestination, Some(dest)) = (&limit.scope, destination) {
+                    let usage = window::destination_usage(env, &limit, dest, current_time)
+                        .checked_add(amount)
+                        .ok_or(LimitsError::Overflow)?;
+                    if Self::over_limit(env, &limit, usage, amount, current_time) {
+                        return Ok(Some((limit, usage)));
+                    }
+                    continue;
+                }
+
+                if limit.window_mode == WindowMode::Rolling {
+                    let usage = window::rolling_usage(env, &limit, current_time)
+                        .checked_add(amount)
+                        .ok_or(LimitsError::Overflow)?;
+                    if Self::over_limit(env, &limit, usage, amount, current_time) {
+                        return Ok(Some((limit, usage)));
+                    }
+                    continue;
+                }
+
+                let mut limit = limit;
+                if window::roll_fixed(&mut limit, current_time) {
+                    storage::set_limit(env, &limit);
+                }
+                let usage = limit
+                    .current_usage
+                    .checked_add(amount)
+                    .ok_or(LimitsError::Overflow)?;
+                if Self::over_limit(env, &limit, usage, amount, current_time) {
+                    return Ok(Some((limit, usage)));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Helper function to check whether a limit applies to an asset: its own
+    /// asset, or any asset for a reference-currency limit
+    fn covers_asset(limit: &SecurityLimit, asset: &Symbol) -> bool {
+        matches!(limit.denomination, Denomination::Reference(_)) || limit.asset == *asset
+    }
+
+    /// Helper function to express `amount` of `asset` in a limit's units
+    fn counted_amount(
+        env: &Env,
+        limit: &SecurityLimit,
+        asset: &Symbol,
+        amount: i128,
+    ) -> Result<i128, LimitsError> {
+        match &limit.denomination {
+            Denomination::Reference(valuation) => {
+                valuation::value_of(env, valuation, asset, &limit.asset, amount)
+            }
+            Denomination::Asset => Ok(amount),
+        }
+    }
+
+    /// Helper function to check whether a limit's scope and pair cover a
+    /// transfer with the given tags
+    fn counts_transfer(
+        limit: &SecurityLimit,
+        destination: &Option<Address>,
+        counter_asset: &Option<Symbol>,
+        tags: &Tags,
+    ) -> bool {
+        if limit.pair_asset.is_some() && limit.pair_asset != *counter_asset {
+            return false;
+        }
+        match &limit.scope {
+            LimitScope::Aggregate => true,
+            LimitScope::PerDestination => destination.is_some(),
+            LimitScope::Destination(target) => destination.as_ref() == Some(target),
+            LimitScope::Category(tag) => tags.category.as_ref() == Some(tag),
+            LimitScope::Caller(caller) => tags.caller.as_ref() == Some(caller),
+            LimitScope::NetFlow => true,
+        }
+    }
+
+    /// Helper function to get the usage a limit would count right now
+    fn effective_usage(env: &Env, limit: &SecurityLimit, now: u64) -> i128 {
+        if limit.window_mode == WindowMode::Rolling {
+            return window::rolling_usage(env, limit, now);
+        }
+        let mut limit = limit.clone();
+        window::roll_fixed(&mut limit, now);
+        limit.current_usage
+    }
+
+    /// Helper function to get when a limit's counted usage next drops,
+    /// mirroring [`Self::effective_usage`]
+    fn usage_resets_at(env: &Env, limit: &SecurityLimit, now: u64) -> u64 {
+        if limit.window_mode == WindowMode::Rolling {
+            return window::rolling_resets_at(env, limit, now).unwrap_or(now);
+        }
+        match &limit.window_mode {
+            WindowMode::Calendar => {
+                return calendar::next_boundary(&limit.limit_type, now).unwrap_or(now);
+            }
+            WindowMode::Scheduled(schedule) => {
+                return calendar::scheduled_next(schedule, &limit.limit_type, now).unwrap_or(now);
+            }
+            _ => {}
+        }
+        let Some(period) = window::fixed_period(limit) else {
+            return now;
+        };
+        let mut limit = limit.clone();
+        window::roll_fixed(&mut limit, now);
+        limit.last_reset.saturating_add(period)
+    }
+
+    /// Helper function to check whether counting `amount` takes a limit's
+    /// usage to `usage`, above its `max_amount` by more than its burst budget
+    /// left covers
+    fn over_limit(env: &Env, limit: &SecurityLimit, usage: i128, amount: i128, now: u64) -> bool {
+        if usage <= limit.max_amount {
+            return false;
+        }
+        match storage::get_burst(env, &limit.owner, limit.id) {
+            Some(burst) => {
+                let over = usage - limit.max_amount.max(usage - amount);
+                over > window::burst_remaining(limit, &burst, now)
+            }
+            None => true,
+        }
+    }
+
+    /// Helper function to draw the part of a limit's usage going from
+    /// `before` to `after` that lies above `max_amount` from its burst
+    /// budget, returning the amount drawn
+    fn draw_burst(env: &Env, limit: &SecurityLimit, before: i128, after: i128, now: u64) -> i128 {
+        if after <= limit.max_amount {
+            return 0;
+        }
+        let Some(mut burst) = storage::get_burst(env, &limit.owner, limit.id) else {
+            return 0;
+        };
+        let remaining = window::burst_remaining(limit, &burst, now);
+        let drawn = (after - limit.max_amount.max(before)).min(remaining);
+        if drawn <= 0 {
+            return 0;
+        }
+        window::roll_burst(limit, &mut burst, now);
+        burst.used += drawn;
+        storage::set_burst(env, &limit.owner, limit.id, &Some(burst));
+        events::burst_drawn(env, limit, drawn, remaining - drawn);
+        drawn
+    }
+
+    /// Helper function to update limit usage
+    fn update_limit_usage(
+        env: &Env,
+        owner: &Address,
+        asset: &Symbol,
+        amount: i128,
+        destination: &Option<Address>,
+        counter_asset: &Option<Symbol>,
+        tags: &Tags,
+    ) -> Result<(), LimitsError> {
+        let current_time = env.ledger().timestamp();
+        let thresholds = storage::alert_thresholds(env, owner);
+        for mut limit in storage::owner_limits(env, owner).iter() {
+            if Self::covers_asset(&limit, asset)
+                && limit.is_active
+                && Self::counts_transfer(&limit, destination, counter_asset, tags)
+            {
+                let amount = Self::counted_amount(env, &limit, asset, amount)?;
+
+                if let (LimitScope::PerDestination, Some(dest)) = (&limit.scope, destination) {
+                    // Per-destination counters live beside the limit, which
+                    // itself is left untouched
+                    let usage =
+                        window::add_destination_usage(env, &limit, dest, current_time, amount);
+                    let drawn = Self::draw_burst(env, &limit, usage - amount, usage, current_time);
+                    Self::publish_usage_alerts(
+                        env,
+                        &limit,
+                        &thresholds,
+                        usage - amount,
+                        usage,
+                        drawn,
+                    );
+                    continue;
+                }
+
+                if let Denomination::Reference(_) = limit.denomination {
+                    window::add_asset_usage(env, &limit, asset, current_time, amount);
+                }
+
+                let before = Self::effective_usage(env, &limit, current_time);
+                let after = if window::window_length(&limit) == 0 {
+                    // Per-transaction limits judge each transaction alone
+                    window::roll_fixed(&mut limit, current_time);
+                    amount
+                } else if limit.window_mode == WindowMode::Rolling {
+                    limit.current_usage =
+                        window::add_rolling_usage(env, &limit, current_time, amount);
+                    limit.current_usage
+                } else {
+                    window::roll_fixed(&mut limit, current_time);
+                    limit.current_usage = limit
+                        .current_usage
+                        .checked_add(amount)
+                        .ok_or(LimitsError::Overflow)?;
+                    limit.current_usage
+                };
+                let drawn = Self::draw_burst(env, &limit, before, after, current_time);
+                Self::publish_usage_alerts(env, &limit, &thresholds, before, after, drawn);
+                storage::set_limit(env, &limit);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Helper function to publish the threshold and breach events for a
+    /// limit whose usage went from `before` to `after`, `burst_drawn` of it
+    /// covered by the burst budget
+    fn publish_usage_alerts(
+        env: &Env,
+        limit: &SecurityLimit,
+        thresholds: &Vec<u32>,
+        before: i128,
+        after: i128,
+        burst_drawn: i128,
+    ) {
+        for threshold in thresholds.iter() {
+            // Split to keep `max_amount * threshold` from overflowing
+            let threshold_bps = i128::from(threshold);
+            let level = limit.max_amount / 10_000 * threshold_bps
+                + limit.max_amount % 10_000 * threshold_bps / 10_000;
+            if before < level && after >= level {
+                events::threshold_crossed(env, limit, threshold, after);
+            }
+        }
+        if let Some(soft) = storage::get_soft_limit(env, &limit.owner, limit.id) {
+            if before <= soft.amount && after > soft.amount {
+                events::soft_limit_crossed(env, limit, after, soft.amount);
+            }
+        }
+        if after - burst_drawn > limit.max_amount {
+            events::limit_breached(env, limit, after);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,92 @@
+//! Security Limits — aggregated usage statistics
+//!
+//! Per-owner, per-asset aggregates are updated as each transaction is
+//! recorded, so statistics views cost a single storage read instead of a
+//! scan over the transaction history.  Daily volume is kept in a seven-slot
+//! ring of day buckets (see `window.rs`).
+//!
+//! Report periods are arbitrary, so their totals are instead summed from the
+//! transaction history inside the period.
+
+use soroban_sdk::{Address, Env, Map, Symbol, Vec};
+
+use crate::storage;
+use crate::types::{AssetStats, PeriodTotals, UsageStats};
+use crate::window;
+
+const DAY: u64 = 86400;
+const DAYS_TRACKED: u32 = 7;
+
+/// Fold a recorded transaction into the owner's aggregates for `asset`.
+pub fn record(env: &Env, owner: &Address, asset: &Symbol, amount: i128) {
+    let mut stats = storage::get_asset_stats(env, owner, asset).unwrap_or(AssetStats {
+        daily_volume: Vec::new(env),
+        largest_transaction: 0,
+        total_volume: 0,
+        transaction_count: 0,
+    });
+
+    let now = env.ledger().timestamp();
+    window::ring_add(env, &mut stats.daily_volume, DAY, DAYS_TRACKED, now, amount);
+    stats.largest_transaction = stats.largest_transaction.max(amount);
+    stats.total_volume = stats.total_volume.saturating_add(amount);
+    stats.transaction_count += 1;
+
+    storage::set_asset_stats(env, owner, asset, &stats);
+}
+
+/// Today's volume of `asset` for `owner` and their average daily volume over
+/// the six days before, the baseline for velocity anomaly rules.
+pub fn velocity(env: &Env, owner: &Address, asset: &Symbol) -> (i128, i128) {
+    let usage = usage(env, owner, asset);
+    let trailing = usage.week_volume - usage.today_volume;
+    (usage.today_volume, trailing / i128::from(DAYS_TRACKED - 1))
+}
+
+/// Per-asset totals of the owner's transactions with
+/// `from_ts <= timestamp <= to_ts`.
+pub fn period(env: &Env, owner: &Address, from_ts: u64, to_ts: u64) -> Map<Symbol, PeriodTotals> {
+    let mut totals: Map<Symbol, PeriodTotals> = Map::new(env);
+    let count = storage::owner_tx_count(env, owner);
+    for index in storage::first_owner_tx_from(env, owner, from_ts)..count {
+        let Some(record) = storage::get_owner_tx(env, owner, index) else {
+            continue;
+        };
+        if record.timestamp > to_ts {
+            break;
+        }
+        let mut entry = totals.get(record.asset.clone()).unwrap_or(PeriodTotals {
+            volume: 0,
+            transaction_count: 0,
+            largest_transaction: 0,
+        });
+        entry.volume = entry.volume.saturating_add(record.amount);
+        entry.transaction_count += 1;
+        entry.largest_transaction = entry.largest_transaction.max(record.amount);
+        totals.set(record.asset, entry);
+    }
+    totals
+}
+
+/// Statistics view for `owner` and `asset` as of the current ledger.
+pub fn usage(env: &Env, owner: &Address, asset: &Symbol) -> UsageStats {
+    let now = env.ledger().timestamp();
+    match storage::get_asset_stats(env, owner, asset) {
+        Some(stats) => UsageStats {
+            asset: asset.clone(),
+            today_volume: window::ring_usage(&stats.daily_volume, DAY, 1, now),
+            week_volume: window::ring_usage(&stats.daily_volume, DAY, DAYS_TRACKED, now),
+            largest_transaction: stats.largest_transaction,
+            total_volume: stats.total_volume,
+            transaction_count: stats.transaction_count,
+        },
+        None => UsageStats {
+            asset: asset.clone(),
+            today_volume: 0,
+            week_volume: 0,
+            largest_transaction: 0,
+            total_volume: 0,
+            transaction_count: 0,
+        },
+    }
+}
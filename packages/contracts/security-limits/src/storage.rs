@@ -0,0 +1,889 @@
+//! Security Limits — persistent storage layout
+//!
+//! Limits live in one persistent entry per `(owner, id)` plus a per-owner
+//! index of IDs, so a write touches only the affected limit and a read scans
+//! only the owner's own entries.
+
+use soroban_sdk::{contracttype, Address, Env, Map, Symbol, Vec};
+
+use crate::types::{
+    AssetStats, AuditEntry, BurstAllowance, Cooldown, FreezeInfo, GlobalLimit, LimitException,
+    ManagerGrant, Proposal, ReportCommitment, RiskLevel, RiskTemplate, SecurityLimit, SoftLimit,
+    TemplateLimit, TemplateSubscription, TimelockedOp, TransactionRecord, UsageBucket,
+    VelocityRule,
+};
+
+/// TTL constants for persistent entries (in ledgers). ~1 ledger ≈ 5 seconds.
+const PERSISTENT_TTL_THRESHOLD: u32 = 60_480; // ~3.5 days
+const PERSISTENT_TTL_EXTEND: u32 = 120_960; // ~7 days
+
+/// Keys for persistent storage.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DataKey {
+    /// `SecurityLimit` keyed by owner and limit ID.
+    Limit(Address, u64),
+    /// `Vec<u64>` — IDs of every limit belonging to an owner.
+    OwnerLimits(Address),
+    /// `Vec<UsageBucket>` — rolling-window ring buffer for a limit.
+    Buckets(Address, u64),
+    /// `Map<Address, Vec<UsageBucket>>` — per-destination counters for a
+    /// `PerDestination` limit.
+    DestUsage(Address, u64),
+    /// `Map<Symbol, Vec<UsageBucket>>` — per-asset share of a
+    /// reference-currency limit's usage, in reference units.
+    AssetUsage(Address, u64),
+    /// `SoftLimit` keyed by owner and limit ID.
+    SoftLimit(Address, u64),
+    /// `BurstAllowance` keyed by owner and limit ID.
+    Burst(Address, u64),
+    /// `FreezeInfo` — present while an owner's account is frozen.
+    Frozen(Address),
+    /// `LimitException` keyed by owner and asset.
+    Exception(Address, Symbol),
+    /// `AuditEntry` keyed by sequential ID, starting at 1.
+    Audit(u64),
+    /// `u64` — number of audit entries written.
+    AuditCount,
+    /// `Address` — co-signer who must approve an owner's loosening changes.
+    CoSigner(Address),
+    /// `Proposal` keyed by owner and proposal ID.
+    Proposal(Address, u64),
+    /// `TransactionRecord` keyed by owner and position in their history.
+    OwnerTx(Address, u32),
+    /// `u32` — number of transactions recorded for an owner.
+    OwnerTxCount(Address),
+    /// `AssetStats` keyed by owner and asset.
+    AssetStats(Address, Symbol),
+    /// `ReportCommitment` keyed by owner and period bounds.
+    Report(Address, u64, u64),
+    /// `RiskTemplate` keyed by template ID.
+    Template(Symbol),
+    /// `Vec<Symbol>` — IDs of every template, in creation order.
+    TemplateIds,
+    /// `Vec<Address>` — owners subscribed to a template's updates.
+    TemplateSubscribers(Symbol),
+    /// `TemplateSubscription` — the template an owner last applied.
+    Subscription(Address),
+    /// `Vec<Address>` — an owner's approved destinations.
+    Whitelist(Address),
+    /// Present while an owner's whitelist mode is on.
+    WhitelistOn(Address),
+    /// `TimelockedOp` keyed by owner and change ID.
+    Timelocked(Address, u64),
+    /// `Cooldown` keyed by owner and asset.
+    Cooldown(Address, Symbol),
+    /// `VelocityRule` keyed by owner and asset.
+    Velocity(Address, Symbol),
+    /// `Vec<Address>` — members drawing on a group address's limits.
+    GroupMembers(Address),
+    /// `Address` — the limit group a member belongs to.
+    GroupOf(Address),
+    /// `Vec<Address>` — addresses an owner lets report transactions.
+    Reporters(Address),
+    /// `ManagerGrant` keyed by owner and manager.
+    Manager(Address, Address),
+    /// `Vec<u32>` — an owner's usage alert thresholds, in basis points.
+    AlertThresholds(Address),
+    /// `Vec<TemplateLimit>` — limits installed for owners at a risk level.
+    LevelDefaults(RiskLevel),
+    /// `Vec<TemplateLimit>` — limits applied to owners without their own
+    /// limit or risk level default for an asset.
+    DefaultLimits,
+    /// `GlobalLimit` keyed by asset.
+    GlobalLimit(Symbol),
+}
+
+fn extend(env: &Env, key: &DataKey) {
+    env.storage()
+        .persistent()
+        .extend_ttl(key, PERSISTENT_TTL_THRESHOLD, PERSISTENT_TTL_EXTEND);
+}
+
+pub fn get_limit(env: &Env, owner: &Address, limit_id: u64) -> Option<SecurityLimit> {
+    let key = DataKey::Limit(owner.clone(), limit_id);
+    let limit: Option<SecurityLimit> = env.storage().persistent().get(&key);
+    if limit.is_some() {
+        extend(env, &key);
+    }
+    limit
+}
+
+pub fn set_limit(env: &Env, limit: &SecurityLimit) {
+    let key = DataKey::Limit(limit.owner.clone(), limit.id);
+    env.storage().persistent().set(&key, limit);
+    extend(env, &key);
+}
+
+pub fn remove_limit(env: &Env, owner: &Address, limit_id: u64) {
+    env.storage()
+        .persistent()
+        .remove(&DataKey::Limit(owner.clone(), limit_id));
+}
+
+/// IDs of every limit owned by `owner`, in creation order.
+pub fn owner_limit_ids(env: &Env, owner: &Address) -> Vec<u64> {
+    let key = DataKey::OwnerLimits(owner.clone());
+    let ids: Option<Vec<u64>> = env.storage().persistent().get(&key);
+    match ids {
+        Some(ids) => {
+            extend(env, &key);
+            ids
+        }
+        None => Vec::new(env),
+    }
+}
+
+pub fn index_limit(env: &Env, owner: &Address, limit_id: u64) {
+    let key = DataKey::OwnerLimits(owner.clone());
+    let mut ids = owner_limit_ids(env, owner);
+    ids.push_back(limit_id);
+    env.storage().persistent().set(&key, &ids);
+    extend(env, &key);
+}
+
+pub fn unindex_limit(env: &Env, owner: &Address, limit_id: u64) {
+    let key = DataKey::OwnerLimits(owner.clone());
+    let mut ids = owner_limit_ids(env, owner);
+    if let Some(pos) = ids.first_index_of(limit_id) {
+        ids.remove(pos);
+    }
+    if ids.is_empty() {
+        env.storage().persistent().remove(&key);
+    } else {
+        env.storage().persistent().set(&key, &ids);
+        extend(env, &key);
+    }
+}
+
+/// Every limit owned by `owner`, in creation order.
+pub fn owner_limits(env: &Env, owner: &Address) -> Vec<SecurityLimit> {
+    let mut limits = Vec::new(env);
+    for id in owner_limit_ids(env, owner).iter() {
+        if let Some(limit) = get_limit(env, owner, id) {
+            limits.push_back(limit);
+        }
+    }
+    limits
+}
+
+/// Rolling-window buckets for a limit; empty when none have been written.
+pub fn get_buckets(env: &Env, owner: &Address, limit_id: u64) -> Vec<UsageBucket> {
+    let key = DataKey::Buckets(owner.clone(), limit_id);
+    let buckets: Option<Vec<UsageBucket>> = env.storage().persistent().get(&key);
+    match buckets {
+        Some(buckets) => {
+            extend(env, &key);
+            buckets
+        }
+        None => Vec::new(env),
+    }
+}
+
+pub fn set_buckets(env: &Env, owner: &Address, limit_id: u64, buckets: &Vec<UsageBucket>) {
+    let key = DataKey::Buckets(owner.clone(), limit_id);
+    env.storage().persistent().set(&key, buckets);
+    extend(env, &key);
+}
+
+pub fn remove_buckets(env: &Env, owner: &Address, limit_id: u64) {
+    env.storage()
+        .persistent()
+        .remove(&DataKey::Buckets(owner.clone(), limit_id));
+}
+
+/// Per-destination counters for a limit; empty when none have been written.
+pub fn get_destination_usage(
+    env: &Env,
+    owner: &Address,
+    limit_id: u64,
+) -> Map<Address, Vec<UsageBucket>> {
+    let key = DataKey::DestUsage(owner.clone(), limit_id);
+    let usage: Option<Map<Address, Vec<UsageBucket>>> = env.storage().persistent().get(&key);
+    match usage {
+        Some(usage) => {
+            extend(env, &key);
+            usage
+        }
+        None => Map::new(env),
+    }
+}
+
+pub fn set_destination_usage(
+    env: &Env,
+    owner: &Address,
+    limit_id: u64,
+    usage: &Map<Address, Vec<UsageBucket>>,
+) {
+    let key = DataKey::DestUsage(owner.clone(), limit_id);
+    env.storage().persistent().set(&key, usage);
+    extend(env, &key);
+}
+
+pub fn get_asset_usage(env: &Env, owner: &Address, limit_id: u64) -> Map<Symbol, Vec<UsageBucket>> {
+    let key = DataKey::AssetUsage(owner.clone(), limit_id);
+    let usage: Option<Map<Symbol, Vec<UsageBucket>>> = env.storage().persistent().get(&key);
+    match usage {
+        Some(usage) => {
+            extend(env, &key);
+            usage
+        }
+        None => Map::new(env),
+    }
+}
+
+pub fn set_asset_usage(
+    env: &Env,
+    owner: &Address,
+    limit_id: u64,
+    usage: &Map<Symbol, Vec<UsageBucket>>,
+) {
+    let key = DataKey::AssetUsage(owner.clone(), limit_id);
+    env.storage().persistent().set(&key, usage);
+    extend(env, &key);
+}
+
+pub fn get_soft_limit(env: &Env, owner: &Address, limit_id: u64) -> Option<SoftLimit> {
+    let key = DataKey::SoftLimit(owner.clone(), limit_id);
+    let soft: Option<SoftLimit> = env.storage().persistent().get(&key);
+    if soft.is_some() {
+        extend(env, &key);
+    }
+    soft
+}
+
+pub fn set_soft_limit(env: &Env, owner: &Address, limit_id: u64, soft: &Option<SoftLimit>) {
+    let key = DataKey::SoftLimit(owner.clone(), limit_id);
+    match soft {
+        Some(soft) => {
+            env.storage().persistent().set(&key, soft);
+            extend(env, &key);
+        }
+        None => env.storage().persistent().remove(&key),
+    }
+}
+
+pub fn get_burst(env: &Env, owner: &Address, limit_id: u64) -> Option<BurstAllowance> {
+    let key = DataKey::Burst(owner.clone(), limit_id);
+    let burst: Option<BurstAllowance> = env.storage().persistent().get(&key);
+    if burst.is_some() {
+        extend(env, &key);
+    }
+    burst
+}
+
+pub fn set_burst(env: &Env, owner: &Address, limit_id: u64, burst: &Option<BurstAllowance>) {
+    let key = DataKey::Burst(owner.clone(), limit_id);
+    match burst {
+        Some(burst) => {
+            env.storage().persistent().set(&key, burst);
+            extend(env, &key);
+        }
+        None => env.storage().persistent().remove(&key),
+    }
+}
+
+/// Drop every usage counter (aggregate buckets, per-destination and
+/// per-asset) of a limit.
+pub fn clear_usage(env: &Env, owner: &Address, limit_id: u64) {
+    remove_buckets(env, owner, limit_id);
+    env.storage()
+        .persistent()
+        .remove(&DataKey::DestUsage(owner.clone(), limit_id));
+    env.storage()
+        .persistent()
+        .remove(&DataKey::AssetUsage(owner.clone(), limit_id));
+}
+
+// ---------------------------------------------------------------------------
+// Transaction history
+// ---------------------------------------------------------------------------
+
+pub fn owner_tx_count(env: &Env, owner: &Address) -> u32 {
+    let key = DataKey::OwnerTxCount(owner.clone());
+    let count: Option<u32> = env.storage().persistent().get(&key);
+    match count {
+        Some(count) => {
+            extend(env, &key);
+            count
+        }
+        None => 0,
+    }
+}
+
+/// Append a record to its owner's history.  Records are written in ledger
+/// order, so timestamps never decrease along an owner's history.
+pub fn push_owner_tx(env: &Env, record: &TransactionRecord) {
+    let count = owner_tx_count(env, &record.owner);
+    let key = DataKey::OwnerTx(record.owner.clone(), count);
+    env.storage().persistent().set(&key, record);
+    extend(env, &key);
+
+    let count_key = DataKey::OwnerTxCount(record.owner.clone());
+    env.storage().persistent().set(&count_key, &(count + 1));
+    extend(env, &count_key);
+}
+
+pub fn get_owner_tx(env: &Env, owner: &Address, index: u32) -> Option<TransactionRecord> {
+    let key = DataKey::OwnerTx(owner.clone(), index);
+    let record: Option<TransactionRecord> = env.storage().persistent().get(&key);
+    if record.is_some() {
+        extend(env, &key);
+    }
+    record
+}
+
+/// Position of the owner's first transaction at or after `from_ts`, found by
+/// binary search since records are appended in timestamp order.
+pub fn first_owner_tx_from(env: &Env, owner: &Address, from_ts: u64) -> u32 {
+    let (mut lo, mut hi) = (0u32, owner_tx_count(env, owner));
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        match get_owner_tx(env, owner, mid) {
+            Some(record) if record.timestamp < from_ts => lo = mid + 1,
+            _ => hi = mid,
+        }
+    }
+    lo
+}
+
+pub fn get_report(
+    env: &Env,
+    owner: &Address,
+    from_ts: u64,
+    to_ts: u64,
+) -> Option<ReportCommitment> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Report(owner.clone(), from_ts, to_ts))
+}
+
+pub fn set_report(env: &Env, owner: &Address, report: &ReportCommitment) {
+    let key = DataKey::Report(owner.clone(), report.from_ts, report.to_ts);
+    env.storage().persistent().set(&key, report);
+    extend(env, &key);
+}
+
+pub fn get_asset_stats(env: &Env, owner: &Address, asset: &Symbol) -> Option<AssetStats> {
+    let key = DataKey::AssetStats(owner.clone(), asset.clone());
+    let stats: Option<AssetStats> = env.storage().persistent().get(&key);
+    if stats.is_some() {
+        extend(env, &key);
+    }
+    stats
+}
+
+pub fn set_asset_stats(env: &Env, owner: &Address, asset: &Symbol, stats: &AssetStats) {
+    let key = DataKey::AssetStats(owner.clone(), asset.clone());
+    env.storage().persistent().set(&key, stats);
+    extend(env, &key);
+}
+
+// ---------------------------------------------------------------------------
+// Two-party approval
+// ---------------------------------------------------------------------------
+
+pub fn get_co_signer(env: &Env, owner: &Address) -> Option<Address> {
+    let key = DataKey::CoSigner(owner.clone());
+    let co_signer: Option<Address> = env.storage().persistent().get(&key);
+    if co_signer.is_some() {
+        extend(env, &key);
+    }
+    co_signer
+}
+
+pub fn set_co_signer(env: &Env, owner: &Address, co_signer: &Option<Address>) {
+    let key = DataKey::CoSigner(owner.clone());
+    match co_signer {
+        Some(co_signer) => {
+            env.storage().persistent().set(&key, co_signer);
+            extend(env, &key);
+        }
+        None => env.storage().persistent().remove(&key),
+    }
+}
+
+pub fn get_proposal(env: &Env, owner: &Address, proposal_id: u64) -> Option<Proposal> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Proposal(owner.clone(), proposal_id))
+}
+
+pub fn set_proposal(env: &Env, proposal: &Proposal) {
+    let key = DataKey::Proposal(proposal.owner.clone(), proposal.id);
+    env.storage().persistent().set(&key, proposal);
+    extend(env, &key);
+}
+
+pub fn remove_proposal(env: &Env, owner: &Address, proposal_id: u64) {
+    env.storage()
+        .persistent()
+        .remove(&DataKey::Proposal(owner.clone(), proposal_id));
+}
+
+// ---------------------------------------------------------------------------
+// Whitelist, cooldowns and timelock
+// ---------------------------------------------------------------------------
+
+pub fn whitelist(env: &Env, owner: &Address) -> Vec<Address> {
+    let key = DataKey::Whitelist(owner.clone());
+    let list: Option<Vec<Address>> = env.storage().persistent().get(&key);
+    match list {
+        Some(list) => {
+            extend(env, &key);
+            list
+        }
+        None => Vec::new(env),
+    }
+}
+
+pub fn set_whitelist(env: &Env, owner: &Address, list: &Vec<Address>) {
+    let key = DataKey::Whitelist(owner.clone());
+    if list.is_empty() {
+        env.storage().persistent().remove(&key);
+    } else {
+        env.storage().persistent().set(&key, list);
+        extend(env, &key);
+    }
+}
+
+pub fn whitelist_enabled(env: &Env, owner: &Address) -> bool {
+    let key = DataKey::WhitelistOn(owner.clone());
+    let enabled = env.storage().persistent().has(&key);
+    if enabled {
+        extend(env, &key);
+    }
+    enabled
+}
+
+pub fn set_whitelist_enabled(env: &Env, owner: &Address, enabled: bool) {
+    let key = DataKey::WhitelistOn(owner.clone());
+    if enabled {
+        env.storage().persistent().set(&key, &true);
+        extend(env, &key);
+    } else {
+        env.storage().persistent().remove(&key);
+    }
+}
+
+pub fn alert_thresholds(env: &Env, owner: &Address) -> Vec<u32> {
+    let key = DataKey::AlertThresholds(owner.clone());
+    let thresholds: Option<Vec<u32>> = env.storage().persistent().get(&key);
+    match thresholds {
+        Some(thresholds) => {
+            extend(env, &key);
+            thresholds
+        }
+        None => Vec::new(env),
+    }
+}
+
+pub fn set_alert_thresholds(env: &Env, owner: &Address, thresholds: &Vec<u32>) {
+    let key = DataKey::AlertThresholds(owner.clone());
+    if thresholds.is_empty() {
+        env.storage().persistent().remove(&key);
+    } else {
+        env.storage().persistent().set(&key, thresholds);
+        extend(env, &key);
+    }
+}
+
+pub fn get_global_limit(env: &Env, asset: &Symbol) -> Option<GlobalLimit> {
+    let key = DataKey::GlobalLimit(asset.clone());
+    let limit: Option<GlobalLimit> = env.storage().persistent().get(&key);
+    if limit.is_some() {
+        extend(env, &key);
+    }
+    limit
+}
+
+pub fn set_global_limit(env: &Env, limit: &GlobalLimit) {
+    let key = DataKey::GlobalLimit(limit.asset.clone());
+    env.storage().persistent().set(&key, limit);
+    extend(env, &key);
+}
+
+pub fn remove_global_limit(env: &Env, asset: &Symbol) {
+    env.storage()
+        .persistent()
+        .remove(&DataKey::GlobalLimit(asset.clone()));
+}
+
+pub fn get_cooldown(env: &Env, owner: &Address, asset: &Symbol) -> Option<Cooldown> {
+    let key = DataKey::Cooldown(owner.clone(), asset.clone());
+    let cooldown: Option<Cooldown> = env.storage().persistent().get(&key);
+    if cooldown.is_some() {
+        extend(env, &key);
+    }
+    cooldown
+}
+
+pub fn set_cooldown(env: &Env, owner: &Address, asset: &Symbol, cooldown: &Cooldown) {
+    let key = DataKey::Cooldown(owner.clone(), asset.clone());
+    env.storage().persistent().set(&key, cooldown);
+    extend(env, &key);
+}
+
+pub fn remove_cooldown(env: &Env, owner: &Address, asset: &Symbol) {
+    env.storage()
+        .persistent()
+        .remove(&DataKey::Cooldown(owner.clone(), asset.clone()));
+}
+
+pub fn get_velocity_rule(env: &Env, owner: &Address, asset: &Symbol) -> Option<VelocityRule> {
+    let key = DataKey::Velocity(owner.clone(), asset.clone());
+    let rule: Option<VelocityRule> = env.storage().persistent().get(&key);
+    if rule.is_some() {
+        extend(env, &key);
+    }
+    rule
+}
+
+pub fn set_velocity_rule(env: &Env, owner: &Address, asset: &Symbol, rule: &VelocityRule) {
+    let key = DataKey::Velocity(owner.clone(), asset.clone());
+    env.storage().persistent().set(&key, rule);
+    extend(env, &key);
+}
+
+pub fn remove_velocity_rule(env: &Env, owner: &Address, asset: &Symbol) {
+    env.storage()
+        .persistent()
+        .remove(&DataKey::Velocity(owner.clone(), asset.clone()));
+}
+
+pub fn get_timelocked(env: &Env, owner: &Address, id: u64) -> Option<TimelockedOp> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Timelocked(owner.clone(), id))
+}
+
+pub fn set_timelocked(env: &Env, op: &TimelockedOp) {
+    let key = DataKey::Timelocked(op.owner.clone(), op.id);
+    env.storage().persistent().set(&key, op);
+    extend(env, &key);
+}
+
+pub fn remove_timelocked(env: &Env, owner: &Address, id: u64) {
+    env.storage()
+        .persistent()
+        .remove(&DataKey::Timelocked(owner.clone(), id));
+}
+
+// ---------------------------------------------------------------------------
+// Risk levels and templates
+// ---------------------------------------------------------------------------
+
+pub fn level_defaults(env: &Env, level: &RiskLevel) -> Vec<TemplateLimit> {
+    let key = DataKey::LevelDefaults(level.clone());
+    let defaults: Option<Vec<TemplateLimit>> = env.storage().persistent().get(&key);
+    match defaults {
+        Some(defaults) => {
+            extend(env, &key);
+            defaults
+        }
+        None => Vec::new(env),
+    }
+}
+
+pub fn set_level_defaults(env: &Env, level: &RiskLevel, defaults: &Vec<TemplateLimit>) {
+    set_defaults(env, &DataKey::LevelDefaults(level.clone()), defaults);
+}
+
+pub fn default_limits(env: &Env) -> Vec<TemplateLimit> {
+    let key = DataKey::DefaultLimits;
+    match env.storage().persistent().get(&key) {
+        Some(defaults) => {
+            extend(env, &key);
+            defaults
+        }
+        None => Vec::new(env),
+    }
+}
+
+pub fn set_default_limits(env: &Env, defaults: &Vec<TemplateLimit>) {
+    set_defaults(env, &DataKey::DefaultLimits, defaults);
+}
+
+fn set_defaults(env: &Env, key: &DataKey, defaults: &Vec<TemplateLimit>) {
+    if defaults.is_empty() {
+        env.storage().persistent().remove(key);
+    } else {
+        env.storage().persistent().set(key, defaults);
+        extend(env, key);
+    }
+}
+
+pub fn get_template(env: &Env, template_id: &Symbol) -> Option<RiskTemplate> {
+    let key = DataKey::Template(template_id.clone());
+    let template: Option<RiskTemplate> = env.storage().persistent().get(&key);
+    if template.is_some() {
+        extend(env, &key);
+    }
+    template
+}
+
+/// Store a template, adding its ID to the index when it is new.
+pub fn set_template(env: &Env, template: &RiskTemplate) {
+    let key = DataKey::Template(template.id.clone());
+    if !env.storage().persistent().has(&key) {
+        let mut ids = template_ids(env);
+        ids.push_back(template.id.clone());
+        env.storage().persistent().set(&DataKey::TemplateIds, &ids);
+        extend(env, &DataKey::TemplateIds);
+    }
+    env.storage().persistent().set(&key, template);
+    extend(env, &key);
+}
+
+/// Remove a template together with its subscriber list.
+pub fn remove_template(env: &Env, template_id: &Symbol) {
+    let mut ids = template_ids(env);
+    if let Some(pos) = ids.first_index_of(template_id) {
+        ids.remove(pos);
+    }
+    env.storage().persistent().set(&DataKey::TemplateIds, &ids);
+    env.storage()
+        .persistent()
+        .remove(&DataKey::Template(template_id.clone()));
+    env.storage()
+        .persistent()
+        .remove(&DataKey::TemplateSubscribers(template_id.clone()));
+}
+
+pub fn template_ids(env: &Env) -> Vec<Symbol> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::TemplateIds)
+        .unwrap_or(Vec::new(env))
+}
+
+pub fn template_subscribers(env: &Env, template_id: &Symbol) -> Vec<Address> {
+    let key = DataKey::TemplateSubscribers(template_id.clone());
+    let owners: Option<Vec<Address>> = env.storage().persistent().get(&key);
+    match owners {
+        Some(owners) => {
+            extend(env, &key);
+            owners
+        }
+        None => Vec::new(env),
+    }
+}
+
+fn set_template_subscribers(env: &Env, template_id: &Symbol, owners: &Vec<Address>) {
+    let key = DataKey::TemplateSubscribers(template_id.clone());
+    if owners.is_empty() {
+        env.storage().persistent().remove(&key);
+    } else {
+        env.storage().persistent().set(&key, owners);
+        extend(env, &key);
+    }
+}
+
+pub fn get_subscription(env: &Env, owner: &Address) -> Option<TemplateSubscription> {
+    let key = DataKey::Subscription(owner.clone());
+    let sub: Option<TemplateSubscription> = env.storage().persistent().get(&key);
+    if sub.is_some() {
+        extend(env, &key);
+    }
+    sub
+}
+
+/// Record the owner's subscription, moving them between subscriber lists
+/// when they switch templates.
+pub fn set_subscription(env: &Env, owner: &Address, sub: &TemplateSubscription) {
+    if let Some(previous) = get_subscription(env, owner) {
+        if previous.template_id != sub.template_id {
+            unsubscribe(env, &previous.template_id, owner);
+        }
+    }
+    let mut owners = template_subscribers(env, &sub.template_id);
+    if !owners.contains(owner) {
+        owners.push_back(owner.clone());
+        set_template_subscribers(env, &sub.template_id, &owners);
+    }
+
+    let key = DataKey::Subscription(owner.clone());
+    env.storage().persistent().set(&key, sub);
+    extend(env, &key);
+}
+
+pub fn remove_subscription(env: &Env, owner: &Address) {
+    if let Some(sub) = get_subscription(env, owner) {
+        unsubscribe(env, &sub.template_id, owner);
+    }
+    env.storage()
+        .persistent()
+        .remove(&DataKey::Subscription(owner.clone()));
+}
+
+fn unsubscribe(env: &Env, template_id: &Symbol, owner: &Address) {
+    let mut owners = template_subscribers(env, template_id);
+    if let Some(pos) = owners.first_index_of(owner) {
+        owners.remove(pos);
+    }
+    set_template_subscribers(env, template_id, &owners);
+}
+
+// ---------------------------------------------------------------------------
+// Limit groups
+// ---------------------------------------------------------------------------
+
+pub fn group_members(env: &Env, group: &Address) -> Vec<Address> {
+    let key = DataKey::GroupMembers(group.clone());
+    match env.storage().persistent().get(&key) {
+        Some(members) => {
+            extend(env, &key);
+            members
+        }
+        None => Vec::new(env),
+    }
+}
+
+pub fn set_group_members(env: &Env, group: &Address, members: &Vec<Address>) {
+    let key = DataKey::GroupMembers(group.clone());
+    if members.is_empty() {
+        env.storage().persistent().remove(&key);
+    } else {
+        env.storage().persistent().set(&key, members);
+        extend(env, &key);
+    }
+}
+
+pub fn reporters(env: &Env, owner: &Address) -> Vec<Address> {
+    let key = DataKey::Reporters(owner.clone());
+    match env.storage().persistent().get(&key) {
+        Some(reporters) => {
+            extend(env, &key);
+            reporters
+        }
+        None => Vec::new(env),
+    }
+}
+
+pub fn set_reporters(env: &Env, owner: &Address, reporters: &Vec<Address>) {
+    let key = DataKey::Reporters(owner.clone());
+    if reporters.is_empty() {
+        env.storage().persistent().remove(&key);
+    } else {
+        env.storage().persistent().set(&key, reporters);
+        extend(env, &key);
+    }
+}
+
+pub fn group_of(env: &Env, member: &Address) -> Option<Address> {
+    let key = DataKey::GroupOf(member.clone());
+    let group: Option<Address> = env.storage().persistent().get(&key);
+    if group.is_some() {
+        extend(env, &key);
+    }
+    group
+}
+
+pub fn set_group_of(env: &Env, member: &Address, group: &Option<Address>) {
+    let key = DataKey::GroupOf(member.clone());
+    match group {
+        Some(group) => {
+            env.storage().persistent().set(&key, group);
+            extend(env, &key);
+        }
+        None => env.storage().persistent().remove(&key),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Delegation
+// ---------------------------------------------------------------------------
+
+pub fn get_manager(env: &Env, owner: &Address, manager: &Address) -> Option<ManagerGrant> {
+    let key = DataKey::Manager(owner.clone(), manager.clone());
+    let grant: Option<ManagerGrant> = env.storage().persistent().get(&key);
+    if grant.is_some() {
+        extend(env, &key);
+    }
+    grant
+}
+
+pub fn set_manager(env: &Env, owner: &Address, grant: &ManagerGrant) {
+    let key = DataKey::Manager(owner.clone(), grant.manager.clone());
+    env.storage().persistent().set(&key, grant);
+    extend(env, &key);
+}
+
+pub fn remove_manager(env: &Env, owner: &Address, manager: &Address) {
+    env.storage()
+        .persistent()
+        .remove(&DataKey::Manager(owner.clone(), manager.clone()));
+}
+
+// ---------------------------------------------------------------------------
+// Compliance
+// ---------------------------------------------------------------------------
+
+pub fn is_frozen(env: &Env, owner: &Address) -> bool {
+    env.storage()
+        .persistent()
+        .has(&DataKey::Frozen(owner.clone()))
+}
+
+pub fn get_freeze(env: &Env, owner: &Address) -> Option<FreezeInfo> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Frozen(owner.clone()))
+}
+
+pub fn set_freeze(env: &Env, owner: &Address, info: &FreezeInfo) {
+    let key = DataKey::Frozen(owner.clone());
+    env.storage().persistent().set(&key, info);
+    extend(env, &key);
+}
+
+pub fn remove_freeze(env: &Env, owner: &Address) {
+    env.storage()
+        .persistent()
+        .remove(&DataKey::Frozen(owner.clone()));
+}
+
+pub fn get_exception(env: &Env, owner: &Address, asset: &Symbol) -> Option<LimitException> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Exception(owner.clone(), asset.clone()))
+}
+
+pub fn set_exception(env: &Env, exception: &LimitException) {
+    let key = DataKey::Exception(exception.owner.clone(), exception.asset.clone());
+    env.storage().persistent().set(&key, exception);
+    extend(env, &key);
+}
+
+pub fn remove_exception(env: &Env, owner: &Address, asset: &Symbol) {
+    env.storage()
+        .persistent()
+        .remove(&DataKey::Exception(owner.clone(), asset.clone()));
+}
+
+pub fn audit_count(env: &Env) -> u64 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::AuditCount)
+        .unwrap_or(0)
+}
+
+/// Append an entry to the audit log; entries are never modified or removed.
+pub fn append_audit(env: &Env, entry: &AuditEntry) {
+    let key = DataKey::Audit(entry.id);
+    env.storage().persistent().set(&key, entry);
+    extend(env, &key);
+    env.storage()
+        .persistent()
+        .set(&DataKey::AuditCount, &entry.id);
+    extend(env, &DataKey::AuditCount);
+}
+
+pub fn get_audit(env: &Env, id: u64) -> Option<AuditEntry> {
+    let key = DataKey::Audit(id);
+    let entry: Option<AuditEntry> = env.storage().persistent().get(&key);
+    if entry.is_some() {
+        extend(env, &key);
+    }
+    entry
+}
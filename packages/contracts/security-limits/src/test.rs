@@ -1,16 +1,26 @@
 //! Tests for Security Limits Contract
 
+extern crate std;
+
 use super::*;
-use soroban_sdk::{testutils::Address as _, Address, Env, Symbol, BytesN};
+use soroban_sdk::{
+    map,
+    testutils::{
+        Address as _, AuthorizedFunction, AuthorizedInvocation, Events, Ledger, MockAuth,
+        MockAuthInvoke,
+    },
+    vec, Address, BytesN, Env, IntoVal, String, TryFromVal, Val, Vec,
+};
 
 #[test]
 fn test_initialize() {
     let env = Env::default();
+    env.mock_all_auths();
     let contract_id = env.register_contract(None, SecurityLimitsContract);
     let client = SecurityLimitsContractClient::new(&env, &contract_id);
-    
-    client.initialize();
-    
+
+    client.initialize(&None);
+
     // Test that contract is initialized
     // This would typically check storage values
 }
@@ -18,91 +28,84 @@ fn test_initialize() {
 #[test]
 fn test_create_security_limit() {
     let env = Env::default();
+    env.mock_all_auths();
     let contract_id = env.register_contract(None, SecurityLimitsContract);
     let client = SecurityLimitsContractClient::new(&env, &contract_id);
-    
-    client.initialize();
-    
+
+    client.initialize(&None);
+
     let owner = Address::generate(&env);
     let limit_type = LimitType::Daily;
-    let asset = Symbol::short("XLM");
+    let asset = symbol_short!("XLM");
     let max_amount = 10000;
     let time_window = 86400;
-    
-    let limit_id = client.create_security_limit(
-        &owner,
-        &limit_type,
-        &asset,
-        &max_amount,
-        &time_window,
-    );
-    
+
+    let limit_id =
+        client.create_security_limit(&owner, &limit_type, &asset, &max_amount, &time_window);
+
     assert_eq!(limit_id, 1);
 }
 
 #[test]
 fn test_check_transaction_allowed() {
     let env = Env::default();
+    env.mock_all_auths();
     let contract_id = env.register_contract(None, SecurityLimitsContract);
     let client = SecurityLimitsContractClient::new(&env, &contract_id);
-    
-    client.initialize();
-    
+
+    client.initialize(&None);
+
     let owner = Address::generate(&env);
-    let asset = Symbol::short("XLM");
+    let asset = symbol_short!("XLM");
     let limit_type = LimitType::Daily;
     let max_amount = 10000;
     let time_window = 86400;
-    
-    client.create_security_limit(
-        &owner,
-        &limit_type,
-        &asset,
-        &max_amount,
-        &time_window,
-    );
-    
+
+    client.create_security_limit(&owner, &limit_type, &asset, &max_amount, &time_window);
+
     // Test transaction within limit
-    let allowed = client.check_transaction_allowed(&owner, &asset, &5000);
+    let allowed = client.check_transaction_allowed(&owner, &asset, &5000, &None);
     assert!(allowed);
-    
+
     // Test transaction exceeding limit
-    let allowed = client.check_transaction_allowed(&owner, &asset, &15000);
+    let allowed = client.check_transaction_allowed(&owner, &asset, &15000, &None);
     assert!(!allowed);
 }
 
 #[test]
 fn test_record_transaction() {
     let env = Env::default();
+    env.mock_all_auths();
     let contract_id = env.register_contract(None, SecurityLimitsContract);
     let client = SecurityLimitsContractClient::new(&env, &contract_id);
-    
-    client.initialize();
-    
+
+    client.initialize(&None);
+
     let owner = Address::generate(&env);
-    let asset = Symbol::short("XLM");
+    let asset = symbol_short!("XLM");
     let amount = 1000;
     let tx_hash = BytesN::from_array(&env, &[1u8; 32]);
-    
-    let tx_id = client.record_transaction(&owner, &asset, &amount, &tx_hash);
+
+    let tx_id = client.record_transaction(&owner, &asset, &amount, &None, &tx_hash);
     assert_eq!(tx_id, 1);
 }
 
 #[test]
 fn test_set_risk_profile() {
     let env = Env::default();
+    env.mock_all_auths();
     let contract_id = env.register_contract(None, SecurityLimitsContract);
     let client = SecurityLimitsContractClient::new(&env, &contract_id);
-    
-    client.initialize();
-    
+
+    client.initialize(&None);
+
     let owner = Address::generate(&env);
     let risk_level = RiskLevel::Medium;
     let max_daily_volume = 50000;
     let max_single_transaction = 10000;
-    let allowed_assets = vec![&env, Symbol::short("XLM"), Symbol::short("USDC")];
-    let blacklisted_assets = vec![&env, Symbol::short("SCAM")];
-    
+    let allowed_assets = vec![&env, symbol_short!("XLM"), symbol_short!("USDC")];
+    let blacklisted_assets = vec![&env, symbol_short!("SCAM")];
+
     client.set_risk_profile(
         &owner,
         &risk_level,
@@ -111,7 +114,7 @@ fn test_set_risk_profile() {
         &allowed_assets,
         &blacklisted_assets,
     );
-    
+
     let profile = client.get_risk_profile(&owner);
     assert!(profile.is_some());
     assert_eq!(profile.unwrap().risk_level, RiskLevel::Medium);
@@ -120,22 +123,23 @@ fn test_set_risk_profile() {
 #[test]
 fn test_is_asset_allowed() {
     let env = Env::default();
+    env.mock_all_auths();
     let contract_id = env.register_contract(None, SecurityLimitsContract);
     let client = SecurityLimitsContractClient::new(&env, &contract_id);
-    
-    client.initialize();
-    
+
+    client.initialize(&None);
+
     let owner = Address::generate(&env);
-    let allowed_asset = Symbol::short("XLM");
-    let blacklisted_asset = Symbol::short("SCAM");
-    
+    let allowed_asset = symbol_short!("XLM");
+    let blacklisted_asset = symbol_short!("SCAM");
+
     // Set risk profile with allowed and blacklisted assets
     let risk_level = RiskLevel::Medium;
     let max_daily_volume = 50000;
     let max_single_transaction = 10000;
     let allowed_assets = vec![&env, allowed_asset.clone()];
     let blacklisted_assets = vec![&env, blacklisted_asset.clone()];
-    
+
     client.set_risk_profile(
         &owner,
         &risk_level,
@@ -144,13 +148,3284 @@ fn test_is_asset_allowed() {
         &allowed_assets,
         &blacklisted_assets,
     );
-    
+
     // Test allowed asset
     let allowed = client.is_asset_allowed(&owner, &allowed_asset);
     assert!(allowed);
-    
+
     // Test blacklisted asset
     let allowed = client.is_asset_allowed(&owner, &blacklisted_asset);
     assert!(!allowed);
 }
 
+#[test]
+fn test_asset_lists_are_edited_in_place() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDC");
+    let scam = symbol_short!("SCAM");
+
+    // The first edit creates an uncapped profile
+    client.add_blacklisted_asset(&owner, &scam);
+    assert_last_event(
+        &env,
+        &client.address,
+        (symbol_short!("block_add"), owner.clone()).into_val(&env),
+        scam.into_val(&env),
+    );
+    let profile = client.get_risk_profile(&owner).unwrap();
+    assert_eq!(profile.risk_level, RiskLevel::Low);
+    assert_eq!(profile.blacklisted_assets, vec![&env, scam.clone()]);
+    assert!(!client.is_asset_allowed(&owner, &scam));
+
+    client.add_allowed_asset(&owner, &xlm);
+    client.add_allowed_asset(&owner, &usdc);
+    client.add_allowed_asset(&owner, &xlm);
+    client.remove_allowed_asset(&owner, &usdc);
+    assert_eq!(
+        client.get_risk_profile(&owner).unwrap().allowed_assets,
+        vec![&env, xlm.clone()]
+    );
+    assert!(!client.is_asset_allowed(&owner, &usdc));
+
+    // Unblocking an asset loosens protection, so a co-signer must approve it
+    client.set_co_signer(&owner, &Some(Address::generate(&env)));
+    assert_eq!(
+        client.try_remove_blacklisted_asset(&owner, &scam),
+        Err(Ok(LimitsError::CoSignRequired))
+    );
+    // So does widening the allow-list, or emptying it to allow everything
+    assert_eq!(
+        client.try_add_allowed_asset(&owner, &usdc),
+        Err(Ok(LimitsError::CoSignRequired))
+    );
+    assert_eq!(
+        client.try_remove_allowed_asset(&owner, &xlm),
+        Err(Ok(LimitsError::CoSignRequired))
+    );
+    client.set_co_signer(&owner, &None);
+    client.remove_blacklisted_asset(&owner, &scam);
+    assert!(client
+        .get_risk_profile(&owner)
+        .unwrap()
+        .blacklisted_assets
+        .is_empty());
+}
+
+#[test]
+fn test_write_entrypoints_require_owner_auth() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, SecurityLimitsContract);
+    let client = SecurityLimitsContractClient::new(&env, &contract_id);
+
+    client.initialize(&None);
+
+    let owner = Address::generate(&env);
+    let asset = symbol_short!("XLM");
+
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &asset, &10000, &86400);
+    assert_eq!(env.auths().first().unwrap().0, owner);
+
+    client.update_security_limit(&limit_id, &owner, &20000, &86400, &true);
+    assert_eq!(env.auths().first().unwrap().0, owner);
+
+    client.record_transaction(
+        &owner,
+        &asset,
+        &100,
+        &None,
+        &BytesN::from_array(&env, &[2u8; 32]),
+    );
+    assert_eq!(env.auths().first().unwrap().0, owner);
+
+    client.set_risk_profile(
+        &owner,
+        &RiskLevel::Low,
+        &1000,
+        &100,
+        &vec![&env],
+        &vec![&env],
+    );
+    assert_eq!(env.auths().first().unwrap().0, owner);
+
+    client.delete_security_limit(&limit_id, &owner);
+    assert_eq!(env.auths().first().unwrap().0, owner);
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_create_security_limit_without_auth_panics() {
+    let env = Env::default();
+    let contract_id = env.register_contract(None, SecurityLimitsContract);
+    let client = SecurityLimitsContractClient::new(&env, &contract_id);
+
+    client.initialize(&None);
+
+    let owner = Address::generate(&env);
+    client.create_security_limit(
+        &owner,
+        &LimitType::Daily,
+        &symbol_short!("XLM"),
+        &10000,
+        &86400,
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_record_transaction_signed_by_other_address_panics() {
+    let env = Env::default();
+    let contract_id = env.register_contract(None, SecurityLimitsContract);
+    let client = SecurityLimitsContractClient::new(&env, &contract_id);
+
+    client.initialize(&None);
+
+    let owner = Address::generate(&env);
+    let attacker = Address::generate(&env);
+    let asset = symbol_short!("XLM");
+    let tx_hash = BytesN::from_array(&env, &[3u8; 32]);
+
+    // Only the attacker signs; usage for `owner` must be rejected.
+    client
+        .mock_auths(&[MockAuth {
+            address: &attacker,
+            invoke: &MockAuthInvoke {
+                contract: &contract_id,
+                fn_name: "record_transaction",
+                args: (
+                    owner.clone(),
+                    asset.clone(),
+                    5000i128,
+                    None::<Address>,
+                    tx_hash.clone(),
+                )
+                    .into_val(&env),
+                sub_invokes: &[],
+            },
+        }])
+        .record_transaction(&owner, &asset, &5000, &None, &tx_hash);
+}
+
+#[test]
+fn test_registered_reporter_records_usage() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let reporter = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let tx_hash = BytesN::from_array(&env, &[4u8; 32]);
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+
+    assert_eq!(
+        client.try_report_transaction(&reporter, &owner, &xlm, &900, &None, &tx_hash),
+        Err(Ok(LimitsError::NotReporter))
+    );
+
+    client.add_reporter(&owner, &reporter);
+    assert_eq!(env.auths().first().unwrap().0, owner);
+    assert_last_event(
+        &env,
+        &client.address,
+        (symbol_short!("rep_add"), owner.clone()).into_val(&env),
+        reporter.clone().into_val(&env),
+    );
+    client.add_reporter(&owner, &reporter);
+    assert_eq!(client.get_reporters(&owner), vec![&env, reporter.clone()]);
+
+    client.report_transaction(&reporter, &owner, &xlm, &900, &None, &tx_hash);
+    assert_eq!(env.auths().first().unwrap().0, reporter);
+    assert_eq!(
+        client.get_security_limit(&owner, &limit_id).current_usage,
+        900
+    );
+
+    client.remove_reporter(&owner, &reporter);
+    assert_eq!(
+        client.try_report_transaction(&reporter, &owner, &xlm, &50, &None, &tx_hash),
+        Err(Ok(LimitsError::NotReporter))
+    );
+    assert_eq!(
+        client.try_remove_reporter(&owner, &reporter),
+        Err(Ok(LimitsError::NotReporter))
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_delete_security_limit_without_auth_panics() {
+    let env = Env::default();
+    let contract_id = env.register_contract(None, SecurityLimitsContract);
+    let client = SecurityLimitsContractClient::new(&env, &contract_id);
+
+    client.initialize(&None);
+
+    let owner = Address::generate(&env);
+    let limit_id = client.mock_all_auths().create_security_limit(
+        &owner,
+        &LimitType::Daily,
+        &symbol_short!("XLM"),
+        &10000,
+        &86400,
+    );
+
+    client.delete_security_limit(&limit_id, &owner);
+}
+
+/// Assert that the most recent event was published by `contract` with the
+/// given topics and data.
+fn assert_last_event(env: &Env, contract: &Address, topics: Vec<Val>, data: Val) {
+    let events = env.events().all();
+    assert_eq!(
+        events.slice(events.len() - 1..),
+        vec![env, (contract.clone(), topics, data)]
+    );
+}
+
+fn setup() -> (Env, SecurityLimitsContractClient<'static>) {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, SecurityLimitsContract);
+    let client = SecurityLimitsContractClient::new(&env, &contract_id);
+    client.initialize(&None);
+    (env, client)
+}
+
+#[test]
+fn test_initialize_twice_returns_error() {
+    let (_env, client) = setup();
+    assert_eq!(
+        client.try_initialize(&None),
+        Err(Ok(LimitsError::AlreadyInitialized))
+    );
+}
+
+#[test]
+fn test_update_missing_limit_returns_not_found() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    assert_eq!(
+        client.try_update_security_limit(&42, &owner, &100, &86400, &true),
+        Err(Ok(LimitsError::LimitNotFound))
+    );
+    assert_eq!(
+        client.try_delete_security_limit(&42, &owner),
+        Err(Ok(LimitsError::LimitNotFound))
+    );
+}
+
+#[test]
+fn test_foreign_owner_cannot_reach_limit() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let other = Address::generate(&env);
+    let limit_id = client.create_security_limit(
+        &owner,
+        &LimitType::Daily,
+        &symbol_short!("XLM"),
+        &10000,
+        &86400,
+    );
+
+    // Limits are namespaced by owner, so another owner's ID does not resolve
+    assert_eq!(
+        client.try_update_security_limit(&limit_id, &other, &1, &86400, &true),
+        Err(Ok(LimitsError::LimitNotFound))
+    );
+    assert_eq!(
+        client.try_delete_security_limit(&limit_id, &other),
+        Err(Ok(LimitsError::LimitNotFound))
+    );
+    assert_eq!(
+        client.get_security_limit(&owner, &limit_id).max_amount,
+        10000
+    );
+}
+
+#[test]
+fn test_zero_amounts_return_invalid_amount() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let asset = symbol_short!("XLM");
+
+    assert_eq!(
+        client.try_create_security_limit(&owner, &LimitType::Daily, &asset, &0, &86400),
+        Err(Ok(LimitsError::InvalidAmount))
+    );
+    assert_eq!(
+        client.try_record_transaction(
+            &owner,
+            &asset,
+            &0,
+            &None,
+            &BytesN::from_array(&env, &[0u8; 32])
+        ),
+        Err(Ok(LimitsError::InvalidAmount))
+    );
+}
+
+#[test]
+fn test_validate_transaction_reports_reason() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let scam = symbol_short!("SCAM");
+
+    client.create_security_limit(&owner, &LimitType::Daily, &xlm, &10000, &86400);
+    client.set_risk_profile(
+        &owner,
+        &RiskLevel::Medium,
+        &50000,
+        &10000,
+        &vec![&env],
+        &vec![&env, scam.clone()],
+    );
+
+    assert_eq!(
+        client.try_validate_transaction(&owner, &xlm, &5000, &None),
+        Ok(Ok(()))
+    );
+    assert_eq!(
+        client.try_validate_transaction(&owner, &xlm, &15000, &None),
+        Err(Ok(LimitsError::LimitExceeded))
+    );
+    assert_eq!(
+        client.try_validate_transaction(&owner, &scam, &1, &None),
+        Err(Ok(LimitsError::AssetBlocked))
+    );
+}
+
+#[test]
+fn test_events_emitted_for_limit_lifecycle() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+    assert_last_event(
+        &env,
+        &client.address,
+        (symbol_short!("lim_new"), owner.clone()).into_val(&env),
+        (limit_id, xlm.clone(), 1000i128).into_val(&env),
+    );
+
+    client.update_security_limit(&limit_id, &owner, &2000, &86400, &false);
+    assert_last_event(
+        &env,
+        &client.address,
+        (symbol_short!("lim_upd"), owner.clone()).into_val(&env),
+        (limit_id, 2000i128, 86400u64, false).into_val(&env),
+    );
+
+    client.delete_security_limit(&limit_id, &owner);
+    assert_last_event(
+        &env,
+        &client.address,
+        (symbol_short!("lim_del"), owner.clone()).into_val(&env),
+        limit_id.into_val(&env),
+    );
+}
+
+#[test]
+fn test_events_emitted_for_profile_and_transactions() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+
+    client.set_risk_profile(
+        &owner,
+        &RiskLevel::High,
+        &50000,
+        &10000,
+        &vec![&env],
+        &vec![&env],
+    );
+    assert_last_event(
+        &env,
+        &client.address,
+        (symbol_short!("profile"), owner.clone()).into_val(&env),
+        RiskLevel::High.into_val(&env),
+    );
+
+    let tx_id = client.record_transaction(
+        &owner,
+        &xlm,
+        &250,
+        &None,
+        &BytesN::from_array(&env, &[4u8; 32]),
+    );
+    assert_last_event(
+        &env,
+        &client.address,
+        (symbol_short!("tx_rec"), owner.clone()).into_val(&env),
+        (tx_id, xlm.clone(), 250i128).into_val(&env),
+    );
+}
+
+#[test]
+fn test_breach_event_when_usage_exceeds_limit() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+
+    client.record_transaction(
+        &owner,
+        &xlm,
+        &1500,
+        &None,
+        &BytesN::from_array(&env, &[5u8; 32]),
+    );
+    assert_last_event(
+        &env,
+        &client.address,
+        (symbol_short!("breach"), owner.clone()).into_val(&env),
+        (limit_id, xlm.clone(), 1500i128, 1000i128).into_val(&env),
+    );
+}
+
+#[test]
+fn test_get_security_limits_paginates_per_owner() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let other = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+
+    for i in 1..=5i128 {
+        client.create_security_limit(&owner, &LimitType::Daily, &xlm, &(i * 100), &86400);
+    }
+    client.create_security_limit(&other, &LimitType::Daily, &xlm, &999, &86400);
+
+    assert_eq!(client.get_security_limit_count(&owner), 5);
+    assert_eq!(client.get_security_limit_count(&other), 1);
+
+    let first = client.get_security_limits(&owner, &0, &2);
+    assert_eq!(first.len(), 2);
+    assert_eq!(first.get(0).unwrap().max_amount, 100);
+    assert_eq!(first.get(1).unwrap().max_amount, 200);
+
+    let last = client.get_security_limits(&owner, &4, &10);
+    assert_eq!(last.len(), 1);
+    assert_eq!(last.get(0).unwrap().max_amount, 500);
+
+    assert_eq!(client.get_security_limits(&owner, &10, &10).len(), 0);
+    assert_eq!(client.get_security_limits(&other, &0, &10).len(), 1);
+}
+
+#[test]
+fn test_delete_removes_limit_from_owner_index() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+
+    let first = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &100, &86400);
+    let second = client.create_security_limit(&owner, &LimitType::Weekly, &xlm, &200, &604800);
+    client.delete_security_limit(&first, &owner);
+
+    let remaining = client.get_security_limits(&owner, &0, &10);
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining.get(0).unwrap().id, second);
+    assert_eq!(
+        client.try_get_security_limit(&owner, &first),
+        Err(Ok(LimitsError::LimitNotFound))
+    );
+}
+
+#[test]
+fn test_usage_is_isolated_between_owners() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let other = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+
+    client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+    let other_limit = client.create_security_limit(&other, &LimitType::Daily, &xlm, &1000, &86400);
+
+    client.record_transaction(
+        &owner,
+        &xlm,
+        &900,
+        &None,
+        &BytesN::from_array(&env, &[6u8; 32]),
+    );
+
+    assert!(!client.check_transaction_allowed(&owner, &xlm, &200, &None));
+    assert!(client.check_transaction_allowed(&other, &xlm, &200, &None));
+    assert_eq!(
+        client
+            .get_security_limit(&other, &other_limit)
+            .current_usage,
+        0
+    );
+}
+
+/// Minimal protected contract that gates a state change on the limits hook.
+#[contract]
+pub struct ProtectedContract;
+
+#[contractimpl]
+impl ProtectedContract {
+    pub fn guarded_transfer(
+        env: Env,
+        limits: Address,
+        owner: Address,
+        asset: Symbol,
+        amount: i128,
+    ) -> u64 {
+        owner.require_auth();
+        env.storage()
+            .instance()
+            .set(&symbol_short!("MOVED"), &amount);
+        SecurityLimitsContractClient::new(&env, &limits)
+            .authorize_or_fail(&owner, &asset, &amount, &None)
+    }
+
+    pub fn moved(env: Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&symbol_short!("MOVED"))
+            .unwrap_or(0)
+    }
+}
+
+#[test]
+fn test_authorize_or_fail_records_usage() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+
+    assert_eq!(client.authorize_or_fail(&owner, &xlm, &400, &None), 1);
+    assert_eq!(client.authorize_or_fail(&owner, &xlm, &600, &None), 2);
+    assert_eq!(
+        client.get_security_limit(&owner, &limit_id).current_usage,
+        1000
+    );
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &xlm, &1, &None),
+        Err(Ok(LimitsError::LimitExceeded))
+    );
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &xlm, &0, &None),
+        Err(Ok(LimitsError::InvalidAmount))
+    );
+}
+
+#[test]
+fn test_authorize_or_fail_rejects_blocked_asset() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+    client.set_risk_profile(
+        &owner,
+        &RiskLevel::High,
+        &10_000,
+        &1000,
+        &Vec::new(&env),
+        &vec![&env, xlm.clone()],
+    );
+
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &xlm, &10, &None),
+        Err(Ok(LimitsError::AssetBlocked))
+    );
+    assert_eq!(
+        client.get_security_limit(&owner, &limit_id).current_usage,
+        0
+    );
+}
+
+#[test]
+fn test_authorize_or_fail_requires_owner_auth() {
+    let env = Env::default();
+    let contract_id = env.register_contract(None, SecurityLimitsContract);
+    let client = SecurityLimitsContractClient::new(&env, &contract_id);
+    client.initialize(&None);
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+
+    client
+        .mock_auths(&[MockAuth {
+            address: &owner,
+            invoke: &MockAuthInvoke {
+                contract: &contract_id,
+                fn_name: "authorize_or_fail",
+                args: (owner.clone(), xlm.clone(), 5i128, None::<Address>).into_val(&env),
+                sub_invokes: &[],
+            },
+        }])
+        .authorize_or_fail(&owner, &xlm, &5, &None);
+
+    let intruder = Address::generate(&env);
+    assert!(client
+        .mock_auths(&[MockAuth {
+            address: &intruder,
+            invoke: &MockAuthInvoke {
+                contract: &contract_id,
+                fn_name: "authorize_or_fail",
+                args: (owner.clone(), xlm.clone(), 5i128, None::<Address>).into_val(&env),
+                sub_invokes: &[],
+            },
+        }])
+        .try_authorize_or_fail(&owner, &xlm, &5, &None)
+        .is_err());
+}
+
+#[test]
+fn test_authorize_or_fail_aborts_calling_contract() {
+    let (env, client) = setup();
+    let protected_id = env.register_contract(None, ProtectedContract);
+    let protected = ProtectedContractClient::new(&env, &protected_id);
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+
+    assert_eq!(
+        protected.guarded_transfer(&client.address, &owner, &xlm, &700),
+        1
+    );
+    assert_eq!(protected.moved(), 700);
+
+    // Over the limit: the hook fails and the caller's write is rolled back.
+    assert!(protected
+        .try_guarded_transfer(&client.address, &owner, &xlm, &500)
+        .is_err());
+    assert_eq!(protected.moved(), 700);
+    assert_eq!(
+        client.get_security_limit(&owner, &limit_id).current_usage,
+        700
+    );
+}
+
+fn set_time(env: &Env, timestamp: u64) {
+    env.ledger().with_mut(|li| li.timestamp = timestamp);
+}
+
+#[test]
+fn test_rolling_window_blocks_boundary_double_spend() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let fixed = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+    let hash = BytesN::from_array(&env, &[7u8; 32]);
+
+    // Fixed window: spend the max just before the boundary and again after
+    set_time(&env, 86_000);
+    client.record_transaction(&owner, &xlm, &1000, &None, &hash);
+    set_time(&env, 86_401);
+    assert!(client.check_transaction_allowed(&owner, &xlm, &1000, &None));
+
+    // Rolling window: the earlier spend still counts after the boundary
+    client.delete_security_limit(&fixed, &owner);
+    let rolling = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+    client.set_limit_window_mode(&rolling, &owner, &WindowMode::Rolling);
+
+    set_time(&env, 86_000);
+    client.record_transaction(&owner, &xlm, &1000, &None, &hash);
+    assert_eq!(
+        client.get_security_limit(&owner, &rolling).current_usage,
+        1000
+    );
+    set_time(&env, 86_401);
+    assert!(!client.check_transaction_allowed(&owner, &xlm, &1, &None));
+
+    // Allowed again once the spend's bucket has left the window
+    set_time(&env, 169_199);
+    assert!(!client.check_transaction_allowed(&owner, &xlm, &1, &None));
+    set_time(&env, 169_200);
+    assert!(client.check_transaction_allowed(&owner, &xlm, &1000, &None));
+}
+
+#[test]
+fn test_simulate_check_explains_each_limit() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    set_time(&env, 1000);
+
+    let daily = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+    let hourly = client.create_security_limit(&owner, &LimitType::PerHour, &xlm, &500, &3600);
+    client.set_limit_window_mode(&hourly, &owner, &WindowMode::Rolling);
+    let per_dest = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &10, &86400);
+    client.set_limit_scope(&per_dest, &owner, &LimitScope::PerDestination);
+    client.record_transaction(
+        &owner,
+        &xlm,
+        &300,
+        &None,
+        &BytesN::from_array(&env, &[20; 32]),
+    );
+
+    // The hourly limit blocks; its 150-second bucket from 900 ages out at 4500
+    assert_eq!(
+        client.simulate_check(&owner, &xlm, &300),
+        vec![
+            &env,
+            LimitCheck {
+                limit_id: daily,
+                blocks: false,
+                headroom: 700,
+                resets_at: 1000 + 86400,
+            },
+            LimitCheck {
+                limit_id: hourly,
+                blocks: true,
+                headroom: 200,
+                resets_at: 4500,
+            },
+        ]
+    );
+
+    // Simulating past the boundary does not store the reset
+    set_time(&env, 1000 + 86400 + 10);
+    assert_eq!(
+        client.simulate_check(&owner, &xlm, &300).get(0).unwrap(),
+        LimitCheck {
+            limit_id: daily,
+            blocks: false,
+            headroom: 1000,
+            resets_at: 1000 + 86400 + 10,
+        }
+    );
+    assert_eq!(client.get_security_limit(&owner, &daily).last_reset, 1000);
+}
+
+#[test]
+fn test_fixed_window_reset_is_persisted_across_boundaries() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    set_time(&env, 1000);
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+    let hash = BytesN::from_array(&env, &[9u8; 32]);
+
+    client.record_transaction(&owner, &xlm, &800, &None, &hash);
+    assert!(!client.check_transaction_allowed(&owner, &xlm, &201, &None));
+
+    // A check past the boundary stores the new window, aligned to the first
+    set_time(&env, 1000 + 86400 + 500);
+    assert!(client.check_transaction_allowed(&owner, &xlm, &1000, &None));
+    let limit = client.get_security_limit(&owner, &limit_id);
+    assert_eq!((limit.current_usage, limit.last_reset), (0, 1000 + 86400));
+
+    // Later windows are still enforced, and records reset on their own
+    client.record_transaction(&owner, &xlm, &900, &None, &hash);
+    assert!(!client.check_transaction_allowed(&owner, &xlm, &101, &None));
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &xlm, &101, &None),
+        Err(Ok(LimitsError::LimitExceeded))
+    );
+
+    set_time(&env, 1000 + 3 * 86400 + 7);
+    client.record_transaction(&owner, &xlm, &1000, &None, &hash);
+    let limit = client.get_security_limit(&owner, &limit_id);
+    assert_eq!(
+        (limit.current_usage, limit.last_reset),
+        (1000, 1000 + 3 * 86400)
+    );
+    assert_eq!(
+        client.remaining_allowance(&owner, &xlm),
+        vec![&env, (limit_id, 0i128, 1000u64 + 4 * 86400)]
+    );
+
+    // A shorter time_window shortens the fixed window
+    client.update_security_limit(&limit_id, &owner, &1000, &3600, &true);
+    set_time(&env, 1000 + 3 * 86400 + 3600);
+    assert!(client.check_transaction_allowed(&owner, &xlm, &1000, &None));
+}
+
+#[test]
+fn test_per_transaction_limit_checks_each_transaction_alone() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let limit_id = client.create_security_limit(&owner, &LimitType::PerTransaction, &xlm, &500, &0);
+    let hash = BytesN::from_array(&env, &[10u8; 32]);
+
+    client.record_transaction(&owner, &xlm, &400, &None, &hash);
+    client.authorize_or_fail(&owner, &xlm, &500, &None);
+    assert_eq!(
+        client.get_security_limit(&owner, &limit_id).current_usage,
+        0
+    );
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &xlm, &501, &None),
+        Err(Ok(LimitsError::LimitExceeded))
+    );
+}
+
+#[test]
+fn test_calendar_boundaries() {
+    // Thursday 2024-02-15 12:00 UTC, in a leap February
+    let now = 1_707_998_400;
+    let cases = [
+        (LimitType::PerHour, 1_707_998_400, 1_708_002_000),
+        (LimitType::Daily, 1_707_955_200, 1_708_041_600),
+        (LimitType::Weekly, 1_707_696_000, 1_708_300_800),
+        (LimitType::Monthly, 1_706_745_600, 1_709_251_200),
+    ];
+    for (limit_type, start, next) in cases {
+        assert_eq!(calendar::period_start(&limit_type, now), Some(start));
+        assert_eq!(calendar::next_boundary(&limit_type, now), Some(next));
+    }
+
+    // December rolls over into the next year
+    assert_eq!(
+        calendar::next_boundary(&LimitType::Monthly, 1_704_067_199),
+        Some(1_704_067_200)
+    );
+    assert_eq!(
+        calendar::period_start(&LimitType::PerTransaction, now),
+        None
+    );
+}
+
+#[test]
+fn test_calendar_limit_resets_at_midnight() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let midnight = 1_707_955_200;
+    let hash = BytesN::from_array(&env, &[11u8; 32]);
+
+    // Created late in the evening: a relative window would run until the
+    // next evening, a calendar one ends at midnight
+    set_time(&env, midnight - 600);
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+    client.set_limit_window_mode(&limit_id, &owner, &WindowMode::Calendar);
+    client.record_transaction(&owner, &xlm, &1000, &None, &hash);
+    assert_eq!(
+        client.remaining_allowance(&owner, &xlm),
+        vec![&env, (limit_id, 0i128, midnight)]
+    );
+
+    set_time(&env, midnight - 1);
+    assert!(!client.check_transaction_allowed(&owner, &xlm, &1, &None));
+    set_time(&env, midnight);
+    client.record_transaction(&owner, &xlm, &1000, &None, &hash);
+    let limit = client.get_security_limit(&owner, &limit_id);
+    assert_eq!((limit.current_usage, limit.last_reset), (1000, midnight));
+    assert!(!client.check_transaction_allowed(&owner, &xlm, &1, &None));
+}
+
+#[test]
+fn test_scheduled_limit_follows_local_business_day() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let midnight = 1_707_955_200;
+    let hash = BytesN::from_array(&env, &[12u8; 32]);
+
+    // Business day starting 06:00 in UTC+9, i.e. 21:00 UTC
+    let tokyo = ResetSchedule {
+        interval: 0,
+        anchor: 6 * 3600,
+        utc_offset: 9 * 3600,
+    };
+    let boundary = midnight - 3 * 3600;
+    assert_eq!(
+        calendar::scheduled_start(&tokyo, &LimitType::Daily, boundary - 1),
+        Some(boundary - 86400)
+    );
+    assert_eq!(
+        calendar::scheduled_next(&tokyo, &LimitType::Daily, boundary),
+        Some(boundary + 86400)
+    );
+    // Every two hours from 00:30 UTC
+    let shifts = ResetSchedule {
+        interval: 7200,
+        anchor: 1800,
+        utc_offset: 0,
+    };
+    assert_eq!(
+        calendar::scheduled_start(&shifts, &LimitType::Daily, midnight + 3600),
+        Some(midnight + 1800)
+    );
+
+    set_time(&env, boundary - 600);
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+    client.set_limit_window_mode(&limit_id, &owner, &WindowMode::Scheduled(tokyo));
+    client.record_transaction(&owner, &xlm, &1000, &None, &hash);
+    assert_eq!(
+        client.remaining_allowance(&owner, &xlm),
+        vec![&env, (limit_id, 0i128, boundary)]
+    );
+    set_time(&env, boundary);
+    assert!(client.check_transaction_allowed(&owner, &xlm, &1000, &None));
+
+    let per_tx = client.create_security_limit(&owner, &LimitType::PerTransaction, &xlm, &1000, &0);
+    assert_eq!(
+        client.try_set_limit_window_mode(&per_tx, &owner, &WindowMode::Scheduled(shifts)),
+        Err(Ok(LimitsError::InvalidSchedule))
+    );
+    let far_east = ResetSchedule {
+        utc_offset: 15 * 3600,
+        ..tokyo
+    };
+    assert_eq!(
+        client.try_set_limit_window_mode(&limit_id, &owner, &WindowMode::Scheduled(far_east)),
+        Err(Ok(LimitsError::InvalidSchedule))
+    );
+}
+
+#[test]
+fn test_rolling_window_ages_out_buckets_individually() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+    client.set_limit_window_mode(&limit_id, &owner, &WindowMode::Rolling);
+    let hash = BytesN::from_array(&env, &[8u8; 32]);
+
+    client.record_transaction(&owner, &xlm, &600, &None, &hash);
+    set_time(&env, 43_200);
+    client.record_transaction(&owner, &xlm, &300, &None, &hash);
+    assert!(!client.check_transaction_allowed(&owner, &xlm, &101, &None));
+
+    // The first bucket has expired; the half-day-old spend has not
+    set_time(&env, 90_000);
+    assert!(client.check_transaction_allowed(&owner, &xlm, &700, &None));
+    assert!(!client.check_transaction_allowed(&owner, &xlm, &701, &None));
+    client.record_transaction(&owner, &xlm, &700, &None, &hash);
+    assert_eq!(
+        client.get_security_limit(&owner, &limit_id).current_usage,
+        1000
+    );
+}
+
+#[test]
+fn test_set_limit_window_mode() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let other = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+    assert_eq!(
+        client.get_security_limit(&owner, &limit_id).window_mode,
+        WindowMode::Fixed
+    );
+
+    client.record_transaction(
+        &owner,
+        &xlm,
+        &400,
+        &None,
+        &BytesN::from_array(&env, &[9u8; 32]),
+    );
+    client.set_limit_window_mode(&limit_id, &owner, &WindowMode::Rolling);
+    assert_last_event(
+        &env,
+        &client.address,
+        (symbol_short!("lim_mode"), owner.clone()).into_val(&env),
+        (limit_id, WindowMode::Rolling).into_val(&env),
+    );
+
+    let limit = client.get_security_limit(&owner, &limit_id);
+    assert_eq!(limit.window_mode, WindowMode::Rolling);
+    assert_eq!(limit.current_usage, 0);
+    assert!(client.check_transaction_allowed(&owner, &xlm, &1000, &None));
+
+    assert_eq!(
+        client.try_set_limit_window_mode(&limit_id, &other, &WindowMode::Fixed),
+        Err(Ok(LimitsError::LimitNotFound))
+    );
+}
+
+#[test]
+fn test_per_destination_limit_caps_each_counterparty() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let usdc = symbol_short!("USDC");
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &usdc, &1000, &86400);
+    client.set_limit_scope(&limit_id, &owner, &LimitScope::PerDestination);
+    let hash = BytesN::from_array(&env, &[10u8; 32]);
+
+    client.record_transaction(&owner, &usdc, &800, &Some(alice.clone()), &hash);
+    assert!(!client.check_transaction_allowed(&owner, &usdc, &201, &Some(alice.clone())));
+    assert!(client.check_transaction_allowed(&owner, &usdc, &1000, &Some(bob.clone())));
+    // Transfers without a destination are outside the limit's scope
+    assert!(client.check_transaction_allowed(&owner, &usdc, &5000, &None));
+
+    client.record_transaction(&owner, &usdc, &600, &Some(bob.clone()), &hash);
+    assert_eq!(client.get_destination_usage(&owner, &limit_id, &alice), 800);
+    assert_eq!(client.get_destination_usage(&owner, &limit_id, &bob), 600);
+    assert_eq!(
+        client.get_security_limit(&owner, &limit_id).current_usage,
+        0
+    );
+
+    // Counters age out with the window
+    set_time(&env, 86_400);
+    assert_eq!(client.get_destination_usage(&owner, &limit_id, &alice), 0);
+    assert!(client.check_transaction_allowed(&owner, &usdc, &1000, &Some(alice.clone())));
+}
+
+#[test]
+fn test_per_destination_counters_follow_the_limit_window() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let usdc = symbol_short!("USDC");
+    let hash = BytesN::from_array(&env, &[10u8; 32]);
+    // Sunday noon, day 10 after the epoch
+    let sunday = 10 * 86_400 + 43_200;
+    set_time(&env, sunday);
+
+    let weekly = client.create_security_limit(&owner, &LimitType::Weekly, &usdc, &1000, &0);
+    client.set_limit_scope(&weekly, &owner, &LimitScope::PerDestination);
+    client.set_limit_window_mode(&weekly, &owner, &WindowMode::Calendar);
+    // Hourly windows of a daily limit shortened by `time_window`
+    let hourly = client.create_security_limit(&owner, &LimitType::Daily, &usdc, &5000, &3600);
+    client.set_limit_scope(&hourly, &owner, &LimitScope::PerDestination);
+
+    client.record_transaction(&owner, &usdc, &800, &Some(alice.clone()), &hash);
+    assert_eq!(client.get_destination_usage(&owner, &weekly, &alice), 800);
+    assert_eq!(client.get_destination_usage(&owner, &hourly, &alice), 800);
+
+    set_time(&env, sunday + 3600);
+    assert_eq!(client.get_destination_usage(&owner, &weekly, &alice), 800);
+    assert_eq!(client.get_destination_usage(&owner, &hourly, &alice), 0);
+
+    // The calendar week starts at Monday midnight
+    set_time(&env, 11 * 86_400);
+    assert_eq!(client.get_destination_usage(&owner, &weekly, &alice), 0);
+    assert!(client.check_transaction_allowed(&owner, &usdc, &1000, &Some(alice.clone())));
+}
+
+#[test]
+fn test_destination_limit_only_counts_its_address() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let exchange = Address::generate(&env);
+    let other = Address::generate(&env);
+    let usdc = symbol_short!("USDC");
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &usdc, &500, &86400);
+    client.set_limit_scope(
+        &limit_id,
+        &owner,
+        &LimitScope::Destination(exchange.clone()),
+    );
+    assert_last_event(
+        &env,
+        &client.address,
+        (symbol_short!("lim_scope"), owner.clone()).into_val(&env),
+        (limit_id, LimitScope::Destination(exchange.clone())).into_val(&env),
+    );
+
+    client.authorize_or_fail(&owner, &usdc, &2000, &Some(other.clone()));
+    assert_eq!(
+        client.get_security_limit(&owner, &limit_id).current_usage,
+        0
+    );
+
+    client.authorize_or_fail(&owner, &usdc, &400, &Some(exchange.clone()));
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &usdc, &101, &Some(exchange.clone())),
+        Err(Ok(LimitsError::LimitExceeded))
+    );
+    assert_eq!(
+        client.get_security_limit(&owner, &limit_id).current_usage,
+        400
+    );
+}
+
+#[test]
+fn test_net_flow_limit_credits_inflows() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let wallet = Address::generate(&env);
+    let usdc = symbol_short!("USDC");
+    set_time(&env, 1000);
+    let fixed = client.create_security_limit(&owner, &LimitType::Daily, &usdc, &1000, &86400);
+    let rolling = client.create_security_limit(&owner, &LimitType::Daily, &usdc, &1000, &86400);
+    let gross = client.create_security_limit(&owner, &LimitType::Daily, &usdc, &5000, &86400);
+    client.set_limit_scope(&fixed, &owner, &LimitScope::NetFlow);
+    client.set_limit_scope(&rolling, &owner, &LimitScope::NetFlow);
+    client.set_limit_window_mode(&rolling, &owner, &WindowMode::Rolling);
+
+    client.authorize_or_fail(&owner, &usdc, &800, &None);
+    // The owner alone cannot claim funds came back in
+    assert_eq!(
+        client.try_record_inflow(&owner, &owner, &usdc, &500),
+        Err(Ok(LimitsError::NotReporter))
+    );
+    client.add_reporter(&owner, &wallet);
+    assert_eq!(
+        client.try_record_inflow(&wallet, &owner, &usdc, &0),
+        Err(Ok(LimitsError::InvalidAmount))
+    );
+    client.record_inflow(&wallet, &owner, &usdc, &500);
+    assert_eq!(env.auths().first().unwrap().0, wallet);
+    assert_last_event(
+        &env,
+        &client.address,
+        (symbol_short!("inflow"), owner.clone()).into_val(&env),
+        (usdc.clone(), 500i128).into_val(&env),
+    );
+    let usage = |id: u64| client.get_security_limit(&owner, &id).current_usage;
+    assert_eq!(
+        (usage(fixed), usage(rolling), usage(gross)),
+        (300, 300, 800)
+    );
+
+    // Rebalanced funds can go back out, pure withdrawals still hit the cap
+    client.authorize_or_fail(&owner, &usdc, &700, &None);
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &usdc, &1, &None),
+        Err(Ok(LimitsError::LimitExceeded))
+    );
+
+    // Inflows free spent budget but never bank more than the cap
+    client.record_inflow(&wallet, &owner, &usdc, &5000);
+    assert_eq!((usage(fixed), usage(rolling), usage(gross)), (0, 0, 1500));
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &usdc, &1001, &None),
+        Err(Ok(LimitsError::LimitExceeded))
+    );
+    client.authorize_or_fail(&owner, &usdc, &1000, &None);
+}
+
+#[test]
+fn test_transaction_record_stores_destination() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let dest = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+
+    client.record_transaction(
+        &owner,
+        &xlm,
+        &100,
+        &Some(dest.clone()),
+        &BytesN::from_array(&env, &[11u8; 32]),
+    );
+
+    let records = client.get_transactions(&owner, &None, &0, &u64::MAX, &0, &10);
+    assert_eq!(records.get(0).unwrap().destination, Some(dest));
+}
+
+fn setup_with_admin() -> (Env, SecurityLimitsContractClient<'static>, Address) {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, SecurityLimitsContract);
+    let client = SecurityLimitsContractClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    client.initialize(&Some(admin.clone()));
+    (env, client, admin)
+}
+
+#[test]
+fn test_admin_actions_require_configured_admin() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+    assert_eq!(client.get_compliance_admin(), None);
+    assert_eq!(
+        client.try_force_lower_limit(&owner, &limit_id, &10),
+        Err(Ok(LimitsError::NotAuthorized))
+    );
+    assert_eq!(client.get_audit_log_count(), 0);
+}
+
+#[test]
+fn test_freeze_account_blocks_transactions() {
+    let (env, client, admin) = setup_with_admin();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+
+    let reason = String::from_str(&env, "sanctions screening");
+    client.freeze_account(&admin, &owner, &reason);
+    assert_eq!(
+        env.auths(),
+        std::vec![(
+            admin.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    client.address.clone(),
+                    Symbol::new(&env, "freeze_account"),
+                    (admin.clone(), owner.clone(), reason.clone()).into_val(&env),
+                )),
+                sub_invocations: std::vec![],
+            }
+        )]
+    );
+    assert!(client.is_account_frozen(&owner));
+    assert!(!client.check_transaction_allowed(&owner, &xlm, &1, &None));
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &xlm, &1, &None),
+        Err(Ok(LimitsError::AccountFrozen))
+    );
+
+    assert_eq!(
+        client.try_validate_transaction(&owner, &xlm, &1, &None),
+        Err(Ok(LimitsError::AccountFrozen))
+    );
+
+    // The owner cannot lift a freeze placed by the admin
+    assert_eq!(
+        client.try_unfreeze_account(&owner, &owner),
+        Err(Ok(LimitsError::NotAuthorized))
+    );
+
+    client.unfreeze_account(&admin, &owner);
+    assert!(client.check_transaction_allowed(&owner, &xlm, &1, &None));
+    assert_eq!(
+        client.try_unfreeze_account(&admin, &owner),
+        Err(Ok(LimitsError::AccountNotFrozen))
+    );
+}
+
+#[test]
+fn test_force_lower_limit() {
+    let (env, client, _admin) = setup_with_admin();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+
+    assert_eq!(
+        client.try_force_lower_limit(&owner, &limit_id, &1000),
+        Err(Ok(LimitsError::InvalidAmount))
+    );
+    assert_eq!(
+        client.try_force_lower_limit(&owner, &limit_id, &0),
+        Err(Ok(LimitsError::InvalidAmount))
+    );
+
+    client.force_lower_limit(&owner, &limit_id, &250);
+    assert_eq!(client.get_security_limit(&owner, &limit_id).max_amount, 250);
+    assert!(!client.check_transaction_allowed(&owner, &xlm, &251, &None));
+}
+
+#[test]
+fn test_exception_is_single_use_and_expires() {
+    let (env, client, _admin) = setup_with_admin();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+
+    assert_eq!(
+        client.try_approve_exception(&owner, &xlm, &5000, &0),
+        Err(Ok(LimitsError::InvalidExpiry))
+    );
+
+    client.approve_exception(&owner, &xlm, &5000, &3600);
+    assert!(client.check_transaction_allowed(&owner, &xlm, &5000, &None));
+    assert!(!client.check_transaction_allowed(&owner, &xlm, &5001, &None));
+
+    client.authorize_or_fail(&owner, &xlm, &5000, &None);
+    assert_eq!(client.get_exception(&owner, &xlm), None);
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &xlm, &1, &None),
+        Err(Ok(LimitsError::LimitExceeded))
+    );
+
+    // A fresh exception that lapses before use
+    let other = Address::generate(&env);
+    client.create_security_limit(&other, &LimitType::Daily, &xlm, &10, &86400);
+    client.approve_exception(&other, &xlm, &500, &3600);
+    set_time(&env, 3600);
+    assert!(!client.check_transaction_allowed(&other, &xlm, &500, &None));
+
+    assert_eq!(
+        client.try_revoke_exception(&owner, &xlm),
+        Err(Ok(LimitsError::ExceptionNotFound))
+    );
+    client.revoke_exception(&other, &xlm);
+    assert_eq!(client.get_exception(&other, &xlm), None);
+}
+
+#[test]
+fn test_audit_log_records_admin_actions() {
+    let (env, client, admin) = setup_with_admin();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+
+    let reason = String::from_str(&env, "court order");
+    client.freeze_account(&admin, &owner, &reason);
+    assert_last_event(
+        &env,
+        &client.address,
+        (symbol_short!("audit"), admin.clone()).into_val(&env),
+        (2u64, AuditAction::AccountFrozen(owner.clone(), reason)).into_val(&env),
+    );
+    client.unfreeze_account(&admin, &owner);
+    client.force_lower_limit(&owner, &limit_id, &500);
+    client.approve_exception(&owner, &xlm, &800, &100);
+
+    let successor = Address::generate(&env);
+    client.set_compliance_admin(&Some(successor.clone()));
+    assert_eq!(client.get_compliance_admin(), Some(successor.clone()));
+
+    assert_eq!(client.get_audit_log_count(), 6);
+    let page = client.get_audit_log(&0, &2);
+    assert_eq!(
+        page.get(0).unwrap().action,
+        AuditAction::AdminChanged(Some(admin.clone()))
+    );
+    assert_eq!(page.get(1).unwrap().id, 2);
+
+    let rest = client.get_audit_log(&2, &10);
+    assert_eq!(rest.len(), 4);
+    assert_eq!(
+        rest.get(1).unwrap().action,
+        AuditAction::LimitLowered(owner.clone(), limit_id, 1000, 500)
+    );
+    assert_eq!(
+        rest.get(3).unwrap().action,
+        AuditAction::AdminChanged(Some(successor))
+    );
+    assert_eq!(rest.get(3).unwrap().admin, admin);
+    assert!(client.get_audit_log(&6, &10).is_empty());
+}
+
+#[test]
+fn test_owner_can_freeze_own_account() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let stranger = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let reason = String::from_str(&env, "lost device");
+
+    assert_eq!(
+        client.try_freeze_account(&stranger, &owner, &reason),
+        Err(Ok(LimitsError::NotAuthorized))
+    );
+
+    client.freeze_account(&owner, &owner, &reason);
+    assert_last_event(
+        &env,
+        &client.address,
+        (symbol_short!("frozen"), owner.clone()).into_val(&env),
+        (owner.clone(), reason.clone()).into_val(&env),
+    );
+    let info = client.get_freeze_info(&owner).unwrap();
+    assert_eq!(info.reason, reason);
+    assert_eq!(info.frozen_by, owner);
+    assert!(!client.check_transaction_allowed(&owner, &xlm, &1, &None));
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &xlm, &1, &None),
+        Err(Ok(LimitsError::AccountFrozen))
+    );
+    assert_eq!(
+        client.try_freeze_account(&owner, &owner, &reason),
+        Err(Ok(LimitsError::AccountFrozen))
+    );
+    assert_eq!(
+        client.try_unfreeze_account(&stranger, &owner),
+        Err(Ok(LimitsError::NotAuthorized))
+    );
+
+    // Self-service freezes are not admin actions
+    assert_eq!(client.get_audit_log_count(), 0);
+
+    client.unfreeze_account(&owner, &owner);
+    assert_eq!(client.get_freeze_info(&owner), None);
+    assert!(client.check_transaction_allowed(&owner, &xlm, &1, &None));
+}
+
+#[test]
+fn test_admin_takes_over_self_freeze() {
+    let (env, client, admin) = setup_with_admin();
+    let owner = Address::generate(&env);
+
+    client.freeze_account(&owner, &owner, &String::from_str(&env, "travelling"));
+    client.freeze_account(&admin, &owner, &String::from_str(&env, "under review"));
+
+    assert_eq!(client.get_freeze_info(&owner).unwrap().frozen_by, admin);
+    assert_eq!(
+        client.try_unfreeze_account(&owner, &owner),
+        Err(Ok(LimitsError::NotAuthorized))
+    );
+}
+
+#[test]
+fn test_co_signer_gates_loosening_changes() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let co_signer = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let scam = symbol_short!("SCAM");
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+    client.set_risk_profile(
+        &owner,
+        &RiskLevel::Medium,
+        &10_000,
+        &1000,
+        &Vec::new(&env),
+        &vec![&env, scam.clone()],
+    );
+    client.set_co_signer(&owner, &Some(co_signer.clone()));
+    assert_eq!(client.get_co_signer(&owner), Some(co_signer.clone()));
+
+    // Tightening still goes straight through
+    client.update_security_limit(&limit_id, &owner, &500, &86400, &true);
+
+    assert_eq!(
+        client.try_update_security_limit(&limit_id, &owner, &2000, &86400, &true),
+        Err(Ok(LimitsError::CoSignRequired))
+    );
+    assert_eq!(
+        client.try_update_security_limit(&limit_id, &owner, &500, &86400, &false),
+        Err(Ok(LimitsError::CoSignRequired))
+    );
+    // A shorter window hands the budget back sooner
+    assert_eq!(
+        client.try_update_security_limit(&limit_id, &owner, &500, &3600, &true),
+        Err(Ok(LimitsError::CoSignRequired))
+    );
+    // Switching how usage is counted would zero it
+    assert_eq!(
+        client.try_set_limit_window_mode(&limit_id, &owner, &WindowMode::Rolling),
+        Err(Ok(LimitsError::CoSignRequired))
+    );
+    assert_eq!(
+        client.try_set_limit_scope(&limit_id, &owner, &LimitScope::PerDestination),
+        Err(Ok(LimitsError::CoSignRequired))
+    );
+    assert_eq!(
+        client.try_set_limit_pair(&limit_id, &owner, &Some(symbol_short!("USDC"))),
+        Err(Ok(LimitsError::CoSignRequired))
+    );
+    assert_eq!(
+        client.try_set_limit_denomination(&limit_id, &owner, &Denomination::Asset),
+        Err(Ok(LimitsError::CoSignRequired))
+    );
+    assert_eq!(
+        client.try_delete_security_limit(&limit_id, &owner),
+        Err(Ok(LimitsError::CoSignRequired))
+    );
+    assert_eq!(
+        client.try_set_risk_profile(
+            &owner,
+            &RiskLevel::Medium,
+            &10_000,
+            &1000,
+            &Vec::new(&env),
+            &Vec::new(&env),
+        ),
+        Err(Ok(LimitsError::CoSignRequired))
+    );
+    let blacklist = vec![&env, scam.clone(), symbol_short!("RUG")];
+    client.set_risk_profile(
+        &owner,
+        &RiskLevel::High,
+        &10_000,
+        &1000,
+        &Vec::new(&env),
+        &blacklist,
+    );
+
+    // Raising or lifting (zeroing) a cap loosens the profile
+    for (daily, single) in [(20_000, 1000), (0, 1000), (10_000, 2000), (10_000, 0)] {
+        assert_eq!(
+            client.try_set_risk_profile(
+                &owner,
+                &RiskLevel::High,
+                &daily,
+                &single,
+                &Vec::new(&env),
+                &blacklist,
+            ),
+            Err(Ok(LimitsError::CoSignRequired))
+        );
+    }
+
+    // Narrowing the allow-list is fine, widening or emptying it is not
+    client.set_risk_profile(
+        &owner,
+        &RiskLevel::High,
+        &5000,
+        &500,
+        &vec![&env, xlm.clone()],
+        &blacklist,
+    );
+    for allowed in [
+        vec![&env, xlm.clone(), symbol_short!("USDC")],
+        Vec::new(&env),
+    ] {
+        assert_eq!(
+            client.try_set_risk_profile(
+                &owner,
+                &RiskLevel::High,
+                &5000,
+                &500,
+                &allowed,
+                &blacklist,
+            ),
+            Err(Ok(LimitsError::CoSignRequired))
+        );
+    }
+}
+
+#[test]
+fn test_proposal_applies_after_co_signer_approval() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let co_signer = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+    client.set_co_signer(&owner, &Some(co_signer.clone()));
+
+    let change = ProposedChange::UpdateLimit(limit_id, 5000, 86400, true);
+    let proposal_id = client.propose_change(&owner, &change, &3600);
+    assert_eq!(
+        client.get_proposal(&owner, &proposal_id).unwrap().change,
+        change
+    );
+    // Nothing changes until the co-signer approves
+    assert_eq!(
+        client.get_security_limit(&owner, &limit_id).max_amount,
+        1000
+    );
+
+    client.approve_change(&owner, &proposal_id);
+    assert_eq!(
+        env.auths(),
+        std::vec![(
+            co_signer.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    client.address.clone(),
+                    Symbol::new(&env, "approve_change"),
+                    (owner.clone(), proposal_id).into_val(&env),
+                )),
+                sub_invocations: std::vec![],
+            }
+        )]
+    );
+    assert_eq!(
+        client.get_security_limit(&owner, &limit_id).max_amount,
+        5000
+    );
+    assert_eq!(client.get_proposal(&owner, &proposal_id), None);
+    assert_eq!(
+        client.try_approve_change(&owner, &proposal_id),
+        Err(Ok(LimitsError::ProposalNotFound))
+    );
+
+    client.record_transaction(
+        &owner,
+        &xlm,
+        &800,
+        &None,
+        &BytesN::from_array(&env, &[1; 32]),
+    );
+    let mode_id = client.propose_change(
+        &owner,
+        &ProposedChange::SetWindowMode(limit_id, WindowMode::Rolling),
+        &3600,
+    );
+    client.approve_change(&owner, &mode_id);
+    let limit = client.get_security_limit(&owner, &limit_id);
+    assert_eq!(limit.window_mode, WindowMode::Rolling);
+    assert_eq!(limit.current_usage, 0);
+
+    let scope_id = client.propose_change(
+        &owner,
+        &ProposedChange::SetScope(limit_id, LimitScope::PerDestination),
+        &3600,
+    );
+    client.approve_change(&owner, &scope_id);
+    assert_eq!(
+        client.get_security_limit(&owner, &limit_id).scope,
+        LimitScope::PerDestination
+    );
+
+    let usdc = Some(symbol_short!("USDC"));
+    let pair_id = client.propose_change(
+        &owner,
+        &ProposedChange::SetPair(limit_id, usdc.clone()),
+        &3600,
+    );
+    client.approve_change(&owner, &pair_id);
+    assert_eq!(
+        client.get_security_limit(&owner, &limit_id).pair_asset,
+        usdc
+    );
+
+    let denom_id = client.propose_change(
+        &owner,
+        &ProposedChange::SetDenomination(limit_id, Denomination::Asset),
+        &3600,
+    );
+    client.approve_change(&owner, &denom_id);
+    assert_last_event(
+        &env,
+        &client.address,
+        (symbol_short!("approved"), owner.clone()).into_val(&env),
+        denom_id.into_val(&env),
+    );
+
+    let delete_id = client.propose_change(&owner, &ProposedChange::DeleteLimit(limit_id), &3600);
+    client.approve_change(&owner, &delete_id);
+    assert_eq!(client.get_security_limit_count(&owner), 0);
+}
+
+#[test]
+fn test_proposal_expiry_and_cancellation() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let co_signer = Address::generate(&env);
+    let stranger = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+    client.set_co_signer(&owner, &Some(co_signer.clone()));
+    let change = ProposedChange::UpdateLimit(limit_id, 5000, 86400, true);
+
+    assert_eq!(
+        client.try_propose_change(&owner, &change, &0),
+        Err(Ok(LimitsError::InvalidExpiry))
+    );
+
+    let expiring = client.propose_change(&owner, &change, &100);
+    set_time(&env, 100);
+    assert_eq!(
+        client.try_approve_change(&owner, &expiring),
+        Err(Ok(LimitsError::ProposalExpired))
+    );
+    client.cancel_change(&owner, &owner, &expiring);
+    assert_last_event(
+        &env,
+        &client.address,
+        (symbol_short!("cancelled"), owner.clone()).into_val(&env),
+        expiring.into_val(&env),
+    );
+
+    let pending = client.propose_change(&owner, &change, &1000);
+    assert_eq!(
+        client.try_cancel_change(&stranger, &owner, &pending),
+        Err(Ok(LimitsError::NotAuthorized))
+    );
+    client.cancel_change(&co_signer, &owner, &pending);
+    assert_eq!(
+        client.try_approve_change(&owner, &pending),
+        Err(Ok(LimitsError::ProposalNotFound))
+    );
+    assert_eq!(
+        client.get_security_limit(&owner, &limit_id).max_amount,
+        1000
+    );
+}
+
+#[test]
+fn test_replacing_co_signer_requires_current_co_signer() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let co_signer = Address::generate(&env);
+    client.set_co_signer(&owner, &Some(co_signer.clone()));
+
+    // Reporters can credit inflows, so registering one needs the co-signer
+    client.add_reporter(&owner, &Address::generate(&env));
+    assert!(env.auths().iter().any(|(addr, _)| *addr == co_signer));
+
+    client.set_co_signer(&owner, &None);
+    let auths = env.auths();
+    assert_eq!(auths.len(), 2);
+    assert!(auths.iter().any(|(addr, _)| *addr == co_signer));
+    assert_eq!(client.get_co_signer(&owner), None);
+}
+
+#[test]
+fn test_get_transactions_filters_by_asset_and_time() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let other = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDC");
+    let hash = BytesN::from_array(&env, &[12u8; 32]);
+
+    // (timestamp, asset, amount)
+    let history = [
+        (100u64, &xlm, 1i128),
+        (200, &usdc, 2),
+        (200, &xlm, 3),
+        (300, &xlm, 4),
+        (400, &usdc, 5),
+        (500, &xlm, 6),
+    ];
+    for (timestamp, asset, amount) in history {
+        set_time(&env, timestamp);
+        client.record_transaction(&owner, asset, &amount, &None, &hash);
+        client.record_transaction(&other, asset, &100, &None, &hash);
+    }
+    assert_eq!(client.get_transaction_count(&owner), 6);
+
+    let amounts = |records: Vec<TransactionRecord>| -> std::vec::Vec<i128> {
+        records.iter().map(|r| r.amount).collect()
+    };
+
+    assert_eq!(
+        amounts(client.get_transactions(&owner, &None, &200, &400, &0, &10)),
+        [2, 3, 4, 5]
+    );
+    assert_eq!(
+        amounts(client.get_transactions(&owner, &Some(xlm.clone()), &150, &u64::MAX, &0, &10)),
+        [3, 4, 6]
+    );
+    assert_eq!(
+        amounts(client.get_transactions(&owner, &Some(xlm.clone()), &0, &u64::MAX, &1, &2)),
+        [3, 4]
+    );
+    assert!(client
+        .get_transactions(&owner, &Some(usdc), &450, &u64::MAX, &0, &10)
+        .is_empty());
+    assert!(client
+        .get_transactions(&owner, &None, &400, &100, &0, &10)
+        .is_empty());
+    assert_eq!(
+        client
+            .get_transactions(&owner, &None, &0, &u64::MAX, &0, &0)
+            .len(),
+        0
+    );
+}
+
+#[test]
+fn test_report_commitment_matches_period_totals() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDC");
+    for (time, asset, amount) in [
+        (100, &xlm, 40),
+        (200, &usdc, 25),
+        (300, &xlm, 60),
+        (400, &xlm, 5),
+    ] {
+        set_time(&env, time);
+        client.authorize_or_fail(&owner, asset, &amount, &None);
+    }
+
+    let totals = client.get_period_totals(&owner, &100, &300);
+    assert_eq!(
+        totals,
+        map![
+            &env,
+            (
+                usdc.clone(),
+                PeriodTotals {
+                    volume: 25,
+                    transaction_count: 1,
+                    largest_transaction: 25
+                }
+            ),
+            (
+                xlm.clone(),
+                PeriodTotals {
+                    volume: 100,
+                    transaction_count: 2,
+                    largest_transaction: 60
+                }
+            )
+        ]
+    );
+
+    assert_eq!(
+        client.try_commit_report(&owner, &100, &400),
+        Err(Ok(LimitsError::InvalidPeriod))
+    );
+    let digest = client.commit_report(&owner, &100, &300);
+    let expected: BytesN<32> = env
+        .crypto()
+        .sha256(&(owner.clone(), 100u64, 300u64, totals).to_xdr(&env))
+        .into();
+    assert_eq!(digest, expected);
+    assert_last_event(
+        &env,
+        &client.address,
+        (symbol_short!("report"), owner.clone()).into_val(&env),
+        (100u64, 300u64, digest.clone()).into_val(&env),
+    );
+    let report = client.get_report(&owner, &100, &300).unwrap();
+    assert_eq!((report.digest, report.committed_at), (digest, 400));
+    assert_eq!(
+        client.try_commit_report(&owner, &100, &300),
+        Err(Ok(LimitsError::ReportExists))
+    );
+}
+
+#[test]
+fn test_usage_stats_track_daily_and_weekly_volume() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let hash = BytesN::from_array(&env, &[13u8; 32]);
+
+    let empty = client.get_usage_stats(&owner, &xlm);
+    assert_eq!(empty.transaction_count, 0);
+    assert_eq!(empty.week_volume, 0);
+
+    // Day 0, day 3 and two transactions on day 7
+    for (timestamp, amount) in [
+        (1_000u64, 500i128),
+        (3 * 86400, 50),
+        (7 * 86400, 20),
+        (7 * 86400 + 60, 30),
+    ] {
+        set_time(&env, timestamp);
+        client.record_transaction(&owner, &xlm, &amount, &None, &hash);
+    }
+    client.record_transaction(&owner, &symbol_short!("USDC"), &9_999, &None, &hash);
+
+    let stats = client.get_usage_stats(&owner, &xlm);
+    assert_eq!(stats.today_volume, 50);
+    // Day 0 has left the seven-day window
+    assert_eq!(stats.week_volume, 100);
+    assert_eq!(stats.largest_transaction, 500);
+    assert_eq!(stats.total_volume, 600);
+    assert_eq!(stats.transaction_count, 4);
+
+    set_time(&env, 8 * 86400);
+    let stats = client.get_usage_stats(&owner, &xlm);
+    assert_eq!(stats.today_volume, 0);
+    assert_eq!(stats.week_volume, 100);
+}
+
+#[test]
+fn test_remaining_budgets_per_active_limit() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let hash = BytesN::from_array(&env, &[14u8; 32]);
+    let daily = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+    let rolling = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &600, &86400);
+    client.set_limit_window_mode(&rolling, &owner, &WindowMode::Rolling);
+    let inactive = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &100, &86400);
+    client.update_security_limit(&inactive, &owner, &100, &86400, &false);
+    let per_dest = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &100, &86400);
+    client.set_limit_scope(&per_dest, &owner, &LimitScope::PerDestination);
+
+    client.record_transaction(&owner, &xlm, &400, &None, &hash);
+
+    let budgets = client.get_remaining_budgets(&owner, &xlm);
+    assert_eq!(
+        budgets,
+        vec![
+            &env,
+            LimitBudget {
+                limit_id: daily,
+                max_amount: 1000,
+                usage: 400,
+                remaining: 600,
+            },
+            LimitBudget {
+                limit_id: rolling,
+                max_amount: 600,
+                usage: 400,
+                remaining: 200,
+            },
+        ]
+    );
+    assert!(client
+        .get_remaining_budgets(&owner, &symbol_short!("USDC"))
+        .is_empty());
+}
+
+#[test]
+fn test_pair_limit_only_counts_its_market() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDC");
+    let eurc = symbol_short!("EURC");
+    let pair = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &5000, &86400);
+    client.set_limit_pair(&pair, &owner, &Some(usdc.clone()));
+    assert_last_event(
+        &env,
+        &client.address,
+        (symbol_short!("lim_pair"), owner.clone()).into_val(&env),
+        (pair, Some(usdc.clone())).into_val(&env),
+    );
+
+    client.authorize_swap_or_fail(&owner, &xlm, &usdc, &4000);
+    assert_eq!(client.get_security_limit(&owner, &pair).current_usage, 4000);
+    assert!(!client.check_swap_allowed(&owner, &xlm, &usdc, &1001));
+    assert_eq!(
+        client.try_authorize_swap_or_fail(&owner, &xlm, &usdc, &1001),
+        Err(Ok(LimitsError::LimitExceeded))
+    );
+
+    // Other markets and plain transfers are outside the pair limit
+    assert!(client.check_swap_allowed(&owner, &xlm, &eurc, &9000));
+    assert!(client.check_transaction_allowed(&owner, &xlm, &9000, &None));
+    client.record_swap(
+        &owner,
+        &xlm,
+        &eurc,
+        &9000,
+        &BytesN::from_array(&env, &[15u8; 32]),
+    );
+    assert_eq!(client.get_security_limit(&owner, &pair).current_usage, 4000);
+
+    let swaps = client.get_transactions(&owner, &Some(xlm.clone()), &0, &u64::MAX, &0, &10);
+    assert_eq!(swaps.get(0).unwrap().counter_asset, Some(usdc));
+    assert_eq!(swaps.get(1).unwrap().counter_asset, Some(eurc));
+}
+
+#[test]
+fn test_plain_limits_also_cover_swaps() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDC");
+    let scam = symbol_short!("SCAM");
+    let plain = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+
+    client.authorize_swap_or_fail(&owner, &xlm, &usdc, &700);
+    assert_eq!(client.get_security_limit(&owner, &plain).current_usage, 700);
+    assert!(!client.check_swap_allowed(&owner, &xlm, &usdc, &301));
+
+    // Swapping into a blacklisted asset is blocked too
+    client.set_risk_profile(
+        &owner,
+        &RiskLevel::Medium,
+        &10_000,
+        &1000,
+        &Vec::new(&env),
+        &vec![&env, scam.clone()],
+    );
+    assert_eq!(
+        client.try_authorize_swap_or_fail(&owner, &xlm, &scam, &1),
+        Err(Ok(LimitsError::AssetBlocked))
+    );
+}
+
+/// Minimal stand-in for the price-oracle contract: serves per-pair prices
+/// stamped with the time they were set.
+#[contract]
+pub struct MockOracle;
+
+#[contractimpl]
+impl MockOracle {
+    pub fn set_price(env: Env, base: Symbol, quote: Symbol, price: i128) {
+        let entry = OraclePrice {
+            price,
+            timestamp: env.ledger().timestamp(),
+            pusher: env.current_contract_address(),
+        };
+        env.storage().instance().set(&(base, quote), &entry);
+    }
+
+    pub fn get_price(env: Env, base: Symbol, quote: Symbol) -> OraclePrice {
+        env.storage().instance().get(&(base, quote)).unwrap()
+    }
+}
+
+/// Register a mock oracle quoting XLM at 0.10 USD and EURC at 1.10 USD
+/// (6 decimals).
+fn setup_oracle(env: &Env) -> (Address, MockOracleClient<'_>) {
+    let oracle_id = env.register_contract(None, MockOracle);
+    let oracle = MockOracleClient::new(env, &oracle_id);
+    let usd = symbol_short!("USD");
+    oracle.set_price(&symbol_short!("XLM"), &usd, &100_000);
+    oracle.set_price(&symbol_short!("EURC"), &usd, &1_100_000);
+    (oracle_id, oracle)
+}
+
+#[test]
+fn test_category_limit_counts_only_tagged_transactions() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let usdc = symbol_short!("USDC");
+    let defi = symbol_short!("defi");
+    let hash = BytesN::from_array(&env, &[19u8; 32]);
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &usdc, &1000, &86400);
+    client.set_limit_scope(&limit_id, &owner, &LimitScope::Category(defi.clone()));
+
+    // Untagged and differently tagged transfers are not capped
+    client.record_transaction(&owner, &usdc, &5000, &None, &hash);
+    client.authorize_categorized_or_fail(&owner, &usdc, &5000, &None, &symbol_short!("transfer"));
+
+    client.authorize_categorized_or_fail(&owner, &usdc, &800, &None, &defi);
+    client.record_categorized_transaction(&owner, &usdc, &200, &None, &defi, &hash);
+    assert_eq!(
+        client.get_security_limit(&owner, &limit_id).current_usage,
+        1000
+    );
+    assert!(!client.check_categorized_allowed(&owner, &usdc, &1, &None, &defi));
+    assert_eq!(
+        client.try_authorize_categorized_or_fail(&owner, &usdc, &1, &None, &defi),
+        Err(Ok(LimitsError::LimitExceeded))
+    );
+    assert!(client.check_transaction_allowed(&owner, &usdc, &1, &None));
+}
+
+#[test]
+fn test_caller_limit_counts_only_that_contract() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let router = Address::generate(&env);
+    let lender = Address::generate(&env);
+    let usdc = symbol_short!("USDC");
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &usdc, &500, &86400);
+    client.set_limit_scope(&limit_id, &owner, &LimitScope::Caller(router.clone()));
+
+    client.authorize_from_caller_or_fail(&owner, &router, &usdc, &500, &None);
+    assert_eq!(
+        env.auths()
+            .iter()
+            .map(|(address, _)| address.clone())
+            .collect::<std::vec::Vec<_>>(),
+        [owner.clone(), router.clone()]
+    );
+    assert_eq!(
+        client.get_security_limit(&owner, &limit_id).current_usage,
+        500
+    );
+    assert_eq!(
+        client.try_authorize_from_caller_or_fail(&owner, &router, &usdc, &1, &None),
+        Err(Ok(LimitsError::LimitExceeded))
+    );
+
+    // Other callers and direct authorizations are not capped
+    client.authorize_from_caller_or_fail(&owner, &lender, &usdc, &2000, &None);
+    client.authorize_or_fail(&owner, &usdc, &2000, &None);
+}
+
+#[test]
+fn test_reference_limit_values_every_asset() {
+    let (env, client) = setup();
+    let (oracle_id, _oracle) = setup_oracle(&env);
+    let owner = Address::generate(&env);
+    let usd = symbol_short!("USD");
+    let xlm = symbol_short!("XLM");
+    let eurc = symbol_short!("EURC");
+    let hash = BytesN::from_array(&env, &[16u8; 32]);
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &usd, &1000, &86400);
+    let denomination = Denomination::Reference(Valuation {
+        oracle: oracle_id,
+        decimals: 6,
+        max_age: 300,
+    });
+    client.set_limit_denomination(&limit_id, &owner, &denomination);
+    assert_last_event(
+        &env,
+        &client.address,
+        (symbol_short!("lim_denom"), owner.clone()).into_val(&env),
+        (limit_id, denomination).into_val(&env),
+    );
+
+    // 5,000 XLM = 500 USD, 300 EURC = 330 USD, 100 USD = 100 USD
+    client.record_transaction(&owner, &xlm, &5000, &None, &hash);
+    client.record_transaction(&owner, &eurc, &300, &None, &hash);
+    client.record_transaction(&owner, &usd, &100, &None, &hash);
+    assert_eq!(
+        client.get_security_limit(&owner, &limit_id).current_usage,
+        930
+    );
+
+    assert!(client.check_transaction_allowed(&owner, &xlm, &700, &None));
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &xlm, &800, &None),
+        Err(Ok(LimitsError::LimitExceeded))
+    );
+}
+
+#[test]
+fn test_reference_limit_rejects_stale_or_missing_prices() {
+    let (env, client) = setup();
+    let (oracle_id, oracle) = setup_oracle(&env);
+    let owner = Address::generate(&env);
+    let usd = symbol_short!("USD");
+    let xlm = symbol_short!("XLM");
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &usd, &1000, &86400);
+
+    assert_eq!(
+        client.try_set_limit_denomination(
+            &limit_id,
+            &owner,
+            &Denomination::Reference(Valuation {
+                oracle: oracle_id.clone(),
+                decimals: 19,
+                max_age: 300,
+            }),
+        ),
+        Err(Ok(LimitsError::InvalidValuation))
+    );
+    client.set_limit_denomination(
+        &limit_id,
+        &owner,
+        &Denomination::Reference(Valuation {
+            oracle: oracle_id,
+            decimals: 6,
+            max_age: 300,
+        }),
+    );
+
+    set_time(&env, 301);
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &xlm, &10, &None),
+        Err(Ok(LimitsError::PriceStale))
+    );
+    assert!(!client.check_transaction_allowed(&owner, &xlm, &10, &None));
+
+    oracle.set_price(&xlm, &usd, &100_000);
+    client.authorize_or_fail(&owner, &xlm, &10, &None);
+
+    // No feed for this asset at all
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &symbol_short!("BTC"), &1, &None),
+        Err(Ok(LimitsError::PriceUnavailable))
+    );
+
+    // Reverting to a plain limit stops valuing other assets
+    client.set_limit_denomination(&limit_id, &owner, &Denomination::Asset);
+    assert!(client.check_transaction_allowed(&owner, &symbol_short!("BTC"), &1_000_000, &None));
+}
+
+#[test]
+fn test_portfolio_limit_combines_assets() {
+    let (env, client) = setup();
+    let (oracle_id, _oracle) = setup_oracle(&env);
+    let owner = Address::generate(&env);
+    let usd = symbol_short!("USD");
+    let xlm = symbol_short!("XLM");
+    let eurc = symbol_short!("EURC");
+    let hash = BytesN::from_array(&env, &[17u8; 32]);
+    let valuation = Valuation {
+        oracle: oracle_id,
+        decimals: 6,
+        max_age: 300,
+    };
+
+    assert_eq!(
+        client.try_create_portfolio_limit(
+            &owner,
+            &LimitType::Daily,
+            &usd,
+            &1000,
+            &86400,
+            &Valuation {
+                max_age: 0,
+                ..valuation.clone()
+            },
+        ),
+        Err(Ok(LimitsError::InvalidValuation))
+    );
+    let limit_id =
+        client.create_portfolio_limit(&owner, &LimitType::Daily, &usd, &1000, &86400, &valuation);
+    assert_eq!(
+        client.get_security_limit(&owner, &limit_id).denomination,
+        Denomination::Reference(valuation)
+    );
+
+    client.record_transaction(&owner, &xlm, &5000, &None, &hash);
+    client.record_transaction(&owner, &eurc, &300, &None, &hash);
+    client.record_transaction(&owner, &xlm, &1000, &None, &hash);
+    assert_eq!(
+        client.get_portfolio_breakdown(&owner, &limit_id),
+        map![&env, (xlm.clone(), 600i128), (eurc.clone(), 330i128)]
+    );
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &usd, &100, &None),
+        Err(Ok(LimitsError::LimitExceeded))
+    );
+
+    // The breakdown ages out with the window
+    set_time(&env, 86400);
+    assert!(client.get_portfolio_breakdown(&owner, &limit_id).is_empty());
+}
+
+#[test]
+fn test_portfolio_breakdown_follows_scheduled_periods() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let usd = symbol_short!("USD");
+    let xlm = symbol_short!("XLM");
+    let hash = BytesN::from_array(&env, &[18u8; 32]);
+    // Business day starting 06:00 in UTC+9, i.e. 21:00 UTC
+    let boundary = 1_707_955_200 - 3 * 3600;
+    set_time(&env, boundary - 600);
+    let (oracle_id, _oracle) = setup_oracle(&env);
+
+    let valuation = Valuation {
+        oracle: oracle_id,
+        decimals: 6,
+        max_age: 300,
+    };
+    let limit_id =
+        client.create_portfolio_limit(&owner, &LimitType::Daily, &usd, &1000, &86400, &valuation);
+    let tokyo = ResetSchedule {
+        interval: 0,
+        anchor: 6 * 3600,
+        utc_offset: 9 * 3600,
+    };
+    client.set_limit_window_mode(&limit_id, &owner, &WindowMode::Scheduled(tokyo));
+
+    client.record_transaction(&owner, &xlm, &5000, &None, &hash);
+    assert_eq!(
+        client.get_portfolio_breakdown(&owner, &limit_id),
+        map![&env, (xlm.clone(), 500i128)]
+    );
+
+    // The breakdown resets with the local business day, not the UTC one
+    set_time(&env, boundary);
+    assert!(client.get_portfolio_breakdown(&owner, &limit_id).is_empty());
+}
+
+fn template_params(env: &Env, blacklisted: Vec<Symbol>) -> RiskProfileParams {
+    RiskProfileParams {
+        risk_level: RiskLevel::Low,
+        max_daily_volume: 5_000,
+        max_single_transaction: 1_000,
+        allowed_assets: Vec::new(env),
+        blacklisted_assets: blacklisted,
+    }
+}
+
+fn template_limit(asset: Symbol, max_amount: i128) -> TemplateLimit {
+    TemplateLimit {
+        limit_type: LimitType::Daily,
+        asset,
+        max_amount,
+        time_window: 86400,
+    }
+}
+
+#[test]
+fn test_risk_level_installs_defaults_and_caps_volume() {
+    let (env, client, _admin) = setup_with_admin();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDC");
+    let eurc = symbol_short!("EURC");
+    let hash = BytesN::from_array(&env, &[18u8; 32]);
+
+    let defaults = vec![
+        &env,
+        template_limit(xlm.clone(), 5000),
+        template_limit(usdc.clone(), 1000),
+        template_limit(eurc.clone(), 700),
+    ];
+    assert_eq!(
+        client.try_set_risk_level_defaults(
+            &RiskLevel::Medium,
+            &vec![&env, template_limit(xlm.clone(), 0)]
+        ),
+        Err(Ok(LimitsError::InvalidAmount))
+    );
+    client.set_risk_level_defaults(&RiskLevel::Medium, &defaults);
+    assert_eq!(client.get_risk_level_defaults(&RiskLevel::Medium), defaults);
+
+    let xlm_id = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &8000, &86400);
+    let usdc_id = client.create_security_limit(&owner, &LimitType::Daily, &usdc, &500, &86400);
+    client.set_risk_profile(
+        &owner,
+        &RiskLevel::Medium,
+        &3000,
+        &2000,
+        &Vec::new(&env),
+        &Vec::new(&env),
+    );
+
+    // Looser limits are lowered, tighter ones kept, missing ones created
+    let limits = client.get_security_limits(&owner, &0, &10);
+    assert_eq!(limits.len(), 3);
+    assert_eq!(client.get_security_limit(&owner, &xlm_id).max_amount, 5000);
+    assert_eq!(client.get_security_limit(&owner, &usdc_id).max_amount, 500);
+    let created = limits.get(2).unwrap();
+    assert_eq!((created.asset, created.max_amount), (eurc, 700));
+
+    // The profile caps single transactions and each asset's daily volume
+    assert!(!client.check_transaction_allowed(&owner, &xlm, &2001, &None));
+    client.authorize_or_fail(&owner, &xlm, &2000, &None);
+    client.record_transaction(&owner, &xlm, &1000, &None, &hash);
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &xlm, &1, &None),
+        Err(Ok(LimitsError::LimitExceeded))
+    );
+    set_time(&env, 86400);
+    assert!(client.check_transaction_allowed(&owner, &xlm, &2000, &None));
+}
+
+#[test]
+fn test_default_limits_resolve_per_asset() {
+    let (env, client, _admin) = setup_with_admin();
+    let owner = Address::generate(&env);
+    let other = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDC");
+
+    let defaults = vec![
+        &env,
+        template_limit(xlm.clone(), 1000),
+        template_limit(usdc.clone(), 500),
+    ];
+    assert_eq!(
+        client.try_set_default_limits(&vec![&env, template_limit(xlm.clone(), -1)]),
+        Err(Ok(LimitsError::InvalidAmount))
+    );
+    client.set_default_limits(&defaults);
+    assert_eq!(client.get_default_limits(), defaults);
+
+    // Risk level defaults take precedence over the global ones per asset
+    client.set_risk_profile(
+        &owner,
+        &RiskLevel::Medium,
+        &1_000_000,
+        &1_000_000,
+        &Vec::new(&env),
+        &Vec::new(&env),
+    );
+    client.set_risk_level_defaults(
+        &RiskLevel::Medium,
+        &vec![&env, template_limit(usdc.clone(), 300)],
+    );
+    assert_eq!(
+        client.resolve_default_limits(&owner, &usdc),
+        vec![&env, template_limit(usdc.clone(), 300)]
+    );
+    assert_eq!(
+        client.resolve_default_limits(&owner, &xlm),
+        vec![&env, template_limit(xlm.clone(), 1000)]
+    );
+    assert_eq!(client.get_security_limits(&owner, &0, &10).len(), 0);
+
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &usdc, &301, &None),
+        Err(Ok(LimitsError::LimitExceeded))
+    );
+    client.authorize_or_fail(&owner, &usdc, &200, &None);
+
+    // The first transaction installs the default, which then tracks usage
+    let limits = client.get_security_limits(&owner, &0, &10);
+    assert_eq!(limits.len(), 1);
+    let installed = limits.get(0).unwrap();
+    assert_eq!((installed.asset, installed.max_amount), (usdc.clone(), 300));
+    assert_eq!(installed.current_usage, 200);
+    assert_eq!(client.resolve_default_limits(&owner, &usdc).len(), 0);
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &usdc, &150, &None),
+        Err(Ok(LimitsError::LimitExceeded))
+    );
+
+    // An owner's own limit overrides the defaults for that asset
+    client.create_security_limit(&other, &LimitType::Daily, &xlm, &5000, &86400);
+    assert_eq!(client.resolve_default_limits(&other, &xlm).len(), 0);
+    client.authorize_or_fail(&other, &xlm, &2000, &None);
+    assert_eq!(
+        client.try_authorize_or_fail(&other, &usdc, &501, &None),
+        Err(Ok(LimitsError::LimitExceeded))
+    );
+}
+
+#[test]
+fn test_template_applies_and_propagates_to_subscribers() {
+    let (env, client, _admin) = setup_with_admin();
+    let owner = Address::generate(&env);
+    let other = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDC");
+    let conservative = symbol_short!("CONSERV");
+    let scam = symbol_short!("SCAM");
+
+    assert_eq!(
+        client.try_apply_template(&owner, &conservative),
+        Err(Ok(LimitsError::TemplateNotFound))
+    );
+    assert_eq!(
+        client.try_set_template(
+            &conservative,
+            &template_params(&env, Vec::new(&env)),
+            &vec![&env, template_limit(xlm.clone(), 0)],
+            &false,
+        ),
+        Err(Ok(LimitsError::InvalidAmount))
+    );
+
+    let version = client.set_template(
+        &conservative,
+        &template_params(&env, vec![&env, scam.clone()]),
+        &vec![
+            &env,
+            template_limit(xlm.clone(), 1000),
+            template_limit(usdc.clone(), 500),
+        ],
+        &false,
+    );
+    assert_eq!(version, 1);
+    assert_eq!(client.get_template_ids(), vec![&env, conservative.clone()]);
+
+    let own_id = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &9000, &86400);
+    let ids = client.apply_template(&owner, &conservative);
+    assert_eq!(ids.len(), 2);
+    assert_last_event(
+        &env,
+        &client.address,
+        (symbol_short!("tpl_apply"), owner.clone()).into_val(&env),
+        (conservative.clone(), 1u32).into_val(&env),
+    );
+    assert_eq!(client.get_security_limit_count(&owner), 3);
+    assert_eq!(
+        client.get_risk_profile(&owner).unwrap().risk_level,
+        RiskLevel::Low
+    );
+    assert!(!client.is_asset_allowed(&owner, &scam));
+    client.apply_template(&other, &conservative);
+
+    client.record_transaction(
+        &owner,
+        &xlm,
+        &400,
+        &None,
+        &BytesN::from_array(&env, &[1; 32]),
+    );
+
+    // Tightening XLM and dropping USDC reaches subscribers; usage carries over
+    client.unsubscribe_template(&other);
+    let version = client.set_template(
+        &conservative,
+        &template_params(&env, vec![&env, scam.clone()]),
+        &vec![&env, template_limit(xlm.clone(), 800)],
+        &true,
+    );
+    assert_eq!(version, 2);
+
+    let sub = client.get_template_subscription(&owner).unwrap();
+    assert_eq!(sub.version, 2);
+    assert_eq!(sub.limit_ids, vec![&env, ids.get(0).unwrap()]);
+    let xlm_limit = client.get_security_limit(&owner, &ids.get(0).unwrap());
+    assert_eq!(xlm_limit.max_amount, 800);
+    assert_eq!(xlm_limit.current_usage, 400);
+    assert_eq!(
+        client.try_get_security_limit(&owner, &ids.get(1).unwrap()),
+        Err(Ok(LimitsError::LimitNotFound))
+    );
+    // The owner's own limit is untouched
+    assert_eq!(client.get_security_limit(&owner, &own_id).max_amount, 9000);
+
+    // Unsubscribed owners keep the version they applied
+    assert_eq!(client.get_template_subscription(&other), None);
+    assert_eq!(client.get_security_limit_count(&other), 2);
+
+    client.remove_template(&conservative);
+    assert_eq!(client.get_template(&conservative), None);
+    assert_eq!(client.get_template_ids().len(), 0);
+    assert_eq!(client.get_security_limit_count(&owner), 2);
+}
+
+#[test]
+fn test_template_loosening_needs_co_signer() {
+    let (env, client, _admin) = setup_with_admin();
+    let owner = Address::generate(&env);
+    let co_signer = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let standard = symbol_short!("STANDARD");
+    let aggressive = symbol_short!("AGGRESS");
+
+    client.set_template(
+        &standard,
+        &template_params(&env, Vec::new(&env)),
+        &vec![&env, template_limit(xlm.clone(), 1000)],
+        &false,
+    );
+    client.set_template(
+        &aggressive,
+        &template_params(&env, Vec::new(&env)),
+        &vec![&env, template_limit(xlm.clone(), 10_000)],
+        &false,
+    );
+    let ids = client.apply_template(&owner, &standard);
+    client.set_co_signer(&owner, &Some(co_signer.clone()));
+
+    assert_eq!(
+        client.try_apply_template(&owner, &aggressive),
+        Err(Ok(LimitsError::CoSignRequired))
+    );
+
+    // Propagated loosening skips owners with a co-signer
+    client.set_template(
+        &standard,
+        &template_params(&env, Vec::new(&env)),
+        &vec![&env, template_limit(xlm.clone(), 2000)],
+        &true,
+    );
+    assert_eq!(client.get_template_subscription(&owner).unwrap().version, 1);
+    assert_eq!(
+        client
+            .get_security_limit(&owner, &ids.get(0).unwrap())
+            .max_amount,
+        1000
+    );
+
+    let proposal_id = client.propose_change(
+        &owner,
+        &ProposedChange::ApplyTemplate(aggressive.clone()),
+        &3600,
+    );
+    client.approve_change(&owner, &proposal_id);
+    let sub = client.get_template_subscription(&owner).unwrap();
+    assert_eq!(sub.template_id, aggressive);
+    assert_eq!(
+        client
+            .get_security_limit(&owner, &sub.limit_ids.get(0).unwrap())
+            .max_amount,
+        10_000
+    );
+    assert_eq!(client.get_security_limit_count(&owner), 1);
+}
+
+#[test]
+fn test_whitelist_mode_restricts_destinations() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let co_signer = Address::generate(&env);
+    let friend = Address::generate(&env);
+    let attacker = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    client.set_co_signer(&owner, &Some(co_signer.clone()));
+
+    // Additions apply at once while the mode is off
+    assert_eq!(client.add_whitelisted_destination(&owner, &friend), None);
+    assert_eq!(client.set_whitelist_mode(&owner, &true), None);
+    assert!(client.is_whitelist_enabled(&owner));
+
+    client.authorize_or_fail(&owner, &xlm, &100, &Some(friend.clone()));
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &xlm, &100, &Some(attacker.clone())),
+        Err(Ok(LimitsError::DestinationNotAllowed))
+    );
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &xlm, &100, &None),
+        Err(Ok(LimitsError::DestinationNotAllowed))
+    );
+    assert!(!client.check_transaction_allowed(&owner, &xlm, &100, &Some(attacker.clone())));
+    // Swaps are not transfers to a third party
+    client.authorize_swap_or_fail(&owner, &xlm, &symbol_short!("USDC"), &100);
+
+    // Once enabled, additions wait for the timelock
+    let change_id = client
+        .add_whitelisted_destination(&owner, &attacker)
+        .unwrap();
+    assert_eq!(
+        client
+            .get_timelocked(&owner, &change_id)
+            .unwrap()
+            .executable_at,
+        TIMELOCK_DELAY
+    );
+    assert_eq!(
+        client.try_execute_timelocked(&owner, &change_id),
+        Err(Ok(LimitsError::TimelockNotElapsed))
+    );
+    assert_eq!(client.get_whitelist(&owner), vec![&env, friend.clone()]);
+
+    // The co-signer spots the unexpected addition and cancels it
+    client.cancel_timelocked(&co_signer, &owner, &change_id);
+    set_time(&env, TIMELOCK_DELAY);
+    assert_eq!(
+        client.try_execute_timelocked(&owner, &change_id),
+        Err(Ok(LimitsError::TimelockNotFound))
+    );
+
+    let other_id = client
+        .add_whitelisted_destination(&owner, &attacker)
+        .unwrap();
+    set_time(&env, 2 * TIMELOCK_DELAY);
+    client.execute_timelocked(&owner, &other_id);
+    client.authorize_or_fail(&owner, &xlm, &100, &Some(attacker.clone()));
+
+    // Removal is immediate; turning the mode off is timelocked
+    client.remove_whitelisted_destination(&owner, &attacker);
+    assert_eq!(client.get_whitelist(&owner), vec![&env, friend.clone()]);
+    let disable_id = client.set_whitelist_mode(&owner, &false).unwrap();
+    assert!(client.is_whitelist_enabled(&owner));
+    assert_eq!(
+        client.try_cancel_timelocked(&attacker, &owner, &disable_id),
+        Err(Ok(LimitsError::NotAuthorized))
+    );
+    set_time(&env, 3 * TIMELOCK_DELAY);
+    client.execute_timelocked(&owner, &disable_id);
+    assert!(!client.is_whitelist_enabled(&owner));
+    client.authorize_or_fail(&owner, &xlm, &100, &Some(attacker.clone()));
+}
+
+#[test]
+fn test_cooldown_throttles_transactions() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDC");
+    set_time(&env, 1000);
+
+    assert_eq!(client.set_cooldown(&owner, &xlm, &60), None);
+    assert_eq!(client.get_cooldown(&owner, &xlm).unwrap().interval, 60);
+    client.authorize_or_fail(&owner, &xlm, &100, &None);
+    assert_eq!(
+        client.get_cooldown(&owner, &xlm).unwrap().last_transaction,
+        Some(1000)
+    );
+
+    set_time(&env, 1059);
+    assert!(!client.check_transaction_allowed(&owner, &xlm, &100, &None));
+    assert!(!client.check_swap_allowed(&owner, &xlm, &usdc, &100));
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &xlm, &100, &None),
+        Err(Ok(LimitsError::CooldownActive))
+    );
+    // Other assets are not throttled
+    client.authorize_or_fail(&owner, &usdc, &100, &None);
+
+    set_time(&env, 1060);
+    client.authorize_or_fail(&owner, &xlm, &100, &None);
+
+    // Recorded transactions restart the cooldown too
+    set_time(&env, 2000);
+    client.record_transaction(
+        &owner,
+        &xlm,
+        &10,
+        &None,
+        &BytesN::from_array(&env, &[7; 32]),
+    );
+    assert!(!client.check_transaction_allowed(&owner, &xlm, &100, &None));
+
+    // Lengthening applies at once; removal waits for the timelock
+    assert_eq!(client.set_cooldown(&owner, &xlm, &120), None);
+    let change_id = client.set_cooldown(&owner, &xlm, &0).unwrap();
+    set_time(&env, 2100);
+    assert!(!client.check_transaction_allowed(&owner, &xlm, &100, &None));
+    assert_eq!(
+        client.try_execute_timelocked(&owner, &change_id),
+        Err(Ok(LimitsError::TimelockNotElapsed))
+    );
+
+    set_time(&env, 2000 + TIMELOCK_DELAY);
+    client.execute_timelocked(&owner, &change_id);
+    assert_last_event(
+        &env,
+        &client.address,
+        (symbol_short!("cooldown"), owner.clone()).into_val(&env),
+        (xlm.clone(), 0u64).into_val(&env),
+    );
+    assert_eq!(client.get_cooldown(&owner, &xlm), None);
+    client.authorize_or_fail(&owner, &xlm, &100, &None);
+    client.authorize_or_fail(&owner, &xlm, &100, &None);
+}
+
+#[test]
+fn test_global_limit_caps_all_owners() {
+    const DAY: u64 = 86400;
+    let (_env, client) = setup();
+    let xlm = symbol_short!("XLM");
+    assert_eq!(
+        client.try_set_global_limit(&xlm, &100, &DAY),
+        Err(Ok(LimitsError::NotAuthorized))
+    );
+
+    let (env, client, _admin) = setup_with_admin();
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let usdc = symbol_short!("USDC");
+    set_time(&env, 1000);
+    assert_eq!(
+        client.try_set_global_limit(&xlm, &100, &0),
+        Err(Ok(LimitsError::InvalidAmount))
+    );
+    client.set_global_limit(&xlm, &100, &DAY);
+
+    client.authorize_or_fail(&alice, &xlm, &60, &None);
+    assert_eq!(client.get_global_usage(&xlm), 60);
+    assert!(!client.check_transaction_allowed(&bob, &xlm, &50, &None));
+    assert!(!client.check_swap_allowed(&bob, &xlm, &usdc, &50));
+    assert_eq!(
+        client.try_authorize_or_fail(&bob, &xlm, &50, &None),
+        Err(Ok(LimitsError::GlobalLimitExceeded))
+    );
+    client.authorize_or_fail(&bob, &xlm, &40, &None);
+    client.authorize_or_fail(&bob, &usdc, &1000, &None);
+
+    // Owner exceptions do not lift it
+    set_time(&env, 1000 + DAY);
+    client.authorize_or_fail(&alice, &xlm, &100, &None);
+    client.approve_exception(&alice, &xlm, &1_000, &(2000 + DAY));
+    assert_eq!(
+        client.try_authorize_or_fail(&alice, &xlm, &1, &None),
+        Err(Ok(LimitsError::GlobalLimitExceeded))
+    );
+
+    // A zero cap halts the asset; removing the limit lifts it
+    set_time(&env, 1000 + 3 * DAY);
+    client.set_global_limit(&xlm, &0, &DAY);
+    assert!(!client.check_transaction_allowed(&alice, &xlm, &1, &None));
+    client.remove_global_limit(&xlm);
+    assert_eq!(client.get_global_limit(&xlm), None);
+    client.authorize_or_fail(&alice, &xlm, &1, &None);
+    assert_eq!(
+        client.try_remove_global_limit(&xlm),
+        Err(Ok(LimitsError::LimitNotFound))
+    );
+}
+
+#[test]
+fn test_velocity_rule_flags_bursts_over_baseline() {
+    const DAY: u64 = 86400;
+    let (env, client, _admin) = setup_with_admin();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let anomalies = |env: &Env| {
+        let mut found = Vec::new(env);
+        for (_, topics, data) in env.events().all().iter() {
+            let name = Symbol::try_from_val(env, &topics.get(0).unwrap());
+            if name == Ok(symbol_short!("anomaly")) {
+                found.push_back(<(Symbol, i128, i128, i128)>::try_from_val(env, &data).unwrap());
+            }
+        }
+        found
+    };
+
+    // No baseline yet, so nothing is flagged
+    assert_eq!(client.set_velocity_rule(&owner, &xlm, &3, &false), None);
+    set_time(&env, 0);
+    client.authorize_or_fail(&owner, &xlm, &10_000, &None);
+    assert_eq!(anomalies(&env).len(), 0);
+    for day in 1..6 {
+        set_time(&env, day * DAY);
+        client.authorize_or_fail(&owner, &xlm, &200, &None);
+    }
+
+    // The six days before average 1833
+    set_time(&env, 6 * DAY);
+    client.authorize_or_fail(&owner, &xlm, &5000, &None);
+    assert_eq!(anomalies(&env).len(), 0);
+    client.authorize_or_fail(&owner, &xlm, &1001, &None);
+    assert_eq!(anomalies(&env), vec![&env, (xlm.clone(), 1001, 5000, 1833)]);
+
+    assert_eq!(client.set_velocity_rule(&owner, &xlm, &3, &true), None);
+    assert!(!client.check_transaction_allowed(&owner, &xlm, &1, &None));
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &xlm, &1, &None),
+        Err(Ok(LimitsError::VelocityAnomaly))
+    );
+    client.approve_exception(&owner, &xlm, &500, &(7 * DAY));
+    client.authorize_or_fail(&owner, &xlm, &500, &None);
+    assert_eq!(anomalies(&env).len(), 2);
+
+    // Loosening waits for the timelock
+    let change_id = client.set_velocity_rule(&owner, &xlm, &0, &false).unwrap();
+    assert!(
+        client
+            .get_velocity_rule(&owner, &xlm)
+            .unwrap()
+            .require_approval
+    );
+    set_time(&env, 6 * DAY + TIMELOCK_DELAY);
+    client.execute_timelocked(&owner, &change_id);
+    assert_eq!(client.get_velocity_rule(&owner, &xlm), None);
+}
+
+#[test]
+fn test_group_members_share_group_limits() {
+    let (env, client) = setup();
+    let treasury = Address::generate(&env);
+    let hot_a = Address::generate(&env);
+    let hot_b = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+
+    let group_limit =
+        client.create_security_limit(&treasury, &LimitType::Daily, &xlm, &1000, &86400);
+    client.create_security_limit(&hot_a, &LimitType::Daily, &xlm, &700, &86400);
+    client.add_group_member(&treasury, &hot_a);
+    client.add_group_member(&treasury, &hot_b);
+    assert_eq!(
+        client.get_group_members(&treasury),
+        vec![&env, hot_a.clone(), hot_b.clone()]
+    );
+    assert_eq!(client.get_group(&hot_b), Some(treasury.clone()));
+    assert_eq!(
+        client.try_add_group_member(&hot_a, &Address::generate(&env)),
+        Err(Ok(LimitsError::AlreadyInGroup))
+    );
+    assert_eq!(
+        client.try_add_group_member(&Address::generate(&env), &hot_a),
+        Err(Ok(LimitsError::AlreadyInGroup))
+    );
+
+    // Each member's own limit still applies on top of the shared one
+    assert_eq!(
+        client.try_authorize_or_fail(&hot_a, &xlm, &800, &None),
+        Err(Ok(LimitsError::LimitExceeded))
+    );
+    client.authorize_or_fail(&hot_a, &xlm, &600, &None);
+    client.record_transaction(
+        &hot_b,
+        &xlm,
+        &300,
+        &None,
+        &BytesN::from_array(&env, &[1; 32]),
+    );
+    assert_eq!(
+        client
+            .get_security_limit(&treasury, &group_limit)
+            .current_usage,
+        900
+    );
+    assert!(!client.check_transaction_allowed(&hot_b, &xlm, &101, &None));
+    let checks = client.simulate_check(&hot_b, &xlm, &101);
+    assert_eq!(checks.len(), 1);
+    assert!(checks.get(0).unwrap().blocks);
+
+    // Removal needs the group's co-signer when it has one
+    let co_signer = Address::generate(&env);
+    client.set_co_signer(&treasury, &Some(co_signer.clone()));
+    client.remove_group_member(&treasury, &hot_b);
+    assert!(env.auths().iter().any(|(address, _)| *address == co_signer));
+    assert_eq!(client.get_group(&hot_b), None);
+    client.authorize_or_fail(&hot_b, &xlm, &500, &None);
+    assert_eq!(
+        client.try_remove_group_member(&treasury, &hot_b),
+        Err(Ok(LimitsError::NotGroupMember))
+    );
+}
+
+#[test]
+fn test_soft_limit_warns_or_needs_confirmation() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+
+    assert_eq!(
+        client.try_set_soft_limit(&limit_id, &owner, &1000, &false),
+        Err(Ok(LimitsError::InvalidAmount))
+    );
+    client.set_soft_limit(&limit_id, &owner, &600, &false);
+
+    // Warning only
+    client.authorize_or_fail(&owner, &xlm, &500, &None);
+    client.authorize_or_fail(&owner, &xlm, &200, &None);
+    let warning = env
+        .events()
+        .all()
+        .iter()
+        .find(|(_, topics, _)| {
+            Symbol::try_from_val(&env, &topics.get(0).unwrap()) == Ok(symbol_short!("soft_hit"))
+        })
+        .unwrap();
+    assert_eq!(
+        <(u64, Symbol, i128, i128)>::try_from_val(&env, &warning.2).unwrap(),
+        (limit_id, xlm.clone(), 700, 600)
+    );
+
+    // Confirmation required; the hard limit still blocks confirmed calls
+    client.set_soft_limit(&limit_id, &owner, &750, &true);
+    assert_eq!(
+        client.get_soft_limit(&owner, &limit_id),
+        Some(SoftLimit {
+            amount: 750,
+            require_confirmation: true
+        })
+    );
+    client.authorize_or_fail(&owner, &xlm, &50, &None);
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &xlm, &1, &None),
+        Err(Ok(LimitsError::SoftLimitUnconfirmed))
+    );
+    assert_eq!(
+        client.try_validate_transaction(&owner, &xlm, &1, &None),
+        Err(Ok(LimitsError::SoftLimitUnconfirmed))
+    );
+    client.authorize_confirmed_or_fail(&owner, &xlm, &250, &None);
+    assert_eq!(
+        client.try_authorize_confirmed_or_fail(&owner, &xlm, &1, &None),
+        Err(Ok(LimitsError::LimitExceeded))
+    );
+
+    // With a co-signer, raising, removing or unconfirming it needs approval
+    client.set_co_signer(&owner, &Some(Address::generate(&env)));
+    for (amount, require_confirmation) in [(800, true), (0, false), (750, false)] {
+        assert_eq!(
+            client.try_set_soft_limit(&limit_id, &owner, &amount, &require_confirmation),
+            Err(Ok(LimitsError::CoSignRequired))
+        );
+    }
+    client.set_soft_limit(&limit_id, &owner, &700, &true);
+    let change = ProposedChange::SetSoftLimit(limit_id, 900, false);
+    let proposal_id = client.propose_change(&owner, &change, &3600);
+    client.approve_change(&owner, &proposal_id);
+    assert_eq!(
+        client.get_soft_limit(&owner, &limit_id),
+        Some(SoftLimit {
+            amount: 900,
+            require_confirmation: false
+        })
+    );
+
+    client.set_co_signer(&owner, &None);
+    client.delete_security_limit(&limit_id, &owner);
+    assert_eq!(client.get_soft_limit(&owner, &limit_id), None);
+}
+
+#[test]
+fn test_burst_allowance_absorbs_occasional_spikes() {
+    const DAY: u64 = 86400;
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    set_time(&env, 0);
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &DAY);
+    let per_tx = client.create_security_limit(&owner, &LimitType::PerTransaction, &xlm, &5000, &0);
+    assert_eq!(
+        client.try_set_burst_allowance(&per_tx, &owner, &2000, &3),
+        Err(Ok(LimitsError::InvalidAmount))
+    );
+
+    // 20% extra, refilled every three days
+    client.set_burst_allowance(&limit_id, &owner, &2000, &3);
+    client.authorize_or_fail(&owner, &xlm, &1100, &None);
+    assert_eq!(
+        client.get_burst_allowance(&owner, &limit_id).unwrap().used,
+        100
+    );
+    client.authorize_or_fail(&owner, &xlm, &100, &None);
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &xlm, &1, &None),
+        Err(Ok(LimitsError::LimitExceeded))
+    );
+
+    // Usage resets the next day but the burst budget does not
+    set_time(&env, DAY);
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &xlm, &1001, &None),
+        Err(Ok(LimitsError::LimitExceeded))
+    );
+    client.authorize_or_fail(&owner, &xlm, &1000, &None);
+
+    set_time(&env, 3 * DAY);
+    assert!(client
+        .simulate_check(&owner, &xlm, &1200)
+        .iter()
+        .all(|check| !check.blocks));
+    client.authorize_or_fail(&owner, &xlm, &1200, &None);
+
+    // Enlarging it needs the co-signer
+    client.set_co_signer(&owner, &Some(Address::generate(&env)));
+    assert_eq!(
+        client.try_set_burst_allowance(&limit_id, &owner, &3000, &3),
+        Err(Ok(LimitsError::CoSignRequired))
+    );
+    client.set_burst_allowance(&limit_id, &owner, &0, &0);
+    assert_eq!(client.get_burst_allowance(&owner, &limit_id), None);
+}
+
+#[test]
+fn test_limit_manager_stays_within_caps() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let manager = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDC");
+    let mut caps = Map::new(&env);
+    caps.set(xlm.clone(), 5000i128);
+
+    assert_eq!(
+        client.try_manager_create_limit(&manager, &owner, &LimitType::Daily, &xlm, &1000, &86400),
+        Err(Ok(LimitsError::NotAuthorized))
+    );
+
+    client.set_limit_manager(&owner, &manager, &caps);
+    assert_eq!(
+        client.get_limit_manager(&owner, &manager).unwrap().caps,
+        caps
+    );
+
+    let limit_id =
+        client.manager_create_limit(&manager, &owner, &LimitType::Daily, &xlm, &1000, &86400);
+    assert_eq!(
+        env.auths(),
+        std::vec![(
+            manager.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    client.address.clone(),
+                    Symbol::new(&env, "manager_create_limit"),
+                    (
+                        manager.clone(),
+                        owner.clone(),
+                        LimitType::Daily,
+                        xlm.clone(),
+                        1000i128,
+                        86400u64,
+                    )
+                        .into_val(&env),
+                )),
+                sub_invocations: std::vec![],
+            }
+        )]
+    );
+    assert_eq!(client.get_security_limit(&owner, &limit_id).owner, owner);
+
+    client.manager_update_limit(&manager, &owner, &limit_id, &5000, &86400, &true);
+    assert_eq!(
+        client.try_manager_update_limit(&manager, &owner, &limit_id, &5001, &86400, &true),
+        Err(Ok(LimitsError::ManagerCapExceeded))
+    );
+    assert_eq!(
+        client.try_manager_update_limit(&manager, &owner, &limit_id, &1000, &86400, &false),
+        Err(Ok(LimitsError::NotAuthorized))
+    );
+    // The cap holds per window, so the window cannot shrink either
+    assert_eq!(
+        client.try_manager_update_limit(&manager, &owner, &limit_id, &5000, &3600, &true),
+        Err(Ok(LimitsError::NotAuthorized))
+    );
+    assert_eq!(
+        client.try_manager_create_limit(&manager, &owner, &LimitType::Daily, &usdc, &10, &86400),
+        Err(Ok(LimitsError::NotAuthorized))
+    );
+
+    // The owner's own caps are not bound by the grant
+    client.update_security_limit(&limit_id, &owner, &9000, &86400, &true);
+
+    client.revoke_limit_manager(&owner, &manager);
+    assert_eq!(client.get_limit_manager(&owner, &manager), None);
+    assert_eq!(
+        client.try_manager_update_limit(&manager, &owner, &limit_id, &1000, &86400, &true),
+        Err(Ok(LimitsError::NotAuthorized))
+    );
+}
+
+#[test]
+fn test_amounts_beyond_u64_and_overflow() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    let big = u64::MAX as i128 * 4;
+
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &big, &86400);
+    client.authorize_or_fail(&owner, &xlm, &(u64::MAX as i128 * 3), &None);
+    assert_eq!(
+        client.get_security_limit(&owner, &limit_id).current_usage,
+        u64::MAX as i128 * 3
+    );
+    assert_eq!(
+        client.try_authorize_or_fail(&owner, &xlm, &(u64::MAX as i128 * 2), &None),
+        Err(Ok(LimitsError::LimitExceeded))
+    );
+
+    // Usage plus amount past i128::MAX is reported, not wrapped
+    client.update_security_limit(&limit_id, &owner, &i128::MAX, &86400, &true);
+    assert_eq!(
+        client.try_validate_transaction(&owner, &xlm, &i128::MAX, &None),
+        Err(Ok(LimitsError::Overflow))
+    );
+
+    assert_eq!(
+        client.try_validate_transaction(&owner, &xlm, &-1, &None),
+        Err(Ok(LimitsError::InvalidAmount))
+    );
+    assert!(!client.check_transaction_allowed(&owner, &xlm, &-1, &None));
+    assert_eq!(
+        client.try_create_security_limit(&owner, &LimitType::Daily, &xlm, &-5, &86400),
+        Err(Ok(LimitsError::InvalidAmount))
+    );
+    assert_eq!(
+        client.try_set_risk_profile(
+            &owner,
+            &RiskLevel::Low,
+            &-1,
+            &0,
+            &Vec::new(&env),
+            &Vec::new(&env),
+        ),
+        Err(Ok(LimitsError::InvalidAmount))
+    );
+}
+
+#[test]
+fn test_migrate_brings_legacy_deployment_to_current_schema() {
+    let (env, client) = setup();
+    assert_eq!(
+        client.try_upgrade(&BytesN::from_array(&env, &[0; 32])),
+        Err(Ok(LimitsError::NotAuthorized))
+    );
+    assert_eq!(client.try_migrate(), Err(Ok(LimitsError::NotAuthorized)));
+
+    let (env, client, admin) = setup_with_admin();
+    let owner = Address::generate(&env);
+    assert_eq!(client.get_schema_version(), SCHEMA_VERSION);
+    assert_eq!(client.migrate(), SCHEMA_VERSION);
+    assert_eq!(client.get_audit_log_count(), 1);
+
+    // A deployment from before the schema version was stored
+    client.set_risk_profile(
+        &owner,
+        &RiskLevel::High,
+        &50,
+        &10,
+        &Vec::new(&env),
+        &Vec::new(&env),
+    );
+    env.as_contract(&client.address, || {
+        env.storage().instance().remove(&SCHEMA);
+    });
+    assert_eq!(client.get_schema_version(), 1);
+
+    assert_eq!(client.migrate(), 2);
+    assert_eq!(client.get_schema_version(), 2);
+    let entry = client.get_audit_log(&1, &1).get(0).unwrap();
+    assert_eq!(
+        (entry.admin, entry.action),
+        (admin, AuditAction::Migrated(1, 2))
+    );
+    assert_eq!(
+        client.get_risk_profile(&owner).unwrap().max_daily_volume,
+        50
+    );
+
+    // Stored data from a newer build is refused
+    env.as_contract(&client.address, || {
+        env.storage().instance().set(&SCHEMA, &(SCHEMA_VERSION + 1));
+    });
+    assert_eq!(
+        client.try_migrate(),
+        Err(Ok(LimitsError::UnsupportedSchema))
+    );
+}
+
+#[test]
+fn test_remaining_allowance_reports_resets() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+    set_time(&env, 1000);
+
+    let fixed_id = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+    let rolling_id = client.create_security_limit(&owner, &LimitType::PerHour, &xlm, &500, &3600);
+    client.set_limit_window_mode(&rolling_id, &owner, &WindowMode::Rolling);
+    let per_dest_id = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &100, &86400);
+    client.set_limit_scope(&per_dest_id, &owner, &LimitScope::PerDestination);
+    client.create_security_limit(
+        &owner,
+        &LimitType::Daily,
+        &symbol_short!("USDC"),
+        &1,
+        &86400,
+    );
+
+    assert_eq!(
+        client.remaining_allowance(&owner, &xlm),
+        vec![
+            &env,
+            (fixed_id, 1000i128, 1000u64),
+            (rolling_id, 500i128, 1000u64)
+        ]
+    );
+
+    client.record_transaction(
+        &owner,
+        &xlm,
+        &300,
+        &None,
+        &BytesN::from_array(&env, &[1; 32]),
+    );
+    set_time(&env, 2000);
+    client.record_transaction(
+        &owner,
+        &xlm,
+        &100,
+        &None,
+        &BytesN::from_array(&env, &[2; 32]),
+    );
+
+    // The fixed window ends a day after it started; the rolling limit frees
+    // the first 300 once its 150-second bucket (from 900) leaves the hour
+    assert_eq!(
+        client.remaining_allowance(&owner, &xlm),
+        vec![
+            &env,
+            (fixed_id, 600i128, 1000u64 + 86400),
+            (rolling_id, 100i128, 900u64 + 3600)
+        ]
+    );
+
+    set_time(&env, 900 + 3600);
+    assert_eq!(
+        client.remaining_allowance(&owner, &xlm).get(1).unwrap(),
+        (rolling_id, 400i128, 1950u64 + 3600)
+    );
+}
+
+#[test]
+fn test_alerts_on_thresholds_and_blocked_checks() {
+    let (env, client) = setup();
+    let owner = Address::generate(&env);
+    let xlm = symbol_short!("XLM");
+
+    assert_eq!(
+        client.try_set_alert_thresholds(&owner, &vec![&env, 8000u32, 5000]),
+        Err(Ok(LimitsError::InvalidThreshold))
+    );
+    assert_eq!(
+        client.try_set_alert_thresholds(&owner, &vec![&env, 10_001u32]),
+        Err(Ok(LimitsError::InvalidThreshold))
+    );
+    client.set_alert_thresholds(&owner, &vec![&env, 5000u32, 8000]);
+    assert_eq!(
+        client.get_alert_thresholds(&owner),
+        vec![&env, 5000u32, 8000]
+    );
+
+    let limit_id = client.create_security_limit(&owner, &LimitType::Daily, &xlm, &1000, &86400);
+    let threshold_alerts = |env: &Env| {
+        let mut alerts = Vec::new(env);
+        for (_, topics, data) in env.events().all().iter() {
+            let name = Symbol::try_from_val(env, &topics.get(0).unwrap());
+            if name == Ok(symbol_short!("threshold")) {
+                let alert = <(u64, Symbol, u32, i128, i128)>::try_from_val(env, &data).unwrap();
+                alerts.push_back(alert);
+            }
+        }
+        alerts
+    };
+
+    client.record_transaction(
+        &owner,
+        &xlm,
+        &400,
+        &None,
+        &BytesN::from_array(&env, &[1; 32]),
+    );
+    assert!(threshold_alerts(&env).is_empty());
+
+    // One transaction can cross several thresholds at once
+    client.record_transaction(
+        &owner,
+        &xlm,
+        &450,
+        &None,
+        &BytesN::from_array(&env, &[2; 32]),
+    );
+    assert_eq!(
+        threshold_alerts(&env),
+        vec![
+            &env,
+            (limit_id, xlm.clone(), 5000u32, 850i128, 1000i128),
+            (limit_id, xlm.clone(), 8000u32, 850i128, 1000i128)
+        ]
+    );
+
+    assert!(!client.check_transaction_allowed(&owner, &xlm, &200, &None));
+    assert_last_event(
+        &env,
+        &client.address,
+        (symbol_short!("blocked"), owner.clone()).into_val(&env),
+        (limit_id, xlm.clone(), 200i128, 1050i128, 1000i128).into_val(&env),
+    );
+
+    // Usage already above a threshold does not alert again
+    client.record_transaction(
+        &owner,
+        &xlm,
+        &50,
+        &None,
+        &BytesN::from_array(&env, &[3; 32]),
+    );
+    assert_eq!(threshold_alerts(&env).len(), 2);
+}
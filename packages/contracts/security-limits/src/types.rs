@@ -0,0 +1,620 @@
+//! Security Limits — shared types and error codes
+//!
+//! All SDK-annotated items live here so that `lib.rs` can stay focused on logic.
+
+use soroban_sdk::{contracterror, contracttype, Address, BytesN, Map, String, Symbol, Vec};
+
+// ---------------------------------------------------------------------------
+// Error codes
+// ---------------------------------------------------------------------------
+
+/// Every error the security limits contract can return.
+///
+/// Values are stable — do **not** renumber without a migration plan.
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum LimitsError {
+    /// Caller does not own the targeted limit or profile.
+    NotAuthorized = 1,
+    /// No limit exists with the given ID.
+    LimitNotFound = 2,
+    /// The transaction would push usage above an active limit.
+    LimitExceeded = 3,
+    /// The asset is blacklisted or missing from the owner's allow-list.
+    AssetBlocked = 4,
+    /// The contract has already been initialised.
+    AlreadyInitialized = 5,
+    /// Amount arguments must be greater than zero.
+    InvalidAmount = 6,
+    /// The owner's account is frozen.
+    AccountFrozen = 7,
+    /// No exception is approved for the given owner and asset.
+    ExceptionNotFound = 8,
+    /// An exception's expiry must lie in the future.
+    InvalidExpiry = 9,
+    /// The owner's account is not frozen.
+    AccountNotFrozen = 10,
+    /// The change loosens protection and needs the co-signer's approval.
+    CoSignRequired = 11,
+    /// No pending proposal exists with the given ID.
+    ProposalNotFound = 12,
+    /// The proposal passed its expiry before being approved.
+    ProposalExpired = 13,
+    /// The oracle price used for valuation is older than allowed.
+    PriceStale = 14,
+    /// The oracle returned no usable price for the conversion.
+    PriceUnavailable = 15,
+    /// Valuation settings are out of range.
+    InvalidValuation = 16,
+    /// No risk profile template exists with the given ID.
+    TemplateNotFound = 17,
+    /// Whitelist mode is on and the destination is not on the list.
+    DestinationNotAllowed = 18,
+    /// No timelocked change exists with the given ID.
+    TimelockNotFound = 19,
+    /// The timelocked change cannot be executed yet.
+    TimelockNotElapsed = 20,
+    /// The asset's cooldown since the previous transaction has not passed.
+    CooldownActive = 21,
+    /// A limit manager tried to go above the cap the owner granted.
+    ManagerCapExceeded = 22,
+    /// Amount arithmetic overflowed.
+    Overflow = 23,
+    /// Alert thresholds must be ascending basis points between 1 and 10,000.
+    InvalidThreshold = 24,
+    /// Stored data was written by a newer schema than this contract knows.
+    UnsupportedSchema = 25,
+    /// The transaction would push the deployment-wide volume for the asset
+    /// above its global limit.
+    GlobalLimitExceeded = 26,
+    /// The transaction is flagged as a velocity anomaly and the owner's rule
+    /// requires an approved exception for it.
+    VelocityAnomaly = 27,
+    /// The address already belongs to a limit group, or is a group itself.
+    AlreadyInGroup = 28,
+    /// The address is not a member of the limit group.
+    NotGroupMember = 29,
+    /// The transaction would cross a soft limit that requires the caller to
+    /// confirm it.
+    SoftLimitUnconfirmed = 30,
+    /// A report period must have `from_ts <= to_ts` and be over.
+    InvalidPeriod = 31,
+    /// A report for the owner and period was already committed.
+    ReportExists = 32,
+    /// A reset schedule's UTC offset is out of range, or it has no periods
+    /// for the limit's type.
+    InvalidSchedule = 33,
+    /// The reporter is not registered for the owner.
+    NotReporter = 34,
+}
+
+// ---------------------------------------------------------------------------
+// Limits
+// ---------------------------------------------------------------------------
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SecurityLimit {
+    pub id: u64,
+    pub owner: Address,
+    pub limit_type: LimitType,
+    pub asset: Symbol,
+    pub max_amount: i128,
+    pub time_window: u64,
+    pub current_usage: i128,
+    pub last_reset: u64,
+    pub is_active: bool,
+    pub created_at: u64,
+    pub window_mode: WindowMode,
+    pub scope: LimitScope,
+    /// When set, the limit only counts swaps from `asset` into this asset.
+    pub pair_asset: Option<Symbol>,
+    pub denomination: Denomination,
+}
+
+/// Warning tier below a limit's `max_amount`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SoftLimit {
+    /// Usage above which transactions are flagged; below `max_amount`.
+    pub amount: i128,
+    /// Block crossing transactions unless the call confirms them, rather
+    /// than only warning.
+    pub require_confirmation: bool,
+}
+
+/// Extra room above a limit's `max_amount` for occasional spikes.
+///
+/// Transactions may take usage above `max_amount` by drawing on the burst
+/// budget, which is tracked apart from usage and only refills every
+/// `refill_windows` windows of the limit.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BurstAllowance {
+    /// Budget in basis points of `max_amount`.
+    pub extra_bps: u32,
+    pub refill_windows: u32,
+    /// Budget drawn since `refilled_at`.
+    pub used: i128,
+    /// Start of the current refill period.
+    pub refilled_at: u64,
+}
+
+/// Unit a limit's amounts are expressed in.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Denomination {
+    /// Units of the limit's own `asset`; only that asset counts.
+    Asset,
+    /// `asset` names a reference currency (e.g. `USD`) and every asset
+    /// counts, converted at the oracle price.
+    Reference(Valuation),
+}
+
+/// Oracle settings for a reference-currency limit.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Valuation {
+    /// Price oracle queried as `get_price(asset, reference)`.
+    pub oracle: Address,
+    /// Decimals the oracle scales its prices by.
+    pub decimals: u32,
+    /// Oldest acceptable price, in seconds.
+    pub max_age: u64,
+}
+
+/// Price observation returned by the oracle's `get_price(base, quote)`.
+///
+/// Mirrors the price-oracle contract's `PriceEntry`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OraclePrice {
+    pub price: i128,
+    pub timestamp: u64,
+    pub pusher: Address,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LimitType {
+    Daily,
+    Weekly,
+    Monthly,
+    PerTransaction,
+    PerHour,
+    Custom(u64),
+}
+
+/// Which transfers a limit counts.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum LimitScope {
+    /// Every transfer of the asset, regardless of destination.
+    Aggregate,
+    /// Each destination separately, e.g. "max 1,000 USDC/day to any single
+    /// address".  Transfers without a destination are not counted, and
+    /// usage is tracked per counterparty rather than in `current_usage`.
+    PerDestination,
+    /// Only transfers to the given address.
+    Destination(Address),
+    /// Only transactions tagged with the given spending category (e.g.
+    /// `defi`) through the `*_categorized_*` entrypoints.
+    Category(Symbol),
+    /// Only transactions authorized through the given calling contract
+    /// (e.g. a swap router or lending adapter) via
+    /// `authorize_from_caller_or_fail`.
+    Caller(Address),
+    /// Net outflow: every transfer of the asset, less the inflows recorded
+    /// through `record_inflow` in the same window.  Credits never take
+    /// usage below zero, so inflows free spent budget but cannot bank more.
+    NetFlow,
+}
+
+/// How usage is aged out of a limit's window.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WindowMode {
+    /// Usage resets to zero once the window has elapsed since `last_reset`.
+    Fixed,
+    /// Usage is the sum of the most recent window, tracked in time buckets,
+    /// so spending cannot be doubled up across a reset boundary.
+    Rolling,
+    /// Usage resets at UTC calendar boundaries for the limit type: the hour,
+    /// midnight, Monday or the 1st of the month.
+    Calendar,
+    /// Usage resets on the limit's own schedule, e.g. at the start of a
+    /// regional business day.
+    Scheduled(ResetSchedule),
+}
+
+/// Reset policy of a `Scheduled` limit, read in local time.
+///
+/// Periods start every `interval` seconds from `anchor`, a local-time Unix
+/// timestamp.  With an `interval` of `0` they follow the limit type's
+/// calendar periods instead, starting `anchor` seconds into each one (e.g.
+/// `21_600` for a business day that starts at 06:00).
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ResetSchedule {
+    pub interval: u64,
+    pub anchor: u64,
+    /// Seconds local time is ahead of UTC, within ±14 hours.
+    pub utc_offset: i32,
+}
+
+/// One slot of a rolling-window ring buffer.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UsageBucket {
+    /// Bucket number (`timestamp / bucket_span`) the slot currently holds.
+    pub epoch: u64,
+    pub amount: i128,
+}
+
+// ---------------------------------------------------------------------------
+// Transactions
+// ---------------------------------------------------------------------------
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TransactionRecord {
+    pub id: u64,
+    pub owner: Address,
+    pub asset: Symbol,
+    pub amount: i128,
+    /// Counterparty, when the caller supplied one.
+    pub destination: Option<Address>,
+    /// Asset received, for swaps recorded via the swap entrypoints.
+    pub counter_asset: Option<Symbol>,
+    pub timestamp: u64,
+    pub transaction_hash: BytesN<32>,
+}
+
+// ---------------------------------------------------------------------------
+// Usage statistics
+// ---------------------------------------------------------------------------
+
+/// Stored running aggregates for one owner and asset.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AssetStats {
+    /// Ring of day buckets covering the last seven days.
+    pub daily_volume: Vec<UsageBucket>,
+    pub largest_transaction: i128,
+    pub total_volume: i128,
+    pub transaction_count: u32,
+}
+
+/// Usage statistics view for one owner and asset.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UsageStats {
+    pub asset: Symbol,
+    /// Volume since the start of the current UTC day.
+    pub today_volume: i128,
+    /// Volume over the current and previous six UTC days.
+    pub week_volume: i128,
+    pub largest_transaction: i128,
+    pub total_volume: i128,
+    pub transaction_count: u32,
+}
+
+/// Totals of one owner's transactions in one asset over a report period.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PeriodTotals {
+    pub volume: i128,
+    pub transaction_count: u32,
+    pub largest_transaction: i128,
+}
+
+/// On-chain commitment to an owner's usage report for a closed period.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReportCommitment {
+    pub from_ts: u64,
+    pub to_ts: u64,
+    /// SHA-256 of the XDR encoding of `(owner, from_ts, to_ts, totals)`,
+    /// `totals` being the `Map<Symbol, PeriodTotals>` of the period.
+    pub digest: BytesN<32>,
+    pub committed_at: u64,
+}
+
+/// Remaining headroom on one active limit.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LimitBudget {
+    pub limit_id: u64,
+    pub max_amount: i128,
+    pub usage: i128,
+    /// `max_amount - usage`, floored at zero.
+    pub remaining: i128,
+}
+
+/// Deployment-wide cap on an asset's volume across every owner, set by the
+/// compliance admin as a circuit breaker.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GlobalLimit {
+    pub asset: Symbol,
+    /// Most the deployment authorizes per window; `0` halts the asset.
+    pub max_amount: i128,
+    /// Length of the rolling window, in seconds.
+    pub time_window: u64,
+    /// Rolling ring of usage buckets (see `window.rs`).
+    pub usage: Vec<UsageBucket>,
+}
+
+/// How one limit judges a simulated transaction.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LimitCheck {
+    pub limit_id: u64,
+    /// Whether this limit would block the transaction.
+    pub blocks: bool,
+    /// Amount the limit still allows before the transaction, in its units.
+    pub headroom: i128,
+    /// When counted usage next drops; the current time if nothing counts.
+    pub resets_at: u64,
+}
+
+// ---------------------------------------------------------------------------
+// Risk profiles
+// ---------------------------------------------------------------------------
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RiskProfile {
+    pub owner: Address,
+    pub risk_level: RiskLevel,
+    pub max_daily_volume: i128,
+    pub max_single_transaction: i128,
+    pub allowed_assets: Vec<Symbol>,
+    pub blacklisted_assets: Vec<Symbol>,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RiskLevel {
+    Low,
+    Medium,
+    High,
+    Restricted,
+}
+
+// ---------------------------------------------------------------------------
+// Two-party approval
+// ---------------------------------------------------------------------------
+
+/// Arguments of [`set_risk_profile`](crate::SecurityLimitsContract::set_risk_profile)
+/// carried by a proposal.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RiskProfileParams {
+    pub risk_level: RiskLevel,
+    pub max_daily_volume: i128,
+    pub max_single_transaction: i128,
+    pub allowed_assets: Vec<Symbol>,
+    pub blacklisted_assets: Vec<Symbol>,
+}
+
+/// Change an owner proposes for co-signer approval.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ProposedChange {
+    /// `(limit_id, max_amount, time_window, is_active)`
+    UpdateLimit(u64, i128, u64, bool),
+    /// `(limit_id)`
+    DeleteLimit(u64),
+    SetRiskProfile(RiskProfileParams),
+    /// `(template_id)`
+    ApplyTemplate(Symbol),
+    /// `(limit_id, extra_bps, refill_windows)`
+    SetBurstAllowance(u64, u32, u32),
+    /// `(limit_id, window_mode)`
+    SetWindowMode(u64, WindowMode),
+    /// `(limit_id, scope)`
+    SetScope(u64, LimitScope),
+    /// `(limit_id, pair_asset)`
+    SetPair(u64, Option<Symbol>),
+    /// `(limit_id, denomination)`
+    SetDenomination(u64, Denomination),
+    /// `(limit_id, amount, require_confirmation)`
+    SetSoftLimit(u64, i128, bool),
+}
+
+/// Pending change awaiting the co-signer.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Proposal {
+    pub id: u64,
+    pub owner: Address,
+    pub change: ProposedChange,
+    pub proposed_at: u64,
+    pub expires_at: u64,
+}
+
+// ---------------------------------------------------------------------------
+// Delegation
+// ---------------------------------------------------------------------------
+
+/// Permission for a manager to administer an owner's limits.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ManagerGrant {
+    pub manager: Address,
+    /// Highest `max_amount` the manager may set, per limit asset.  Limits on
+    /// assets missing from the map are out of the manager's reach.
+    pub caps: Map<Symbol, i128>,
+    pub granted_at: u64,
+}
+
+// ---------------------------------------------------------------------------
+// Timelock
+// ---------------------------------------------------------------------------
+
+/// Change that only takes effect once the timelock delay has passed.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TimelockedChange {
+    /// `(destination)` — add to the owner's destination whitelist.
+    AddDestination(Address),
+    /// Turn whitelist mode off.
+    DisableWhitelist,
+    /// `(asset, interval)` — shorten or, with `0`, remove a cooldown.
+    SetCooldown(Symbol, u64),
+    /// `(asset, multiplier, require_approval)` — loosen or, with a
+    /// `multiplier` of `0`, remove a velocity rule.
+    SetVelocityRule(Symbol, u32, bool),
+}
+
+/// Minimum interval between an owner's transactions in one asset.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Cooldown {
+    /// Seconds that must pass after a transaction before the next one.
+    pub interval: u64,
+    /// Timestamp of the last transaction recorded since the cooldown was set.
+    pub last_transaction: Option<u64>,
+}
+
+/// Velocity anomaly rule for an owner's transactions in one asset.
+///
+/// A transaction is anomalous when it would take the day's volume above
+/// `multiplier` times the owner's average daily volume over the six days
+/// before.  Owners without any volume in those days have no baseline and are
+/// never flagged.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct VelocityRule {
+    pub multiplier: u32,
+    /// Block anomalous transactions unless an exception covers them, rather
+    /// than only flagging them.
+    pub require_approval: bool,
+}
+
+/// Queued timelocked change.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TimelockedOp {
+    pub id: u64,
+    pub owner: Address,
+    pub change: TimelockedChange,
+    pub queued_at: u64,
+    /// Earliest ledger timestamp at which the change can be executed.
+    pub executable_at: u64,
+}
+
+// ---------------------------------------------------------------------------
+// Templates
+// ---------------------------------------------------------------------------
+
+/// Limit created for every owner who applies a template.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TemplateLimit {
+    pub limit_type: LimitType,
+    pub asset: Symbol,
+    pub max_amount: i128,
+    pub time_window: u64,
+}
+
+/// Admin-defined bundle of a risk profile and default limits (e.g.
+/// `Conservative`, `Standard`, `Aggressive`).
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RiskTemplate {
+    pub id: Symbol,
+    pub profile: RiskProfileParams,
+    pub limits: Vec<TemplateLimit>,
+    /// Bumped on every update, starting at 1.
+    pub version: u32,
+    pub updated_at: u64,
+}
+
+/// Template an owner has applied and the limits it created for them.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TemplateSubscription {
+    pub template_id: Symbol,
+    /// Template version the owner's settings currently reflect.
+    pub version: u32,
+    /// Limit IDs in the same order as the template's `limits`.
+    pub limit_ids: Vec<u64>,
+}
+
+// ---------------------------------------------------------------------------
+// Compliance
+// ---------------------------------------------------------------------------
+
+/// Why and by whom an account was frozen.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FreezeInfo {
+    pub reason: String,
+    /// The owner themselves or the compliance admin.
+    pub frozen_by: Address,
+    pub frozen_at: u64,
+}
+
+/// One-off approval letting a single transaction exceed the owner's limits.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LimitException {
+    pub owner: Address,
+    pub asset: Symbol,
+    /// Largest transaction amount the exception covers.
+    pub max_amount: i128,
+    /// Ledger timestamp after which the exception no longer applies.
+    pub expires_at: u64,
+    pub approved_by: Address,
+    pub approved_at: u64,
+}
+
+/// Compliance admin action captured in the audit log.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AuditAction {
+    /// `(new_admin)` — `None` when the role was renounced.
+    AdminChanged(Option<Address>),
+    /// `(owner, reason)`
+    AccountFrozen(Address, String),
+    /// `(owner)`
+    AccountUnfrozen(Address),
+    /// `(owner, limit_id, old_max, new_max)`
+    LimitLowered(Address, u64, i128, i128),
+    /// `(owner, asset, max_amount, expires_at)`
+    ExceptionApproved(Address, Symbol, i128, u64),
+    /// `(owner, asset)`
+    ExceptionRevoked(Address, Symbol),
+    /// `(template_id, version)`
+    TemplateSet(Symbol, u32),
+    /// `(template_id)`
+    TemplateRemoved(Symbol),
+    /// `(risk_level, default_limit_count)`
+    LevelDefaultsSet(RiskLevel, u32),
+    /// `(count)` — deployment-wide default limits replaced.
+    DefaultLimitsSet(u32),
+    /// `(new_wasm_hash)`
+    Upgraded(BytesN<32>),
+    /// `(from_version, to_version)`
+    Migrated(u32, u32),
+    /// `(asset, max_amount, time_window)`
+    GlobalLimitSet(Symbol, i128, u64),
+    /// `(asset)`
+    GlobalLimitRemoved(Symbol),
+}
+
+/// Append-only audit log entry.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AuditEntry {
+    pub id: u64,
+    pub admin: Address,
+    pub action: AuditAction,
+    pub timestamp: u64,
+    pub ledger: u32,
+}
@@ -0,0 +1,60 @@
+//! Security Limits — reference-currency valuation
+//!
+//! A limit denominated in a reference currency is expressed in its `asset`
+//! symbol (e.g. `USD`) and counts every asset the owner moves, converted
+//! through the configured price oracle.  The oracle is expected to expose the Galaxy
+//! price oracle's `get_price(base, quote)` entrypoint; prices older than the
+//! valuation's `max_age` are rejected rather than trusted.
+
+use soroban_sdk::{vec, Address, Env, IntoVal, Symbol};
+
+use crate::types::{LimitsError, OraclePrice, Valuation};
+
+/// Largest decimals value accepted for an oracle feed.
+pub const MAX_ORACLE_DECIMALS: u32 = 18;
+
+/// Value `amount` of `asset` in `reference` units.
+///
+/// Amounts already in the reference currency are returned unchanged.
+pub fn value_of(
+    env: &Env,
+    valuation: &Valuation,
+    asset: &Symbol,
+    reference: &Symbol,
+    amount: i128,
+) -> Result<i128, LimitsError> {
+    if asset == reference {
+        return Ok(amount);
+    }
+
+    let entry = fetch_price(env, &valuation.oracle, asset, reference)?;
+    if entry.price <= 0 {
+        return Err(LimitsError::PriceUnavailable);
+    }
+    if env.ledger().timestamp().saturating_sub(entry.timestamp) > valuation.max_age {
+        return Err(LimitsError::PriceStale);
+    }
+
+    amount
+        .checked_mul(entry.price)
+        .map(|value| value / 10i128.pow(valuation.decimals))
+        .ok_or(LimitsError::Overflow)
+}
+
+fn fetch_price(
+    env: &Env,
+    oracle: &Address,
+    base: &Symbol,
+    quote: &Symbol,
+) -> Result<OraclePrice, LimitsError> {
+    // A missing pair or unreachable oracle must surface as an error, not
+    // abort the caller outright
+    match env.try_invoke_contract::<OraclePrice, soroban_sdk::Error>(
+        oracle,
+        &Symbol::new(env, "get_price"),
+        vec![env, base.into_val(env), quote.into_val(env)],
+    ) {
+        Ok(Ok(entry)) => Ok(entry),
+        _ => Err(LimitsError::PriceUnavailable),
+    }
+}
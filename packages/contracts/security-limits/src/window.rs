@@ -0,0 +1,382 @@
+//! Security Limits — window accounting
+//!
+//! A fixed limit counts usage in `current_usage` from `last_reset` until its
+//! window ([`fixed_period`], or the calendar period in `Calendar` mode)
+//! ends.  The next check or record that sees the window over rolls
+//! `last_reset` forward to the current window's start and zeroes usage, and
+//! persists that, so checks and records always agree on the window.
+//!
+//! A rolling limit splits its window into [`ROLLING_BUCKETS`] equal time
+//! buckets held in a ring buffer.  Usage is the sum of every bucket that
+//! still overlaps the window ending now, so an owner who spends the full
+//! limit cannot spend it again until those buckets age out.
+//!
+//! Bucket granularity errs on the safe side: an amount keeps counting until
+//! its whole bucket has left the window, i.e. for up to one extra bucket span.
+//!
+//! Per-destination counters, and the per-asset breakdown of a portfolio
+//! limit, reuse the same ring: a rolling limit keeps [`ROLLING_BUCKETS`]
+//! slots per key, any other limit a single slot for the window starting at
+//! [`period_start`], so keyed usage resets exactly when `current_usage` does.
+//!
+//! Inflows credit `NetFlow` limits by taking usage back out: from
+//! `current_usage` for a fixed limit, and from the newest buckets first for
+//! a rolling one, so the credit stays in the window as long as possible.
+//! Usage never goes below zero.
+//!
+//! A burst allowance refills like a fixed window, every `refill_windows`
+//! times the limit's fixed period, aligned to when it was set.
+
+use soroban_sdk::{Address, Env, IntoVal, Map, Symbol, TryFromVal, Val, Vec};
+
+use crate::calendar;
+use crate::storage;
+use crate::types::{
+    BurstAllowance, GlobalLimit, LimitType, SecurityLimit, UsageBucket, WindowMode,
+};
+
+/// Number of buckets each rolling window is divided into.
+pub const ROLLING_BUCKETS: u32 = 24;
+
+/// Length in seconds of the window a limit is measured over.
+///
+/// `PerTransaction` limits have no window and return 0.
+pub fn window_length(limit: &SecurityLimit) -> u64 {
+    match limit.limit_type {
+        LimitType::Daily => 86400,
+        LimitType::Weekly => 604800,
+        LimitType::Monthly => 2592000,
+        LimitType::PerTransaction => 0,
+        LimitType::PerHour => 3600,
+        LimitType::Custom(window) => window,
+    }
+}
+
+/// Length of a fixed limit's window: its type's window, shortened to
+/// `time_window` when that is set and shorter.  `None` for per-transaction
+/// limits, which judge each transaction on its own.
+pub fn fixed_period(limit: &SecurityLimit) -> Option<u64> {
+    let window = window_length(limit);
+    if window == 0 {
+        return None;
+    }
+    if limit.time_window > 0 {
+        return Some(window.min(limit.time_window));
+    }
+    Some(window)
+}
+
+/// Start of the fixed window containing `now`: the calendar or scheduled
+/// period start in those modes, otherwise a whole number of fixed periods
+/// after `last_reset`.  `None` for per-transaction limits.
+pub fn period_start(limit: &SecurityLimit, now: u64) -> Option<u64> {
+    let boundary = match &limit.window_mode {
+        WindowMode::Calendar => calendar::period_start(&limit.limit_type, now),
+        WindowMode::Scheduled(schedule) => {
+            calendar::scheduled_start(schedule, &limit.limit_type, now)
+        }
+        _ => None,
+    };
+    if boundary.is_some() {
+        return boundary;
+    }
+    let period = fixed_period(limit)?;
+    let elapsed = now.saturating_sub(limit.last_reset);
+    Some(limit.last_reset + elapsed - elapsed % period)
+}
+
+/// Start a new fixed window if the current one ended by `now`, returning
+/// whether `limit` changed and must be stored.
+///
+/// Windows stay aligned to the original `last_reset` rather than restarting
+/// at whichever transaction first notices the rollover.
+pub fn roll_fixed(limit: &mut SecurityLimit, now: u64) -> bool {
+    let Some(start) = period_start(limit, now) else {
+        // Per-transaction limits never carry usage between transactions
+        let stale = limit.current_usage != 0;
+        limit.current_usage = 0;
+        return stale;
+    };
+    if start <= limit.last_reset {
+        return false;
+    }
+    limit.last_reset = start;
+    limit.current_usage = 0;
+    true
+}
+
+/// Refill the burst budget if its refill period ended by `now`.
+pub fn roll_burst(limit: &SecurityLimit, burst: &mut BurstAllowance, now: u64) {
+    let Some(period) = fixed_period(limit) else {
+        return;
+    };
+    let period = period.saturating_mul(u64::from(burst.refill_windows));
+    let elapsed = now.saturating_sub(burst.refilled_at);
+    if period > 0 && elapsed >= period {
+        burst.refilled_at += elapsed - elapsed % period;
+        burst.used = 0;
+    }
+}
+
+/// Burst budget left at `now`.
+pub fn burst_remaining(limit: &SecurityLimit, burst: &BurstAllowance, now: u64) -> i128 {
+    let mut burst = burst.clone();
+    roll_burst(limit, &mut burst, now);
+    // Split to keep `max_amount * extra_bps` from overflowing
+    let bps = i128::from(burst.extra_bps);
+    let budget = limit.max_amount / 10_000 * bps + limit.max_amount % 10_000 * bps / 10_000;
+    (budget - burst.used).max(0)
+}
+
+/// `(bucket span in seconds, slot count)` for a limit's ring, or `None` when
+/// the limit has no window to accumulate over.
+fn ring_shape(limit: &SecurityLimit) -> Option<(u64, u32)> {
+    let window = window_length(limit);
+    if window == 0 {
+        return None;
+    }
+    match limit.window_mode {
+        // Round the span up so the buckets cover the whole window
+        WindowMode::Rolling => Some((
+            window.div_ceil(ROLLING_BUCKETS as u64).max(1),
+            ROLLING_BUCKETS,
+        )),
+        WindowMode::Fixed | WindowMode::Calendar | WindowMode::Scheduled(_) => Some((window, 1)),
+    }
+}
+
+/// `(bucket span, slot count, position)` of `now` in a limit's per-key
+/// rings, or `None` when the limit has no window to accumulate over.
+///
+/// A rolling limit buckets by time like its own ring.  Any other limit keeps
+/// one slot, positioned at the start of the window containing `now`.
+fn keyed_ring(limit: &SecurityLimit, now: u64) -> Option<(u64, u32, u64)> {
+    if window_length(limit) == 0 {
+        return None;
+    }
+    if limit.window_mode == WindowMode::Rolling {
+        let (span, slots) = ring_shape(limit)?;
+        return Some((span, slots, now));
+    }
+    Some((1, 1, period_start(limit, now)?))
+}
+
+/// Sum of the buckets still inside the window ending at `now`.
+pub fn ring_usage(buckets: &Vec<UsageBucket>, span: u64, slots: u32, now: u64) -> i128 {
+    let current = now / span;
+    let mut usage = 0i128;
+    for bucket in buckets.iter() {
+        if current - bucket.epoch < slots as u64 {
+            usage = usage.saturating_add(bucket.amount);
+        }
+    }
+    usage
+}
+
+/// Add `amount` to the bucket for `now`, allocating the ring if needed.
+pub fn ring_add(
+    env: &Env,
+    buckets: &mut Vec<UsageBucket>,
+    span: u64,
+    slots: u32,
+    now: u64,
+    amount: i128,
+) {
+    if buckets.len() != slots {
+        *buckets = Vec::new(env);
+        for _ in 0..slots {
+            buckets.push_back(UsageBucket {
+                epoch: 0,
+                amount: 0,
+            });
+        }
+    }
+    let current = now / span;
+    let slot = (current % slots as u64) as u32;
+    let mut bucket = buckets.get(slot).unwrap();
+    if bucket.epoch == current {
+        bucket.amount = bucket.amount.saturating_add(amount);
+    } else {
+        // The slot holds a bucket from a previous lap; overwrite it
+        bucket = UsageBucket {
+            epoch: current,
+            amount,
+        };
+    }
+    buckets.set(slot, bucket);
+}
+
+/// Usage of `limit` inside the rolling window ending at `now`.
+pub fn rolling_usage(env: &Env, limit: &SecurityLimit, now: u64) -> i128 {
+    match ring_shape(limit) {
+        Some((span, slots)) => ring_usage(
+            &storage::get_buckets(env, &limit.owner, limit.id),
+            span,
+            slots,
+            now,
+        ),
+        None => 0,
+    }
+}
+
+/// When the oldest bucket still counted in the rolling window ending at `now`
+/// leaves it, or `None` when nothing is counted.
+pub fn rolling_resets_at(env: &Env, limit: &SecurityLimit, now: u64) -> Option<u64> {
+    let (span, slots) = ring_shape(limit)?;
+    let current = now / span;
+    storage::get_buckets(env, &limit.owner, limit.id)
+        .iter()
+        .filter(|bucket| bucket.amount > 0 && current - bucket.epoch < slots as u64)
+        .map(|bucket| (bucket.epoch + slots as u64) * span)
+        .min()
+}
+
+/// Add `amount` to the bucket for `now` and return the new rolling usage.
+pub fn add_rolling_usage(env: &Env, limit: &SecurityLimit, now: u64, amount: i128) -> i128 {
+    let Some((span, slots)) = ring_shape(limit) else {
+        return 0;
+    };
+    let mut buckets = storage::get_buckets(env, &limit.owner, limit.id);
+    ring_add(env, &mut buckets, span, slots, now, amount);
+    storage::set_buckets(env, &limit.owner, limit.id, &buckets);
+    ring_usage(&buckets, span, slots, now)
+}
+
+/// Take up to `amount` out of the rolling window ending at `now`, newest
+/// bucket first, and return the new rolling usage.
+pub fn credit_rolling_usage(env: &Env, limit: &SecurityLimit, now: u64, amount: i128) -> i128 {
+    let Some((span, slots)) = ring_shape(limit) else {
+        return 0;
+    };
+    let mut buckets = storage::get_buckets(env, &limit.owner, limit.id);
+    let current = now / span;
+    let mut left = amount;
+    for age in 0..(slots as u64).min(current + 1) {
+        if left == 0 {
+            break;
+        }
+        let epoch = current - age;
+        let slot = (epoch % slots as u64) as u32;
+        if let Some(mut bucket) = buckets.get(slot) {
+            if bucket.epoch == epoch && bucket.amount > 0 {
+                let taken = bucket.amount.min(left);
+                bucket.amount -= taken;
+                left -= taken;
+                buckets.set(slot, bucket);
+            }
+        }
+    }
+    storage::set_buckets(env, &limit.owner, limit.id, &buckets);
+    ring_usage(&buckets, span, slots, now)
+}
+
+/// Deployment-wide usage of `global` inside its rolling window at `now`.
+pub fn global_usage(global: &GlobalLimit, now: u64) -> i128 {
+    let (span, slots) = global_shape(global);
+    ring_usage(&global.usage, span, slots, now)
+}
+
+/// Add `amount` to `global`'s ring; the caller stores it.
+pub fn add_global_usage(env: &Env, global: &mut GlobalLimit, now: u64, amount: i128) {
+    let (span, slots) = global_shape(global);
+    ring_add(env, &mut global.usage, span, slots, now, amount);
+}
+
+fn global_shape(global: &GlobalLimit) -> (u64, u32) {
+    (
+        global.time_window.div_ceil(ROLLING_BUCKETS as u64).max(1),
+        ROLLING_BUCKETS,
+    )
+}
+
+/// Usage of a per-destination `limit` towards `destination` at `now`.
+pub fn destination_usage(
+    env: &Env,
+    limit: &SecurityLimit,
+    destination: &Address,
+    now: u64,
+) -> i128 {
+    let Some((span, slots, at)) = keyed_ring(limit, now) else {
+        return 0;
+    };
+    match storage::get_destination_usage(env, &limit.owner, limit.id).get(destination.clone()) {
+        Some(buckets) => ring_usage(&buckets, span, slots, at),
+        None => 0,
+    }
+}
+
+/// Add `amount` to `destination`'s counter and return its new usage.
+///
+/// Destinations whose usage has aged out are pruned on every write, so the
+/// stored map only holds counterparties active within the window.
+pub fn add_destination_usage(
+    env: &Env,
+    limit: &SecurityLimit,
+    destination: &Address,
+    now: u64,
+    amount: i128,
+) -> i128 {
+    let Some((span, slots, at)) = keyed_ring(limit, now) else {
+        return 0;
+    };
+    let stored = storage::get_destination_usage(env, &limit.owner, limit.id);
+    let (usage, pruned) = add_keyed(env, stored, destination, span, slots, at, amount);
+    storage::set_destination_usage(env, &limit.owner, limit.id, &pruned);
+    usage
+}
+
+/// Reference-valued usage of a portfolio `limit`, per asset, at `now`.
+///
+/// Assets with nothing left in the window are omitted.
+pub fn asset_usage(env: &Env, limit: &SecurityLimit, now: u64) -> Map<Symbol, i128> {
+    let mut usage = Map::new(env);
+    let Some((span, slots, at)) = keyed_ring(limit, now) else {
+        return usage;
+    };
+    for (asset, buckets) in storage::get_asset_usage(env, &limit.owner, limit.id).iter() {
+        let amount = ring_usage(&buckets, span, slots, at);
+        if amount > 0 {
+            usage.set(asset, amount);
+        }
+    }
+    usage
+}
+
+/// Add the reference-valued `amount` to `asset`'s share of a portfolio
+/// `limit`.
+pub fn add_asset_usage(env: &Env, limit: &SecurityLimit, asset: &Symbol, now: u64, amount: i128) {
+    let Some((span, slots, at)) = keyed_ring(limit, now) else {
+        return;
+    };
+    let stored = storage::get_asset_usage(env, &limit.owner, limit.id);
+    let (_, pruned) = add_keyed(env, stored, asset, span, slots, at, amount);
+    storage::set_asset_usage(env, &limit.owner, limit.id, &pruned);
+}
+
+/// Add `amount` to `key`'s ring, pruning keys whose usage has aged out, and
+/// return `key`'s new usage with the pruned map.
+fn add_keyed<K>(
+    env: &Env,
+    stored: Map<K, Vec<UsageBucket>>,
+    key: &K,
+    span: u64,
+    slots: u32,
+    now: u64,
+    amount: i128,
+) -> (i128, Map<K, Vec<UsageBucket>>)
+where
+    K: Clone + IntoVal<Env, Val> + TryFromVal<Env, Val>,
+{
+    let mut pruned: Map<K, Vec<UsageBucket>> = Map::new(env);
+    for (k, buckets) in stored.iter() {
+        if ring_usage(&buckets, span, slots, now) > 0 {
+            pruned.set(k, buckets);
+        }
+    }
+
+    let mut buckets = pruned.get(key.clone()).unwrap_or(Vec::new(env));
+    ring_add(env, &mut buckets, span, slots, now, amount);
+    let usage = ring_usage(&buckets, span, slots, now);
+    pruned.set(key.clone(), buckets);
+    (usage, pruned)
+}
@@ -5,9 +5,12 @@
 
 #![no_std]
 
+mod normalize;
+pub use normalize::{MAX_ORACLE_DECIMALS, PRICE_DECIMALS};
+
 use soroban_sdk::{
-    contract, contractimpl, contracttype, symbol_short, vec, Address, BytesN, Env, Map, Symbol,
-    Vec, String as SorobanString,
+    contract, contractimpl, contracttype, symbol_short, vec, xdr::ToXdr, Address, BytesN, Env,
+    IntoVal, Map, Symbol, Vec,
 };
 
 /// Contract type definitions
@@ -57,11 +60,34 @@ pub struct SwapExecution {
     pub transaction_hash: BytesN<32>,
 }
 
+/// Price observation returned by the oracle's `get_price(base, quote)`.
+///
+/// Mirrors the price-oracle contract's `PriceEntry`; `price` is scaled by the
+/// decimals configured for that oracle via `set_oracle_decimals`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct OraclePrice {
+    pub price: i128,
+    pub timestamp: u64,
+    pub pusher: Address,
+}
+
 /// Contract storage keys
 const SWAP_CONDITIONS: Symbol = symbol_short!("SWAP_COND");
 const SWAP_EXECUTIONS: Symbol = symbol_short!("SWAP_EXEC");
 const NEXT_CONDITION_ID: Symbol = symbol_short!("NEXT_ID");
-const PRICE_ORACLE: Symbol = symbol_short!("PRICE_ORACLE");
+const PRICE_ORACLE: Symbol = symbol_short!("ORACLE");
+const ADMIN: Symbol = symbol_short!("ADMIN");
+/// `Map<Address, u32>` — decimals reported by each configured oracle.
+const ORACLE_DECIMALS: Symbol = symbol_short!("ORACLE_DC");
+const GUARDIAN: Symbol = symbol_short!("GUARDIAN");
+/// `Map<(Symbol, Symbol), u64>` — halted pairs and the time they were halted.
+const PAUSED_PAIRS: Symbol = symbol_short!("PAUSED");
+
+/// Event topics
+const EVT_GUARDIAN: Symbol = symbol_short!("guardian");
+const EVT_PAIR_PAUSE: Symbol = symbol_short!("pr_pause");
+const EVT_PAIR_RESUME: Symbol = symbol_short!("pr_resume");
 
 /// Smart Swap Contract
 #[contract]
@@ -71,13 +97,128 @@ pub struct SmartSwapContract;
 #[contractimpl]
 impl SmartSwapContract {
     /// Initialize the contract
-    pub fn initialize(env: &Env, price_oracle: Address) {
+    pub fn initialize(env: &Env, admin: Address, price_oracle: Address) {
         let storage = env.storage().instance();
+        if storage.has(&ADMIN) {
+            panic!("Already initialized");
+        }
+        storage.set(&ADMIN, &admin);
         storage.set(&PRICE_ORACLE, &price_oracle);
         storage.set(&NEXT_CONDITION_ID, &1u64);
     }
 
+    /// Configure the decimal scaling used by `oracle`'s price feed.
+    ///
+    /// Oracles without an explicit entry are assumed to report at
+    /// `PRICE_DECIMALS`.  Only the admin may call.
+    pub fn set_oracle_decimals(env: &Env, oracle: Address, decimals: u32) {
+        let storage = env.storage().instance();
+        let admin: Address = storage.get(&ADMIN).unwrap();
+        admin.require_auth();
+
+        if decimals > MAX_ORACLE_DECIMALS {
+            panic!("Unsupported oracle decimals");
+        }
+
+        let mut configured: Map<Address, u32> =
+            storage.get(&ORACLE_DECIMALS).unwrap_or(Map::new(env));
+        configured.set(oracle, decimals);
+        storage.set(&ORACLE_DECIMALS, &configured);
+    }
+
+    /// Get the decimal scaling used by `oracle`'s price feed
+    pub fn get_oracle_decimals(env: &Env, oracle: Address) -> u32 {
+        let configured: Map<Address, u32> = env
+            .storage()
+            .instance()
+            .get(&ORACLE_DECIMALS)
+            .unwrap_or(Map::new(env));
+        configured.get(oracle).unwrap_or(PRICE_DECIMALS)
+    }
+
+    /// Set the guardian allowed to halt individual asset pairs.
+    /// Only the admin may call; until set, the admin acts as guardian.
+    pub fn set_guardian(env: &Env, guardian: Address) {
+        let storage = env.storage().instance();
+        let admin: Address = storage.get(&ADMIN).unwrap();
+        admin.require_auth();
+        storage.set(&GUARDIAN, &guardian);
+
+        env.events().publish((EVT_GUARDIAN,), guardian);
+    }
+
+    /// Get the current guardian
+    pub fn get_guardian(env: &Env) -> Address {
+        let storage = env.storage().instance();
+        storage
+            .get(&GUARDIAN)
+            .unwrap_or_else(|| storage.get(&ADMIN).unwrap())
+    }
+
+    /// Halt execution for a single asset pair, in either direction, while
+    /// other markets keep operating.  Only the guardian may call.
+    pub fn pause_pair(env: &Env, source_asset: Symbol, destination_asset: Symbol) {
+        Self::get_guardian(env).require_auth();
+
+        let storage = env.storage().instance();
+        let mut paused: Map<(Symbol, Symbol), u64> =
+            storage.get(&PAUSED_PAIRS).unwrap_or(Map::new(env));
+        let key = Self::pair_key(&source_asset, &destination_asset);
+        if paused.contains_key(key.clone()) {
+            panic!("Pair already paused");
+        }
+        paused.set(key, env.ledger().timestamp());
+        storage.set(&PAUSED_PAIRS, &paused);
+
+        env.events().publish(
+            (EVT_PAIR_PAUSE, source_asset, destination_asset),
+            env.ledger().timestamp(),
+        );
+    }
+
+    /// Resume execution for a previously halted pair.  Only the guardian may call.
+    pub fn unpause_pair(env: &Env, source_asset: Symbol, destination_asset: Symbol) {
+        Self::get_guardian(env).require_auth();
+
+        let storage = env.storage().instance();
+        let mut paused: Map<(Symbol, Symbol), u64> =
+            storage.get(&PAUSED_PAIRS).unwrap_or(Map::new(env));
+        let key = Self::pair_key(&source_asset, &destination_asset);
+        if !paused.contains_key(key.clone()) {
+            panic!("Pair is not paused");
+        }
+        paused.remove(key);
+        storage.set(&PAUSED_PAIRS, &paused);
+
+        env.events().publish(
+            (EVT_PAIR_RESUME, source_asset, destination_asset),
+            env.ledger().timestamp(),
+        );
+    }
+
+    /// Check whether execution is halted for a pair (in either direction)
+    pub fn pair_paused(env: &Env, source_asset: Symbol, destination_asset: Symbol) -> bool {
+        let paused: Map<(Symbol, Symbol), u64> = env
+            .storage()
+            .instance()
+            .get(&PAUSED_PAIRS)
+            .unwrap_or(Map::new(env));
+        paused.contains_key(Self::pair_key(&source_asset, &destination_asset))
+    }
+
+    /// Get every halted pair with the timestamp it was halted at
+    pub fn get_paused_pairs(env: &Env) -> Map<(Symbol, Symbol), u64> {
+        env.storage()
+            .instance()
+            .get(&PAUSED_PAIRS)
+            .unwrap_or(Map::new(env))
+    }
+
     /// Create a new swap condition
+    ///
+    /// Prices carried by `condition_type` are expressed at `PRICE_DECIMALS`
+    /// precision regardless of the oracle's native scaling.
+    #[allow(clippy::too_many_arguments)]
     pub fn create_swap_condition(
         env: &Env,
         owner: Address,
@@ -108,7 +249,7 @@ impl SmartSwapContract {
         };
 
         // Store the condition
-        let mut conditions: Map<u64, SwapCondition> = storage.get(&SWAP_CONDITIONS).unwrap_or(Map::new(&env));
+        let mut conditions: Map<u64, SwapCondition> = storage.get(&SWAP_CONDITIONS).unwrap_or(Map::new(env));
         conditions.set(next_id, condition);
         storage.set(&SWAP_CONDITIONS, &conditions);
         
@@ -122,7 +263,7 @@ impl SmartSwapContract {
     /// Execute a swap condition if conditions are met
     pub fn execute_swap_condition(env: &Env, condition_id: u64) -> SwapExecution {
         let storage = env.storage().instance();
-        let mut conditions: Map<u64, SwapCondition> = storage.get(&SWAP_CONDITIONS).unwrap_or(Map::new(&env));
+        let mut conditions: Map<u64, SwapCondition> = storage.get(&SWAP_CONDITIONS).unwrap_or(Map::new(env));
         
         let mut condition = conditions.get(condition_id).unwrap();
         
@@ -131,6 +272,15 @@ impl SmartSwapContract {
             panic!("Condition is not active");
         }
 
+        // Check if the pair has been halted by the guardian
+        if Self::pair_paused(
+            env,
+            condition.source_asset.clone(),
+            condition.destination_asset.clone(),
+        ) {
+            panic!("Pair is paused");
+        }
+
         // Check if condition has expired
         if env.ledger().timestamp() > condition.expires_at {
             condition.status = SwapStatus::Expired;
@@ -155,6 +305,7 @@ impl SmartSwapContract {
             &condition.source_asset,
             &condition.destination_asset,
             condition.amount_to_swap,
+            current_price,
             condition.min_amount_out,
             condition.max_slippage,
         );
@@ -171,10 +322,13 @@ impl SmartSwapContract {
             executed_at: env.ledger().timestamp(),
             actual_amount_out,
             price_at_execution: current_price,
-            transaction_hash: env.current_contract_address().to_array(),
+            transaction_hash: env
+                .crypto()
+                .sha256(&(condition_id, env.ledger().timestamp()).to_xdr(env))
+                .into(),
         };
 
-        let mut executions: Vec<SwapExecution> = storage.get(&SWAP_EXECUTIONS).unwrap_or(Vec::new(&env));
+        let mut executions: Vec<SwapExecution> = storage.get(&SWAP_EXECUTIONS).unwrap_or(Vec::new(env));
         executions.push_back(execution.clone());
         storage.set(&SWAP_EXECUTIONS, &executions);
 
@@ -184,9 +338,9 @@ impl SmartSwapContract {
     /// Get all active swap conditions for an owner
     pub fn get_active_conditions(env: &Env, owner: Address) -> Vec<SwapCondition> {
         let storage = env.storage().instance();
-        let conditions: Map<u64, SwapCondition> = storage.get(&SWAP_CONDITIONS).unwrap_or(Map::new(&env));
+        let conditions: Map<u64, SwapCondition> = storage.get(&SWAP_CONDITIONS).unwrap_or(Map::new(env));
         
-        let mut active_conditions = Vec::new(&env);
+        let mut active_conditions = Vec::new(env);
         
         for (_, condition) in conditions.iter() {
             if condition.owner == owner && condition.status == SwapStatus::Active {
@@ -200,7 +354,7 @@ impl SmartSwapContract {
     /// Cancel a swap condition
     pub fn cancel_condition(env: &Env, condition_id: u64, owner: Address) {
         let storage = env.storage().instance();
-        let mut conditions: Map<u64, SwapCondition> = storage.get(&SWAP_CONDITIONS).unwrap_or(Map::new(&env));
+        let mut conditions: Map<u64, SwapCondition> = storage.get(&SWAP_CONDITIONS).unwrap_or(Map::new(env));
         
         let mut condition = conditions.get(condition_id).unwrap();
         
@@ -223,9 +377,9 @@ impl SmartSwapContract {
     /// Get swap execution history
     pub fn get_execution_history(env: &Env, condition_id: u64) -> Vec<SwapExecution> {
         let storage = env.storage().instance();
-        let executions: Vec<SwapExecution> = storage.get(&SWAP_EXECUTIONS).unwrap_or(Vec::new(&env));
+        let executions: Vec<SwapExecution> = storage.get(&SWAP_EXECUTIONS).unwrap_or(Vec::new(env));
         
-        let mut filtered_executions = Vec::new(&env);
+        let mut filtered_executions = Vec::new(env);
         
         for execution in executions.iter() {
             if execution.condition_id == condition_id {
@@ -236,16 +390,36 @@ impl SmartSwapContract {
         filtered_executions
     }
 
-    /// Helper function to get current price from oracle
+    /// Helper function to get current price from oracle, normalized to
+    /// `PRICE_DECIMALS`
     fn get_current_price(
         env: &Env,
         price_oracle: &Address,
         source_asset: &Symbol,
         destination_asset: &Symbol,
     ) -> u64 {
-        // This would typically call a price oracle contract
-        // For now, return a mock price
-        1000 // Mock price
+        let entry: OraclePrice = env.invoke_contract(
+            price_oracle,
+            &Symbol::new(env, "get_price"),
+            vec![
+                env,
+                source_asset.into_val(env),
+                destination_asset.into_val(env),
+            ],
+        );
+
+        let decimals = Self::get_oracle_decimals(env, price_oracle.clone());
+        normalize::normalize_price(entry.price, decimals)
+            .unwrap_or_else(|| panic!("Invalid oracle price"))
+    }
+
+    /// Helper function to build a direction-independent pair key
+    fn pair_key(a: &Symbol, b: &Symbol) -> (Symbol, Symbol) {
+        if a <= b {
+            (a.clone(), b.clone())
+        } else {
+            (b.clone(), a.clone())
+        }
     }
 
     /// Helper function to check if condition is met
@@ -266,18 +440,33 @@ impl SmartSwapContract {
     }
 
     /// Helper function to execute the actual swap
+    ///
+    /// The acceptable floor is the stricter of `min_amount_out` and the
+    /// oracle-quoted output less `max_slippage` percent.
+    #[allow(clippy::too_many_arguments)]
     fn execute_swap(
-        env: &Env,
-        owner: &Address,
-        source_asset: &Symbol,
-        destination_asset: &Symbol,
+        _env: &Env,
+        _owner: &Address,
+        _source_asset: &Symbol,
+        _destination_asset: &Symbol,
         amount_in: u64,
+        price: u64,
         min_amount_out: u64,
         max_slippage: u32,
     ) -> u64 {
+        let expected_out = normalize::quote_amount(amount_in, price)
+            .unwrap_or_else(|| panic!("Quote overflow"));
+        let floor = normalize::apply_slippage(expected_out, max_slippage).max(min_amount_out);
+
         // This would typically interact with a DEX or AMM
-        // For now, return a mock amount
-        min_amount_out
+        // For now, fill at the oracle quote
+        let amount_out = expected_out;
+
+        if amount_out < floor {
+            panic!("Slippage exceeded");
+        }
+
+        amount_out
     }
 }
 
@@ -0,0 +1,61 @@
+//! Price normalization helpers
+//!
+//! Oracles report prices with their own decimal scaling (the Galaxy price
+//! oracle uses 6 decimals, many external feeds use 7, 8 or 18).  Everything
+//! inside the swap contract — trigger prices, reference prices and slippage
+//! math — is expressed at [`PRICE_DECIMALS`] precision, so raw feed values are
+//! rescaled here before they reach any comparison.
+
+/// Canonical precision for every price handled by the contract.
+pub const PRICE_DECIMALS: u32 = 6;
+
+/// Largest decimals value accepted for an oracle feed.
+///
+/// `10^38` is the largest power of ten that fits in an `i128`; capping at 18
+/// leaves ample headroom for the intermediate products below.
+pub const MAX_ORACLE_DECIMALS: u32 = 18;
+
+/// Return `10^exp`, or `None` when it does not fit in an `i128`.
+fn pow10(exp: u32) -> Option<i128> {
+    10i128.checked_pow(exp)
+}
+
+/// Rescale `value` from `from_decimals` to `to_decimals` precision.
+///
+/// Scaling down truncates toward zero.  Returns `None` on overflow.
+pub fn rescale(value: i128, from_decimals: u32, to_decimals: u32) -> Option<i128> {
+    if from_decimals == to_decimals {
+        Some(value)
+    } else if from_decimals > to_decimals {
+        value.checked_div(pow10(from_decimals - to_decimals)?)
+    } else {
+        value.checked_mul(pow10(to_decimals - from_decimals)?)
+    }
+}
+
+/// Convert a raw oracle price reported with `oracle_decimals` into the
+/// contract's canonical [`PRICE_DECIMALS`] scale.
+///
+/// Returns `None` for non-positive prices or values that do not fit in `u64`.
+pub fn normalize_price(raw_price: i128, oracle_decimals: u32) -> Option<u64> {
+    if raw_price <= 0 {
+        return None;
+    }
+    let scaled = rescale(raw_price, oracle_decimals, PRICE_DECIMALS)?;
+    u64::try_from(scaled).ok()
+}
+
+/// Expected output for `amount_in` units of the source asset at a normalized
+/// `price` (destination units per source unit, [`PRICE_DECIMALS`] scale).
+pub fn quote_amount(amount_in: u64, price: u64) -> Option<u64> {
+    let product = (amount_in as u128).checked_mul(price as u128)?;
+    let out = product / 10u128.pow(PRICE_DECIMALS);
+    u64::try_from(out).ok()
+}
+
+/// Lowest acceptable output once `max_slippage` (whole percent, capped at
+/// 100) is deducted from `expected_out`.
+pub fn apply_slippage(expected_out: u64, max_slippage: u32) -> u64 {
+    let slippage = max_slippage.min(100) as u128;
+    ((expected_out as u128) * (100 - slippage) / 100) as u64
+}
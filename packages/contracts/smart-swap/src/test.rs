@@ -1,7 +1,64 @@
 //! Tests for Smart Swap Contract
 
 use super::*;
-use soroban_sdk::{testutils::Address as _, Address, Env, Symbol};
+use soroban_sdk::{contract, contractimpl, testutils::Address as _, Address, Env, Symbol};
+
+/// Minimal stand-in for the price-oracle contract: serves whatever raw price
+/// was last stored, at the oracle's own decimal scaling.
+#[contract]
+pub struct MockOracle;
+
+#[contractimpl]
+impl MockOracle {
+    pub fn set_price(env: Env, price: i128) {
+        env.storage()
+            .instance()
+            .set(&symbol_short!("PRICE"), &price);
+    }
+
+    pub fn get_price(env: Env, _base: Symbol, _quote: Symbol) -> OraclePrice {
+        OraclePrice {
+            price: env
+                .storage()
+                .instance()
+                .get(&symbol_short!("PRICE"))
+                .unwrap(),
+            timestamp: env.ledger().timestamp(),
+            pusher: env.current_contract_address(),
+        }
+    }
+}
+
+/// Deploy the swap contract wired to a mock oracle reporting `raw_price`.
+fn setup_with_oracle(env: &Env, raw_price: i128) -> (SmartSwapContractClient<'_>, Address) {
+    env.mock_all_auths();
+    let oracle_id = env.register_contract(None, MockOracle);
+    MockOracleClient::new(env, &oracle_id).set_price(&raw_price);
+
+    let contract_id = env.register_contract(None, SmartSwapContract);
+    let client = SmartSwapContractClient::new(env, &contract_id);
+    client.initialize(&Address::generate(env), &oracle_id);
+    (client, oracle_id)
+}
+
+fn create_condition(
+    env: &Env,
+    client: &SmartSwapContractClient,
+    condition_type: SwapConditionType,
+    amount_to_swap: u64,
+    min_amount_out: u64,
+) -> u64 {
+    client.create_swap_condition(
+        &Address::generate(env),
+        &symbol_short!("XLM"),
+        &symbol_short!("USDC"),
+        &condition_type,
+        &amount_to_swap,
+        &min_amount_out,
+        &5,
+        &1000000,
+    )
+}
 
 #[test]
 fn test_initialize() {
@@ -9,8 +66,9 @@ fn test_initialize() {
     let contract_id = env.register_contract(None, SmartSwapContract);
     let client = SmartSwapContractClient::new(&env, &contract_id);
     
+    let admin = Address::generate(&env);
     let price_oracle = Address::generate(&env);
-    client.initialize(&price_oracle);
+    client.initialize(&admin, &price_oracle);
     
     // Test that contract is initialized
     // This would typically check storage values
@@ -22,12 +80,13 @@ fn test_create_swap_condition() {
     let contract_id = env.register_contract(None, SmartSwapContract);
     let client = SmartSwapContractClient::new(&env, &contract_id);
     
+    let admin = Address::generate(&env);
     let price_oracle = Address::generate(&env);
-    client.initialize(&price_oracle);
+    client.initialize(&admin, &price_oracle);
     
     let owner = Address::generate(&env);
-    let source_asset = Symbol::short("XLM");
-    let destination_asset = Symbol::short("USDC");
+    let source_asset = symbol_short!("XLM");
+    let destination_asset = symbol_short!("USDC");
     let condition_type = SwapConditionType::PriceAbove(1000);
     let amount_to_swap = 1000;
     let min_amount_out = 950;
@@ -54,12 +113,13 @@ fn test_get_active_conditions() {
     let contract_id = env.register_contract(None, SmartSwapContract);
     let client = SmartSwapContractClient::new(&env, &contract_id);
     
+    let admin = Address::generate(&env);
     let price_oracle = Address::generate(&env);
-    client.initialize(&price_oracle);
+    client.initialize(&admin, &price_oracle);
     
     let owner = Address::generate(&env);
-    let source_asset = Symbol::short("XLM");
-    let destination_asset = Symbol::short("USDC");
+    let source_asset = symbol_short!("XLM");
+    let destination_asset = symbol_short!("USDC");
     let condition_type = SwapConditionType::PriceAbove(1000);
     let amount_to_swap = 1000;
     let min_amount_out = 950;
@@ -87,12 +147,13 @@ fn test_cancel_condition() {
     let contract_id = env.register_contract(None, SmartSwapContract);
     let client = SmartSwapContractClient::new(&env, &contract_id);
     
+    let admin = Address::generate(&env);
     let price_oracle = Address::generate(&env);
-    client.initialize(&price_oracle);
+    client.initialize(&admin, &price_oracle);
     
     let owner = Address::generate(&env);
-    let source_asset = Symbol::short("XLM");
-    let destination_asset = Symbol::short("USDC");
+    let source_asset = symbol_short!("XLM");
+    let destination_asset = symbol_short!("USDC");
     let condition_type = SwapConditionType::PriceAbove(1000);
     let amount_to_swap = 1000;
     let min_amount_out = 950;
@@ -116,3 +177,181 @@ fn test_cancel_condition() {
     assert_eq!(active_conditions.len(), 0);
 }
 
+
+#[test]
+fn test_oracle_decimals_default_and_configure() {
+    let env = Env::default();
+    let (client, oracle_id) = setup_with_oracle(&env, 0);
+
+    assert_eq!(client.get_oracle_decimals(&oracle_id), PRICE_DECIMALS);
+
+    client.set_oracle_decimals(&oracle_id, &8);
+    assert_eq!(client.get_oracle_decimals(&oracle_id), 8);
+}
+
+#[test]
+#[should_panic]
+fn test_oracle_decimals_above_max_panics() {
+    let env = Env::default();
+    let (client, oracle_id) = setup_with_oracle(&env, 0);
+    client.set_oracle_decimals(&oracle_id, &(MAX_ORACLE_DECIMALS + 1));
+}
+
+#[test]
+fn test_execute_normalizes_eight_decimal_feed() {
+    let env = Env::default();
+    // 0.12345678 USDC per XLM reported with 8 decimals
+    let (client, oracle_id) = setup_with_oracle(&env, 12_345_678);
+    client.set_oracle_decimals(&oracle_id, &8);
+
+    // Trigger expressed at PRICE_DECIMALS: 0.12 USDC
+    let condition_id = create_condition(
+        &env,
+        &client,
+        SwapConditionType::PriceAbove(120_000),
+        1_000_000_000,
+        0,
+    );
+    let execution = client.execute_swap_condition(&condition_id);
+
+    assert_eq!(execution.price_at_execution, 123_456);
+    assert_eq!(execution.actual_amount_out, 123_456_000);
+}
+
+#[test]
+#[should_panic]
+fn test_execute_unnormalized_feed_misses_trigger() {
+    let env = Env::default();
+    // Same 8-decimal feed, but the oracle is left at the default scale so the
+    // raw value reads as 12.345678 and a PriceBelow(0.2) trigger never fires.
+    let (client, _) = setup_with_oracle(&env, 12_345_678);
+    let condition_id = create_condition(
+        &env,
+        &client,
+        SwapConditionType::PriceBelow(200_000),
+        1_000_000_000,
+        0,
+    );
+    client.execute_swap_condition(&condition_id);
+}
+
+#[test]
+#[should_panic]
+fn test_execute_below_min_amount_out_panics() {
+    let env = Env::default();
+    let (client, _) = setup_with_oracle(&env, 1_000_000);
+    // Oracle quotes 1:1, so 1_000 in can never satisfy 2_000 out
+    let condition_id = create_condition(
+        &env,
+        &client,
+        SwapConditionType::PriceAbove(500_000),
+        1_000,
+        2_000,
+    );
+    client.execute_swap_condition(&condition_id);
+}
+
+#[test]
+fn test_normalize_helpers() {
+    assert_eq!(normalize::rescale(12_345_678, 8, 6), Some(123_456));
+    assert_eq!(normalize::rescale(1_234, 2, 6), Some(12_340_000));
+    assert_eq!(normalize::rescale(5, 6, 6), Some(5));
+    assert_eq!(normalize::normalize_price(0, 6), None);
+    assert_eq!(normalize::normalize_price(-1, 6), None);
+    assert_eq!(normalize::normalize_price(i128::MAX, 0), None);
+    assert_eq!(
+        normalize::quote_amount(2_000_000, 1_500_000),
+        Some(3_000_000)
+    );
+    assert_eq!(normalize::apply_slippage(1_000, 5), 950);
+    assert_eq!(normalize::apply_slippage(1_000, 250), 0);
+}
+
+#[test]
+fn test_guardian_defaults_to_admin() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, SmartSwapContract);
+    let client = SmartSwapContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin, &Address::generate(&env));
+    assert_eq!(client.get_guardian(), admin);
+
+    let guardian = Address::generate(&env);
+    client.set_guardian(&guardian);
+    assert_eq!(client.get_guardian(), guardian);
+}
+
+#[test]
+fn test_pause_and_unpause_pair() {
+    let env = Env::default();
+    let (client, _) = setup_with_oracle(&env, 1_000_000);
+    let xlm = symbol_short!("XLM");
+    let usdc = symbol_short!("USDC");
+    let btc = symbol_short!("BTC");
+
+    client.pause_pair(&xlm, &usdc);
+    assert!(client.pair_paused(&xlm, &usdc));
+    assert!(client.pair_paused(&usdc, &xlm));
+    assert!(!client.pair_paused(&xlm, &btc));
+    assert_eq!(client.get_paused_pairs().len(), 1);
+
+    client.unpause_pair(&usdc, &xlm);
+    assert!(!client.pair_paused(&xlm, &usdc));
+}
+
+#[test]
+fn test_pause_pair_requires_guardian_auth() {
+    let env = Env::default();
+    let (client, _) = setup_with_oracle(&env, 1_000_000);
+    let guardian = Address::generate(&env);
+    client.set_guardian(&guardian);
+
+    client.pause_pair(&symbol_short!("XLM"), &symbol_short!("USDC"));
+    let auths = env.auths();
+    assert_eq!(auths.len(), 1);
+    assert_eq!(auths.first().unwrap().0, guardian);
+}
+
+#[test]
+#[should_panic]
+fn test_execute_on_paused_pair_panics() {
+    let env = Env::default();
+    let (client, _) = setup_with_oracle(&env, 1_000_000);
+    let condition_id = create_condition(
+        &env,
+        &client,
+        SwapConditionType::PriceAbove(500_000),
+        1_000,
+        0,
+    );
+
+    client.pause_pair(&symbol_short!("USDC"), &symbol_short!("XLM"));
+    client.execute_swap_condition(&condition_id);
+}
+
+#[test]
+fn test_other_pairs_execute_while_one_is_paused() {
+    let env = Env::default();
+    let (client, _) = setup_with_oracle(&env, 1_000_000);
+    let condition_id = create_condition(
+        &env,
+        &client,
+        SwapConditionType::PriceAbove(500_000),
+        1_000,
+        0,
+    );
+
+    client.pause_pair(&symbol_short!("XLM"), &symbol_short!("BTC"));
+    let execution = client.execute_swap_condition(&condition_id);
+    assert_eq!(execution.actual_amount_out, 1_000);
+}
+
+#[test]
+#[should_panic]
+fn test_unpause_unknown_pair_panics() {
+    let env = Env::default();
+    let (client, _) = setup_with_oracle(&env, 1_000_000);
+    client.unpause_pair(&symbol_short!("XLM"), &symbol_short!("USDC"));
+}
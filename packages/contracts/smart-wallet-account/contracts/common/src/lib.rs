@@ -1,5 +1,5 @@
 #![no_std]
-use soroban_sdk::{contracterror, contracttype, Bytes, BytesN};
+use soroban_sdk::{contracterror, contracttype, Address, Bytes, BytesN, String, Symbol, Val, Vec};
 
 // ─── WebAuthn (passkey) signature ────────────────────────────────────────────
 
@@ -11,8 +11,9 @@ pub struct Signature {
     pub client_data_json: Bytes,
     /// Base64url-decoded credential ID that identifies the passkey.
     pub id: Bytes,
-    /// 64-byte compact ECDSA signature (R ‖ S, big-endian).
-    pub signature: BytesN<64>,
+    /// ECDSA signature, either 64-byte compact (R ‖ S, big-endian) or DER
+    /// as the authenticator returns it.
+    pub signature: Bytes,
 }
 
 // ─── Session-key (Ed25519) signature ─────────────────────────────────────────
@@ -34,26 +35,49 @@ pub struct SessionSig {
 
 /// Top-level signature type for the smart-wallet `__check_auth`.
 ///
-/// The wallet supports two signer kinds:
-/// - `WebAuthn`   — admin passkey (secp256r1 / P-256).  Requires a biometric
-///                  prompt for every signed transaction.
-/// - `SessionKey` — short-lived Ed25519 key registered on-chain via
-///                  `add_session_signer`.  Allows many transactions within a
-///                  time window without repeated biometric prompts.
+/// The wallet supports three signature types:
+/// - `WebAuthn`      — admin or operator passkey (secp256r1 / P-256).
+///                     Requires a biometric prompt for every signed
+///                     transaction.
+/// - `MultiWebAuthn` — assertions from several passkeys over the same
+///                     payload, for wallets whose auth threshold is above 1.
+/// - `SessionKey`    — short-lived Ed25519 key registered on-chain via
+///                     `add_session_signer`.  Allows many transactions within
+///                     a time window without repeated biometric prompts.
 #[contracttype]
 #[derive(Clone, Debug)]
 pub enum AccountSignature {
     WebAuthn(Signature),
+    MultiWebAuthn(Vec<Signature>),
     SessionKey(SessionSig),
 }
 
 // ─── Signer kinds ─────────────────────────────────────────────────────────────
 
 #[contracttype]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum SignerKind {
     Admin,
     Session,
+    /// Passkey that can authorize transactions but not manage the wallet.
+    Operator,
+}
+
+// ─── Session spending policy ─────────────────────────────────────────────────
+
+/// Spending caps for a session signer, enforced in `__check_auth` against the
+/// SEP-41 token calls (`transfer`, `transfer_from`, `approve`, `burn`,
+/// `burn_from`) it authorizes.  Amounts are in each token's base units.
+/// The default policy, with no caps and no token list, is unrestricted.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SessionPolicy {
+    /// Largest amount a single token call may move, `None` for no cap.
+    pub max_per_tx: Option<i128>,
+    /// Largest total the session may move over its lifetime, `None` for no cap.
+    pub max_total: Option<i128>,
+    /// Token contracts the session may move; empty allows any token.
+    pub allowed_tokens: Vec<Address>,
 }
 
 // ─── Stored signer ────────────────────────────────────────────────────────────
@@ -62,19 +86,383 @@ pub enum SignerKind {
 ///
 /// `public_key` is variable-length `Bytes` to accommodate both key types:
 /// - Admin  (`SignerKind::Admin`)   → 65 bytes (SEC-1 uncompressed P-256: `0x04 ‖ X ‖ Y`).
+/// - Operator (`SignerKind::Operator`) → 65 bytes, like admins.
 /// - Session (`SignerKind::Session`) → 32 bytes (raw Ed25519 public key).
 ///
 /// `ttl_ledgers` is the session lifetime originally requested via
 /// `add_session_signer`.  It is stored so that `extend_signer_ttl` can
 /// accurately renew the TTL on each successful auth without over-extending.
-/// Admin signers set this to `0` (sentinel — TTL is managed by constants).
+/// Admin and operator signers set this to `0` (sentinel — TTL is managed by constants).
+///
+/// `expires_at` is the ledger timestamp (seconds) after which a session
+/// signer is rejected, however far its storage TTL has been extended.
+/// Admin and operator signers set this to `0` (never expire).
+///
+/// `policy`, `spent`, `allowed_contracts` and `uses_left` only apply to
+/// session signers, except that operators may have `policy.allowed_tokens`
+/// set too (admins keep the unrestricted defaults): `spent` is the
+/// total moved so far under the policy's `max_total`, a non-empty
+/// `allowed_contracts` limits the contracts the session may authorize calls
+/// to, and a non-zero `uses_left` counts down the auths it has left.
+///
+/// `label`, `added_at` and `last_used_at` let wallet UIs show a device list
+/// ("iPhone passkey, last used 2d ago"); the timestamps are ledger
+/// timestamps, and `last_used_at` is 0 until the signer first passes
+/// `__check_auth`.
 #[contracttype]
 #[derive(Clone, Debug)]
 pub struct Signer {
     pub public_key: Bytes,
     pub kind: SignerKind,
-    /// Session TTL in ledgers (0 for admin signers).
+    /// Session TTL in ledgers (0 for passkey signers).
     pub ttl_ledgers: u32,
+    /// Absolute session expiry as a ledger timestamp (0 for passkey signers).
+    pub expires_at: u64,
+    pub policy: SessionPolicy,
+    pub spent: i128,
+    pub allowed_contracts: Vec<Address>,
+    /// Successful auths left before the session is removed (0 for no cap).
+    pub uses_left: u32,
+    pub label: String,
+    pub added_at: u64,
+    pub last_used_at: u64,
+    /// Sub-account whose budgets an operator or session signer spends
+    /// from; `None` for the wallet as a whole.
+    pub sub_account: Option<Symbol>,
+    /// WebAuthn origin a passkey signer's assertions must come from, on
+    /// top of the wallet's origin allowlist; `None` for any allowed origin.
+    pub origin: Option<Bytes>,
+}
+
+// ─── Signer listing ───────────────────────────────────────────────────────────
+
+/// A live signer as returned by `list_signers`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SignerInfo {
+    pub credential_id: Bytes,
+    pub kind: SignerKind,
+    /// Same encoding as [`Signer::public_key`].
+    pub public_key: Bytes,
+}
+
+// ─── Initial signers ──────────────────────────────────────────────────────────
+
+/// A passkey a wallet is deployed with besides its first admin, e.g. a
+/// backup security key.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InitialSigner {
+    pub credential_id: Bytes,
+    pub public_key: BytesN<65>,
+    /// `Admin` or `Operator`; session keys are not passkeys.
+    pub kind: SignerKind,
+}
+
+// ─── Multisig proposals ───────────────────────────────────────────────────────
+
+/// A sensitive wallet operation that needs `threshold` admin approvals once
+/// the threshold is above 1.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AdminOperation {
+    /// Add an admin signer: `(credential_id, public_key)`.
+    AddSigner(Bytes, BytesN<65>),
+    /// Remove an admin signer by credential ID.
+    RemoveSigner(Bytes),
+    /// Replace an admin signer's credential and key in one step:
+    /// `(old_credential_id, new_credential_id, new_public_key)`.
+    RotateSigner(Bytes, Bytes, BytesN<65>),
+    /// Change the number of admin approvals required.
+    SetThreshold(u32),
+    /// Change the number of passkeys that must sign each authorization.
+    SetAuthThreshold(u32),
+    /// Replace the wallet's code with an uploaded Wasm hash.
+    Upgrade(BytesN<32>),
+    /// Make a batch of calls from the wallet outside its daily spending
+    /// limits; needs at least two admin approvals.
+    Invoke(Vec<Invocation>),
+    /// Lift a freeze immediately.
+    Unfreeze,
+    /// Run a queued timelocked action now, before its delay has passed;
+    /// needs a supermajority of admins.
+    FastTrackAction(u64),
+    /// Finalize the pending guardian recovery now, once its guardians have
+    /// approved it, instead of waiting out the recovery delay; needs a
+    /// supermajority of admins.
+    FastTrackRecovery,
+    /// Designate a guardian.
+    AddGuardian(Address),
+    /// Remove a guardian.
+    RemoveGuardian(Address),
+    /// Change the recovery config: `(threshold, delay_ledgers)`.
+    SetRecoveryConfig(u32, u32),
+    /// Raise or lift a token's daily spending limit: `(token, limit)`;
+    /// needs at least two admin approvals.
+    SetDailyLimit(Address, Option<i128>),
+}
+
+/// A pending [`AdminOperation`] and the admin credentials that approved it.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Proposal {
+    pub operation: AdminOperation,
+    /// Credential IDs of the approving admins, in approval order.
+    pub approvals: Vec<Bytes>,
+    /// Ledger sequence the proposal was created at.
+    pub created_ledger: u32,
+}
+
+// ─── Batched calls ────────────────────────────────────────────────────────────
+
+/// One contract call made by the wallet's `execute`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Invocation {
+    pub contract: Address,
+    pub function: Symbol,
+    pub args: Vec<Val>,
+}
+
+// ─── Daily spending limits ────────────────────────────────────────────────────
+
+/// What a limited token has moved on one UTC day.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DailySpend {
+    /// Ledger timestamp divided by 86 400.
+    pub day: u64,
+    pub spent: i128,
+}
+
+/// How many sponsored operations a rate-limited relayer has submitted on
+/// one UTC day.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RelayerUsage {
+    /// Ledger timestamp divided by 86 400.
+    pub day: u64,
+    pub count: u32,
+}
+
+// ─── Spending intents ─────────────────────────────────────────────────────────
+
+/// A transfer an admin approved ahead of time, which anyone may then make
+/// with `execute_intent` until its deadline.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Intent {
+    pub token: Address,
+    pub to: Address,
+    pub amount: i128,
+    /// Last ledger timestamp the transfer can be made at.
+    pub deadline: u64,
+}
+
+// ─── Gas tank ─────────────────────────────────────────────────────────────────
+
+/// Budget from which the wallet pays back whitelisted executors for the
+/// network fees of tasks they run for it.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct GasTank {
+    /// Token reimbursements are paid in, normally the native XLM contract.
+    pub token: Address,
+    /// What the tank may still pay out in total.
+    pub budget: i128,
+    /// Paid for each task an executor runs.
+    pub reimbursement: i128,
+    /// Most a single executor is paid per UTC day.
+    pub daily_cap: i128,
+}
+
+/// A permissionless wallet operation an executor runs with `run_task`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum KeeperTask {
+    /// `execute_intent(intent_id)`.
+    ExecuteIntent(u64),
+    /// `execute_action(action_id)`.
+    ExecuteAction(u64),
+    /// `execute_proposal(proposal_id)`.
+    ExecuteProposal(u64),
+    /// `finalize_recovery()`.
+    FinalizeRecovery,
+}
+
+// ─── Modules ──────────────────────────────────────────────────────────────────
+
+/// Where the wallet calls the module contracts installed for it.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ModuleHook {
+    /// `before_auth(wallet: Address, calls: Vec<Context>)`, from
+    /// `__check_auth` once the signature is valid, with the calls on other
+    /// contracts being authorized.  The module rejects them by failing.
+    BeforeAuth,
+    /// `after_execute(wallet: Address, invocations: Vec<Invocation>,
+    /// results: Vec<Val>)`, once a batch has run.
+    AfterExecute,
+}
+
+// ─── Timelocked actions ───────────────────────────────────────────────────────
+
+/// A sensitive change scheduled with `queue_action`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TimelockOperation {
+    /// Add an admin signer: `(credential_id, public_key)`.
+    AddSigner(Bytes, BytesN<65>),
+    /// Rotate an admin signer:
+    /// `(old_credential_id, new_credential_id, new_public_key)`.
+    RotateSigner(Bytes, Bytes, BytesN<65>),
+    AddGuardian(Address),
+    RemoveGuardian(Address),
+    /// Change the recovery config: `(threshold, delay_ledgers)`.
+    SetRecoveryConfig(u32, u32),
+    /// Change the timelock delay itself.
+    SetDelay(u32),
+}
+
+/// A queued [`TimelockOperation`] and the ledger it can run from.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct QueuedAction {
+    pub operation: TimelockOperation,
+    pub executable_ledger: u32,
+}
+
+// ─── Guardian recovery ────────────────────────────────────────────────────────
+
+/// How many guardians a recovery needs, and how long it then waits.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecoveryConfig {
+    pub threshold: u32,
+    pub delay_ledgers: u32,
+}
+
+/// A new admin signer proposed by guardians, collecting approvals and then
+/// waiting out the recovery delay.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecoveryRequest {
+    pub credential_id: Bytes,
+    pub public_key: BytesN<65>,
+    /// Approving guardians in approval order; the proposer comes first.
+    pub approvals: Vec<Address>,
+    /// Ledger sequence from which `finalize_recovery` may add the signer,
+    /// or `0` until the guardian threshold is reached.
+    pub executable_ledger: u32,
+}
+
+// ─── Wallet info ──────────────────────────────────────────────────────────────
+
+/// Snapshot of a wallet's state as returned by `get_wallet_info`.
+///
+/// List-valued settings are summarized as the SHA-256 of their XDR, so a
+/// reader can tell whether one changed and only fetch it when it did.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WalletInfo {
+    pub version: u32,
+    pub admin_signers: u32,
+    pub operator_signers: u32,
+    /// Session signers whose TTL has not run out.
+    pub session_signers: u32,
+    pub frozen: bool,
+    pub threshold: u32,
+    pub auth_threshold: u32,
+    pub timelock_delay: u32,
+    pub user_verification: bool,
+    pub attestation_required: bool,
+    pub guardians: u32,
+    pub recovery_config: RecoveryConfig,
+    pub recovery_pending: bool,
+    /// Digest of `get_origins()`.
+    pub origins_digest: BytesN<32>,
+    /// Digest of `get_rp_id_hashes()`.
+    pub rp_id_hashes_digest: BytesN<32>,
+    /// Digest of `get_relayers()`.
+    pub relayers_digest: BytesN<32>,
+    /// Digest of the `(BeforeAuth, AfterExecute)` pair of `get_modules` lists.
+    pub modules_digest: BytesN<32>,
+}
+
+// ─── Wallet profile ───────────────────────────────────────────────────────────
+
+/// Identity metadata frontends show for a wallet.  Every field is optional;
+/// a wallet that never set a profile reads as all-empty.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WalletProfile {
+    /// At most 64 bytes; empty when unset.
+    pub display_name: String,
+    /// Hash of the avatar image, e.g. a SHA-256 or IPFS digest, so frontends
+    /// can check whatever copy they fetch.
+    pub avatar_hash: Option<BytesN<32>>,
+    /// Currency code to show balances in, e.g. `USD` or `XLM`.
+    pub currency: Option<Symbol>,
+}
+
+// ─── Factory deployments ──────────────────────────────────────────────────────
+
+/// Code a wallet was deployed with by the factory.  A wallet that later
+/// upgrades itself keeps this record.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WalletDeployment {
+    pub wasm_hash: BytesN<32>,
+    /// Registry version `wasm_hash` was picked by, or `None` for the
+    /// factory's current hash.
+    pub version: Option<u32>,
+}
+
+// ─── Audit log ────────────────────────────────────────────────────────────────
+
+/// An administrative change recorded in the wallet's audit log.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AuditAction {
+    /// A signer was added: `(credential_id, kind)`.
+    AddSigner(Bytes, SignerKind),
+    /// A signer was removed: `(credential_id, kind)`.
+    RemoveSigner(Bytes, SignerKind),
+    /// A signer moved to a new credential: `(old_credential_id, new_credential_id)`.
+    RotateSigner(Bytes, Bytes),
+    /// The wallet code was replaced with this WASM hash.
+    Upgrade(BytesN<32>),
+    /// The wallet was frozen.
+    Freeze,
+    /// An unfreeze was scheduled, or a proposal lifted the freeze.
+    Unfreeze,
+}
+
+/// Who authorized an audited change.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AuditActor {
+    /// Credential IDs of the passkeys, or the session key, that signed the
+    /// wallet's authorization; empty if none was checked.
+    Signers(Vec<Bytes>),
+    /// Admins whose approvals executed a proposal.
+    Approvals(Vec<Bytes>),
+    /// A due timelocked action, run by whoever called `execute_action`.
+    QueuedAction(u64),
+    /// Guardians that approved a recovery.
+    Guardians(Vec<Address>),
+}
+
+/// One audit log entry, as returned by `get_audit_log`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AuditEntry {
+    /// Position in the log, counting from 0 and never reused.
+    pub id: u64,
+    pub action: AuditAction,
+    pub actor: AuditActor,
+    pub ledger: u32,
+    pub timestamp: u64,
 }
 
 // ─── Storage keys ─────────────────────────────────────────────────────────────
@@ -85,13 +473,93 @@ pub enum WalletDataKey {
     Signer(Bytes),
     WalletAddress,
     AdminSignerCount,
+    /// Credential IDs of registered signers, in registration order.  Session
+    /// entries may outlive their signer's TTL until the index is next written.
+    SignerIndex,
+    /// Admin approvals required for an `AdminOperation` (defaults to 1).
+    Threshold,
+    /// Pending `Proposal` by ID.
+    Proposal(u64),
+    NextProposalId,
+    /// Guardian addresses allowed to start a recovery.
+    Guardians,
+    /// The pending `RecoveryRequest`, if any.
+    Recovery,
+    /// `RecoveryConfig` (defaults to one guardian and `RECOVERY_DELAY`).
+    RecoveryConfig,
+    /// Schema version the wallet's storage was last migrated to.
+    Version,
+    /// WebAuthn origins admin passkey assertions may come from.
+    AllowedOrigins,
+    /// Whether admin assertions need the User Verification flag (default false).
+    RequireUserVerification,
+    /// Daily cap on what a token's SEP-41 calls may move.
+    DailyLimit(Address),
+    /// `DailySpend` for a limited token (temporary storage).
+    DailySpend(Address),
+    /// Ledgers a queued action waits before it can run (defaults to 0, off).
+    TimelockDelay,
+    /// Pending `QueuedAction` by ID.
+    QueuedAction(u64),
+    NextActionId,
+    /// Ledger a freeze ends at; `u32::MAX` until `unfreeze` is called.
+    FrozenUntil,
+    /// Whether `add_signer` is refused in favour of
+    /// `add_signer_with_attestation` (default false).
+    RequireAttestation,
+    /// Distinct passkeys that must sign a WebAuthn authorization (defaults
+    /// to 1).
+    AuthThreshold,
+    /// Addresses allowed to submit `execute_sponsored` for a fee.
+    Relayers,
+    /// Daily cap on a relayer's `execute_sponsored` calls.
+    RelayerRateLimit(Address),
+    /// `RelayerUsage` for a rate-limited relayer (temporary storage).
+    RelayerUsage(Address),
+    /// Names of the wallet's sub-accounts, in creation order.
+    SubAccounts,
+    /// Token budgets left in a sub-account.
+    SubAccount(Symbol),
+    /// Pending `Intent` by ID.
+    Intent(u64),
+    NextIntentId,
+    /// Module contracts installed for a `ModuleHook`, in call order.
+    Modules(ModuleHook),
+    /// SHA-256 hashes of the relying party IDs passkey assertions may be
+    /// scoped to.
+    AllowedRpIdHashes,
+    /// `AuditEntry` by ID.
+    AuditEntry(u64),
+    NextAuditId,
+    /// `AuditActor` of the operation in progress (temporary storage).
+    AuditActor,
+    /// The wallet's `WalletProfile`.
+    Profile,
+    /// The wallet's `GasTank`, if it has one.
+    GasTank,
+    /// Addresses whose `run_task` calls the gas tank pays back.
+    Executors,
+    /// `DailySpend` of what an executor was paid back (temporary storage).
+    ExecutorPaid(Address),
 }
 
 #[contracttype]
 #[derive(Clone, Debug)]
 pub enum FactoryDataKey {
+    /// Address allowed to change the factory's settings.
+    Admin,
     WalletWasmHash,
-    Deployed(Bytes),
+    /// Map of registered version → wallet Wasm hash.
+    WasmVersions,
+    /// Wallet address for a `(credential_id, public_key)` pair.
+    Deployed(Bytes, BytesN<65>),
+    /// `WalletDeployment` of the wallet for a `(credential_id, public_key)`
+    /// pair.
+    Deployment(Bytes, BytesN<65>),
+    /// Number of wallets deployed.
+    WalletCount,
+    /// Address of the wallet deployed at a position, counting from 0.
+    WalletAt(u32),
 }
 
 // ─── Errors ───────────────────────────────────────────────────────────────────
@@ -108,4 +576,109 @@ pub enum WalletError {
     NotAuthorized = 6,
     InvalidPublicKey = 7,
     InvalidClientData = 8,
+    /// The operation needs multisig approval; go through `propose`.
+    ApprovalRequired = 9,
+    /// The threshold is 0 or above the number of admin signers.
+    InvalidThreshold = 10,
+    ProposalNotFound = 11,
+    AlreadyApproved = 12,
+    /// The proposal has fewer admin approvals than the threshold.
+    InsufficientApprovals = 13,
+    /// A session signer's spending policy does not allow the call.
+    SessionLimitExceeded = 14,
+    /// A spending policy has a negative or zero cap.
+    InvalidPolicy = 15,
+    /// A session signer authorized a call outside its contract allowlist.
+    ContractNotAllowed = 16,
+    /// The session signer is past its `expires_at` timestamp.
+    SessionExpired = 17,
+    NotGuardian = 18,
+    /// A recovery is already pending; it must be vetoed or finalized first.
+    RecoveryPending = 19,
+    RecoveryNotFound = 20,
+    /// The recovery delay has not elapsed yet.
+    RecoveryNotReady = 21,
+    /// A recovery delay of zero ledgers.
+    InvalidDelay = 22,
+    /// The WebAuthn assertion's origin is not on the wallet's allowlist.
+    OriginNotAllowed = 23,
+    OriginNotFound = 24,
+    /// The clientDataJSON `type` is not `webauthn.get`, e.g. a registration
+    /// (`webauthn.create`) response.
+    InvalidClientDataType = 25,
+    /// `authenticator_data` is shorter than its fixed 37-byte header.
+    InvalidAuthenticatorData = 26,
+    /// The authenticator did not set the User Present flag.
+    UserNotPresent = 27,
+    /// The wallet requires User Verification and the flag is not set.
+    UserNotVerified = 28,
+    /// The call would take a token over its daily spending limit; spend
+    /// more through an `AdminOperation::Invoke` proposal.
+    DailyLimitExceeded = 29,
+    /// A signer label longer than 64 bytes.
+    InvalidLabel = 30,
+    /// The wallet has a timelock delay; schedule the change with
+    /// `queue_action` instead.
+    TimelockRequired = 31,
+    ActionNotFound = 32,
+    /// The queued action's delay has not elapsed yet.
+    ActionNotReady = 33,
+    /// The wallet is frozen; only recovery and unfreezing are possible.
+    WalletFrozen = 34,
+    NotFrozen = 35,
+    /// A malformed attestation object, or one that does not attest the
+    /// credential being registered.
+    InvalidAttestation = 36,
+    /// An attestation format or algorithm other than `packed` ES256.
+    UnsupportedAttestation = 37,
+    /// The wallet only adds admin signers through
+    /// `add_signer_with_attestation`.
+    AttestationRequired = 38,
+    /// An ECDSA signature that is neither 64-byte compact nor valid DER.
+    InvalidSignature = 39,
+    /// A compact ECDSA signature whose S is in the upper half of the curve
+    /// order; send `(r, n - s)` instead.
+    NonCanonicalSignature = 40,
+    /// Fewer passkey signatures than the wallet's auth threshold.
+    InsufficientSignatures = 41,
+    /// The same credential signed more than once in a `MultiWebAuthn`.
+    DuplicateSignature = 42,
+    /// `execute_sponsored` from an address not on the relayer list.
+    RelayerNotAllowed = 43,
+    /// A negative `execute_sponsored` fee.
+    InvalidFee = 44,
+    /// A `BeforeAuth` module rejected the authorization.
+    ModuleRejected = 45,
+    ModuleNotInstalled = 46,
+    /// A hook already has `MAX_MODULES` modules.
+    TooManyModules = 47,
+    /// The authenticator data was produced for a relying party not on the
+    /// wallet's RP ID hash list.
+    RpIdMismatch = 48,
+    RpIdHashNotFound = 49,
+    /// Credential IDs must be 16 to 1023 bytes long, as WebAuthn allows.
+    InvalidCredentialId = 50,
+    /// The wallet already has `MAX_SIGNERS` live signers.
+    TooManySigners = 51,
+    /// The signer may not move funds of this token contract.
+    TokenNotAllowed = 52,
+    /// The relayer has already submitted its daily cap of sponsored
+    /// operations.
+    RelayerRateLimited = 53,
+    SubAccountNotFound = 54,
+    SubAccountExists = 55,
+    /// The wallet already has `MAX_SUB_ACCOUNTS` sub-accounts.
+    TooManySubAccounts = 56,
+    /// A sub-account signer's call would move more of a token than its
+    /// sub-account's budget has left.
+    BudgetExceeded = 57,
+    IntentNotFound = 58,
+    /// The intent's deadline has passed.
+    IntentExpired = 59,
+    /// Intents must move a positive amount.
+    InvalidIntent = 60,
+    /// A batch call was given more than `MAX_BATCH_ITEMS` items.
+    BatchTooLarge = 61,
+    /// `run_task` from an address not on the executor list.
+    ExecutorNotAllowed = 62,
 }
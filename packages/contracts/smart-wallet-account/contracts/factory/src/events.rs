@@ -0,0 +1,24 @@
+//! Factory events, published with `Events::publish` like the wallet's.
+#![allow(deprecated)]
+
+use soroban_sdk::{symbol_short, Address, BytesN, Env, Symbol};
+
+const EVT_ADMIN: Symbol = symbol_short!("admin");
+const EVT_WASM_UPDATED: Symbol = symbol_short!("wasm_upd");
+const EVT_WASM_VERSION: Symbol = symbol_short!("wasm_ver");
+
+/// `("admin",)` → `new_admin`
+pub fn admin_changed(env: &Env, new_admin: &Address) {
+    env.events().publish((EVT_ADMIN,), new_admin.clone());
+}
+
+/// `("wasm_upd",)` → `new_hash`
+pub fn wasm_hash_updated(env: &Env, new_hash: &BytesN<32>) {
+    env.events().publish((EVT_WASM_UPDATED,), new_hash.clone());
+}
+
+/// `("wasm_ver", version)` → `wasm_hash`
+pub fn wasm_version_registered(env: &Env, version: u32, wasm_hash: &BytesN<32>) {
+    env.events()
+        .publish((EVT_WASM_VERSION, version), wasm_hash.clone());
+}
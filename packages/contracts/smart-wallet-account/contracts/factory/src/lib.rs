@@ -1,73 +1,279 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl, Address, Bytes, BytesN, Env, IntoVal, Symbol};
+use soroban_sdk::{
+    contract, contractimpl, token, Address, Bytes, BytesN, Env, IntoVal, Map, Symbol, Vec,
+};
 
-use smart_wallet_account_common::FactoryDataKey;
+use smart_wallet_account_common::{FactoryDataKey, InitialSigner, WalletDeployment};
+
+mod events;
 
 const DEPLOYED_TTL_THRESHOLD: u32 = 60_480;
 const DEPLOYED_TTL_EXTEND: u32 = 120_960;
 
+/// Most wallets `list_wallets` returns per call.
+const MAX_WALLET_PAGE: u32 = 50;
+
+/// Most wallets `deploy_batch` deploys in one call, to stay well inside a
+/// transaction's resource limits.
+const MAX_BATCH_DEPLOYS: u32 = 10;
+
 #[contract]
 pub struct Factory;
 
 #[contractimpl]
 impl Factory {
-    pub fn init(env: Env, wallet_wasm_hash: BytesN<32>) {
-        if env
-            .storage()
+    /// Stores the factory admin and the wallet Wasm hash as part of the
+    /// factory's deployment.
+    pub fn __constructor(env: Env, admin: Address, wallet_wasm_hash: BytesN<32>) {
+        env.storage().instance().set(&FactoryDataKey::Admin, &admin);
+        env.storage()
+            .instance()
+            .set(&FactoryDataKey::WalletWasmHash, &wallet_wasm_hash);
+    }
+
+    /// Hand the factory over to `new_admin`.  Requires the current admin's
+    /// auth.
+    pub fn set_admin(env: Env, new_admin: Address) {
+        load_admin(&env).require_auth();
+        env.storage()
             .instance()
-            .has(&FactoryDataKey::WalletWasmHash)
-        {
-            panic!("factory already initialized");
+            .set(&FactoryDataKey::Admin, &new_admin);
+        events::admin_changed(&env, &new_admin);
+    }
+
+    pub fn get_admin(env: Env) -> Address {
+        load_admin(&env)
+    }
+
+    /// Deploy wallets from `new_hash`, which must already be uploaded, from
+    /// now on.  Requires the admin's auth.  Wallets deployed earlier keep
+    /// their code until they `upgrade` themselves, and wallet addresses do
+    /// not depend on the hash, so `get_wallet` lookups are unaffected.
+    pub fn set_wallet_wasm_hash(env: Env, new_hash: BytesN<32>) {
+        load_admin(&env).require_auth();
+        env.storage()
+            .instance()
+            .set(&FactoryDataKey::WalletWasmHash, &new_hash);
+        events::wasm_hash_updated(&env, &new_hash);
+    }
+
+    pub fn get_wallet_wasm_hash(env: Env) -> BytesN<32> {
+        load_wasm_hash(&env)
+    }
+
+    /// Register wallet code `wasm_hash`, which must already be uploaded and
+    /// take the `(credential_id, public_key, signers)` constructor, as
+    /// `version`, for `deploy_version`.  Requires the admin's auth.  A
+    /// version cannot be registered twice, so it always deploys the same
+    /// code.
+    pub fn register_wasm_version(env: Env, version: u32, wasm_hash: BytesN<32>) {
+        load_admin(&env).require_auth();
+        let mut versions = Self::get_wasm_versions(env.clone());
+        if versions.contains_key(version) {
+            panic!("wallet version already registered");
         }
+        versions.set(version, wasm_hash.clone());
         env.storage()
             .instance()
-            .set(&FactoryDataKey::WalletWasmHash, &wallet_wasm_hash);
+            .set(&FactoryDataKey::WasmVersions, &versions);
+        events::wasm_version_registered(&env, version, &wasm_hash);
     }
 
+    pub fn get_wasm_versions(env: Env) -> Map<u32, BytesN<32>> {
+        env.storage()
+            .instance()
+            .get(&FactoryDataKey::WasmVersions)
+            .unwrap_or_else(|| Map::new(&env))
+    }
+
+    /// Deploy a wallet whose first admin is `public_key`, along with
+    /// `signers`, e.g. a backup key enrolled in the same onboarding, so they
+    /// need no second transaction signed by the first passkey.
+    ///
+    /// The wallet's address and its registry entry are keyed by both
+    /// `credential_id` and `public_key`, so deploying another user's
+    /// credential ID with a key of one's own yields a separate wallet and
+    /// neither blocks nor shadows theirs.  Each pair can be deployed once.
     pub fn deploy(
         env: Env,
         deployer: Address,
         credential_id: Bytes,
         public_key: BytesN<65>,
+        signers: Vec<InitialSigner>,
     ) -> Address {
         deployer.require_auth();
+        let wasm_hash = load_wasm_hash(&env);
+        deploy_wallet(&env, credential_id, public_key, signers, wasm_hash, None)
+    }
 
-        let wasm_hash: BytesN<32> = env
-            .storage()
-            .instance()
-            .get(&FactoryDataKey::WalletWasmHash)
-            .expect("factory not initialized");
+    /// Like `deploy`, then move `amount` of `token` from `deployer` to the
+    /// new wallet, so onboarding backends can hand out a starter balance in
+    /// the same transaction.  `deployer` authorizes the transfer along with
+    /// the deployment, and if either fails neither happens.
+    pub fn deploy_and_fund(
+        env: Env,
+        deployer: Address,
+        credential_id: Bytes,
+        public_key: BytesN<65>,
+        token: Address,
+        amount: i128,
+    ) -> Address {
+        deployer.require_auth();
+        if amount <= 0 {
+            panic!("funding amount must be positive");
+        }
+        let wasm_hash = load_wasm_hash(&env);
+        let wallet_address = deploy_wallet(
+            &env,
+            credential_id,
+            public_key,
+            Vec::new(&env),
+            wasm_hash,
+            None,
+        );
+        token::Client::new(&env, &token).transfer(&deployer, &wallet_address, &amount);
+        wallet_address
+    }
 
-        // Deterministic salt from the credential ID.
-        let salt = env.crypto().sha256(&credential_id);
+    /// Deploy a wallet for each `(credential_id, public_key)` in `wallets`,
+    /// as `deploy` would, under a single auth of `deployer`, and return
+    /// their addresses in order.  At most `MAX_BATCH_DEPLOYS` wallets fit
+    /// in one call; if any deployment fails, none of them happen.
+    pub fn deploy_batch(
+        env: Env,
+        deployer: Address,
+        wallets: Vec<(Bytes, BytesN<65>)>,
+    ) -> Vec<Address> {
+        deployer.require_auth();
+        if wallets.len() > MAX_BATCH_DEPLOYS {
+            panic!("too many wallets in one batch");
+        }
+        let wasm_hash = load_wasm_hash(&env);
+        let mut addresses = Vec::new(&env);
+        for (credential_id, public_key) in wallets.iter() {
+            addresses.push_back(deploy_wallet(
+                &env,
+                credential_id,
+                public_key,
+                Vec::new(&env),
+                wasm_hash.clone(),
+                None,
+            ));
+        }
+        addresses
+    }
 
-        // Deploy the wallet contract using `deployer().with_current_contract`.
-        let wallet_address = env.deployer().with_current_contract(salt).deploy(wasm_hash);
+    /// Like `deploy`, with the wallet code registered as `version` rather
+    /// than the current hash, e.g. to hold some users on an older release
+    /// during a staged rollout.
+    pub fn deploy_version(
+        env: Env,
+        deployer: Address,
+        credential_id: Bytes,
+        public_key: BytesN<65>,
+        version: u32,
+    ) -> Address {
+        deployer.require_auth();
+        let wasm_hash = Self::get_wasm_versions(env.clone())
+            .get(version)
+            .expect("unknown wallet version");
+        deploy_wallet(
+            &env,
+            credential_id,
+            public_key,
+            Vec::new(&env),
+            wasm_hash,
+            Some(version),
+        )
+    }
 
-        // Initialize the wallet with the first signer.
-        let _: soroban_sdk::Val = env.invoke_contract(
+    /// Like `deploy`, with the first signer's WebAuthn registration
+    /// response (`attestation_object` and its `webauthn.create`
+    /// `client_data_json`).  The new wallet's `prove_registration` must
+    /// accept it for `public_key`, or the whole deployment fails.
+    ///
+    /// This only proves that whoever produced the response held the private
+    /// key.  The attestation's certificate chain is not checked, and self
+    /// attestation can come from a software key, so it does not show that
+    /// the key lives on a hardware authenticator.
+    pub fn deploy_with_attestation(
+        env: Env,
+        deployer: Address,
+        credential_id: Bytes,
+        public_key: BytesN<65>,
+        attestation_object: Bytes,
+        client_data_json: Bytes,
+    ) -> Address {
+        deployer.require_auth();
+        let wasm_hash = load_wasm_hash(&env);
+        let wallet_address = deploy_wallet(
+            &env,
+            credential_id.clone(),
+            public_key,
+            Vec::new(&env),
+            wasm_hash,
+            None,
+        );
+        env.invoke_contract::<()>(
             &wallet_address,
-            &Symbol::new(&env, "init"),
-            (credential_id.clone(), public_key).into_val(&env),
+            &Symbol::new(&env, "prove_registration"),
+            (credential_id, attestation_object, client_data_json).into_val(&env),
         );
+        wallet_address
+    }
 
-        // Track the deployment.
-        let deployed_key = FactoryDataKey::Deployed(credential_id);
+    /// The wallet deployed for `credential_id` with `public_key` as its
+    /// first admin, if any.
+    pub fn get_wallet(env: Env, credential_id: Bytes, public_key: BytesN<65>) -> Option<Address> {
+        let key = FactoryDataKey::Deployed(credential_id, public_key);
+        let result: Option<Address> = env.storage().persistent().get(&key);
+        if result.is_some() {
+            env.storage().persistent().extend_ttl(
+                &key,
+                DEPLOYED_TTL_THRESHOLD,
+                DEPLOYED_TTL_EXTEND,
+            );
+        }
+        result
+    }
+
+    /// Number of wallets this factory has deployed.
+    pub fn wallet_count(env: Env) -> u32 {
         env.storage()
-            .persistent()
-            .set(&deployed_key, &wallet_address);
-        env.storage().persistent().extend_ttl(
-            &deployed_key,
-            DEPLOYED_TTL_THRESHOLD,
-            DEPLOYED_TTL_EXTEND,
-        );
+            .instance()
+            .get(&FactoryDataKey::WalletCount)
+            .unwrap_or(0)
+    }
 
-        wallet_address
+    /// Deployed wallets in deployment order, skipping `offset` of them and
+    /// returning at most `limit` (capped at `MAX_WALLET_PAGE`).
+    pub fn list_wallets(env: Env, offset: u32, limit: u32) -> Vec<Address> {
+        let limit = limit.min(MAX_WALLET_PAGE);
+        let end = Self::wallet_count(env.clone()).min(offset.saturating_add(limit));
+        let mut wallets = Vec::new(&env);
+        for index in offset..end {
+            let key = FactoryDataKey::WalletAt(index);
+            if let Some(wallet) = env.storage().persistent().get(&key) {
+                env.storage().persistent().extend_ttl(
+                    &key,
+                    DEPLOYED_TTL_THRESHOLD,
+                    DEPLOYED_TTL_EXTEND,
+                );
+                wallets.push_back(wallet);
+            }
+        }
+        wallets
     }
 
-    pub fn get_wallet(env: Env, credential_id: Bytes) -> Option<Address> {
-        let key = FactoryDataKey::Deployed(credential_id);
-        let result: Option<Address> = env.storage().persistent().get(&key);
+    /// The code the wallet for `credential_id` and `public_key` was
+    /// deployed with.
+    pub fn get_deployment(
+        env: Env,
+        credential_id: Bytes,
+        public_key: BytesN<65>,
+    ) -> Option<WalletDeployment> {
+        let key = FactoryDataKey::Deployment(credential_id, public_key);
+        let result: Option<WalletDeployment> = env.storage().persistent().get(&key);
         if result.is_some() {
             env.storage().persistent().extend_ttl(
                 &key,
@@ -78,3 +284,79 @@ impl Factory {
         result
     }
 }
+
+fn load_admin(env: &Env) -> Address {
+    env.storage()
+        .instance()
+        .get(&FactoryDataKey::Admin)
+        .expect("factory not initialized")
+}
+
+fn load_wasm_hash(env: &Env) -> BytesN<32> {
+    env.storage()
+        .instance()
+        .get(&FactoryDataKey::WalletWasmHash)
+        .expect("factory not initialized")
+}
+
+/// Deploy a wallet for `credential_id` and `public_key` from `wasm_hash`
+/// and record it, along with the code it got.
+fn deploy_wallet(
+    env: &Env,
+    credential_id: Bytes,
+    public_key: BytesN<65>,
+    signers: Vec<InitialSigner>,
+    wasm_hash: BytesN<32>,
+    version: Option<u32>,
+) -> Address {
+    let deployed_key = FactoryDataKey::Deployed(credential_id.clone(), public_key.clone());
+    if env.storage().persistent().has(&deployed_key) {
+        panic!("wallet already deployed for credential and key");
+    }
+
+    // Deterministic salt from the credential ID and the key, so the address
+    // belongs to whoever holds `public_key`.
+    let mut seed = credential_id.clone();
+    seed.append(&Bytes::from(public_key.clone()));
+    let salt = env.crypto().sha256(&seed);
+
+    // Deploy the wallet with its signers; the wallet's constructor stores
+    // them in the same step, so it is never left uninitialized.
+    let wallet_address = env.deployer().with_current_contract(salt).deploy_v2(
+        wasm_hash.clone(),
+        (credential_id.clone(), public_key.clone(), signers),
+    );
+
+    // Track the deployment.
+    env.storage()
+        .persistent()
+        .set(&deployed_key, &wallet_address);
+    env.storage().persistent().extend_ttl(
+        &deployed_key,
+        DEPLOYED_TTL_THRESHOLD,
+        DEPLOYED_TTL_EXTEND,
+    );
+    let count = Factory::wallet_count(env.clone());
+    let index_key = FactoryDataKey::WalletAt(count);
+    env.storage().persistent().set(&index_key, &wallet_address);
+    env.storage()
+        .persistent()
+        .extend_ttl(&index_key, DEPLOYED_TTL_THRESHOLD, DEPLOYED_TTL_EXTEND);
+    env.storage()
+        .instance()
+        .set(&FactoryDataKey::WalletCount, &(count + 1));
+    let deployment_key = FactoryDataKey::Deployment(credential_id, public_key);
+    env.storage()
+        .persistent()
+        .set(&deployment_key, &WalletDeployment { wasm_hash, version });
+    env.storage().persistent().extend_ttl(
+        &deployment_key,
+        DEPLOYED_TTL_THRESHOLD,
+        DEPLOYED_TTL_EXTEND,
+    );
+
+    wallet_address
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,526 @@
+extern crate std;
+
+use soroban_sdk::{
+    symbol_short,
+    testutils::{Address as _, AuthorizedFunction, AuthorizedInvocation, Events},
+    token, xdr, Address, Bytes, BytesN, Env, IntoVal, Symbol, TryFromVal, Val, Vec,
+};
+
+use smart_wallet_account_common::WalletDeployment;
+
+use crate::{Factory, FactoryClient};
+
+// ────────────────────────────────────────────────────────
+//  Test helpers
+// ────────────────────────────────────────────────────────
+
+/// Stand-in wallet code, section by section: a Wasm module whose
+/// `__constructor` takes the wallet's three constructor arguments and does
+/// nothing.  The factory only deploys and records wallets, so its tests need
+/// no more of one, and no Wasm build of the real wallet.
+const STUB_WALLET_WASM: &[&[u8]] = &[
+    b"\0asm\x01\0\0\0",
+    // Type 0: (i64, i64, i64) -> i64
+    b"\x01\x08\x01\x60\x03\x7e\x7e\x7e\x01\x7e",
+    // Function 0 has type 0
+    b"\x03\x02\x01\x00",
+    // Exported as `__constructor`
+    b"\x07\x11\x01\x0d__constructor\x00\x00",
+    // Body: return `Void`
+    b"\x0a\x06\x01\x04\x00\x42\x02\x0b",
+    // `contractenvmetav0`: interface version, protocol 22
+    b"\x00\x1e\x11contractenvmetav0\0\0\0\0\0\0\0\x16\0\0\0\0",
+];
+
+/// Upload the stub wallet, tagged with `release` so each release has its
+/// own hash, and return its hash.
+fn upload_wallet(env: &Env, release: u8) -> BytesN<32> {
+    let mut wasm = Bytes::from_slice(env, &STUB_WALLET_WASM.concat());
+    // Custom section `release` holding the tag
+    wasm.append(&Bytes::from_slice(env, b"\x00\x09\x07release"));
+    wasm.push_back(release);
+    env.deployer().upload_contract_wasm(wasm)
+}
+
+/// Generate a dummy 65-byte uncompressed public key (0x04 ‖ X ‖ Y).
+fn dummy_public_key(env: &Env, seed: u8) -> BytesN<65> {
+    let mut bytes = [0u8; 65];
+    bytes[0] = 0x04;
+    for (i, byte) in bytes.iter_mut().enumerate().skip(1) {
+        *byte = seed.wrapping_add(i as u8);
+    }
+    BytesN::from_array(env, &bytes)
+}
+
+/// Create a credential ID from `name`, long enough to be a valid one.
+fn cred_id(env: &Env, name: &str) -> Bytes {
+    let mut id = Bytes::from_slice(env, b"test-credential-");
+    id.append(&Bytes::from_slice(env, name.as_bytes()));
+    id
+}
+
+/// Deploy a factory for the first stub release, returning the client and
+/// its admin.
+fn setup_factory(env: &Env) -> (FactoryClient<'_>, Address) {
+    let admin = Address::generate(env);
+    let contract_id = env.register(Factory, (admin.clone(), upload_wallet(env, 0)));
+    (FactoryClient::new(env, &contract_id), admin)
+}
+
+/// Assert that the most recent event was the factory's, with `topics` and
+/// `data`.
+fn assert_last_event(env: &Env, client: &FactoryClient<'_>, topics: Vec<Val>, data: Val) {
+    let events = env.events().all();
+    let last = events.events().last().expect("no events");
+    assert_eq!(
+        events.filter_by_contract(&client.address).events().last(),
+        Some(last),
+        "last event is not the factory's"
+    );
+    let xdr::ContractEventBody::V0(body) = &last.body;
+    let topics: std::vec::Vec<xdr::ScVal> = topics
+        .iter()
+        .map(|topic| xdr::ScVal::try_from_val(env, &topic).unwrap())
+        .collect();
+    assert_eq!(body.topics.to_vec(), topics);
+    assert_eq!(body.data, xdr::ScVal::try_from_val(env, &data).unwrap());
+}
+
+/// The auth `address` gave for calling `function` on the factory with
+/// `args`, as the only auth of the last invocation.
+fn factory_auth(
+    env: &Env,
+    client: &FactoryClient<'_>,
+    address: &Address,
+    function: &str,
+    args: Vec<Val>,
+) -> std::vec::Vec<(Address, AuthorizedInvocation)> {
+    std::vec![(
+        address.clone(),
+        AuthorizedInvocation {
+            function: AuthorizedFunction::Contract((
+                client.address.clone(),
+                Symbol::new(env, function),
+                args,
+            )),
+            sub_invocations: std::vec![],
+        }
+    )]
+}
+
+// ────────────────────────────────────────────────────────
+//  Setup and administration
+// ────────────────────────────────────────────────────────
+
+#[test]
+fn test_constructor_initializes_factory() {
+    let env = Env::default();
+    let (client, admin) = setup_factory(&env);
+    assert_eq!(client.get_admin(), admin);
+    assert_eq!(client.get_wallet_wasm_hash(), upload_wallet(&env, 0));
+    assert_eq!(client.wallet_count(), 0);
+}
+
+#[test]
+fn test_admin_settings_need_the_admin() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, admin) = setup_factory(&env);
+    let new_admin = Address::generate(&env);
+
+    client.set_admin(&new_admin);
+    assert_eq!(
+        env.auths(),
+        factory_auth(
+            &env,
+            &client,
+            &admin,
+            "set_admin",
+            (new_admin.clone(),).into_val(&env)
+        )
+    );
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("admin"),).into_val(&env),
+        new_admin.into_val(&env),
+    );
+    assert_eq!(client.get_admin(), new_admin);
+
+    // The new admin switches later deployments to new code
+    let new_hash = upload_wallet(&env, 1);
+    client.set_wallet_wasm_hash(&new_hash);
+    assert_eq!(
+        env.auths(),
+        factory_auth(
+            &env,
+            &client,
+            &new_admin,
+            "set_wallet_wasm_hash",
+            (new_hash.clone(),).into_val(&env)
+        )
+    );
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("wasm_upd"),).into_val(&env),
+        new_hash.into_val(&env),
+    );
+    assert_eq!(client.get_wallet_wasm_hash(), new_hash);
+
+    // Without the admin's auth neither goes through
+    env.set_auths(&[]);
+    assert!(client.try_set_admin(&admin).is_err());
+    assert!(client
+        .try_set_wallet_wasm_hash(&upload_wallet(&env, 2))
+        .is_err());
+    assert_eq!(client.get_admin(), new_admin);
+    assert_eq!(client.get_wallet_wasm_hash(), new_hash);
+}
+
+// ────────────────────────────────────────────────────────
+//  Wallet versions
+// ────────────────────────────────────────────────────────
+
+#[test]
+fn test_register_wasm_version_needs_the_admin() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, admin) = setup_factory(&env);
+    let release = upload_wallet(&env, 1);
+
+    client.register_wasm_version(&1, &release);
+    assert_eq!(
+        env.auths(),
+        factory_auth(
+            &env,
+            &client,
+            &admin,
+            "register_wasm_version",
+            (1u32, release.clone()).into_val(&env)
+        )
+    );
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("wasm_ver"), 1u32).into_val(&env),
+        release.into_val(&env),
+    );
+    assert_eq!(client.get_wasm_versions().get(1), Some(release.clone()));
+
+    // A version always deploys the same code
+    assert!(client
+        .try_register_wasm_version(&1, &upload_wallet(&env, 2))
+        .is_err());
+
+    env.set_auths(&[]);
+    assert!(client
+        .try_register_wasm_version(&2, &upload_wallet(&env, 2))
+        .is_err());
+    assert_eq!(client.get_wasm_versions().len(), 1);
+}
+
+#[test]
+fn test_deploy_version_uses_registered_code() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup_factory(&env);
+    let deployer = Address::generate(&env);
+    let release = upload_wallet(&env, 1);
+    client.register_wasm_version(&1, &release);
+
+    // Unknown versions deploy nothing
+    let credential_id = cred_id(&env, "admin-0");
+    assert!(client
+        .try_deploy_version(&deployer, &credential_id, &dummy_public_key(&env, 1), &2)
+        .is_err());
+    let public_key = dummy_public_key(&env, 1);
+    assert_eq!(client.get_wallet(&credential_id, &public_key), None);
+
+    let wallet = client.deploy_version(&deployer, &credential_id, &public_key, &1);
+    assert_eq!(client.get_wallet(&credential_id, &public_key), Some(wallet));
+    assert_eq!(
+        client.get_deployment(&credential_id, &public_key),
+        Some(WalletDeployment {
+            wasm_hash: release,
+            version: Some(1),
+        })
+    );
+    assert_eq!(client.wallet_count(), 1);
+}
+
+// ────────────────────────────────────────────────────────
+//  Wallet registry
+// ────────────────────────────────────────────────────────
+
+/// Deploy `count` wallets from `deployer`, returning their credential IDs
+/// and addresses in deployment order.
+fn deploy_wallets(
+    env: &Env,
+    client: &FactoryClient<'_>,
+    deployer: &Address,
+    count: u8,
+) -> std::vec::Vec<(Bytes, Address)> {
+    (0..count)
+        .map(|i| {
+            let credential_id = cred_id(env, &std::format!("admin-{i}"));
+            let wallet = client.deploy(
+                deployer,
+                &credential_id,
+                &dummy_public_key(env, i),
+                &Vec::new(env),
+            );
+            (credential_id, wallet)
+        })
+        .collect()
+}
+
+#[test]
+fn test_list_wallets_pages_through_deployments() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup_factory(&env);
+    let deployed = deploy_wallets(&env, &client, &Address::generate(&env), 55);
+    assert_eq!(client.wallet_count(), 55);
+
+    let page = client.list_wallets(&10, &5);
+    assert_eq!(page.len(), 5);
+    for (i, wallet) in page.iter().enumerate() {
+        assert_eq!(wallet, deployed[10 + i].1);
+    }
+
+    // Pages stop at the last wallet and at `MAX_WALLET_PAGE`
+    assert_eq!(client.list_wallets(&52, &10).len(), 3);
+    assert_eq!(client.list_wallets(&55, &10).len(), 0);
+    let page = client.list_wallets(&0, &u32::MAX);
+    assert_eq!(page.len(), 50);
+    assert_eq!(page.last(), Some(deployed[49].1.clone()));
+    assert_eq!(client.list_wallets(&u32::MAX, &u32::MAX).len(), 0);
+}
+
+#[test]
+fn test_get_deployment_records_the_code() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup_factory(&env);
+    let deployer = Address::generate(&env);
+    let (first, _) = deploy_wallets(&env, &client, &deployer, 1).remove(0);
+    assert_eq!(
+        client.get_deployment(&first, &dummy_public_key(&env, 0)),
+        Some(WalletDeployment {
+            wasm_hash: upload_wallet(&env, 0),
+            version: None,
+        })
+    );
+
+    // Switching the code leaves earlier deployments' records alone
+    let release = upload_wallet(&env, 1);
+    client.set_wallet_wasm_hash(&release);
+    let second = cred_id(&env, "admin-1");
+    client.deploy(
+        &deployer,
+        &second,
+        &dummy_public_key(&env, 2),
+        &Vec::new(&env),
+    );
+    assert_eq!(
+        client
+            .get_deployment(&second, &dummy_public_key(&env, 2))
+            .map(|record| record.wasm_hash),
+        Some(release)
+    );
+    assert_eq!(
+        client
+            .get_deployment(&first, &dummy_public_key(&env, 0))
+            .map(|record| record.wasm_hash),
+        Some(upload_wallet(&env, 0))
+    );
+    assert_eq!(
+        client.get_deployment(&first, &dummy_public_key(&env, 2)),
+        None
+    );
+}
+
+#[test]
+fn test_wallet_address_is_bound_to_the_key() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup_factory(&env);
+    let (user, attacker) = (Address::generate(&env), Address::generate(&env));
+    let credential_id = cred_id(&env, "admin-0");
+    let address_for = |public_key: &BytesN<65>| {
+        let mut seed = credential_id.clone();
+        seed.append(&Bytes::from(public_key.clone()));
+        env.deployer()
+            .with_address(client.address.clone(), env.crypto().sha256(&seed))
+            .deployed_address()
+    };
+
+    // Deploying someone's credential ID first with another key neither
+    // takes their wallet's address nor blocks their own deployment
+    let (user_key, attacker_key) = (dummy_public_key(&env, 1), dummy_public_key(&env, 9));
+    let squatted = client.deploy(&attacker, &credential_id, &attacker_key, &Vec::new(&env));
+    assert_eq!(squatted, address_for(&attacker_key));
+    assert_eq!(client.get_wallet(&credential_id, &user_key), None);
+
+    let wallet = client.deploy(&user, &credential_id, &user_key, &Vec::new(&env));
+    assert_eq!(wallet, address_for(&user_key));
+    assert_eq!(client.get_wallet(&credential_id, &user_key), Some(wallet));
+    assert_eq!(
+        client.get_wallet(&credential_id, &attacker_key),
+        Some(squatted)
+    );
+
+    // Each pair deploys only once
+    assert!(client
+        .try_deploy(&user, &credential_id, &user_key, &Vec::new(&env))
+        .is_err());
+    assert_eq!(client.wallet_count(), 2);
+}
+
+// ────────────────────────────────────────────────────────
+//  Batch and funded deployments
+// ────────────────────────────────────────────────────────
+
+/// `(credential_id, public_key)` entries for `deploy_batch`, named from
+/// `first` on.
+fn batch_entries(env: &Env, first: u8, count: u8) -> Vec<(Bytes, BytesN<65>)> {
+    let mut wallets = Vec::new(env);
+    for i in first..first + count {
+        wallets.push_back((
+            cred_id(env, &std::format!("batch-{i}")),
+            dummy_public_key(env, i),
+        ));
+    }
+    wallets
+}
+
+#[test]
+fn test_deploy_batch_is_all_or_nothing() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup_factory(&env);
+    let deployer = Address::generate(&env);
+
+    // At most `MAX_BATCH_DEPLOYS` per call
+    assert!(client
+        .try_deploy_batch(&deployer, &batch_entries(&env, 0, 11))
+        .is_err());
+    assert_eq!(client.wallet_count(), 0);
+
+    let wallets = batch_entries(&env, 0, 10);
+    let addresses = client.deploy_batch(&deployer, &wallets);
+    assert_eq!(
+        env.auths(),
+        factory_auth(
+            &env,
+            &client,
+            &deployer,
+            "deploy_batch",
+            (deployer.clone(), wallets.clone()).into_val(&env)
+        )
+    );
+    assert_eq!(addresses.len(), 10);
+    for ((credential_id, public_key), address) in wallets.iter().zip(addresses.iter()) {
+        assert_eq!(
+            client.get_wallet(&credential_id, &public_key),
+            Some(address)
+        );
+    }
+    assert_eq!(client.list_wallets(&0, &10), addresses);
+
+    // A pair that already has a wallet fails the whole batch
+    let mut wallets = batch_entries(&env, 10, 3);
+    wallets.set(1, batch_entries(&env, 4, 1).get(0).unwrap());
+    assert!(client.try_deploy_batch(&deployer, &wallets).is_err());
+    assert_eq!(client.wallet_count(), 10);
+    for index in [0, 2] {
+        let (credential_id, public_key) = wallets.get(index).unwrap();
+        assert_eq!(client.get_wallet(&credential_id, &public_key), None);
+    }
+}
+
+#[test]
+fn test_deploy_and_fund_transfers_to_the_wallet() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup_factory(&env);
+    let token = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    let deployer = Address::generate(&env);
+    token::StellarAssetClient::new(&env, &token).mint(&deployer, &1_000);
+    let balance = |address: &Address| token::Client::new(&env, &token).balance(address);
+
+    // Only positive amounts
+    for amount in [0, -5] {
+        assert!(client
+            .try_deploy_and_fund(
+                &deployer,
+                &cred_id(&env, "admin-0"),
+                &dummy_public_key(&env, 1),
+                &token,
+                &amount
+            )
+            .is_err());
+    }
+    assert_eq!(client.wallet_count(), 0);
+
+    let credential_id = cred_id(&env, "admin-0");
+    let wallet = client.deploy_and_fund(
+        &deployer,
+        &credential_id,
+        &dummy_public_key(&env, 1),
+        &token,
+        &250,
+    );
+
+    // The deployer's one auth covers the deployment and the transfer
+    assert_eq!(
+        env.auths(),
+        std::vec![(
+            deployer.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    client.address.clone(),
+                    Symbol::new(&env, "deploy_and_fund"),
+                    (
+                        deployer.clone(),
+                        credential_id.clone(),
+                        dummy_public_key(&env, 1),
+                        token.clone(),
+                        250i128,
+                    )
+                        .into_val(&env),
+                )),
+                sub_invocations: std::vec![AuthorizedInvocation {
+                    function: AuthorizedFunction::Contract((
+                        token.clone(),
+                        symbol_short!("transfer"),
+                        (deployer.clone(), wallet.clone(), 250i128).into_val(&env),
+                    )),
+                    sub_invocations: std::vec![],
+                }],
+            }
+        )]
+    );
+    assert_eq!((balance(&wallet), balance(&deployer)), (250, 750));
+    assert_eq!(
+        client.get_wallet(&credential_id, &dummy_public_key(&env, 1)),
+        Some(wallet)
+    );
+
+    // Without it nothing is deployed or moved
+    env.set_auths(&[]);
+    assert!(client
+        .try_deploy_and_fund(
+            &deployer,
+            &cred_id(&env, "admin-1"),
+            &dummy_public_key(&env, 2),
+            &token,
+            &250
+        )
+        .is_err());
+    assert_eq!(client.wallet_count(), 1);
+    assert_eq!(balance(&deployer), 750);
+}
@@ -0,0 +1,297 @@
+//! WebAuthn attestation checks for `add_signer_with_attestation`.
+//!
+//! Only the `packed` format is accepted, either with an `x5c` certificate
+//! or as self attestation (signed by the new credential's own key).  The
+//! certificate chain is not validated on-chain: the statement must verify
+//! under the leaf certificate's P-256 key, and deployments that trust only
+//! certain authenticator models check the chain (e.g. against the FIDO
+//! Metadata Service) before submitting the registration.
+
+use soroban_sdk::{Bytes, BytesN, Env};
+
+use smart_wallet_account_common::WalletError;
+
+use crate::ecdsa;
+
+/// Largest attestation object accepted, in bytes.
+const MAX_ATTESTATION_LEN: usize = 2048;
+
+/// Authenticator data flag: attested credential data is present.
+const FLAG_ATTESTED_CREDENTIAL: u8 = 0x40;
+
+/// COSE algorithm ES256 (ECDSA P-256 with SHA-256).
+const COSE_ES256: i64 = -7;
+
+/// DER prefix of a P-256 `SubjectPublicKeyInfo`, up to the uncompressed
+/// point's `0x04` tag.
+const P256_SPKI_PREFIX: [u8; 27] = [
+    0x30, 0x59, 0x30, 0x13, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, 0x06, 0x08, 0x2a,
+    0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07, 0x03, 0x42, 0x00, 0x04,
+];
+
+/// A verified registration: the new credential's key and the
+/// authenticator data it was attested with.
+pub struct Registration {
+    pub public_key: BytesN<65>,
+    pub authenticator_data: Bytes,
+}
+
+/// Verify `attestation_object` for `credential_id` against
+/// `client_data_json`, returning the attested public key.
+///
+/// Signature failures panic, as in `__check_auth`.
+pub fn verify(
+    env: &Env,
+    credential_id: &Bytes,
+    attestation_object: &Bytes,
+    client_data_json: &Bytes,
+) -> Result<Registration, WalletError> {
+    let len = attestation_object.len() as usize;
+    if len > MAX_ATTESTATION_LEN {
+        return Err(WalletError::InvalidAttestation);
+    }
+    let mut buf = [0u8; MAX_ATTESTATION_LEN];
+    attestation_object.copy_into_slice(&mut buf[..len]);
+
+    let mut fmt = None;
+    let mut statement = None;
+    let mut auth_data = None;
+    let mut cbor = Cbor::new(&buf[..len]);
+    for _ in 0..cbor.map()? {
+        match cbor.text()? {
+            b"fmt" => fmt = Some(cbor.text()?),
+            b"attStmt" => statement = Some(cbor.packed_statement()?),
+            b"authData" => auth_data = Some(cbor.byte_string()?),
+            _ => cbor.skip(0)?,
+        }
+    }
+    if fmt.ok_or(WalletError::InvalidAttestation)? != b"packed" {
+        return Err(WalletError::UnsupportedAttestation);
+    }
+    let statement = statement.ok_or(WalletError::InvalidAttestation)?;
+    let auth_data = auth_data.ok_or(WalletError::InvalidAttestation)?;
+
+    let (attested_id, public_key) = attested_credential(auth_data)?;
+    if Bytes::from_slice(env, attested_id) != *credential_id {
+        return Err(WalletError::InvalidAttestation);
+    }
+    let public_key = BytesN::from_array(env, &public_key);
+
+    // The statement signs authData ‖ SHA-256(clientDataJSON), with the
+    // attestation certificate's key or, for self attestation, the new key.
+    let signing_key = match statement.certificate {
+        Some(certificate) => BytesN::from_array(env, &certificate_key(certificate)?),
+        None => public_key.clone(),
+    };
+    let authenticator_data = Bytes::from_slice(env, auth_data);
+    let mut signed_data = authenticator_data.clone();
+    signed_data.append(&Bytes::from_array(
+        env,
+        &env.crypto().sha256(client_data_json).to_array(),
+    ));
+    let digest = env.crypto().sha256(&signed_data);
+    let signature =
+        ecdsa::der_to_compact(statement.signature).map_err(|_| WalletError::InvalidAttestation)?;
+    let signature = BytesN::from_array(env, &signature);
+    env.crypto()
+        .secp256r1_verify(&signing_key, &digest, &signature);
+
+    Ok(Registration {
+        public_key,
+        authenticator_data,
+    })
+}
+
+struct PackedStatement<'a> {
+    signature: &'a [u8],
+    certificate: Option<&'a [u8]>,
+}
+
+/// The credential ID and SEC-1 P-256 key in `auth_data`'s attested
+/// credential data.
+fn attested_credential(auth_data: &[u8]) -> Result<(&[u8], [u8; 65]), WalletError> {
+    // rpIdHash (32) ‖ flags (1) ‖ signCount (4) ‖ aaguid (16) ‖ idLen (2)
+    if auth_data.len() < 55 || auth_data[32] & FLAG_ATTESTED_CREDENTIAL == 0 {
+        return Err(WalletError::InvalidAttestation);
+    }
+    let id_len = u16::from_be_bytes([auth_data[53], auth_data[54]]) as usize;
+    let id = auth_data
+        .get(55..55 + id_len)
+        .ok_or(WalletError::InvalidAttestation)?;
+
+    // The key is a single CBOR map; an extensions map may follow it (ED
+    // flag) and is left alone, as the attestation signature covers it
+    let mut cose = Cbor::new(&auth_data[55 + id_len..]);
+    let (mut kty, mut alg, mut crv, mut x, mut y) = (None, None, None, None, None);
+    for _ in 0..cose.map()? {
+        match cose.int()? {
+            1 => kty = Some(cose.int()?),
+            3 => alg = Some(cose.int()?),
+            -1 => crv = Some(cose.int()?),
+            -2 => x = Some(cose.byte_string()?),
+            -3 => y = Some(cose.byte_string()?),
+            _ => cose.skip(0)?,
+        }
+    }
+    // EC2 key on P-256 for ES256
+    if kty != Some(2) || alg != Some(COSE_ES256) || crv != Some(1) {
+        return Err(WalletError::InvalidAttestation);
+    }
+    let (Some(x), Some(y)) = (x, y) else {
+        return Err(WalletError::InvalidAttestation);
+    };
+    if x.len() != 32 || y.len() != 32 {
+        return Err(WalletError::InvalidAttestation);
+    }
+    let mut public_key = [0u8; 65];
+    public_key[0] = 0x04;
+    public_key[1..33].copy_from_slice(x);
+    public_key[33..].copy_from_slice(y);
+    Ok((id, public_key))
+}
+
+/// The P-256 subject key of a DER certificate.
+fn certificate_key(certificate: &[u8]) -> Result<[u8; 65], WalletError> {
+    let start = certificate
+        .windows(P256_SPKI_PREFIX.len())
+        .position(|window| window == P256_SPKI_PREFIX)
+        .ok_or(WalletError::UnsupportedAttestation)?
+        + P256_SPKI_PREFIX.len()
+        - 1;
+    certificate
+        .get(start..start + 65)
+        .and_then(|key| key.try_into().ok())
+        .ok_or(WalletError::InvalidAttestation)
+}
+
+/// Just enough of a CBOR reader for attestation objects and COSE keys:
+/// definite-length items only.
+struct Cbor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cbor<'a> {
+    /// Deepest nesting `skip` follows.
+    const MAX_DEPTH: u32 = 8;
+
+    fn new(data: &'a [u8]) -> Self {
+        Cbor { data, pos: 0 }
+    }
+
+    /// Major type and argument of the next item.
+    fn head(&mut self) -> Result<(u8, u64), WalletError> {
+        let initial = self.take(1)?[0];
+        let argument = match initial & 0x1f {
+            info @ 0..=23 => u64::from(info),
+            24 => u64::from(self.take(1)?[0]),
+            25 => u64::from(u16::from_be_bytes(self.array()?)),
+            26 => u64::from(u32::from_be_bytes(self.array()?)),
+            27 => u64::from_be_bytes(self.array()?),
+            _ => return Err(WalletError::InvalidAttestation),
+        };
+        Ok((initial >> 5, argument))
+    }
+
+    fn take(&mut self, len: u64) -> Result<&'a [u8], WalletError> {
+        let end = usize::try_from(len)
+            .ok()
+            .and_then(|len| self.pos.checked_add(len))
+            .filter(|end| *end <= self.data.len())
+            .ok_or(WalletError::InvalidAttestation)?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], WalletError> {
+        Ok(self.take(N as u64)?.try_into().unwrap())
+    }
+
+    fn expect(&mut self, major: u8) -> Result<u64, WalletError> {
+        match self.head()? {
+            (found, argument) if found == major => Ok(argument),
+            _ => Err(WalletError::InvalidAttestation),
+        }
+    }
+
+    /// Number of entries in the next item, a map.
+    fn map(&mut self) -> Result<u64, WalletError> {
+        self.expect(5)
+    }
+
+    fn byte_string(&mut self) -> Result<&'a [u8], WalletError> {
+        let len = self.expect(2)?;
+        self.take(len)
+    }
+
+    fn text(&mut self) -> Result<&'a [u8], WalletError> {
+        let len = self.expect(3)?;
+        self.take(len)
+    }
+
+    fn int(&mut self) -> Result<i64, WalletError> {
+        let (major, argument) = self.head()?;
+        let value = i64::try_from(argument).map_err(|_| WalletError::InvalidAttestation)?;
+        match major {
+            0 => Ok(value),
+            1 => Ok(-1 - value),
+            _ => Err(WalletError::InvalidAttestation),
+        }
+    }
+
+    /// Read a `packed` `attStmt` map: `alg`, `sig` and an optional `x5c`.
+    fn packed_statement(&mut self) -> Result<PackedStatement<'a>, WalletError> {
+        let (mut alg, mut signature, mut certificate) = (None, None, None);
+        for _ in 0..self.map()? {
+            match self.text()? {
+                b"alg" => alg = Some(self.int()?),
+                b"sig" => signature = Some(self.byte_string()?),
+                b"x5c" => {
+                    let count = self.expect(4)?;
+                    if count == 0 {
+                        return Err(WalletError::InvalidAttestation);
+                    }
+                    certificate = Some(self.byte_string()?);
+                    for _ in 1..count {
+                        self.byte_string()?;
+                    }
+                }
+                _ => self.skip(0)?,
+            }
+        }
+        if alg != Some(COSE_ES256) {
+            return Err(WalletError::UnsupportedAttestation);
+        }
+        Ok(PackedStatement {
+            signature: signature.ok_or(WalletError::InvalidAttestation)?,
+            certificate,
+        })
+    }
+
+    /// Skip over the next item.
+    fn skip(&mut self, depth: u32) -> Result<(), WalletError> {
+        if depth > Self::MAX_DEPTH {
+            return Err(WalletError::InvalidAttestation);
+        }
+        match self.head()? {
+            (0 | 1 | 7, _) => {}
+            (2 | 3, len) => {
+                self.take(len)?;
+            }
+            (4, count) => {
+                for _ in 0..count {
+                    self.skip(depth + 1)?;
+                }
+            }
+            (5, count) => {
+                for _ in 0..count.saturating_mul(2) {
+                    self.skip(depth + 1)?;
+                }
+            }
+            (6, _) => self.skip(depth + 1)?,
+            _ => return Err(WalletError::InvalidAttestation),
+        }
+        Ok(())
+    }
+}
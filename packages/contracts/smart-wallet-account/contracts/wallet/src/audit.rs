@@ -0,0 +1,85 @@
+//! Audit log of administrative changes.
+//!
+//! Signer changes, upgrades and freezes append an `AuditEntry` to a log in
+//! persistent storage, so a compromised wallet can be reviewed even where
+//! indexers missed its events.  Only the latest `MAX_AUDIT_ENTRIES` are
+//! kept, each extended to the network's maximum TTL when written.
+//!
+//! Entries are recorded deep in the signer helpers, which do not know who
+//! authorized the change; each authorization path notes its `AuditActor`
+//! in temporary storage first, and entries take it from there.
+
+use soroban_sdk::{vec, Env, Vec};
+
+use smart_wallet_account_common::{AuditAction, AuditActor, AuditEntry, WalletDataKey};
+
+/// Entries kept before the oldest is dropped.
+pub const MAX_AUDIT_ENTRIES: u64 = 200;
+
+/// Maximum number of entries returned by one `get_audit_log` call.
+pub const MAX_AUDIT_PAGE: u32 = 50;
+
+/// Note who authorized the operation in progress.
+pub fn set_actor(env: &Env, actor: &AuditActor) {
+    env.storage()
+        .temporary()
+        .set(&WalletDataKey::AuditActor, actor);
+}
+
+fn actor(env: &Env) -> AuditActor {
+    env.storage()
+        .temporary()
+        .get(&WalletDataKey::AuditActor)
+        .unwrap_or_else(|| AuditActor::Signers(vec![env]))
+}
+
+fn next_id(env: &Env) -> u64 {
+    env.storage()
+        .instance()
+        .get(&WalletDataKey::NextAuditId)
+        .unwrap_or(0)
+}
+
+/// Append `action`, dropping the oldest entry once the log is full.
+pub fn record(env: &Env, action: AuditAction) {
+    let id = next_id(env);
+    let entry = AuditEntry {
+        id,
+        action,
+        actor: actor(env),
+        ledger: env.ledger().sequence(),
+        timestamp: env.ledger().timestamp(),
+    };
+    let key = WalletDataKey::AuditEntry(id);
+    let max_ttl = env.storage().max_ttl();
+    env.storage().persistent().set(&key, &entry);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, max_ttl, max_ttl);
+    if id >= MAX_AUDIT_ENTRIES {
+        env.storage()
+            .persistent()
+            .remove(&WalletDataKey::AuditEntry(id - MAX_AUDIT_ENTRIES));
+    }
+    env.storage()
+        .instance()
+        .set(&WalletDataKey::NextAuditId, &(id + 1));
+}
+
+/// Up to `limit` retained entries from `start` on, oldest first.
+pub fn entries(env: &Env, start: u64, limit: u32) -> Vec<AuditEntry> {
+    let next = next_id(env);
+    let first = start.max(next.saturating_sub(MAX_AUDIT_ENTRIES));
+    let end = next.min(first.saturating_add(u64::from(limit.min(MAX_AUDIT_PAGE))));
+    let mut entries = Vec::new(env);
+    for id in first..end {
+        if let Some(entry) = env
+            .storage()
+            .persistent()
+            .get(&WalletDataKey::AuditEntry(id))
+        {
+            entries.push_back(entry);
+        }
+    }
+    entries
+}
@@ -0,0 +1,124 @@
+//! P-256 ECDSA signature encodings.
+//!
+//! The host verifies 64-byte compact `r ‖ s` signatures with `s` in the
+//! lower half of the group order.  Authenticators return DER, with either
+//! half, so DER signatures are decoded and normalized here; both halves
+//! then yield the same compact signature.  Compact signatures with a high
+//! `s` are rejected outright rather than left to fail inside the host, so
+//! `(r, s)` and its mirror `(r, n - s)` are never both accepted as given.
+//!
+//! The host traps on a signature that does not verify, with an error
+//! clients cannot tell apart from any other failure.  [`verify`] rejects
+//! what it can beforehand with a `WalletError`: malformed encodings and
+//! `r` or `s` outside `1..n`.  A well-formed signature that simply does not
+//! match the key and digest still traps, as the host offers no fallible
+//! check.
+
+use soroban_sdk::{crypto::Hash, Bytes, BytesN, Env};
+
+use smart_wallet_account_common::WalletError;
+
+/// Longest DER encoding of a P-256 signature.
+const MAX_DER_LEN: usize = 72;
+
+/// P-256 group order, and half of it for low-S normalization.
+pub const P256_ORDER: [u8; 32] = [
+    0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xbc, 0xe6, 0xfa, 0xad, 0xa7, 0x17, 0x9e, 0x84, 0xf3, 0xb9, 0xca, 0xc2, 0xfc, 0x63, 0x25, 0x51,
+];
+const P256_HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0x80, 0x00, 0x00, 0x00, 0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xde, 0x73, 0x7d, 0x56, 0xd3, 0x8b, 0xcf, 0x42, 0x79, 0xdc, 0xe5, 0x61, 0x7e, 0x31, 0x92, 0xa8,
+];
+
+/// Verify `signature` by `public_key` over `digest`, in either encoding.
+pub fn verify(
+    env: &Env,
+    public_key: &BytesN<65>,
+    digest: &Hash<32>,
+    signature: &Bytes,
+) -> Result<(), WalletError> {
+    let signature = compact(env, signature)?;
+    env.crypto()
+        .secp256r1_verify(public_key, digest, &signature);
+    Ok(())
+}
+
+/// `signature` in compact form: 64 bytes are taken as `r ‖ s` and must
+/// have a low `s`, anything else is decoded as DER.
+pub fn compact(env: &Env, signature: &Bytes) -> Result<BytesN<64>, WalletError> {
+    let len = signature.len() as usize;
+    if len == 64 {
+        let signature: BytesN<64> = signature.clone().try_into().unwrap();
+        let scalars = signature.to_array();
+        check_scalars(&scalars)?;
+        if scalars[32..] > P256_HALF_ORDER[..] {
+            return Err(WalletError::NonCanonicalSignature);
+        }
+        return Ok(signature);
+    }
+    if len > MAX_DER_LEN {
+        return Err(WalletError::InvalidSignature);
+    }
+    let mut der = [0u8; MAX_DER_LEN];
+    signature.copy_into_slice(&mut der[..len]);
+    Ok(BytesN::from_array(env, &der_to_compact(&der[..len])?))
+}
+
+/// Convert a DER ECDSA signature to compact `r ‖ s`, with `s` normalized
+/// to the lower half of the order.
+pub fn der_to_compact(der: &[u8]) -> Result<[u8; 64], WalletError> {
+    // SEQUENCE { INTEGER r, INTEGER s }
+    let body = match der {
+        [0x30, len, body @ ..] if *len as usize == body.len() => body,
+        _ => return Err(WalletError::InvalidSignature),
+    };
+    let (r, rest) = der_integer(body)?;
+    let (s, rest) = der_integer(rest)?;
+    if !rest.is_empty() {
+        return Err(WalletError::InvalidSignature);
+    }
+
+    let mut signature = [0u8; 64];
+    signature[32 - r.len()..32].copy_from_slice(r);
+    signature[64 - s.len()..].copy_from_slice(s);
+    check_scalars(&signature)?;
+    if signature[32..] > P256_HALF_ORDER[..] {
+        let mut borrow = 0u16;
+        for i in (0..32).rev() {
+            let diff = 0x100 + P256_ORDER[i] as u16 - signature[32 + i] as u16 - borrow;
+            signature[32 + i] = diff as u8;
+            borrow = u16::from(diff < 0x100);
+        }
+    }
+    Ok(signature)
+}
+
+/// Require both `r` and `s` of compact `signature` to be in `1..n`.
+fn check_scalars(signature: &[u8; 64]) -> Result<(), WalletError> {
+    for scalar in [&signature[..32], &signature[32..]] {
+        if scalar.iter().all(|&byte| byte == 0) || scalar >= &P256_ORDER[..] {
+            return Err(WalletError::InvalidSignature);
+        }
+    }
+    Ok(())
+}
+
+/// A DER INTEGER's unsigned value (at most 32 bytes) and the bytes after it.
+fn der_integer(der: &[u8]) -> Result<(&[u8], &[u8]), WalletError> {
+    let [0x02, len, rest @ ..] = der else {
+        return Err(WalletError::InvalidSignature);
+    };
+    let len = *len as usize;
+    if len == 0 || len > rest.len() {
+        return Err(WalletError::InvalidSignature);
+    }
+    let (mut value, rest) = rest.split_at(len);
+    while let [0, tail @ ..] = value {
+        value = tail;
+    }
+    if value.len() > 32 {
+        return Err(WalletError::InvalidSignature);
+    }
+    Ok((value, rest))
+}
@@ -0,0 +1,390 @@
+//! Wallet events.
+//!
+//! Signer topics are `(name, credential_id)` so frontends and indexers can
+//! follow a single passkey or session key without polling storage; multisig
+//! proposal topics are `(name, proposal_id)`, queued action topics
+//! `(name, action_id)`, intent topics `(name, intent_id)`, guardian topics
+//! `(name, guardian)` and sub-account topics `(name, sub_account)`.
+//!
+//! Events are still published with `Events::publish` rather than
+//! `#[contractevent]` types; moving over must keep the layout above.
+#![allow(deprecated)]
+
+use soroban_sdk::{symbol_short, Address, Bytes, BytesN, Env, Symbol, Vec};
+
+use smart_wallet_account_common::{
+    AdminOperation, GasTank, Intent, ModuleHook, QueuedAction, RecoveryConfig, RecoveryRequest,
+    SessionPolicy, SignerKind, WalletProfile,
+};
+
+const EVT_INIT: Symbol = symbol_short!("init");
+const EVT_SIGNER_ADD: Symbol = symbol_short!("sgn_add");
+const EVT_SIGNER_REM: Symbol = symbol_short!("sgn_rem");
+const EVT_SIGNER_ROT: Symbol = symbol_short!("sgn_rot");
+const EVT_SIGNER_PROVED: Symbol = symbol_short!("sgn_prov");
+const EVT_POLICY: Symbol = symbol_short!("sgn_pol");
+const EVT_TOKENS: Symbol = symbol_short!("sgn_tok");
+const EVT_SIGNER_SUB_ACCOUNT: Symbol = symbol_short!("sgn_sub");
+const EVT_SIGNER_ORIGIN: Symbol = symbol_short!("sgn_org");
+const EVT_AUTH: Symbol = symbol_short!("auth");
+const EVT_PROPOSED: Symbol = symbol_short!("proposed");
+const EVT_APPROVED: Symbol = symbol_short!("approved");
+const EVT_EXECUTED: Symbol = symbol_short!("executed");
+const EVT_THRESHOLD: Symbol = symbol_short!("threshold");
+const EVT_AUTH_THRESHOLD: Symbol = symbol_short!("auth_thr");
+const EVT_FROZEN: Symbol = symbol_short!("frozen");
+const EVT_UNFREEZE: Symbol = symbol_short!("unfreeze");
+const EVT_TIMELOCK_DELAY: Symbol = symbol_short!("tl_delay");
+const EVT_ACTION_QUEUED: Symbol = symbol_short!("tl_queue");
+const EVT_ACTION_CANCELLED: Symbol = symbol_short!("tl_cancel");
+const EVT_ACTION_EXECUTED: Symbol = symbol_short!("tl_exec");
+const EVT_ORIGIN_ADD: Symbol = symbol_short!("org_add");
+const EVT_ORIGIN_REM: Symbol = symbol_short!("org_rem");
+const EVT_RP_ID_ADD: Symbol = symbol_short!("rp_add");
+const EVT_RP_ID_REM: Symbol = symbol_short!("rp_rem");
+const EVT_DAILY_LIMIT: Symbol = symbol_short!("day_lim");
+const EVT_USER_VERIFICATION: Symbol = symbol_short!("uv_req");
+const EVT_ATTESTATION: Symbol = symbol_short!("att_req");
+const EVT_UPGRADED: Symbol = symbol_short!("upgraded");
+const EVT_MIGRATED: Symbol = symbol_short!("migrated");
+const EVT_PROFILE: Symbol = symbol_short!("profile");
+const EVT_MODULE_ADD: Symbol = symbol_short!("mod_add");
+const EVT_MODULE_REM: Symbol = symbol_short!("mod_rem");
+const EVT_SUB_ACCOUNT_ADD: Symbol = symbol_short!("sub_add");
+const EVT_SUB_ACCOUNT_REM: Symbol = symbol_short!("sub_rem");
+const EVT_SUB_ACCOUNT_BUDGET: Symbol = symbol_short!("sub_bud");
+const EVT_RELAYER_ADD: Symbol = symbol_short!("rly_add");
+const EVT_RELAYER_REM: Symbol = symbol_short!("rly_rem");
+const EVT_RELAYER_LIMIT: Symbol = symbol_short!("rly_lim");
+const EVT_SPONSORED: Symbol = symbol_short!("sponsored");
+const EVT_GAS_TANK: Symbol = symbol_short!("gas_tank");
+const EVT_EXECUTOR_ADD: Symbol = symbol_short!("exe_add");
+const EVT_EXECUTOR_REM: Symbol = symbol_short!("exe_rem");
+const EVT_GAS_PAID: Symbol = symbol_short!("gas_paid");
+const EVT_INTENT_ADD: Symbol = symbol_short!("int_add");
+const EVT_INTENT_REM: Symbol = symbol_short!("int_rem");
+const EVT_INTENT_EXEC: Symbol = symbol_short!("int_exec");
+const EVT_GUARDIAN_ADD: Symbol = symbol_short!("grd_add");
+const EVT_GUARDIAN_REM: Symbol = symbol_short!("grd_rem");
+const EVT_RECOVERY_CONFIG: Symbol = symbol_short!("rec_cfg");
+const EVT_RECOVERY_PROPOSED: Symbol = symbol_short!("rec_prop");
+const EVT_RECOVERY_APPROVED: Symbol = symbol_short!("rec_appr");
+const EVT_RECOVERY_VETOED: Symbol = symbol_short!("rec_veto");
+const EVT_RECOVERY_DONE: Symbol = symbol_short!("rec_done");
+
+/// `("init", credential_id)` → `()`, for the wallet's first admin signer.
+pub fn initialized(env: &Env, credential_id: &Bytes) {
+    env.events().publish((EVT_INIT, credential_id.clone()), ());
+}
+
+/// `("sgn_add", credential_id)` → `(kind, ttl_ledgers)`; `ttl_ledgers` is 0
+/// for admin signers.
+pub fn signer_added(env: &Env, credential_id: &Bytes, kind: SignerKind, ttl_ledgers: u32) {
+    env.events()
+        .publish((EVT_SIGNER_ADD, credential_id.clone()), (kind, ttl_ledgers));
+}
+
+/// `("sgn_rem", credential_id)` → `kind`
+pub fn signer_removed(env: &Env, credential_id: &Bytes, kind: SignerKind) {
+    env.events()
+        .publish((EVT_SIGNER_REM, credential_id.clone()), kind);
+}
+
+/// `("sgn_rot", old_credential_id)` → `(new_credential_id, kind)`
+pub fn signer_rotated(
+    env: &Env,
+    old_credential_id: &Bytes,
+    new_credential_id: &Bytes,
+    kind: SignerKind,
+) {
+    env.events().publish(
+        (EVT_SIGNER_ROT, old_credential_id.clone()),
+        (new_credential_id.clone(), kind),
+    );
+}
+
+/// `("sgn_prov", credential_id)` → `()`
+pub fn registration_proved(env: &Env, credential_id: &Bytes) {
+    env.events()
+        .publish((EVT_SIGNER_PROVED, credential_id.clone()), ());
+}
+
+/// `("sgn_pol", credential_id)` → `policy`
+pub fn session_policy_set(env: &Env, credential_id: &Bytes, policy: &SessionPolicy) {
+    env.events()
+        .publish((EVT_POLICY, credential_id.clone()), policy.clone());
+}
+
+/// `("sgn_tok", credential_id)` → `allowed_tokens`
+pub fn allowed_tokens_set(env: &Env, credential_id: &Bytes, allowed_tokens: &Vec<Address>) {
+    env.events()
+        .publish((EVT_TOKENS, credential_id.clone()), allowed_tokens.clone());
+}
+
+/// `("sgn_org", credential_id)` → `Option<origin>`
+pub fn signer_origin_set(env: &Env, credential_id: &Bytes, origin: &Option<Bytes>) {
+    env.events()
+        .publish((EVT_SIGNER_ORIGIN, credential_id.clone()), origin.clone());
+}
+
+/// `("sgn_sub", credential_id)` → `Option<sub_account>`
+pub fn sub_account_assigned(env: &Env, credential_id: &Bytes, sub_account: &Option<Symbol>) {
+    env.events().publish(
+        (EVT_SIGNER_SUB_ACCOUNT, credential_id.clone()),
+        sub_account.clone(),
+    );
+}
+
+/// `("auth", credential_id)` → `kind`, after a signature passes `__check_auth`.
+pub fn authenticated(env: &Env, credential_id: &Bytes, kind: SignerKind) {
+    env.events()
+        .publish((EVT_AUTH, credential_id.clone()), kind);
+}
+
+/// `("proposed", proposal_id)` → `operation`
+pub fn proposed(env: &Env, proposal_id: u64, operation: &AdminOperation) {
+    env.events()
+        .publish((EVT_PROPOSED, proposal_id), operation.clone());
+}
+
+/// `("approved", proposal_id)` → `credential_id`
+pub fn approved(env: &Env, proposal_id: u64, credential_id: &Bytes) {
+    env.events()
+        .publish((EVT_APPROVED, proposal_id), credential_id.clone());
+}
+
+/// `("executed", proposal_id)` → `()`
+pub fn executed(env: &Env, proposal_id: u64) {
+    env.events().publish((EVT_EXECUTED, proposal_id), ());
+}
+
+/// `("threshold",)` → `threshold`
+pub fn threshold_set(env: &Env, threshold: u32) {
+    env.events().publish((EVT_THRESHOLD,), threshold);
+}
+
+/// `("auth_thr",)` → `threshold`
+pub fn auth_threshold_set(env: &Env, threshold: u32) {
+    env.events().publish((EVT_AUTH_THRESHOLD,), threshold);
+}
+
+/// `("frozen",)` → `()`
+pub fn frozen(env: &Env) {
+    env.events().publish((EVT_FROZEN,), ());
+}
+
+/// `("unfreeze",)` → `frozen_until`, the ledger the freeze ends at
+pub fn unfreeze_scheduled(env: &Env, frozen_until: u32) {
+    env.events().publish((EVT_UNFREEZE,), frozen_until);
+}
+
+/// `("tl_delay",)` → `delay_ledgers`
+pub fn timelock_delay_set(env: &Env, delay_ledgers: u32) {
+    env.events().publish((EVT_TIMELOCK_DELAY,), delay_ledgers);
+}
+
+/// `("tl_queue", action_id)` → `action`
+pub fn action_queued(env: &Env, action_id: u64, action: &QueuedAction) {
+    env.events()
+        .publish((EVT_ACTION_QUEUED, action_id), action.clone());
+}
+
+/// `("tl_cancel", action_id)` → `()`
+pub fn action_cancelled(env: &Env, action_id: u64) {
+    env.events().publish((EVT_ACTION_CANCELLED, action_id), ());
+}
+
+/// `("tl_exec", action_id)` → `()`
+pub fn action_executed(env: &Env, action_id: u64) {
+    env.events().publish((EVT_ACTION_EXECUTED, action_id), ());
+}
+
+/// `("org_add",)` → `origin`
+pub fn origin_added(env: &Env, origin: &Bytes) {
+    env.events().publish((EVT_ORIGIN_ADD,), origin.clone());
+}
+
+/// `("org_rem",)` → `origin`
+pub fn origin_removed(env: &Env, origin: &Bytes) {
+    env.events().publish((EVT_ORIGIN_REM,), origin.clone());
+}
+
+/// `("rp_add",)` → `rp_id_hash`
+pub fn rp_id_hash_added(env: &Env, rp_id_hash: &BytesN<32>) {
+    env.events().publish((EVT_RP_ID_ADD,), rp_id_hash.clone());
+}
+
+/// `("rp_rem",)` → `rp_id_hash`
+pub fn rp_id_hash_removed(env: &Env, rp_id_hash: &BytesN<32>) {
+    env.events().publish((EVT_RP_ID_REM,), rp_id_hash.clone());
+}
+
+/// `("day_lim", token)` → `limit`, `None` once lifted
+pub fn daily_limit_set(env: &Env, token: &Address, limit: Option<i128>) {
+    env.events()
+        .publish((EVT_DAILY_LIMIT, token.clone()), limit);
+}
+
+/// `("uv_req",)` → `required`
+pub fn user_verification_set(env: &Env, required: bool) {
+    env.events().publish((EVT_USER_VERIFICATION,), required);
+}
+
+/// `("att_req",)` → `required`
+pub fn attestation_required_set(env: &Env, required: bool) {
+    env.events().publish((EVT_ATTESTATION,), required);
+}
+
+/// `("upgraded",)` → `new_wasm_hash`
+pub fn upgraded(env: &Env, new_wasm_hash: &BytesN<32>) {
+    env.events().publish((EVT_UPGRADED,), new_wasm_hash.clone());
+}
+
+/// `("migrated",)` → `(from_version, to_version)`
+pub fn migrated(env: &Env, from: u32, to: u32) {
+    env.events().publish((EVT_MIGRATED,), (from, to));
+}
+
+/// `("profile",)` → `profile`
+pub fn profile_set(env: &Env, profile: &WalletProfile) {
+    env.events().publish((EVT_PROFILE,), profile.clone());
+}
+
+/// `("mod_add", module)` → `hook`
+pub fn module_installed(env: &Env, module: &Address, hook: &ModuleHook) {
+    env.events()
+        .publish((EVT_MODULE_ADD, module.clone()), hook.clone());
+}
+
+/// `("mod_rem", module)` → `hook`
+pub fn module_uninstalled(env: &Env, module: &Address, hook: &ModuleHook) {
+    env.events()
+        .publish((EVT_MODULE_REM, module.clone()), hook.clone());
+}
+
+/// `("sub_add", name)` → `()`
+pub fn sub_account_created(env: &Env, name: &Symbol) {
+    env.events()
+        .publish((EVT_SUB_ACCOUNT_ADD, name.clone()), ());
+}
+
+/// `("sub_rem", name)` → `()`
+pub fn sub_account_removed(env: &Env, name: &Symbol) {
+    env.events()
+        .publish((EVT_SUB_ACCOUNT_REM, name.clone()), ());
+}
+
+/// `("sub_bud", name, token)` → `amount`
+pub fn sub_account_budget_set(env: &Env, name: &Symbol, token: &Address, amount: i128) {
+    env.events().publish(
+        (EVT_SUB_ACCOUNT_BUDGET, name.clone(), token.clone()),
+        amount,
+    );
+}
+
+/// `("rly_add", relayer)` → `()`
+pub fn relayer_added(env: &Env, relayer: &Address) {
+    env.events().publish((EVT_RELAYER_ADD, relayer.clone()), ());
+}
+
+/// `("rly_rem", relayer)` → `()`
+pub fn relayer_removed(env: &Env, relayer: &Address) {
+    env.events().publish((EVT_RELAYER_REM, relayer.clone()), ());
+}
+
+/// `("rly_lim", relayer)` → `Option<max_per_day>`
+pub fn relayer_rate_limit_set(env: &Env, relayer: &Address, limit: Option<u32>) {
+    env.events()
+        .publish((EVT_RELAYER_LIMIT, relayer.clone()), limit);
+}
+
+/// `("sponsored", relayer)` → `(fee_token, fee)`
+pub fn sponsored(env: &Env, relayer: &Address, fee_token: &Address, fee: i128) {
+    env.events()
+        .publish((EVT_SPONSORED, relayer.clone()), (fee_token.clone(), fee));
+}
+
+/// `("gas_tank",)` → `Option<tank>`
+pub fn gas_tank_set(env: &Env, tank: &Option<GasTank>) {
+    env.events().publish((EVT_GAS_TANK,), tank.clone());
+}
+
+/// `("exe_add", executor)` → `()`
+pub fn executor_added(env: &Env, executor: &Address) {
+    env.events()
+        .publish((EVT_EXECUTOR_ADD, executor.clone()), ());
+}
+
+/// `("exe_rem", executor)` → `()`
+pub fn executor_removed(env: &Env, executor: &Address) {
+    env.events()
+        .publish((EVT_EXECUTOR_REM, executor.clone()), ());
+}
+
+/// `("gas_paid", executor)` → `(token, amount)`
+pub fn executor_reimbursed(env: &Env, executor: &Address, token: &Address, amount: i128) {
+    env.events()
+        .publish((EVT_GAS_PAID, executor.clone()), (token.clone(), amount));
+}
+
+/// `("int_add", intent_id)` → `intent`
+pub fn intent_created(env: &Env, intent_id: u64, intent: &Intent) {
+    env.events()
+        .publish((EVT_INTENT_ADD, intent_id), intent.clone());
+}
+
+/// `("int_rem", intent_id)` → `()`
+pub fn intent_cancelled(env: &Env, intent_id: u64) {
+    env.events().publish((EVT_INTENT_REM, intent_id), ());
+}
+
+/// `("int_exec", intent_id)` → `()`
+pub fn intent_executed(env: &Env, intent_id: u64) {
+    env.events().publish((EVT_INTENT_EXEC, intent_id), ());
+}
+
+/// `("grd_add", guardian)` → `()`
+pub fn guardian_added(env: &Env, guardian: &Address) {
+    env.events()
+        .publish((EVT_GUARDIAN_ADD, guardian.clone()), ());
+}
+
+/// `("grd_rem", guardian)` → `()`
+pub fn guardian_removed(env: &Env, guardian: &Address) {
+    env.events()
+        .publish((EVT_GUARDIAN_REM, guardian.clone()), ());
+}
+
+/// `("rec_cfg",)` → `config`
+pub fn recovery_config_set(env: &Env, config: &RecoveryConfig) {
+    env.events().publish((EVT_RECOVERY_CONFIG,), config.clone());
+}
+
+/// `("rec_prop", guardian)` → `(credential_id, executable_ledger)`;
+/// `executable_ledger` is 0 while more approvals are needed.
+pub fn recovery_proposed(env: &Env, guardian: &Address, request: &RecoveryRequest) {
+    env.events().publish(
+        (EVT_RECOVERY_PROPOSED, guardian.clone()),
+        (request.credential_id.clone(), request.executable_ledger),
+    );
+}
+
+/// `("rec_appr", guardian)` → `(approvals, executable_ledger)`
+pub fn recovery_approved(env: &Env, guardian: &Address, request: &RecoveryRequest) {
+    env.events().publish(
+        (EVT_RECOVERY_APPROVED, guardian.clone()),
+        (request.approvals.len(), request.executable_ledger),
+    );
+}
+
+/// `("rec_veto",)` → `credential_id` of the cancelled recovery signer
+pub fn recovery_vetoed(env: &Env, credential_id: &Bytes) {
+    env.events()
+        .publish((EVT_RECOVERY_VETOED,), credential_id.clone());
+}
+
+/// `("rec_done",)` → `credential_id` of the added admin signer
+pub fn recovery_finalized(env: &Env, credential_id: &Bytes) {
+    env.events()
+        .publish((EVT_RECOVERY_DONE,), credential_id.clone());
+}
@@ -0,0 +1,100 @@
+//! Gas tank.
+//!
+//! Executors (keepers) run the wallet's permissionless operations, such as
+//! due intents or recoveries, with `run_task` and pay the network fees.  The
+//! gas tank pays each of them a fixed reimbursement per task, out of a
+//! budget the wallet sets aside and up to a daily cap per executor; the
+//! contract cannot see the fee actually charged.  What each executor was
+//! paid today is counted in temporary storage, like daily spending.
+
+use soroban_sdk::{Address, Env, Vec};
+
+use smart_wallet_account_common::{DailySpend, GasTank, WalletDataKey, WalletError};
+
+use crate::{
+    events,
+    limits::{today, SPEND_TTL},
+};
+
+pub fn tank(env: &Env) -> Option<GasTank> {
+    env.storage().instance().get(&WalletDataKey::GasTank)
+}
+
+pub fn set_tank(env: &Env, tank: Option<GasTank>) {
+    match tank {
+        Some(tank) => env.storage().instance().set(&WalletDataKey::GasTank, &tank),
+        None => env.storage().instance().remove(&WalletDataKey::GasTank),
+    }
+}
+
+pub fn executors(env: &Env) -> Vec<Address> {
+    env.storage()
+        .instance()
+        .get(&WalletDataKey::Executors)
+        .unwrap_or_else(|| Vec::new(env))
+}
+
+fn set_executors(env: &Env, executors: &Vec<Address>) {
+    env.storage()
+        .instance()
+        .set(&WalletDataKey::Executors, executors);
+}
+
+/// Add `executor` unless it already is one.
+pub fn add_executor(env: &Env, executor: &Address) {
+    let mut executors = executors(env);
+    if !executors.contains(executor) {
+        executors.push_back(executor.clone());
+        set_executors(env, &executors);
+        events::executor_added(env, executor);
+    }
+}
+
+pub fn remove_executor(env: &Env, executor: &Address) -> Result<(), WalletError> {
+    let mut executors = executors(env);
+    let index = executors
+        .first_index_of(executor)
+        .ok_or(WalletError::ExecutorNotAllowed)?;
+    executors.remove(index);
+    set_executors(env, &executors);
+    events::executor_removed(env, executor);
+    Ok(())
+}
+
+/// What `executor` has been paid back so far today.
+pub fn paid_today(env: &Env, executor: &Address) -> i128 {
+    env.storage()
+        .temporary()
+        .get::<_, DailySpend>(&WalletDataKey::ExecutorPaid(executor.clone()))
+        .filter(|paid| paid.day == today(env))
+        .map_or(0, |paid| paid.spent)
+}
+
+/// Take `executor`'s reimbursement for one task out of the tank and return
+/// it, with the token it is paid in.  The amount is cut down to what is
+/// left of the budget and of the executor's daily cap, and may be 0.
+pub fn draw(env: &Env, executor: &Address) -> Option<(Address, i128)> {
+    let mut tank = tank(env)?;
+    let paid = paid_today(env, executor);
+    let amount = tank
+        .reimbursement
+        .min(tank.budget)
+        .min(tank.daily_cap.saturating_sub(paid))
+        .max(0);
+    if amount == 0 {
+        return None;
+    }
+
+    tank.budget -= amount;
+    set_tank(env, Some(tank.clone()));
+    let key = WalletDataKey::ExecutorPaid(executor.clone());
+    let paid = DailySpend {
+        day: today(env),
+        spent: paid + amount,
+    };
+    env.storage().temporary().set(&key, &paid);
+    env.storage()
+        .temporary()
+        .extend_ttl(&key, SPEND_TTL, SPEND_TTL);
+    Some((tank.token, amount))
+}
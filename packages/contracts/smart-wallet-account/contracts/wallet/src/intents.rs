@@ -0,0 +1,94 @@
+//! Spending intents.
+//!
+//! An intent is one token transfer an admin approves ahead of time, e.g. a
+//! scheduled payment, which a relayer or scheduler can then make without
+//! any signer being online.  Intents live in persistent storage until they
+//! run or are cancelled, kept alive at least until their deadline.
+
+use soroban_sdk::{
+    auth::{Context, ContractContext},
+    Env, IntoVal, Symbol,
+};
+
+use smart_wallet_account_common::{Intent, Invocation, WalletDataKey, WalletError};
+
+use crate::{events, ADMIN_TTL_EXTEND, ADMIN_TTL_THRESHOLD, LEDGER_SECONDS};
+
+/// Store `intent` under a new ID and return it, failing with
+/// `InvalidIntent` unless its amount is positive and with `IntentExpired`
+/// for a past deadline.
+pub fn create(env: &Env, intent: &Intent) -> Result<u64, WalletError> {
+    if intent.amount <= 0 {
+        return Err(WalletError::InvalidIntent);
+    }
+    if env.ledger().timestamp() > intent.deadline {
+        return Err(WalletError::IntentExpired);
+    }
+
+    let id = next_id(env);
+    save(env, id, intent);
+    events::intent_created(env, id, intent);
+    Ok(id)
+}
+
+/// Reserve the next intent ID.
+fn next_id(env: &Env) -> u64 {
+    let id: u64 = env
+        .storage()
+        .instance()
+        .get(&WalletDataKey::NextIntentId)
+        .unwrap_or(1);
+    env.storage()
+        .instance()
+        .set(&WalletDataKey::NextIntentId, &(id + 1));
+    id
+}
+
+pub fn load(env: &Env, intent_id: u64) -> Result<Intent, WalletError> {
+    env.storage()
+        .persistent()
+        .get(&WalletDataKey::Intent(intent_id))
+        .ok_or(WalletError::IntentNotFound)
+}
+
+fn save(env: &Env, intent_id: u64, intent: &Intent) {
+    let key = WalletDataKey::Intent(intent_id);
+    let seconds_left = intent.deadline.saturating_sub(env.ledger().timestamp());
+    let ledgers_left = u32::try_from(seconds_left / LEDGER_SECONDS + 1).unwrap_or(u32::MAX);
+    let extend_to = ledgers_left
+        .max(ADMIN_TTL_EXTEND)
+        .min(env.storage().max_ttl());
+    env.storage().persistent().set(&key, intent);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, ADMIN_TTL_THRESHOLD.min(extend_to), extend_to);
+}
+
+pub fn remove(env: &Env, intent_id: u64) {
+    env.storage()
+        .persistent()
+        .remove(&WalletDataKey::Intent(intent_id));
+}
+
+/// The `transfer` call that carries out `intent`.
+pub fn transfer(env: &Env, intent: &Intent) -> Invocation {
+    Invocation {
+        contract: intent.token.clone(),
+        function: Symbol::new(env, "transfer"),
+        args: (
+            env.current_contract_address(),
+            intent.to.clone(),
+            intent.amount,
+        )
+            .into_val(env),
+    }
+}
+
+/// `invocation` as the auth context daily limits are charged for.
+pub fn context(invocation: &Invocation) -> Context {
+    Context::Contract(ContractContext {
+        contract: invocation.contract.clone(),
+        fn_name: invocation.function.clone(),
+        args: invocation.args.clone(),
+    })
+}
@@ -1,19 +1,74 @@
 #![no_std]
 use soroban_sdk::{
-    auth::{Context, CustomAccountInterface},
+    auth::{Context, ContractContext, CustomAccountInterface},
     contract, contractimpl,
     crypto::Hash,
-    Bytes, BytesN, Env, Vec,
+    vec,
+    xdr::ToXdr,
+    Address, Bytes, BytesN, Env, IntoVal, Map, String, Symbol, TryFromVal, Val, Vec,
 };
 
 use smart_wallet_account_common::{
-    AccountSignature, Signer, SignerKind, WalletDataKey, WalletError,
+    AccountSignature, AdminOperation, AuditAction, AuditActor, AuditEntry, GasTank, InitialSigner,
+    Intent, Invocation, KeeperTask, ModuleHook, Proposal, QueuedAction, RecoveryConfig,
+    RecoveryRequest, SessionPolicy, Signature, Signer, SignerInfo, SignerKind, TimelockOperation,
+    WalletDataKey, WalletError, WalletInfo, WalletProfile,
 };
 
+mod attestation;
+mod audit;
+mod ecdsa;
+mod events;
+mod gas_tank;
+mod intents;
+mod limits;
+mod migration;
+mod modules;
+mod recovery;
+mod session;
+mod sponsor;
+mod subaccounts;
+mod timelock;
+
 /// TTL constants for admin signers (in ledgers). ~1 ledger ≈ 5 seconds.
 const ADMIN_TTL_THRESHOLD: u32 = 60_480; // ~3.5 days
 const ADMIN_TTL_EXTEND: u32 = 120_960;   // ~7 days
 
+/// Nominal ledger close time, used to turn a session TTL into `expires_at`.
+const LEDGER_SECONDS: u64 = 5;
+
+/// Version of this wallet code; `migrate` brings storage written by older
+/// versions up to it.
+const WALLET_VERSION: u32 = 4;
+
+/// WebAuthn authenticator data flag bits.
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+
+/// Longest signer label, in bytes.
+const MAX_LABEL_LEN: u32 = 64;
+
+/// Maximum number of signers returned by one `list_signers` call.
+const MAX_SIGNER_PAGE: u32 = 50;
+
+/// Most live signers of all kinds a wallet can hold.  A guardian recovery
+/// may still add its signer to a full wallet.
+const MAX_SIGNERS: u32 = 20;
+
+/// Credential ID length bounds, in bytes, from the WebAuthn spec.  The
+/// network's ledger key size limit rejects IDs well short of the maximum.
+const MIN_CREDENTIAL_ID_LEN: u32 = 16;
+const MAX_CREDENTIAL_ID_LEN: u32 = 1023;
+
+/// Prefix of the SEP-53 hash `verify_message` expects passkeys to sign.
+const SIGNED_MESSAGE_PREFIX: &[u8] = b"Stellar Signed Message:\n";
+
+/// Ledgers a single-admin `unfreeze` waits before taking effect (~1 day).
+const UNFREEZE_DELAY: u32 = 17_280;
+
+/// Most items `queue_actions` or `create_intents` take in one call.
+const MAX_BATCH_ITEMS: u32 = 20;
+
 #[contract]
 pub struct SmartWallet;
 
@@ -23,280 +78,2474 @@ impl SmartWallet {
     //  Initialization
     // ────────────────────────────────────────────────────────
 
-    /// Called once by the factory right after deployment.
-    /// Stores the first admin signer (the passkey used during registration).
-    pub fn init(env: Env, credential_id: Bytes, public_key: BytesN<65>) -> Result<(), WalletError> {
-        if env.storage().instance().has(&WalletDataKey::WalletAddress) {
-            return Err(WalletError::AlreadyInitialized);
+    /// Stores the first admin signer (the passkey used during
+    /// registration), then any further passkeys in `signers`, e.g. a backup
+    /// key enrolled during the same onboarding.  Runs as part of the
+    /// deployment itself, so a wallet never exists without an admin for
+    /// someone else to claim.  A session kind in `signers` fails with
+    /// `InvalidPolicy`.
+    pub fn __constructor(
+        env: Env,
+        credential_id: Bytes,
+        public_key: BytesN<65>,
+        signers: Vec<InitialSigner>,
+    ) -> Result<(), WalletError> {
+        initialize(&env, &credential_id, public_key)?;
+        for signer in signers.iter() {
+            if signer.kind == SignerKind::Session {
+                return Err(WalletError::InvalidPolicy);
+            }
+            store_passkey_signer(&env, &signer.credential_id, signer.public_key, signer.kind)?;
         }
+        Ok(())
+    }
 
-        validate_admin_public_key(&public_key)?;
+    // ────────────────────────────────────────────────────────
+    //  Signer management (requires wallet self-auth)
+    // ────────────────────────────────────────────────────────
 
-        env.storage().instance().set(
-            &WalletDataKey::WalletAddress,
-            &env.current_contract_address(),
-        );
+    /// Add a new admin signer (secp256r1 / P-256 passkey).
+    /// Requires wallet self-auth (`require_auth` → `__check_auth`).
+    ///
+    /// Fails with `ApprovalRequired` while the multisig threshold is above 1;
+    /// propose an `AdminOperation::AddSigner` instead.  Fails with
+    /// `TimelockRequired` while the wallet has a timelock delay, and with
+    /// `AttestationRequired` once the wallet requires attestation.
+    pub fn add_signer(
+        env: Env,
+        credential_id: Bytes,
+        public_key: BytesN<65>,
+    ) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+        if load_threshold(&env) > 1 {
+            return Err(WalletError::ApprovalRequired);
+        }
+        timelock::check_unlocked(&env)?;
+        if requires_attestation(&env) {
+            return Err(WalletError::AttestationRequired);
+        }
+
+        store_passkey_signer(&env, &credential_id, public_key, SignerKind::Admin)
+    }
+
+    /// Add an admin signer from a WebAuthn registration response instead of
+    /// a bare public key.  Requires wallet self-auth, under the same
+    /// threshold and timelock rules as `add_signer`.
+    ///
+    /// `attestation_object` must be a `packed` ES256 attestation, with an
+    /// `x5c` certificate or self attestation, for `credential_id`;
+    /// `client_data_json` must be a `webauthn.create` response from an
+    /// allowed origin, and the authenticator data must carry the flags
+    /// assertions need.  The key is taken from the attested credential data.
+    pub fn add_signer_with_attestation(
+        env: Env,
+        credential_id: Bytes,
+        attestation_object: Bytes,
+        client_data_json: Bytes,
+    ) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+        if load_threshold(&env) > 1 {
+            return Err(WalletError::ApprovalRequired);
+        }
+        timelock::check_unlocked(&env)?;
+
+        let public_key =
+            verify_registration(&env, &credential_id, &attestation_object, &client_data_json)?;
+        store_passkey_signer(&env, &credential_id, public_key, SignerKind::Admin)
+    }
+
+    /// Check a WebAuthn registration response for the existing passkey
+    /// signer `credential_id`, under the same rules as
+    /// `add_signer_with_attestation`, and fail with `InvalidAttestation`
+    /// unless it attests the stored public key.
+    ///
+    /// Anyone may call this.  It changes nothing, but proves that whoever
+    /// produced the response held the signer's private key: the factory's
+    /// `deploy_with_attestation` calls it for the first signer in the
+    /// deployment itself, and the `sgn_prov` event it publishes lets clients
+    /// show which signers were proven.  The `x5c` chain is not validated
+    /// and self attestation can come from a software key, so it says
+    /// nothing about the authenticator the key lives on.
+    pub fn prove_registration(
+        env: Env,
+        credential_id: Bytes,
+        attestation_object: Bytes,
+        client_data_json: Bytes,
+    ) -> Result<(), WalletError> {
+        let signer = get_signer(&env, &credential_id)?;
+        let public_key =
+            verify_registration(&env, &credential_id, &attestation_object, &client_data_json)?;
+        if Bytes::from(public_key) != signer.public_key {
+            return Err(WalletError::InvalidAttestation);
+        }
+
+        events::registration_proved(&env, &credential_id);
+        Ok(())
+    }
+
+    /// Add an operator signer: a P-256 passkey that can authorize
+    /// transactions, but none of the wallet's own management functions.
+    /// Requires wallet self-auth.
+    ///
+    /// Operators do not count towards the admins, cannot approve proposals,
+    /// and can be added and removed whatever the multisig threshold.
+    pub fn add_operator(
+        env: Env,
+        credential_id: Bytes,
+        public_key: BytesN<65>,
+    ) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+        store_passkey_signer(&env, &credential_id, public_key, SignerKind::Operator)
+    }
+
+    /// Register a short-lived Ed25519 session key with a caller-specified TTL.
+    ///
+    /// Session keys let callers authorise multiple Soroban transactions within a
+    /// time window without repeated biometric prompts — ideal for trading bots,
+    /// DCA strategies, or any high-frequency DeFi flow.
+    ///
+    /// ## On-chain TTL semantics
+    /// The entry is written to **Soroban temporary storage**, which auto-expires
+    /// when its TTL reaches 0.  The TTL is set to `ttl_ledgers` on creation and
+    /// renewed by `extend_signer_ttl` after each successful `__check_auth` call,
+    /// capped at the original `ttl_ledgers` value.  No manual revocation is
+    /// needed after expiry — the entry simply disappears, and any subsequent tx
+    /// that references this credential ID will fail with `SignerNotFound`.
+    ///
+    /// Because auth keeps renewing the entry, the TTL alone does not bound
+    /// the session.  The signer also records `expires_at`, the ledger
+    /// timestamp `ttl_ledgers` × 5 s from now; `__check_auth` rejects the
+    /// session with `SessionExpired` from then on.
+    ///
+    /// ## Key format
+    /// `public_key` must be the 32-byte raw Ed25519 public key (decoded from the
+    /// Stellar G-address via `StrKey.decodeEd25519PublicKey`).
+    ///
+    /// ## Contract allowlist
+    /// A non-empty `allowed_contracts` limits the session to authorizing
+    /// calls to those contracts: `__check_auth` rejects any other invocation,
+    /// and any contract deployment, with `ContractNotAllowed`.  An empty list
+    /// leaves the session unscoped.
+    ///
+    /// ## Usage cap
+    /// A non-zero `max_uses` caps how many times the session can pass
+    /// `__check_auth`; the signer is removed after its last use, so a key
+    /// cannot be replayed indefinitely within its TTL.  `0` means no cap.
+    ///
+    /// Requires wallet self-auth (`require_auth` → `__check_auth` with an admin
+    /// passkey) so only the wallet owner can register new session keys.
+    pub fn add_session_signer(
+        env: Env,
+        credential_id: Bytes,
+        public_key: BytesN<32>,
+        ttl_ledgers: u32,
+        allowed_contracts: Vec<Address>,
+        max_uses: u32,
+    ) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+
+        if ttl_ledgers == 0 {
+            return Err(WalletError::NotAuthorized);
+        }
+        validate_credential_id(&credential_id)?;
+
+        let key = WalletDataKey::Signer(credential_id.clone());
+
+        if env.storage().persistent().has(&key) || env.storage().temporary().has(&key) {
+            return Err(WalletError::SignerAlreadyExists);
+        }
+        check_signer_capacity(&env)?;
 
         let signer = Signer {
             public_key: public_key.into(),
-            kind: SignerKind::Admin,
-            ttl_ledgers: 0, // admin TTL is managed by constants
+            kind: SignerKind::Session,
+            ttl_ledgers,
+            expires_at: env.ledger().timestamp() + u64::from(ttl_ledgers) * LEDGER_SECONDS,
+            policy: unrestricted_policy(&env),
+            spent: 0,
+            allowed_contracts,
+            uses_left: max_uses,
+            label: String::from_str(&env, ""),
+            added_at: env.ledger().timestamp(),
+            last_used_at: 0,
+            sub_account: None,
+            origin: None,
         };
+        env.storage().temporary().set(&key, &signer);
+        // Use the caller-provided TTL for both the threshold and extend so the
+        // entry lives exactly as long as requested.
         env.storage()
-            .persistent()
-            .set(&WalletDataKey::Signer(credential_id.clone()), &signer);
-
-        env.storage().persistent().extend_ttl(
-            &WalletDataKey::Signer(credential_id),
-            ADMIN_TTL_THRESHOLD,
-            ADMIN_TTL_EXTEND,
+            .temporary()
+            .extend_ttl(&key, ttl_ledgers / 2, ttl_ledgers);
+        index_signer(&env, &credential_id);
+        events::signer_added(&env, &credential_id, SignerKind::Session, ttl_ledgers);
+        audit::record(
+            &env,
+            AuditAction::AddSigner(credential_id.clone(), SignerKind::Session),
         );
-        env.storage()
-            .instance()
-            .extend_ttl(ADMIN_TTL_THRESHOLD, ADMIN_TTL_EXTEND);
 
+        Ok(())
+    }
+
+    /// Set a session signer's spending policy; a policy without caps or
+    /// token list lifts the restrictions. Requires wallet self-auth.
+    ///
+    /// The amount already spent carries over, so tightening `max_total`
+    /// counts what the session has moved before.
+    pub fn set_session_policy(
+        env: Env,
+        credential_id: Bytes,
+        policy: SessionPolicy,
+    ) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+
+        let key = WalletDataKey::Signer(credential_id.clone());
+        let mut signer: Signer = env
+            .storage()
+            .temporary()
+            .get(&key)
+            .ok_or(WalletError::SignerNotFound)?;
+        session::validate(&policy)?;
+
+        signer.policy = policy;
+        env.storage().temporary().set(&key, &signer);
+        events::session_policy_set(&env, &credential_id, &signer.policy);
+        Ok(())
+    }
+
+    /// Limit the token contracts an operator or session signer may move the
+    /// wallet's funds of, through the SEP-41 calls a session policy counts;
+    /// an empty list allows any token.  Requires wallet self-auth.
+    ///
+    /// Fails with `InvalidPolicy` for admins, who could lift the limit
+    /// themselves.  Calls on other tokens then fail `__check_auth` with
+    /// `TokenNotAllowed`.  For sessions this is the policy's
+    /// `allowed_tokens`.
+    pub fn set_allowed_tokens(
+        env: Env,
+        credential_id: Bytes,
+        allowed_tokens: Vec<Address>,
+    ) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+
+        let mut signer = get_signer(&env, &credential_id)?;
+        if signer.kind == SignerKind::Admin {
+            return Err(WalletError::InvalidPolicy);
+        }
+        signer.policy.allowed_tokens = allowed_tokens;
+        put_signer(&env, &credential_id, &signer);
+        events::allowed_tokens_set(&env, &credential_id, &signer.policy.allowed_tokens);
+        Ok(())
+    }
+
+    /// Bind a passkey signer to the WebAuthn `origin` it was issued for,
+    /// or lift the binding with `None`.  Requires wallet self-auth.
+    ///
+    /// From then on `__check_auth` rejects the signer's assertions with
+    /// `OriginNotAllowed` unless their clientDataJSON `origin` is exactly
+    /// this one, so a dApp's operator passkey cannot sign for another site
+    /// the wallet allows.  Session keys fail with `InvalidPolicy`: they
+    /// sign without clientDataJSON, so there is no origin to check.
+    pub fn set_signer_origin(
+        env: Env,
+        credential_id: Bytes,
+        origin: Option<Bytes>,
+    ) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+
+        let mut signer = get_signer(&env, &credential_id)?;
+        if signer.kind == SignerKind::Session {
+            return Err(WalletError::InvalidPolicy);
+        }
+        signer.origin = origin;
+        put_signer(&env, &credential_id, &signer);
+        events::signer_origin_set(&env, &credential_id, &signer.origin);
+        Ok(())
+    }
+
+    /// Make an operator or session signer spend from `sub_account`'s
+    /// budgets, or from the wallet as a whole with `None`.  Requires wallet
+    /// self-auth.
+    ///
+    /// Fails with `InvalidPolicy` for admins and `SubAccountNotFound` for an
+    /// unknown sub-account.
+    pub fn assign_sub_account(
+        env: Env,
+        credential_id: Bytes,
+        sub_account: Option<Symbol>,
+    ) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+
+        let mut signer = get_signer(&env, &credential_id)?;
+        if signer.kind == SignerKind::Admin {
+            return Err(WalletError::InvalidPolicy);
+        }
+        if let Some(name) = &sub_account {
+            subaccounts::budgets(&env, name)?;
+        }
+        signer.sub_account = sub_account;
+        put_signer(&env, &credential_id, &signer);
+        events::sub_account_assigned(&env, &credential_id, &signer.sub_account);
+        Ok(())
+    }
+
+    /// Remove a signer by credential ID. Requires wallet self-auth.
+    ///
+    /// Prevents removing the last admin signer to avoid permanently locking
+    /// the wallet.  Removing an admin fails with `ApprovalRequired` while the
+    /// multisig threshold is above 1; session signers can always be removed.
+    pub fn remove_signer(env: Env, credential_id: Bytes) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+
+        let signer = get_signer(&env, &credential_id)?;
+        if signer.kind == SignerKind::Admin && load_threshold(&env) > 1 {
+            return Err(WalletError::ApprovalRequired);
+        }
+
+        delete_signer(&env, &credential_id)
+    }
+
+    /// Remove every session signer at once, for when a dApp session may be
+    /// compromised, and return how many were removed.  Requires wallet
+    /// self-auth, which session keys and operators cannot give.
+    ///
+    /// Publishes `sgn_rem` for each removed session.  Works while the wallet
+    /// is frozen.
+    pub fn revoke_all_session_signers(env: Env) -> u32 {
+        env.current_contract_address().require_auth();
+
+        let mut index = Vec::new(&env);
+        let mut revoked = 0;
+        for credential_id in signer_index(&env).iter() {
+            let key = WalletDataKey::Signer(credential_id.clone());
+            if env.storage().temporary().has(&key) {
+                env.storage().temporary().remove(&key);
+                events::signer_removed(&env, &credential_id, SignerKind::Session);
+                audit::record(
+                    &env,
+                    AuditAction::RemoveSigner(credential_id.clone(), SignerKind::Session),
+                );
+                revoked += 1;
+            } else if env.storage().persistent().has(&key) {
+                index.push_back(credential_id);
+            }
+        }
         env.storage()
             .instance()
-            .set(&WalletDataKey::AdminSignerCount, &1u32);
+            .set(&WalletDataKey::SignerIndex, &index);
+        revoked
+    }
 
-        Ok(())
+    /// Replace signer `old_credential_id` with `new_credential_id` and
+    /// `new_public_key` in one step, so a wallet is never left with both
+    /// keys or neither.  Requires wallet self-auth.
+    ///
+    /// The signer keeps its kind, label, policy, allowlist and usage; its
+    /// place in `list_signers` is kept too.  The key must match the kind: a
+    /// 65-byte SEC-1 P-256 key for admins, a 32-byte Ed25519 key for
+    /// sessions.  Rotating an admin fails with `ApprovalRequired` while the
+    /// multisig threshold is above 1; propose an
+    /// `AdminOperation::RotateSigner` instead.  While the wallet has a
+    /// timelock delay it fails with `TimelockRequired`; queue a
+    /// `TimelockOperation::RotateSigner`.
+    pub fn rotate_signer(
+        env: Env,
+        old_credential_id: Bytes,
+        new_credential_id: Bytes,
+        new_public_key: Bytes,
+    ) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+
+        let signer = get_signer(&env, &old_credential_id)?;
+        if signer.kind == SignerKind::Admin {
+            if load_threshold(&env) > 1 {
+                return Err(WalletError::ApprovalRequired);
+            }
+            timelock::check_unlocked(&env)?;
+        }
+
+        rotate_signer(&env, &old_credential_id, &new_credential_id, new_public_key)
     }
 
     // ────────────────────────────────────────────────────────
-    //  Signer management (requires wallet self-auth)
+    //  Batched calls
     // ────────────────────────────────────────────────────────
 
-    /// Add a new admin signer (secp256r1 / P-256 passkey).
-    /// Requires wallet self-auth (`require_auth` → `__check_auth`).
-    pub fn add_signer(
+    /// Make each call in `invocations` from the wallet, in order, and return
+    /// their results.  The batch is atomic: if any call fails, all revert.
+    ///
+    /// Requires wallet self-auth, so one signature covers e.g. approve +
+    /// swap + transfer.  The wallet is the direct invoker of every call, so
+    /// `require_auth` on the wallet inside them needs no further signature.
+    /// A session key's contract allowlist and spending policy therefore
+    /// apply to the calls in the batch rather than to `execute` itself.
+    /// Calls back into the wallet fail with `NotAuthorized`.
+    pub fn execute(env: Env, invocations: Vec<Invocation>) -> Result<Vec<Val>, WalletError> {
+        env.current_contract_address().require_auth();
+
+        let results = invoke_all(&env, &invocations)?;
+        modules::after_execute(&env, &invocations, &results);
+        Ok(results)
+    }
+
+    /// Like `execute`, for a registered `relayer` that submits the
+    /// transaction and pays its network fee: once the calls have run, the
+    /// wallet pays `fee` of `fee_token` to `relayer`.
+    ///
+    /// Requires auth from both the relayer and the wallet.  The wallet's
+    /// signature covers the relayer, fee token and fee, so the relayer
+    /// cannot change them, and the fee transfer counts against session
+    /// policies and daily limits like the calls in the batch.  Fails with
+    /// `RelayerNotAllowed` for unregistered relayers, `RelayerRateLimited`
+    /// once the relayer has used up its daily rate limit, and `InvalidFee`
+    /// for a negative fee.
+    pub fn execute_sponsored(
         env: Env,
-        credential_id: Bytes,
-        public_key: BytesN<65>,
+        relayer: Address,
+        invocations: Vec<Invocation>,
+        fee_token: Address,
+        fee: i128,
+    ) -> Result<Vec<Val>, WalletError> {
+        relayer.require_auth();
+        env.current_contract_address().require_auth();
+        if !sponsor::relayers(&env).contains(&relayer) {
+            return Err(WalletError::RelayerNotAllowed);
+        }
+        if fee < 0 {
+            return Err(WalletError::InvalidFee);
+        }
+        sponsor::count_use(&env, &relayer)?;
+
+        let results = invoke_all(&env, &invocations)?;
+        modules::after_execute(&env, &invocations, &results);
+        if let Some(payment) = sponsor::fee_invocation(&env, &relayer, &fee_token, fee) {
+            invoke_all(&env, &vec![&env, payment])?;
+        }
+        events::sponsored(&env, &relayer, &fee_token, fee);
+        Ok(results)
+    }
+
+    /// Allow `relayer` to submit `execute_sponsored`.  Requires wallet
+    /// self-auth.
+    pub fn add_relayer(env: Env, relayer: Address) {
+        env.current_contract_address().require_auth();
+        sponsor::add_relayer(&env, &relayer);
+    }
+
+    /// Remove a relayer (`RelayerNotAllowed` if it is not one), along with
+    /// its rate limit.  Requires wallet self-auth.
+    pub fn remove_relayer(env: Env, relayer: Address) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+        sponsor::remove_relayer(&env, &relayer)
+    }
+
+    /// Addresses allowed to submit `execute_sponsored`
+    pub fn get_relayers(env: Env) -> Vec<Address> {
+        sponsor::relayers(&env)
+    }
+
+    /// Cap the `execute_sponsored` calls `relayer` may submit per UTC day,
+    /// or lift its cap with `None`.  Requires wallet self-auth.
+    ///
+    /// Fails with `RelayerNotAllowed` unless `relayer` is registered, and
+    /// with `InvalidPolicy` for a cap of 0 (remove the relayer instead).
+    pub fn set_relayer_rate_limit(
+        env: Env,
+        relayer: Address,
+        max_per_day: Option<u32>,
     ) -> Result<(), WalletError> {
         env.current_contract_address().require_auth();
-        validate_admin_public_key(&public_key)?;
+        if !sponsor::relayers(&env).contains(&relayer) {
+            return Err(WalletError::RelayerNotAllowed);
+        }
+        if max_per_day == Some(0) {
+            return Err(WalletError::InvalidPolicy);
+        }
+
+        sponsor::set_rate_limit(&env, &relayer, max_per_day);
+        events::relayer_rate_limit_set(&env, &relayer, max_per_day);
+        Ok(())
+    }
+
+    /// `relayer`'s daily rate limit, if it has one
+    pub fn get_relayer_rate_limit(env: Env, relayer: Address) -> Option<u32> {
+        sponsor::rate_limit(&env, &relayer)
+    }
+
+    /// Sponsored operations a rate-limited `relayer` has submitted today
+    pub fn get_relayer_usage(env: Env, relayer: Address) -> u32 {
+        sponsor::used_today(&env, &relayer)
+    }
+
+    // ────────────────────────────────────────────────────────
+    //  Gas tank
+    // ────────────────────────────────────────────────────────
+
+    /// Set up the gas tank executors are paid back from, replacing any
+    /// current one, or shut it with `None`.  Requires wallet self-auth.
+    ///
+    /// Each `run_task` call pays its executor `reimbursement` of `token`,
+    /// cut down to what is left of `budget` and of the executor's
+    /// `daily_cap`.  Fails with `InvalidPolicy` for negative amounts.
+    pub fn set_gas_tank(env: Env, tank: Option<GasTank>) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+        if tank
+            .as_ref()
+            .is_some_and(|tank| tank.budget < 0 || tank.reimbursement < 0 || tank.daily_cap < 0)
+        {
+            return Err(WalletError::InvalidPolicy);
+        }
+
+        gas_tank::set_tank(&env, tank.clone());
+        events::gas_tank_set(&env, &tank);
+        Ok(())
+    }
+
+    /// The gas tank, with what is left of its budget
+    pub fn get_gas_tank(env: Env) -> Option<GasTank> {
+        gas_tank::tank(&env)
+    }
+
+    /// Allow `executor` to `run_task` for a reimbursement.  Requires
+    /// wallet self-auth.
+    pub fn add_executor(env: Env, executor: Address) {
+        env.current_contract_address().require_auth();
+        gas_tank::add_executor(&env, &executor);
+    }
+
+    /// Remove an executor (`ExecutorNotAllowed` if it is not one).
+    /// Requires wallet self-auth.
+    pub fn remove_executor(env: Env, executor: Address) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+        gas_tank::remove_executor(&env, &executor)
+    }
+
+    /// Addresses allowed to `run_task`
+    pub fn get_executors(env: Env) -> Vec<Address> {
+        gas_tank::executors(&env)
+    }
+
+    /// What `executor` has been paid back today
+    pub fn get_executor_reimbursed(env: Env, executor: Address) -> i128 {
+        gas_tank::paid_today(&env, &executor)
+    }
+
+    /// Run `task` as the registered `executor`, then pay the executor back
+    /// from the gas tank.  Requires the executor's auth.
+    ///
+    /// The task runs exactly as if anyone had called it directly, with the
+    /// same checks and errors.  Fails with `ExecutorNotAllowed` for
+    /// unregistered executors; without a tank, or once its budget or the
+    /// executor's daily cap is used up, the task still runs unpaid.  The
+    /// reimbursement counts against the tank token's daily limit like any
+    /// other transfer, and fails the call with `DailyLimitExceeded` if it
+    /// would go over, leaving the task to be run directly.
+    pub fn run_task(env: Env, executor: Address, task: KeeperTask) -> Result<(), WalletError> {
+        executor.require_auth();
+        if !gas_tank::executors(&env).contains(&executor) {
+            return Err(WalletError::ExecutorNotAllowed);
+        }
+
+        match task {
+            KeeperTask::ExecuteIntent(intent_id) => Self::execute_intent(env.clone(), intent_id)?,
+            KeeperTask::ExecuteAction(action_id) => Self::execute_action(env.clone(), action_id)?,
+            KeeperTask::ExecuteProposal(proposal_id) => {
+                Self::execute_proposal(env.clone(), proposal_id)?
+            }
+            KeeperTask::FinalizeRecovery => Self::finalize_recovery(env.clone())?,
+        }
+        if let Some((token, amount)) = gas_tank::draw(&env, &executor) {
+            let payment = Vec::from_iter(
+                &env,
+                sponsor::fee_invocation(&env, &executor, &token, amount),
+            );
+            let calls = Vec::from_iter(&env, payment.iter().map(|call| intents::context(&call)));
+            limits::charge(&env, &calls)?;
+            invoke_all(&env, &payment)?;
+            events::executor_reimbursed(&env, &executor, &token, amount);
+        }
+        Ok(())
+    }
+
+    // ────────────────────────────────────────────────────────
+    //  Spending intents
+    // ────────────────────────────────────────────────────────
+
+    /// Approve a transfer of `amount` of `token` to `to` that anyone may
+    /// then make with `execute_intent` until the ledger timestamp passes
+    /// `deadline`, and return its intent ID.  Requires wallet self-auth
+    /// from an admin signer.
+    ///
+    /// Lets a relayer or scheduler make a payment later without a session
+    /// key being kept alive for it.  Fails with `InvalidIntent` unless
+    /// `amount` is positive and with `IntentExpired` for a past deadline.
+    pub fn create_intent(
+        env: Env,
+        token: Address,
+        to: Address,
+        amount: i128,
+        deadline: u64,
+    ) -> Result<u64, WalletError> {
+        env.current_contract_address().require_auth();
+        let intent = Intent {
+            token,
+            to,
+            amount,
+            deadline,
+        };
+        intents::create(&env, &intent)
+    }
+
+    /// Create several intents under one signature, e.g. a month of
+    /// scheduled payments, and return their IDs in order.  Requires wallet
+    /// self-auth from an admin signer, whose signature covers the whole
+    /// batch.
+    ///
+    /// Each intent is checked as in `create_intent`, and any failure
+    /// creates none.  Fails with `BatchTooLarge` past `MAX_BATCH_ITEMS`.
+    pub fn create_intents(env: Env, intents: Vec<Intent>) -> Result<Vec<u64>, WalletError> {
+        env.current_contract_address().require_auth();
+        if intents.len() > MAX_BATCH_ITEMS {
+            return Err(WalletError::BatchTooLarge);
+        }
+
+        let mut ids = Vec::new(&env);
+        for intent in intents.iter() {
+            ids.push_back(intents::create(&env, &intent)?);
+        }
+        Ok(ids)
+    }
+
+    /// Withdraw an intent before it runs.  Requires wallet self-auth from
+    /// an admin signer.
+    pub fn cancel_intent(env: Env, intent_id: u64) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+
+        intents::load(&env, intent_id)?;
+        intents::remove(&env, intent_id);
+        events::intent_cancelled(&env, intent_id);
+        Ok(())
+    }
+
+    /// Make the transfer an intent approved, once.
+    ///
+    /// Anyone may call this: creating the intent was the authorization.
+    /// The transfer still counts against the token's daily limit, and
+    /// `AfterExecute` modules are told about it like any `execute` batch.
+    /// Fails with `IntentExpired` past the deadline and `WalletFrozen`
+    /// while the wallet is frozen.
+    pub fn execute_intent(env: Env, intent_id: u64) -> Result<(), WalletError> {
+        let intent = intents::load(&env, intent_id)?;
+        if is_frozen(&env) {
+            return Err(WalletError::WalletFrozen);
+        }
+        if env.ledger().timestamp() > intent.deadline {
+            return Err(WalletError::IntentExpired);
+        }
+
+        let transfer = intents::transfer(&env, &intent);
+        limits::charge(&env, &vec![&env, intents::context(&transfer)])?;
+        intents::remove(&env, intent_id);
+        let invocations = vec![&env, transfer];
+        let results = invoke_all(&env, &invocations)?;
+        modules::after_execute(&env, &invocations, &results);
+        events::intent_executed(&env, intent_id);
+        Ok(())
+    }
+
+    /// A pending intent
+    pub fn get_intent(env: Env, intent_id: u64) -> Result<Intent, WalletError> {
+        intents::load(&env, intent_id)
+    }
+
+    // ────────────────────────────────────────────────────────
+    //  Modules
+    // ────────────────────────────────────────────────────────
+
+    /// Call `module` at `hook` from now on, after any modules already
+    /// installed there.  Requires wallet self-auth.
+    ///
+    /// A `BeforeAuth` module can reject authorizations of calls on other
+    /// contracts, e.g. to enforce extra limits or allowlists; an
+    /// `AfterExecute` module is told about every batch the wallet runs,
+    /// e.g. for accounting or alerts.  Installing an installed module does
+    /// nothing.  Fails with `TooManyModules` past `MAX_MODULES` per hook.
+    pub fn install_module(env: Env, module: Address, hook: ModuleHook) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+        modules::install(&env, &module, &hook)
+    }
+
+    /// Stop calling `module` at `hook` (`ModuleNotInstalled` if it is not
+    /// installed there).  Requires wallet self-auth.
+    pub fn uninstall_module(
+        env: Env,
+        module: Address,
+        hook: ModuleHook,
+    ) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+        modules::uninstall(&env, &module, &hook)
+    }
+
+    /// Modules installed at `hook`, in call order
+    pub fn get_modules(env: Env, hook: ModuleHook) -> Vec<Address> {
+        modules::modules(&env, &hook)
+    }
+
+    // ────────────────────────────────────────────────────────
+    //  Sub-accounts
+    // ────────────────────────────────────────────────────────
+
+    /// Create an empty sub-account called `name`.  Requires wallet
+    /// self-auth.
+    ///
+    /// Operators and session signers assigned to it with
+    /// `assign_sub_account` can only move tokens it has a budget for, and
+    /// each SEP-41 call they authorize is taken off that budget.  Fails
+    /// with `SubAccountExists` for a taken name and `TooManySubAccounts`
+    /// past `MAX_SUB_ACCOUNTS`.
+    pub fn create_sub_account(env: Env, name: Symbol) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+        subaccounts::create(&env, &name)
+    }
+
+    /// Delete a sub-account and its budgets.  Requires wallet self-auth.
+    ///
+    /// Signers still assigned to it fail `__check_auth` with
+    /// `SubAccountNotFound` until reassigned.
+    pub fn remove_sub_account(env: Env, name: Symbol) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+        subaccounts::remove(&env, &name)
+    }
+
+    /// Set what sub-account `name` may still move of `token`, replacing
+    /// its current budget; 0 removes it.  Requires wallet self-auth.
+    pub fn set_sub_account_budget(
+        env: Env,
+        name: Symbol,
+        token: Address,
+        amount: i128,
+    ) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+        subaccounts::set_budget(&env, &name, &token, amount)
+    }
+
+    /// The wallet's sub-accounts, in creation order
+    pub fn get_sub_accounts(env: Env) -> Vec<Symbol> {
+        subaccounts::names(&env)
+    }
+
+    /// Token budgets left in sub-account `name`
+    pub fn get_sub_account_budgets(
+        env: Env,
+        name: Symbol,
+    ) -> Result<Map<Address, i128>, WalletError> {
+        subaccounts::budgets(&env, &name)
+    }
+
+    // ────────────────────────────────────────────────────────
+    //  Daily spending limits
+    // ────────────────────────────────────────────────────────
+
+    /// Cap what SEP-41 calls on `token` (`transfer`, `transfer_from`,
+    /// `approve`, `burn`, `burn_from`) may move per UTC day, or lift the cap
+    /// with `None`.  Requires wallet self-auth.
+    ///
+    /// The cap covers every call `__check_auth` authorizes, whether an admin
+    /// passkey or a session key signed it, including calls inside `execute`.
+    /// Past it, calls fail with `DailyLimitExceeded`; spending more needs an
+    /// `AdminOperation::Invoke` proposal approved by a second admin.
+    ///
+    /// Setting or lowering a cap takes effect at once.  Raising or lifting
+    /// one fails with `ApprovalRequired`; propose
+    /// `AdminOperation::SetDailyLimit` instead.
+    pub fn set_daily_limit(
+        env: Env,
+        token: Address,
+        limit: Option<i128>,
+    ) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+        let loosens = match (limits::daily_limit(&env, &token), limit) {
+            (Some(_), None) => true,
+            (Some(current), Some(limit)) => limit > current,
+            (None, _) => false,
+        };
+        if loosens {
+            return Err(WalletError::ApprovalRequired);
+        }
+
+        apply_daily_limit(&env, &token, limit)
+    }
+
+    /// `token`'s daily spending limit, if it has one
+    pub fn get_daily_limit(env: Env, token: Address) -> Option<i128> {
+        limits::daily_limit(&env, &token)
+    }
+
+    /// What `token` calls have moved so far today
+    pub fn get_daily_spent(env: Env, token: Address) -> i128 {
+        limits::spent_today(&env, &token)
+    }
+
+    // ────────────────────────────────────────────────────────
+    //  Multisig admin operations
+    // ────────────────────────────────────────────────────────
+
+    /// Set the number of admin approvals sensitive operations need.
+    /// Requires wallet self-auth; once the threshold is above 1 it can only
+    /// be changed through an `AdminOperation::SetThreshold` proposal.
+    pub fn set_threshold(env: Env, threshold: u32) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+        if load_threshold(&env) > 1 {
+            return Err(WalletError::ApprovalRequired);
+        }
+
+        store_threshold(&env, threshold)
+    }
+
+    /// Admin approvals required for sensitive operations
+    pub fn get_threshold(env: Env) -> u32 {
+        load_threshold(&env)
+    }
+
+    /// Set how many distinct passkeys must sign each WebAuthn
+    /// authorization, e.g. 2 so every transfer needs two devices.
+    /// Requires wallet self-auth, which the current auth threshold applies
+    /// to.
+    ///
+    /// Must be between 1 and the number of admin signers.  Operators count
+    /// towards it when they co-sign, but only for calls they may make
+    /// themselves.  Session keys are not affected.  While the multisig
+    /// threshold is above 1 it fails with `ApprovalRequired`; propose an
+    /// `AdminOperation::SetAuthThreshold` instead.
+    pub fn set_auth_threshold(env: Env, threshold: u32) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+        if load_threshold(&env) > 1 {
+            return Err(WalletError::ApprovalRequired);
+        }
+
+        store_auth_threshold(&env, threshold)
+    }
+
+    /// Passkeys that must sign each WebAuthn authorization
+    pub fn get_auth_threshold(env: Env) -> u32 {
+        load_auth_threshold(&env)
+    }
+
+    /// Propose an admin operation and return its ID. Requires wallet
+    /// self-auth; the proposal then collects approvals through `approve`.
+    pub fn propose(env: Env, operation: AdminOperation) -> u64 {
+        env.current_contract_address().require_auth();
+
+        let id: u64 = env
+            .storage()
+            .instance()
+            .get(&WalletDataKey::NextProposalId)
+            .unwrap_or(1);
+        env.storage()
+            .instance()
+            .set(&WalletDataKey::NextProposalId, &(id + 1));
+
+        let proposal = Proposal {
+            operation,
+            approvals: Vec::new(&env),
+            created_ledger: env.ledger().sequence(),
+        };
+        save_proposal(&env, id, &proposal);
+        events::proposed(&env, id, &proposal.operation);
+        id
+    }
+
+    /// Approve a proposal as the admin signer `credential_id`.
+    ///
+    /// Requires wallet self-auth signed by that admin's own passkey:
+    /// `__check_auth` rejects an `approve` call authorized by any other
+    /// credential, so each admin can only approve for themselves.
+    pub fn approve(env: Env, proposal_id: u64, credential_id: Bytes) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+
+        let mut proposal = load_proposal(&env, proposal_id)?;
+        if get_signer(&env, &credential_id)?.kind != SignerKind::Admin {
+            return Err(WalletError::NotAuthorized);
+        }
+        if proposal.approvals.contains(&credential_id) {
+            return Err(WalletError::AlreadyApproved);
+        }
+
+        proposal.approvals.push_back(credential_id.clone());
+        save_proposal(&env, proposal_id, &proposal);
+        events::approved(&env, proposal_id, &credential_id);
+        Ok(())
+    }
+
+    /// Execute a proposal approved by at least `threshold` current admins.
+    ///
+    /// Anyone may call this: the approvals are the authorization.  Approvals
+    /// from admins removed since they approved no longer count.  An
+    /// `Invoke` proposal needs at least two approvals whatever the
+    /// threshold, since its calls bypass the daily spending limits, and the
+    /// fast-track operations need two thirds of all admins, and at least
+    /// two, since they skip a delay meant to let admins react.
+    pub fn execute_proposal(env: Env, proposal_id: u64) -> Result<(), WalletError> {
+        let proposal = load_proposal(&env, proposal_id)?;
+        if is_frozen(&env) && proposal.operation != AdminOperation::Unfreeze {
+            return Err(WalletError::WalletFrozen);
+        }
+        let approvals = proposal
+            .approvals
+            .iter()
+            .filter(|id| get_signer(&env, id).is_ok_and(|s| s.kind == SignerKind::Admin))
+            .count() as u32;
+        let required = match proposal.operation {
+            AdminOperation::Invoke(_) | AdminOperation::SetDailyLimit(..) => {
+                load_threshold(&env).max(2)
+            }
+            AdminOperation::FastTrackAction(_) | AdminOperation::FastTrackRecovery => {
+                load_threshold(&env).max(supermajority(&env)).max(2)
+            }
+            _ => load_threshold(&env),
+        };
+        if approvals < required {
+            return Err(WalletError::InsufficientApprovals);
+        }
+        audit::set_actor(&env, &AuditActor::Approvals(proposal.approvals.clone()));
+
+        match proposal.operation {
+            AdminOperation::AddSigner(credential_id, public_key) => {
+                store_passkey_signer(&env, &credential_id, public_key, SignerKind::Admin)?
+            }
+            AdminOperation::RemoveSigner(credential_id) => delete_signer(&env, &credential_id)?,
+            AdminOperation::RotateSigner(old_credential_id, new_credential_id, public_key) => {
+                rotate_signer(
+                    &env,
+                    &old_credential_id,
+                    &new_credential_id,
+                    public_key.into(),
+                )?
+            }
+            AdminOperation::SetThreshold(threshold) => store_threshold(&env, threshold)?,
+            AdminOperation::SetAuthThreshold(threshold) => store_auth_threshold(&env, threshold)?,
+            AdminOperation::Upgrade(new_wasm_hash) => upgrade_wasm(&env, new_wasm_hash),
+            AdminOperation::Invoke(invocations) => {
+                let results = invoke_all(&env, &invocations)?;
+                modules::after_execute(&env, &invocations, &results);
+            }
+            AdminOperation::Unfreeze => {
+                if !is_frozen(&env) {
+                    return Err(WalletError::NotFrozen);
+                }
+                set_frozen_until(&env, env.ledger().sequence());
+                events::unfreeze_scheduled(&env, env.ledger().sequence());
+                audit::record(&env, AuditAction::Unfreeze);
+            }
+            AdminOperation::FastTrackAction(action_id) => {
+                let action = timelock::load(&env, action_id)?;
+                run_action(&env, action_id, action.operation)?
+            }
+            AdminOperation::FastTrackRecovery => {
+                let request = recovery::load_request(&env)?;
+                if request.executable_ledger == 0 {
+                    return Err(WalletError::RecoveryNotReady);
+                }
+                complete_recovery(&env, request)?
+            }
+            AdminOperation::AddGuardian(guardian) => recovery::add_guardian(&env, &guardian),
+            AdminOperation::RemoveGuardian(guardian) => recovery::remove_guardian(&env, &guardian)?,
+            AdminOperation::SetRecoveryConfig(threshold, delay_ledgers) => {
+                recovery::configure(&env, threshold, delay_ledgers)?
+            }
+            AdminOperation::SetDailyLimit(token, limit) => apply_daily_limit(&env, &token, limit)?,
+        }
+
+        env.storage()
+            .persistent()
+            .remove(&WalletDataKey::Proposal(proposal_id));
+        events::executed(&env, proposal_id);
+        Ok(())
+    }
+
+    /// A pending proposal
+    pub fn get_proposal(env: Env, proposal_id: u64) -> Result<Proposal, WalletError> {
+        load_proposal(&env, proposal_id)
+    }
+
+    // ────────────────────────────────────────────────────────
+    //  Timelocked actions
+    // ────────────────────────────────────────────────────────
+
+    /// Set how many ledgers a queued action waits before it can run.
+    /// Requires wallet self-auth.
+    ///
+    /// At 0, the default, adding admins, rotating them and changing guardians
+    /// or the recovery config take effect directly.  Above 0 those calls
+    /// fail with `TimelockRequired` and must go through `queue_action`, as
+    /// must any later change to the delay itself.
+    pub fn set_timelock_delay(env: Env, delay_ledgers: u32) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+        timelock::check_unlocked(&env)?;
+
+        timelock::set_delay(&env, delay_ledgers);
+        events::timelock_delay_set(&env, delay_ledgers);
+        Ok(())
+    }
+
+    /// Ledgers a queued action waits before it can run
+    pub fn get_timelock_delay(env: Env) -> u32 {
+        timelock::delay(&env)
+    }
+
+    /// Schedule `operation` to run once the timelock delay has passed, and
+    /// return its action ID.  Requires wallet self-auth.
+    ///
+    /// Until then any admin signer can `cancel_action`, so a passkey that is
+    /// compromised for a moment cannot add an admin or take over recovery
+    /// unnoticed.  Adding or rotating an admin and changing guardians or the
+    /// recovery config fail with `ApprovalRequired` while the multisig
+    /// threshold is above 1; those go through `propose` instead.
+    pub fn queue_action(env: Env, operation: TimelockOperation) -> Result<u64, WalletError> {
+        env.current_contract_address().require_auth();
+        queue_timelocked(&env, operation)
+    }
+
+    /// Queue several operations under one signature, so bulk changes need
+    /// a single passkey prompt, and return their action IDs in order.
+    /// Requires wallet self-auth, whose signature covers the whole batch.
+    ///
+    /// Each operation is checked as in `queue_action`, and any failure
+    /// queues none.  Fails with `BatchTooLarge` past `MAX_BATCH_ITEMS`.
+    pub fn queue_actions(
+        env: Env,
+        operations: Vec<TimelockOperation>,
+    ) -> Result<Vec<u64>, WalletError> {
+        env.current_contract_address().require_auth();
+        if operations.len() > MAX_BATCH_ITEMS {
+            return Err(WalletError::BatchTooLarge);
+        }
+
+        let mut ids = Vec::new(&env);
+        for operation in operations.iter() {
+            ids.push_back(queue_timelocked(&env, operation)?);
+        }
+        Ok(ids)
+    }
+
+    /// Cancel a queued action. Requires wallet self-auth from an admin
+    /// signer; `__check_auth` rejects it from operators and session keys.
+    pub fn cancel_action(env: Env, action_id: u64) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+
+        timelock::load(&env, action_id)?;
+        timelock::remove(&env, action_id);
+        events::action_cancelled(&env, action_id);
+        Ok(())
+    }
+
+    /// Run a queued action whose delay has passed.
+    ///
+    /// Anyone may call this: queuing it was the authorization.  Fails with
+    /// `ActionNotReady` before its `executable_ledger`.
+    pub fn execute_action(env: Env, action_id: u64) -> Result<(), WalletError> {
+        let action = timelock::load(&env, action_id)?;
+        if is_frozen(&env) {
+            return Err(WalletError::WalletFrozen);
+        }
+        if env.ledger().sequence() < action.executable_ledger {
+            return Err(WalletError::ActionNotReady);
+        }
+        audit::set_actor(&env, &AuditActor::QueuedAction(action_id));
+
+        run_action(&env, action_id, action.operation)
+    }
+
+    /// A queued action
+    pub fn get_action(env: Env, action_id: u64) -> Result<QueuedAction, WalletError> {
+        timelock::load(&env, action_id)
+    }
+
+    // ────────────────────────────────────────────────────────
+    //  Freeze
+    // ────────────────────────────────────────────────────────
+
+    /// Freeze the wallet at once, e.g. after a device is stolen.  Requires
+    /// wallet self-auth from an admin signer.
+    ///
+    /// While frozen, `__check_auth` only accepts admin passkeys and only for
+    /// `freeze`, `unfreeze`, `propose`, `approve`, `veto_recovery` and
+    /// `cancel_action`; queued actions and proposals other than `Unfreeze`
+    /// cannot run.  Guardian recovery keeps working.  Freezing again cancels
+    /// a scheduled unfreeze.
+    pub fn freeze(env: Env) {
+        env.current_contract_address().require_auth();
+
+        set_frozen_until(&env, u32::MAX);
+        events::frozen(&env);
+        audit::record(&env, AuditAction::Freeze);
+    }
+
+    /// Lift the freeze. Requires wallet self-auth from an admin signer.
+    ///
+    /// With a single-admin threshold the freeze ends `UNFREEZE_DELAY`
+    /// ledgers (~1 day) from now, leaving the owner time to `freeze` again if
+    /// the unfreeze was not theirs.  Above that it fails with
+    /// `ApprovalRequired`; an `AdminOperation::Unfreeze` proposal lifts the
+    /// freeze immediately.
+    pub fn unfreeze(env: Env) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+        if !is_frozen(&env) {
+            return Err(WalletError::NotFrozen);
+        }
+        if load_threshold(&env) > 1 {
+            return Err(WalletError::ApprovalRequired);
+        }
+
+        let frozen_until = env.ledger().sequence() + UNFREEZE_DELAY;
+        set_frozen_until(&env, frozen_until);
+        events::unfreeze_scheduled(&env, frozen_until);
+        audit::record(&env, AuditAction::Unfreeze);
+        Ok(())
+    }
+
+    /// Whether the wallet is frozen
+    pub fn is_frozen(env: Env) -> bool {
+        is_frozen(&env)
+    }
+
+    // ────────────────────────────────────────────────────────
+    //  WebAuthn checks
+    // ────────────────────────────────────────────────────────
+
+    /// Allow admin passkey assertions from `origin` (e.g.
+    /// `https://wallet.galaxy.dev`, exactly as browsers put it in
+    /// `clientDataJSON`).  Requires wallet self-auth; adding an allowed origin
+    /// is a no-op.
+    ///
+    /// While the allowlist is empty any origin is accepted.  Once it has
+    /// entries, `__check_auth` rejects assertions from other origins with
+    /// `OriginNotAllowed`, as a second line of defence against a phishing
+    /// site relaying a credential's assertions.
+    pub fn add_origin(env: Env, origin: Bytes) {
+        env.current_contract_address().require_auth();
+
+        let mut origins = allowed_origins(&env);
+        if !origins.contains(&origin) {
+            origins.push_back(origin.clone());
+            env.storage()
+                .instance()
+                .set(&WalletDataKey::AllowedOrigins, &origins);
+            events::origin_added(&env, &origin);
+        }
+    }
+
+    /// Remove an allowed origin. Requires wallet self-auth.
+    ///
+    /// Removing the last one lifts the origin check altogether.
+    pub fn remove_origin(env: Env, origin: Bytes) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+
+        let mut origins = allowed_origins(&env);
+        let index = origins
+            .first_index_of(&origin)
+            .ok_or(WalletError::OriginNotFound)?;
+        origins.remove(index);
+        env.storage()
+            .instance()
+            .set(&WalletDataKey::AllowedOrigins, &origins);
+        events::origin_removed(&env, &origin);
+        Ok(())
+    }
+
+    /// Origins admin passkey assertions may come from; empty for any
+    pub fn get_origins(env: Env) -> Vec<Bytes> {
+        allowed_origins(&env)
+    }
+
+    /// Accept passkey assertions scoped to the relying party whose RP ID
+    /// (e.g. `wallet.galaxy.dev`) hashes to `rp_id_hash`.  Requires wallet
+    /// self-auth; adding an accepted hash is a no-op.
+    ///
+    /// While the list is empty any RP ID is accepted.  Once it has entries,
+    /// `__check_auth` and `add_signer_with_attestation` reject authenticator
+    /// data whose first 32 bytes are another hash with `RpIdMismatch`.  To
+    /// move to a new domain, add its hash before removing the old one.
+    pub fn add_rp_id_hash(env: Env, rp_id_hash: BytesN<32>) {
+        env.current_contract_address().require_auth();
+
+        let mut hashes = allowed_rp_id_hashes(&env);
+        if !hashes.contains(&rp_id_hash) {
+            hashes.push_back(rp_id_hash.clone());
+            env.storage()
+                .instance()
+                .set(&WalletDataKey::AllowedRpIdHashes, &hashes);
+            events::rp_id_hash_added(&env, &rp_id_hash);
+        }
+    }
+
+    /// Stop accepting an RP ID hash. Requires wallet self-auth.
+    ///
+    /// Removing the last one lifts the RP ID check altogether.
+    pub fn remove_rp_id_hash(env: Env, rp_id_hash: BytesN<32>) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+
+        let mut hashes = allowed_rp_id_hashes(&env);
+        let index = hashes
+            .first_index_of(&rp_id_hash)
+            .ok_or(WalletError::RpIdHashNotFound)?;
+        hashes.remove(index);
+        env.storage()
+            .instance()
+            .set(&WalletDataKey::AllowedRpIdHashes, &hashes);
+        events::rp_id_hash_removed(&env, &rp_id_hash);
+        Ok(())
+    }
+
+    /// RP ID hashes passkey assertions may be scoped to; empty for any
+    pub fn get_rp_id_hashes(env: Env) -> Vec<BytesN<32>> {
+        allowed_rp_id_hashes(&env)
+    }
+
+    /// Choose whether admin passkey assertions must carry the User Verified
+    /// flag (biometric or PIN) on top of User Present, which is always
+    /// required.  Requires wallet self-auth.
+    pub fn set_user_verification(env: Env, required: bool) {
+        env.current_contract_address().require_auth();
+
+        env.storage()
+            .instance()
+            .set(&WalletDataKey::RequireUserVerification, &required);
+        events::user_verification_set(&env, required);
+    }
+
+    /// Whether admin passkey assertions must be user-verified
+    pub fn get_user_verification(env: Env) -> bool {
+        requires_user_verification(&env)
+    }
+
+    /// Require new admin signers to come with a verified attestation:
+    /// `add_signer` then fails with `AttestationRequired`, leaving
+    /// `add_signer_with_attestation`.  Requires wallet self-auth.
+    ///
+    /// Proposals, queued actions and guardian recovery still take a bare
+    /// public key.
+    pub fn set_attestation_required(env: Env, required: bool) {
+        env.current_contract_address().require_auth();
+
+        env.storage()
+            .instance()
+            .set(&WalletDataKey::RequireAttestation, &required);
+        events::attestation_required_set(&env, required);
+    }
+
+    /// Whether `add_signer` requires attestation
+    pub fn get_attestation_required(env: Env) -> bool {
+        requires_attestation(&env)
+    }
+
+    /// Assert that `signature` is a WebAuthn assertion by one of the
+    /// wallet's admin or operator passkeys over `message`, for passkey
+    /// sign-in without a transaction.
+    ///
+    /// The challenge must be the SEP-53 hash
+    /// `SHA-256("Stellar Signed Message:\n" ‖ message)`, and the assertion
+    /// passes the same origin, RP ID and flag checks as `__check_auth`.  One
+    /// passkey is enough whatever the auth threshold.  Unknown credentials
+    /// fail with `SignerNotFound`, session keys with `NotAuthorized` and a
+    /// frozen wallet with `WalletFrozen`.  The host has no non-trapping
+    /// P-256 check, so a well-formed signature that does not match aborts
+    /// the call; any failure is a failed sign-in.
+    pub fn verify_message(
+        env: Env,
+        message: Bytes,
+        signature: Signature,
+    ) -> Result<(), WalletError> {
+        let signer = get_signer(&env, &signature.id)?;
+        if signer.kind == SignerKind::Session {
+            return Err(WalletError::NotAuthorized);
+        }
+        if is_frozen(&env) {
+            return Err(WalletError::WalletFrozen);
+        }
+
+        let mut prefixed = Bytes::from_slice(&env, SIGNED_MESSAGE_PREFIX);
+        prefixed.append(&message);
+        let message_hash = env.crypto().sha256(&prefixed);
+        verify_assertion(&env, &message_hash, &signature, &signer.public_key)
+    }
+
+    // ────────────────────────────────────────────────────────
+    //  Upgrades
+    // ────────────────────────────────────────────────────────
+
+    /// Replace the wallet's code with `new_wasm_hash`, which must already be
+    /// uploaded.  Call `migrate` afterwards so the new code can update
+    /// storage.
+    ///
+    /// Requires wallet self-auth from an admin passkey (`__check_auth`
+    /// rejects an upgrade signed by a session key).  While the multisig
+    /// threshold is above 1 this fails with `ApprovalRequired`; propose an
+    /// `AdminOperation::Upgrade` instead.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+        if load_threshold(&env) > 1 {
+            return Err(WalletError::ApprovalRequired);
+        }
+
+        upgrade_wasm(&env, new_wasm_hash);
+        Ok(())
+    }
+
+    /// Bring storage written by an older wallet version up to this code's
+    /// `WALLET_VERSION`.  Anyone may call it; it is a no-op once migrated.
+    ///
+    /// Wallets deployed before versioning have no stored version and
+    /// migrate from 0.  Signers are rewritten in the current layout, so an
+    /// upgraded wallet cannot authorize anything until this has run.
+    ///
+    /// `credential_ids` names signers missing from the signer index; a
+    /// wallet deployed before the index existed must name all of its
+    /// signers, and fails with `SignerNotFound` if an admin is left out.
+    /// Session keys it leaves out stay unusable until they expire.
+    pub fn migrate(env: Env, credential_ids: Vec<Bytes>) -> Result<(), WalletError> {
+        let from = stored_version(&env);
+        if from >= WALLET_VERSION {
+            return Ok(());
+        }
+
+        // Version 1 only introduced the version entry itself; each later
+        // version adds its storage changes here as `if from < N { .. }`.
+        if from < 4 {
+            migration::signer_fields(&env, &credential_ids)?;
+        }
+        env.storage()
+            .instance()
+            .set(&WalletDataKey::Version, &WALLET_VERSION);
+        events::migrated(&env, from, WALLET_VERSION);
+        Ok(())
+    }
+
+    /// Code version the wallet's storage was last migrated to
+    pub fn version(env: Env) -> u32 {
+        stored_version(&env)
+    }
+
+    // ────────────────────────────────────────────────────────
+    //  Guardian recovery
+    // ────────────────────────────────────────────────────────
+
+    /// Designate `guardian` as able to propose and approve a recovery.
+    /// Requires wallet self-auth; adding an existing guardian is a no-op.
+    ///
+    /// Any address can be a guardian, including a classic `G...` account
+    /// approving with its ed25519 key.
+    ///
+    /// Like the other recovery settings, fails with `ApprovalRequired` while
+    /// the multisig threshold is above 1, since a guardian can recover the
+    /// wallet to a single admin; propose an `AdminOperation::AddGuardian`
+    /// instead.  Fails with `TimelockRequired` while the wallet has a
+    /// timelock delay.
+    pub fn add_guardian(env: Env, guardian: Address) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+        if load_threshold(&env) > 1 {
+            return Err(WalletError::ApprovalRequired);
+        }
+        timelock::check_unlocked(&env)?;
+
+        recovery::add_guardian(&env, &guardian);
+        Ok(())
+    }
+
+    /// Remove a guardian. Requires wallet self-auth.
+    ///
+    /// Fails with `InvalidThreshold` if fewer guardians than the recovery
+    /// threshold would remain (removing the last one is allowed).  Approvals
+    /// the guardian already gave no longer count.  Needs an
+    /// `AdminOperation::RemoveGuardian` proposal while the multisig
+    /// threshold is above 1.
+    pub fn remove_guardian(env: Env, guardian: Address) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+        if load_threshold(&env) > 1 {
+            return Err(WalletError::ApprovalRequired);
+        }
+        timelock::check_unlocked(&env)?;
+
+        recovery::remove_guardian(&env, &guardian)
+    }
+
+    /// The wallet's guardians, in the order they were added.
+    pub fn get_guardians(env: Env) -> Vec<Address> {
+        recovery::guardians(&env)
+    }
+
+    /// Set how many guardians must approve a recovery and how many ledgers
+    /// it then waits before `finalize_recovery`.  Requires wallet self-auth.
+    ///
+    /// `threshold` must be between 1 and the number of guardians, and the
+    /// delay at least `RECOVERY_DELAY` (~1 day).  A pending recovery keeps the delay it was
+    /// scheduled with.  Needs an `AdminOperation::SetRecoveryConfig`
+    /// proposal while the multisig threshold is above 1.
+    pub fn set_recovery_config(
+        env: Env,
+        threshold: u32,
+        delay_ledgers: u32,
+    ) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+        if load_threshold(&env) > 1 {
+            return Err(WalletError::ApprovalRequired);
+        }
+        timelock::check_unlocked(&env)?;
+
+        recovery::configure(&env, threshold, delay_ledgers)
+    }
+
+    /// The recovery threshold and delay
+    pub fn get_recovery_config(env: Env) -> RecoveryConfig {
+        recovery::config(&env)
+    }
+
+    /// Propose `public_key` as a new admin signer after losing the existing
+    /// passkeys.  Requires `guardian`'s auth and counts as its approval.
+    ///
+    /// Once `threshold` guardians have approved, the recovery waits
+    /// `delay_ledgers` before `finalize_recovery` can add the signer, and
+    /// any admin signer can `veto_recovery` until then.  One recovery can be
+    /// pending at a time.
+    pub fn propose_recovery(
+        env: Env,
+        guardian: Address,
+        credential_id: Bytes,
+        public_key: BytesN<65>,
+    ) -> Result<(), WalletError> {
+        guardian.require_auth();
+
+        if !recovery::guardians(&env).contains(&guardian) {
+            return Err(WalletError::NotGuardian);
+        }
+        if recovery::load_request(&env).is_ok() {
+            return Err(WalletError::RecoveryPending);
+        }
+        validate_credential_id(&credential_id)?;
+        validate_admin_public_key(&public_key)?;
+
+        let mut request = RecoveryRequest {
+            credential_id,
+            public_key,
+            approvals: Vec::new(&env),
+            executable_ledger: 0,
+        };
+        recovery::add_approval(&env, &mut request, &guardian);
+        recovery::save_request(&env, &request);
+        events::recovery_proposed(&env, &guardian, &request);
+        Ok(())
+    }
+
+    /// Approve the pending recovery as `guardian`. Requires its auth.
+    pub fn approve_recovery(env: Env, guardian: Address) -> Result<(), WalletError> {
+        guardian.require_auth();
+
+        if !recovery::guardians(&env).contains(&guardian) {
+            return Err(WalletError::NotGuardian);
+        }
+        let mut request = recovery::load_request(&env)?;
+        if request.approvals.contains(&guardian) {
+            return Err(WalletError::AlreadyApproved);
+        }
+
+        recovery::add_approval(&env, &mut request, &guardian);
+        recovery::save_request(&env, &request);
+        events::recovery_approved(&env, &guardian, &request);
+        Ok(())
+    }
+
+    /// Cancel the pending recovery.
+    ///
+    /// Requires wallet self-auth from an admin passkey: `__check_auth`
+    /// rejects a veto signed by a session key, so a leaked session key
+    /// cannot hold off the owner's recovery.
+    pub fn veto_recovery(env: Env) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+
+        let request = recovery::load_request(&env)?;
+        recovery::clear_request(&env);
+        events::recovery_vetoed(&env, &request.credential_id);
+        Ok(())
+    }
+
+    /// Add the recovered admin signer once the delay has passed, and reset
+    /// the auth and multisig thresholds to 1 so the recovered passkey can
+    /// sign and approve admin operations alone.
+    ///
+    /// Anyone may call this.  Only approvals from current guardians count, so
+    /// removing guardians can drop a recovery back below the threshold
+    /// (`InsufficientApprovals`).
+    pub fn finalize_recovery(env: Env) -> Result<(), WalletError> {
+        let request = recovery::load_request(&env)?;
+        if request.executable_ledger == 0 || env.ledger().sequence() < request.executable_ledger {
+            return Err(WalletError::RecoveryNotReady);
+        }
+        if recovery::current_approvals(&env, &request) < recovery::config(&env).threshold {
+            return Err(WalletError::InsufficientApprovals);
+        }
+        audit::set_actor(&env, &AuditActor::Guardians(request.approvals.clone()));
+
+        complete_recovery(&env, request)
+    }
+
+    /// The pending recovery request
+    pub fn get_recovery(env: Env) -> Result<RecoveryRequest, WalletError> {
+        recovery::load_request(&env)
+    }
+
+    // ────────────────────────────────────────────────────────
+    //  Profile
+    // ────────────────────────────────────────────────────────
+
+    /// Replace the wallet's profile, the display name, avatar hash and
+    /// preferred currency frontends show for it.  Requires wallet
+    /// self-auth.
+    ///
+    /// Fails with `InvalidLabel` for a display name over 64 bytes.
+    pub fn set_profile(env: Env, profile: WalletProfile) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+        if profile.display_name.len() > MAX_LABEL_LEN {
+            return Err(WalletError::InvalidLabel);
+        }
+
+        env.storage()
+            .instance()
+            .set(&WalletDataKey::Profile, &profile);
+        events::profile_set(&env, &profile);
+        Ok(())
+    }
+
+    /// The wallet's profile, empty until `set_profile` is called
+    pub fn get_profile(env: Env) -> WalletProfile {
+        env.storage()
+            .instance()
+            .get(&WalletDataKey::Profile)
+            .unwrap_or_else(|| WalletProfile {
+                display_name: String::from_str(&env, ""),
+                avatar_hash: None,
+                currency: None,
+            })
+    }
+
+    // ────────────────────────────────────────────────────────
+    //  Queries
+    // ────────────────────────────────────────────────────────
+
+    /// Give a signer a human-readable label such as "iPhone passkey" (at
+    /// most 64 bytes). Requires wallet self-auth.
+    pub fn set_signer_label(
+        env: Env,
+        credential_id: Bytes,
+        label: String,
+    ) -> Result<(), WalletError> {
+        env.current_contract_address().require_auth();
+        if label.len() > MAX_LABEL_LEN {
+            return Err(WalletError::InvalidLabel);
+        }
+
+        let mut signer = get_signer(&env, &credential_id)?;
+        signer.label = label;
+        put_signer(&env, &credential_id, &signer);
+        Ok(())
+    }
+
+    /// A live signer, including its label and when it was added and last
+    /// used, for wallet device lists.
+    pub fn get_signer(env: Env, credential_id: Bytes) -> Result<Signer, WalletError> {
+        get_signer(&env, &credential_id)
+    }
+
+    /// List live signers in registration order, skipping `offset` of them and
+    /// returning at most `limit` (capped at `MAX_SIGNER_PAGE`).
+    ///
+    /// Expired session signers are left out.
+    pub fn list_signers(env: Env, offset: u32, limit: u32) -> Vec<SignerInfo> {
+        let limit = limit.min(MAX_SIGNER_PAGE);
+        let mut skipped = 0u32;
+        let mut signers = Vec::new(&env);
+        for credential_id in signer_index(&env).iter() {
+            if signers.len() >= limit {
+                break;
+            }
+            let Ok(signer) = get_signer(&env, &credential_id) else {
+                continue;
+            };
+            if skipped < offset {
+                skipped += 1;
+                continue;
+            }
+            signers.push_back(SignerInfo {
+                credential_id,
+                kind: signer.kind,
+                public_key: signer.public_key,
+            });
+        }
+        signers
+    }
+
+    /// Up to `limit` audit log entries (capped at `MAX_AUDIT_PAGE`), oldest
+    /// first, starting at entry `start`.
+    ///
+    /// The log keeps the latest `MAX_AUDIT_ENTRIES` signer changes,
+    /// upgrades and freezes, so entries before those are skipped.
+    pub fn get_audit_log(env: Env, start: u64, limit: u32) -> Vec<AuditEntry> {
+        audit::entries(&env, start, limit)
+    }
+
+    /// Everything a wallet status page shows, in one read: signer counts by
+    /// kind, freeze and recovery status, and the wallet's policies.
+    pub fn get_wallet_info(env: Env) -> WalletInfo {
+        let (mut admin_signers, mut operator_signers, mut session_signers) = (0, 0, 0);
+        for credential_id in signer_index(&env).iter() {
+            match get_signer(&env, &credential_id).map(|signer| signer.kind) {
+                Ok(SignerKind::Admin) => admin_signers += 1,
+                Ok(SignerKind::Operator) => operator_signers += 1,
+                Ok(SignerKind::Session) => session_signers += 1,
+                Err(_) => {}
+            }
+        }
+
+        let modules = (
+            modules::modules(&env, &ModuleHook::BeforeAuth),
+            modules::modules(&env, &ModuleHook::AfterExecute),
+        );
+        WalletInfo {
+            version: stored_version(&env),
+            admin_signers,
+            operator_signers,
+            session_signers,
+            frozen: is_frozen(&env),
+            threshold: load_threshold(&env),
+            auth_threshold: load_auth_threshold(&env),
+            timelock_delay: timelock::delay(&env),
+            user_verification: requires_user_verification(&env),
+            attestation_required: requires_attestation(&env),
+            guardians: recovery::guardians(&env).len(),
+            recovery_config: recovery::config(&env),
+            recovery_pending: recovery::load_request(&env).is_ok(),
+            origins_digest: digest(&env, allowed_origins(&env)),
+            rp_id_hashes_digest: digest(&env, allowed_rp_id_hashes(&env)),
+            relayers_digest: digest(&env, sponsor::relayers(&env)),
+            modules_digest: digest(&env, modules),
+        }
+    }
+}
+
+// ────────────────────────────────────────────────────────
+//  CustomAccountInterface — __check_auth
+// ────────────────────────────────────────────────────────
+
+#[contractimpl]
+impl CustomAccountInterface for SmartWallet {
+    type Signature = AccountSignature;
+    type Error = WalletError;
+
+    #[allow(non_snake_case)]
+    fn __check_auth(
+        env: Env,
+        signature_payload: Hash<32>,
+        signature: AccountSignature,
+        auth_contexts: Vec<Context>,
+    ) -> Result<(), WalletError> {
+        match signature {
+            // ── Admin / operator passkey path (secp256r1 / P-256 / WebAuthn) ──
+            AccountSignature::WebAuthn(sig) => {
+                verify_passkeys(&env, &signature_payload, &vec![&env, sig], &auth_contexts)?;
+            }
+            AccountSignature::MultiWebAuthn(sigs) => {
+                verify_passkeys(&env, &signature_payload, &sigs, &auth_contexts)?;
+            }
+
+            // ── Session key path (Ed25519) ─────────────────────────────────────
+            //
+            // Session keys sign the raw 32-byte `signature_payload` (the Soroban
+            // auth-entry hash) with Ed25519 — no WebAuthn round-trip needed.
+            // Only `SignerKind::Session` entries may use this path; an admin
+            // credential presented here is rejected with `NotAuthorized`.
+            AccountSignature::SessionKey(sig) => {
+                let mut signer = get_signer(&env, &sig.id)?;
+
+                // Session-only check — prevent admin keys from bypassing challenge
+                // verification by sending a bare Ed25519 signature.
+                if !matches!(signer.kind, SignerKind::Session) {
+                    return Err(WalletError::NotAuthorized);
+                }
+                if env.ledger().timestamp() >= signer.expires_at {
+                    return Err(WalletError::SessionExpired);
+                }
+                check_frozen(&env, &auth_contexts, &signer.kind)?;
+                check_admin_contexts(
+                    &env,
+                    &auth_contexts,
+                    &signer.kind,
+                    &vec![&env, sig.id.clone()],
+                )?;
+                let calls = expand_batches(&env, &auth_contexts);
+                session::check_contracts(&signer, &calls)?;
+
+                // Verify Ed25519 signature over the 32-byte auth-entry hash.
+                let pk: BytesN<32> = signer
+                    .public_key
+                    .clone()
+                    .try_into()
+                    .map_err(|_| WalletError::InvalidPublicKey)?;
+                let payload_bytes =
+                    Bytes::from_slice(&env, signature_payload.to_array().as_slice());
+                env.crypto().ed25519_verify(&pk, &payload_bytes, &sig.signature);
+
+                session::charge(&env, &mut signer, &calls)?;
+                if let Some(name) = &signer.sub_account {
+                    subaccounts::charge(&env, name, &calls)?;
+                }
+                limits::charge(&env, &calls)?;
+                modules::before_auth(&env, &calls)?;
+
+                events::authenticated(&env, &sig.id, SignerKind::Session);
+                audit::set_actor(&env, &AuditActor::Signers(vec![&env, sig.id.clone()]));
+                if session::use_once(&mut signer) {
+                    delete_signer(&env, &sig.id)?;
+                    return Ok(());
+                }
+                signer.last_used_at = env.ledger().timestamp();
+                put_signer(&env, &sig.id, &signer);
+                extend_signer_ttl(&env, &sig.id, &signer.kind, signer.ttl_ledgers);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// ────────────────────────────────────────────────────────
+//  Internal helpers
+// ────────────────────────────────────────────────────────
+
+/// Store the first admin signer, unless the wallet is already initialized.
+fn initialize(env: &Env, credential_id: &Bytes, public_key: BytesN<65>) -> Result<(), WalletError> {
+    if env.storage().instance().has(&WalletDataKey::WalletAddress) {
+        return Err(WalletError::AlreadyInitialized);
+    }
+
+    validate_credential_id(credential_id)?;
+    validate_admin_public_key(&public_key)?;
+
+    env.storage().instance().set(
+        &WalletDataKey::WalletAddress,
+        &env.current_contract_address(),
+    );
+    env.storage()
+        .instance()
+        .set(&WalletDataKey::Version, &WALLET_VERSION);
+
+    let signer = Signer {
+        public_key: public_key.into(),
+        kind: SignerKind::Admin,
+        ttl_ledgers: 0, // admin TTL is managed by constants
+        expires_at: 0,
+        policy: unrestricted_policy(env),
+        spent: 0,
+        allowed_contracts: Vec::new(env),
+        uses_left: 0,
+        label: String::from_str(env, ""),
+        added_at: env.ledger().timestamp(),
+        last_used_at: 0,
+        sub_account: None,
+        origin: None,
+    };
+    env.storage()
+        .persistent()
+        .set(&WalletDataKey::Signer(credential_id.clone()), &signer);
+
+    env.storage().persistent().extend_ttl(
+        &WalletDataKey::Signer(credential_id.clone()),
+        ADMIN_TTL_THRESHOLD,
+        ADMIN_TTL_EXTEND,
+    );
+    env.storage()
+        .instance()
+        .extend_ttl(ADMIN_TTL_THRESHOLD, ADMIN_TTL_EXTEND);
+
+    env.storage()
+        .instance()
+        .set(&WalletDataKey::AdminSignerCount, &1u32);
+    index_signer(env, credential_id);
+    events::initialized(env, credential_id);
+
+    Ok(())
+}
+
+/// Verify WebAuthn assertions over `signature_payload` from at least the
+/// auth threshold of distinct admin or operator passkeys.
+///
+/// `auth_contexts` must be allowed for every signer, so an operator among
+/// them limits the authorization to `execute`.
+fn verify_passkeys(
+    env: &Env,
+    signature_payload: &Hash<32>,
+    signatures: &Vec<Signature>,
+    auth_contexts: &Vec<Context>,
+) -> Result<(), WalletError> {
+    if signatures.len() < load_auth_threshold(env) {
+        return Err(WalletError::InsufficientSignatures);
+    }
+    let calls = expand_batches(env, auth_contexts);
+    let mut credential_ids: Vec<Bytes> = Vec::new(env);
+    let mut sub_accounts: Vec<Symbol> = Vec::new(env);
+    for sig in signatures.iter() {
+        if credential_ids.contains(&sig.id) {
+            return Err(WalletError::DuplicateSignature);
+        }
+        credential_ids.push_back(sig.id);
+    }
+
+    for sig in signatures.iter() {
+        let mut signer = get_signer(env, &sig.id)?;
+        check_frozen(env, auth_contexts, &signer.kind)?;
+        check_admin_contexts(env, auth_contexts, &signer.kind, &credential_ids)?;
+        session::check_tokens(env, &signer.policy.allowed_tokens, &calls)?;
+        verify_assertion(env, signature_payload, &sig, &signer.public_key)?;
+        verify_signer_origin(&signer, &sig.client_data_json)?;
+        if let Some(name) = signer.sub_account.clone() {
+            if !sub_accounts.contains(&name) {
+                sub_accounts.push_back(name);
+            }
+        }
+
+        signer.last_used_at = env.ledger().timestamp();
+        put_signer(env, &sig.id, &signer);
+        extend_signer_ttl(env, &sig.id, &signer.kind, signer.ttl_ledgers);
+        events::authenticated(env, &sig.id, signer.kind);
+    }
+    audit::set_actor(env, &AuditActor::Signers(credential_ids));
+    for name in sub_accounts.iter() {
+        subaccounts::charge(env, &name, &calls)?;
+    }
+    limits::charge(env, &calls)?;
+    modules::before_auth(env, &calls)
+}
+
+/// Verify WebAuthn assertion `sig` over `signature_payload` by the passkey
+/// `public_key`.
+fn verify_assertion(
+    env: &Env,
+    signature_payload: &Hash<32>,
+    sig: &Signature,
+    public_key: &Bytes,
+) -> Result<(), WalletError> {
+    // Verify the WebAuthn challenge encodes exactly `signature_payload`.
+    verify_challenge(env, &sig.client_data_json, signature_payload)?;
+    verify_origin(env, &sig.client_data_json)?;
+    verify_rp_id(env, &sig.authenticator_data)?;
+    verify_user_flags(env, &sig.authenticator_data)?;
+
+    // Authenticator-signed message: SHA-256(authData ‖ SHA-256(clientDataJSON))
+    let client_data_hash = env.crypto().sha256(&sig.client_data_json);
+    let mut signed_data = Bytes::new(env);
+    signed_data.append(&sig.authenticator_data);
+    signed_data.append(&Bytes::from_slice(
+        env,
+        client_data_hash.to_array().as_slice(),
+    ));
+    let message_hash = env.crypto().sha256(&signed_data);
+
+    // Verify P-256 signature; a well-formed one that does not match
+    // still traps in the host.
+    let pk: BytesN<65> = public_key
+        .clone()
+        .try_into()
+        .map_err(|_| WalletError::InvalidPublicKey)?;
+    ecdsa::verify(env, &pk, &message_hash, &sig.signature)
+}
+
+/// `auth_contexts` with each call to this wallet's `execute` or
+/// `execute_sponsored` replaced by the invocations it batches (and the fee
+/// it pays), which the wallet makes without asking for auth.
+fn expand_batches(env: &Env, auth_contexts: &Vec<Context>) -> Vec<Context> {
+    let execute = Symbol::new(env, "execute");
+    let execute_sponsored = Symbol::new(env, "execute_sponsored");
+    let mut calls = Vec::new(env);
+    for context in auth_contexts.iter() {
+        let batch = match &context {
+            Context::Contract(call) if call.contract == env.current_contract_address() => {
+                if call.fn_name == execute {
+                    call.args
+                        .get(0)
+                        .and_then(|arg| Vec::<Invocation>::try_from_val(env, &arg).ok())
+                } else if call.fn_name == execute_sponsored {
+                    sponsor::batch(env, &call.args)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        };
+        let Some(batch) = batch else {
+            calls.push_back(context);
+            continue;
+        };
+        for invocation in batch.iter() {
+            calls.push_back(Context::Contract(ContractContext {
+                contract: invocation.contract,
+                fn_name: invocation.function,
+                args: invocation.args,
+            }));
+        }
+    }
+    calls
+}
+
+/// Make each call from the wallet, refusing calls back into the wallet.
+fn invoke_all(env: &Env, invocations: &Vec<Invocation>) -> Result<Vec<Val>, WalletError> {
+    let mut results = Vec::new(env);
+    for invocation in invocations.iter() {
+        if invocation.contract == env.current_contract_address() {
+            return Err(WalletError::NotAuthorized);
+        }
+        results.push_back(env.invoke_contract::<Val>(
+            &invocation.contract,
+            &invocation.function,
+            invocation.args,
+        ));
+    }
+    Ok(results)
+}
+
+/// Carry out queued action `action_id` and remove it from the queue.
+fn run_action(env: &Env, action_id: u64, operation: TimelockOperation) -> Result<(), WalletError> {
+    match operation {
+        TimelockOperation::AddSigner(credential_id, public_key) => {
+            store_passkey_signer(env, &credential_id, public_key, SignerKind::Admin)?
+        }
+        TimelockOperation::RotateSigner(old_credential_id, new_credential_id, public_key) => {
+            rotate_signer(
+                env,
+                &old_credential_id,
+                &new_credential_id,
+                public_key.into(),
+            )?
+        }
+        TimelockOperation::AddGuardian(guardian) => recovery::add_guardian(env, &guardian),
+        TimelockOperation::RemoveGuardian(guardian) => recovery::remove_guardian(env, &guardian)?,
+        TimelockOperation::SetRecoveryConfig(threshold, delay_ledgers) => {
+            recovery::configure(env, threshold, delay_ledgers)?
+        }
+        TimelockOperation::SetDelay(delay_ledgers) => {
+            timelock::set_delay(env, delay_ledgers);
+            events::timelock_delay_set(env, delay_ledgers);
+        }
+    }
+
+    timelock::remove(env, action_id);
+    events::action_executed(env, action_id);
+    Ok(())
+}
+
+/// Add the admin signer `request` recovers the wallet to, and clear it.
+/// The auth threshold drops back to 1, since the lost passkeys may have
+/// been needed to meet it.
+fn complete_recovery(env: &Env, request: RecoveryRequest) -> Result<(), WalletError> {
+    insert_passkey_signer(
+        env,
+        &request.credential_id,
+        request.public_key,
+        SignerKind::Admin,
+    )?;
+    if load_auth_threshold(env) > 1 {
+        store_auth_threshold(env, 1)?;
+    }
+    // The lost admins could otherwise never reach the threshold again
+    if load_threshold(env) > 1 {
+        store_threshold(env, 1)?;
+    }
+    recovery::clear_request(env);
+    events::recovery_finalized(env, &request.credential_id);
+    Ok(())
+}
+
+/// Validate and store `token`'s daily limit.
+fn apply_daily_limit(env: &Env, token: &Address, limit: Option<i128>) -> Result<(), WalletError> {
+    if limit.is_some_and(|limit| limit <= 0) {
+        return Err(WalletError::InvalidPolicy);
+    }
+
+    limits::set_daily_limit(env, token, limit);
+    events::daily_limit_set(env, token, limit);
+    Ok(())
+}
+
+/// Queue `operation` behind the timelock delay and return its action ID.
+/// Everything but a delay change fails with `ApprovalRequired` above a
+/// threshold of 1.
+fn queue_timelocked(env: &Env, operation: TimelockOperation) -> Result<u64, WalletError> {
+    if !matches!(operation, TimelockOperation::SetDelay(..)) && load_threshold(env) > 1 {
+        return Err(WalletError::ApprovalRequired);
+    }
+
+    let id = timelock::next_id(env);
+    let action = QueuedAction {
+        operation,
+        executable_ledger: env.ledger().sequence() + timelock::delay(env),
+    };
+    timelock::save(env, id, &action);
+    events::action_queued(env, id, &action);
+    Ok(id)
+}
+
+/// Resolve a signer from persistent (admin) or temporary (session) storage.
+fn get_signer(env: &Env, credential_id: &Bytes) -> Result<Signer, WalletError> {
+    let key = WalletDataKey::Signer(credential_id.clone());
+
+    if let Some(signer) = env.storage().persistent().get::<_, Signer>(&key) {
+        return Ok(signer);
+    }
+    if let Some(signer) = env.storage().temporary().get::<_, Signer>(&key) {
+        return Ok(signer);
+    }
+
+    Err(WalletError::SignerNotFound)
+}
+
+/// Write back a signer to the storage its kind lives in.
+fn put_signer(env: &Env, credential_id: &Bytes, signer: &Signer) {
+    let key = WalletDataKey::Signer(credential_id.clone());
+    match signer.kind {
+        SignerKind::Admin | SignerKind::Operator => env.storage().persistent().set(&key, signer),
+        SignerKind::Session => env.storage().temporary().set(&key, signer),
+    }
+}
+
+/// Policy of signers without spending restrictions.
+fn unrestricted_policy(env: &Env) -> SessionPolicy {
+    SessionPolicy {
+        max_per_tx: None,
+        max_total: None,
+        allowed_tokens: Vec::new(env),
+    }
+}
+
+/// Store a new admin or operator passkey, unless the wallet is full.
+fn store_passkey_signer(
+    env: &Env,
+    credential_id: &Bytes,
+    public_key: BytesN<65>,
+    kind: SignerKind,
+) -> Result<(), WalletError> {
+    check_signer_capacity(env)?;
+    insert_passkey_signer(env, credential_id, public_key, kind)
+}
+
+/// Store a new admin or operator passkey, bumping the admin count for admins.
+fn insert_passkey_signer(
+    env: &Env,
+    credential_id: &Bytes,
+    public_key: BytesN<65>,
+    kind: SignerKind,
+) -> Result<(), WalletError> {
+    validate_credential_id(credential_id)?;
+    validate_admin_public_key(&public_key)?;
 
-        let key = WalletDataKey::Signer(credential_id.clone());
-        if env.storage().persistent().has(&key) || env.storage().temporary().has(&key) {
-            return Err(WalletError::SignerAlreadyExists);
-        }
+    let key = WalletDataKey::Signer(credential_id.clone());
+    if env.storage().persistent().has(&key) || env.storage().temporary().has(&key) {
+        return Err(WalletError::SignerAlreadyExists);
+    }
 
-        let signer = Signer {
-            public_key: public_key.into(),
-            kind: SignerKind::Admin,
-            ttl_ledgers: 0,
-        };
-        env.storage().persistent().set(&key, &signer);
-        env.storage()
-            .persistent()
-            .extend_ttl(&key, ADMIN_TTL_THRESHOLD, ADMIN_TTL_EXTEND);
+    let signer = Signer {
+        public_key: public_key.into(),
+        kind: kind.clone(),
+        ttl_ledgers: 0,
+        expires_at: 0,
+        policy: unrestricted_policy(env),
+        spent: 0,
+        allowed_contracts: Vec::new(env),
+        uses_left: 0,
+        label: String::from_str(env, ""),
+        added_at: env.ledger().timestamp(),
+        last_used_at: 0,
+        sub_account: None,
+        origin: None,
+    };
+    env.storage().persistent().set(&key, &signer);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, ADMIN_TTL_THRESHOLD, ADMIN_TTL_EXTEND);
 
-        let count: u32 = env
-            .storage()
-            .instance()
-            .get(&WalletDataKey::AdminSignerCount)
-            .unwrap_or(0);
+    if kind == SignerKind::Admin {
+        let count = admin_count(env);
         env.storage()
             .instance()
             .set(&WalletDataKey::AdminSignerCount, &(count + 1));
-
-        Ok(())
     }
+    index_signer(env, credential_id);
+    events::signer_added(env, credential_id, kind.clone(), 0);
+    audit::record(env, AuditAction::AddSigner(credential_id.clone(), kind));
 
-    /// Register a short-lived Ed25519 session key with a caller-specified TTL.
-    ///
-    /// Session keys let callers authorise multiple Soroban transactions within a
-    /// time window without repeated biometric prompts — ideal for trading bots,
-    /// DCA strategies, or any high-frequency DeFi flow.
-    ///
-    /// ## On-chain TTL semantics
-    /// The entry is written to **Soroban temporary storage**, which auto-expires
-    /// when its TTL reaches 0.  The TTL is set to `ttl_ledgers` on creation and
-    /// renewed by `extend_signer_ttl` after each successful `__check_auth` call,
-    /// capped at the original `ttl_ledgers` value.  No manual revocation is
-    /// needed after expiry — the entry simply disappears, and any subsequent tx
-    /// that references this credential ID will fail with `SignerNotFound`.
-    ///
-    /// ## Key format
-    /// `public_key` must be the 32-byte raw Ed25519 public key (decoded from the
-    /// Stellar G-address via `StrKey.decodeEd25519PublicKey`).
-    ///
-    /// Requires wallet self-auth (`require_auth` → `__check_auth` with an admin
-    /// passkey) so only the wallet owner can register new session keys.
-    pub fn add_session_signer(
-        env: Env,
-        credential_id: Bytes,
-        public_key: BytesN<32>,
-        ttl_ledgers: u32,
-    ) -> Result<(), WalletError> {
-        env.current_contract_address().require_auth();
+    Ok(())
+}
 
-        if ttl_ledgers == 0 {
-            return Err(WalletError::NotAuthorized);
+/// Delete an admin or session signer.
+///
+/// An admin can only go while enough remain: never the last one, and never
+/// below the multisig or auth threshold.
+fn delete_signer(env: &Env, credential_id: &Bytes) -> Result<(), WalletError> {
+    let key = WalletDataKey::Signer(credential_id.clone());
+
+    if let Some(signer) = env.storage().persistent().get::<_, Signer>(&key) {
+        if signer.kind == SignerKind::Admin {
+            let count = admin_count(env);
+            if count <= 1 {
+                return Err(WalletError::LastAdminSigner);
+            }
+            if count - 1 < load_threshold(env).max(load_auth_threshold(env)) {
+                return Err(WalletError::InvalidThreshold);
+            }
+            env.storage()
+                .instance()
+                .set(&WalletDataKey::AdminSignerCount, &(count - 1));
         }
+        env.storage().persistent().remove(&key);
+        unindex_signer(env, credential_id);
+        events::signer_removed(env, credential_id, signer.kind.clone());
+        audit::record(
+            env,
+            AuditAction::RemoveSigner(credential_id.clone(), signer.kind),
+        );
+        return Ok(());
+    }
+    if env.storage().temporary().has(&key) {
+        env.storage().temporary().remove(&key);
+        unindex_signer(env, credential_id);
+        events::signer_removed(env, credential_id, SignerKind::Session);
+        audit::record(
+            env,
+            AuditAction::RemoveSigner(credential_id.clone(), SignerKind::Session),
+        );
+        return Ok(());
+    }
 
-        let key = WalletDataKey::Signer(credential_id.clone());
+    Err(WalletError::SignerNotFound)
+}
 
-        if env.storage().persistent().has(&key) || env.storage().temporary().has(&key) {
-            return Err(WalletError::SignerAlreadyExists);
+/// Move a signer to a new credential ID and public key, keeping everything
+/// else about it.
+fn rotate_signer(
+    env: &Env,
+    old_credential_id: &Bytes,
+    new_credential_id: &Bytes,
+    new_public_key: Bytes,
+) -> Result<(), WalletError> {
+    let mut signer = get_signer(env, old_credential_id)?;
+    validate_credential_id(new_credential_id)?;
+    let new_key = WalletDataKey::Signer(new_credential_id.clone());
+    if env.storage().persistent().has(&new_key) || env.storage().temporary().has(&new_key) {
+        return Err(WalletError::SignerAlreadyExists);
+    }
+    match signer.kind {
+        SignerKind::Admin | SignerKind::Operator => {
+            let public_key: BytesN<65> = new_public_key
+                .clone()
+                .try_into()
+                .map_err(|_| WalletError::InvalidPublicKey)?;
+            validate_admin_public_key(&public_key)?;
+        }
+        SignerKind::Session => {
+            if new_public_key.len() != 32 {
+                return Err(WalletError::InvalidPublicKey);
+            }
         }
+    }
 
-        let signer = Signer {
-            public_key: public_key.into(),
-            kind: SignerKind::Session,
-            ttl_ledgers,
-        };
-        env.storage().temporary().set(&key, &signer);
-        // Use the caller-provided TTL for both the threshold and extend so the
-        // entry lives exactly as long as requested.
+    let old_key = WalletDataKey::Signer(old_credential_id.clone());
+    match signer.kind {
+        SignerKind::Admin | SignerKind::Operator => env.storage().persistent().remove(&old_key),
+        SignerKind::Session => env.storage().temporary().remove(&old_key),
+    }
+    signer.public_key = new_public_key;
+    signer.added_at = env.ledger().timestamp();
+    signer.last_used_at = 0;
+    put_signer(env, new_credential_id, &signer);
+    extend_signer_ttl(env, new_credential_id, &signer.kind, signer.ttl_ledgers);
+
+    let mut index = signer_index(env);
+    if let Some(position) = index.first_index_of(old_credential_id) {
+        index.set(position, new_credential_id.clone());
         env.storage()
-            .temporary()
-            .extend_ttl(&key, ttl_ledgers / 2, ttl_ledgers);
+            .instance()
+            .set(&WalletDataKey::SignerIndex, &index);
+    }
+    events::signer_rotated(env, old_credential_id, new_credential_id, signer.kind);
+    audit::record(
+        env,
+        AuditAction::RotateSigner(old_credential_id.clone(), new_credential_id.clone()),
+    );
+    Ok(())
+}
 
-        Ok(())
+fn admin_count(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&WalletDataKey::AdminSignerCount)
+        .unwrap_or(0)
+}
+
+/// Admin approvals a fast-track proposal needs: two thirds of the admins,
+/// rounded up.
+fn supermajority(env: &Env) -> u32 {
+    (admin_count(env) * 2).div_ceil(3)
+}
+
+fn load_threshold(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&WalletDataKey::Threshold)
+        .unwrap_or(1)
+}
+
+/// Set the threshold, which must be reachable by the current admins.
+fn store_threshold(env: &Env, threshold: u32) -> Result<(), WalletError> {
+    if threshold == 0 || threshold > admin_count(env) {
+        return Err(WalletError::InvalidThreshold);
+    }
+    env.storage()
+        .instance()
+        .set(&WalletDataKey::Threshold, &threshold);
+    events::threshold_set(env, threshold);
+    Ok(())
+}
+
+fn load_auth_threshold(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&WalletDataKey::AuthThreshold)
+        .unwrap_or(1)
+}
+
+/// Set the auth threshold, which the admins alone must be able to reach.
+fn store_auth_threshold(env: &Env, threshold: u32) -> Result<(), WalletError> {
+    if threshold == 0 || threshold > admin_count(env) {
+        return Err(WalletError::InvalidThreshold);
     }
+    env.storage()
+        .instance()
+        .set(&WalletDataKey::AuthThreshold, &threshold);
+    events::auth_threshold_set(env, threshold);
+    Ok(())
+}
 
-    /// Remove a signer by credential ID. Requires wallet self-auth.
-    ///
-    /// Prevents removing the last admin signer to avoid permanently locking
-    /// the wallet.
-    pub fn remove_signer(env: Env, credential_id: Bytes) -> Result<(), WalletError> {
-        env.current_contract_address().require_auth();
+fn is_frozen(env: &Env) -> bool {
+    env.storage()
+        .instance()
+        .get::<_, u32>(&WalletDataKey::FrozenUntil)
+        .is_some_and(|frozen_until| env.ledger().sequence() < frozen_until)
+}
 
-        let key = WalletDataKey::Signer(credential_id);
+fn set_frozen_until(env: &Env, frozen_until: u32) {
+    env.storage()
+        .instance()
+        .set(&WalletDataKey::FrozenUntil, &frozen_until);
+}
 
-        if env.storage().persistent().has(&key) {
-            let signer: Signer = env.storage().persistent().get(&key).unwrap();
-            if matches!(signer.kind, SignerKind::Admin) {
-                let count: u32 = env
-                    .storage()
-                    .instance()
-                    .get(&WalletDataKey::AdminSignerCount)
-                    .unwrap_or(1);
-                if count <= 1 {
-                    return Err(WalletError::LastAdminSigner);
-                }
-                env.storage()
-                    .instance()
-                    .set(&WalletDataKey::AdminSignerCount, &(count - 1));
-            }
-            env.storage().persistent().remove(&key);
-            return Ok(());
-        }
-        if env.storage().temporary().has(&key) {
-            env.storage().temporary().remove(&key);
-            return Ok(());
+/// While the wallet is frozen, reject everything but an admin's calls on the
+/// wallet that manage the freeze or stop an unwanted change.
+fn check_frozen(
+    env: &Env,
+    auth_contexts: &Vec<Context>,
+    kind: &SignerKind,
+) -> Result<(), WalletError> {
+    if !is_frozen(env) {
+        return Ok(());
+    }
+    if *kind != SignerKind::Admin {
+        return Err(WalletError::WalletFrozen);
+    }
+    let allowed = [
+        Symbol::new(env, "freeze"),
+        Symbol::new(env, "unfreeze"),
+        Symbol::new(env, "propose"),
+        Symbol::new(env, "approve"),
+        Symbol::new(env, "veto_recovery"),
+        Symbol::new(env, "cancel_action"),
+        Symbol::new(env, "revoke_all_session_signers"),
+    ];
+    for context in auth_contexts.iter() {
+        let Context::Contract(call) = context else {
+            return Err(WalletError::WalletFrozen);
+        };
+        if call.contract != env.current_contract_address() || !allowed.contains(&call.fn_name) {
+            return Err(WalletError::WalletFrozen);
         }
-
-        Err(WalletError::SignerNotFound)
     }
+    Ok(())
 }
 
-// ────────────────────────────────────────────────────────
-//  CustomAccountInterface — __check_auth
-// ────────────────────────────────────────────────────────
+fn requires_user_verification(env: &Env) -> bool {
+    env.storage()
+        .instance()
+        .get(&WalletDataKey::RequireUserVerification)
+        .unwrap_or(false)
+}
 
-#[contractimpl]
-impl CustomAccountInterface for SmartWallet {
-    type Signature = AccountSignature;
-    type Error = WalletError;
+fn requires_attestation(env: &Env) -> bool {
+    env.storage()
+        .instance()
+        .get(&WalletDataKey::RequireAttestation)
+        .unwrap_or(false)
+}
 
-    #[allow(non_snake_case)]
-    fn __check_auth(
-        env: Env,
-        signature_payload: Hash<32>,
-        signature: AccountSignature,
-        _auth_contexts: Vec<Context>,
-    ) -> Result<(), WalletError> {
-        match signature {
-            // ── Admin passkey path (secp256r1 / P-256 / WebAuthn) ─────────────
-            AccountSignature::WebAuthn(sig) => {
-                let signer = get_signer(&env, &sig.id)?;
+fn allowed_origins(env: &Env) -> Vec<Bytes> {
+    env.storage()
+        .instance()
+        .get(&WalletDataKey::AllowedOrigins)
+        .unwrap_or_else(|| Vec::new(env))
+}
 
-                // Verify the WebAuthn challenge encodes exactly `signature_payload`.
-                verify_challenge(&env, &sig.client_data_json, &signature_payload)?;
+/// SHA-256 of `value`'s XDR, for summarizing a setting in `WalletInfo`.
+fn digest(env: &Env, value: impl IntoVal<Env, Val>) -> BytesN<32> {
+    env.crypto().sha256(&value.to_xdr(env)).to_bytes()
+}
 
-                // Authenticator-signed message: SHA-256(authData ‖ SHA-256(clientDataJSON))
-                let client_data_hash = env.crypto().sha256(&sig.client_data_json);
-                let mut signed_data = Bytes::new(&env);
-                signed_data.append(&sig.authenticator_data);
-                signed_data.append(&Bytes::from_slice(
-                    &env,
-                    client_data_hash.to_array().as_slice(),
-                ));
-                let message_hash = env.crypto().sha256(&signed_data);
+fn allowed_rp_id_hashes(env: &Env) -> Vec<BytesN<32>> {
+    env.storage()
+        .instance()
+        .get(&WalletDataKey::AllowedRpIdHashes)
+        .unwrap_or_else(|| Vec::new(env))
+}
 
-                // Verify P-256 signature; panics on failure (Soroban host behaviour).
-                let pk: BytesN<65> = signer
-                    .public_key
-                    .try_into()
-                    .map_err(|_| WalletError::InvalidPublicKey)?;
-                env.crypto()
-                    .secp256r1_verify(&pk, &message_hash, &sig.signature);
+fn stored_version(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&WalletDataKey::Version)
+        .unwrap_or(0)
+}
 
-                extend_signer_ttl(&env, &sig.id, &signer.kind, signer.ttl_ledgers);
-            }
+fn upgrade_wasm(env: &Env, new_wasm_hash: BytesN<32>) {
+    env.deployer()
+        .update_current_contract_wasm(new_wasm_hash.clone());
+    events::upgraded(env, &new_wasm_hash);
+    audit::record(env, AuditAction::Upgrade(new_wasm_hash));
+}
 
-            // ── Session key path (Ed25519) ─────────────────────────────────────
-            //
-            // Session keys sign the raw 32-byte `signature_payload` (the Soroban
-            // auth-entry hash) with Ed25519 — no WebAuthn round-trip needed.
-            // Only `SignerKind::Session` entries may use this path; an admin
-            // credential presented here is rejected with `NotAuthorized`.
-            AccountSignature::SessionKey(sig) => {
-                let signer = get_signer(&env, &sig.id)?;
+fn load_proposal(env: &Env, proposal_id: u64) -> Result<Proposal, WalletError> {
+    let key = WalletDataKey::Proposal(proposal_id);
+    let proposal: Proposal = env
+        .storage()
+        .persistent()
+        .get(&key)
+        .ok_or(WalletError::ProposalNotFound)?;
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, ADMIN_TTL_THRESHOLD, ADMIN_TTL_EXTEND);
+    Ok(proposal)
+}
 
-                // Session-only check — prevent admin keys from bypassing challenge
-                // verification by sending a bare Ed25519 signature.
-                if !matches!(signer.kind, SignerKind::Session) {
+fn save_proposal(env: &Env, proposal_id: u64, proposal: &Proposal) {
+    let key = WalletDataKey::Proposal(proposal_id);
+    env.storage().persistent().set(&key, proposal);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, ADMIN_TTL_THRESHOLD, ADMIN_TTL_EXTEND);
+}
+
+/// Check the calls on this wallet that signer `credential_id` of `kind`
+/// authorizes:
+///
+/// - an admin may only `approve` as one of the `credential_ids` signing;
+/// - operators and session keys may only call `execute` and
+///   `execute_sponsored`: they can never manage signers, upgrade or change
+///   any other wallet setting, even though any signer satisfies the
+///   wallet's own `require_auth`.
+fn check_admin_contexts(
+    env: &Env,
+    auth_contexts: &Vec<Context>,
+    kind: &SignerKind,
+    credential_ids: &Vec<Bytes>,
+) -> Result<(), WalletError> {
+    let approve = Symbol::new(env, "approve");
+    let execute = Symbol::new(env, "execute");
+    let execute_sponsored = Symbol::new(env, "execute_sponsored");
+    for context in auth_contexts.iter() {
+        let Context::Contract(call) = context else {
+            continue;
+        };
+        if call.contract != env.current_contract_address() {
+            continue;
+        }
+        match kind {
+            SignerKind::Operator | SignerKind::Session
+                if call.fn_name != execute && call.fn_name != execute_sponsored =>
+            {
+                return Err(WalletError::NotAuthorized);
+            }
+            SignerKind::Admin if call.fn_name == approve => {
+                let approver = call
+                    .args
+                    .get(1)
+                    .and_then(|arg| Bytes::try_from_val(env, &arg).ok());
+                if !approver.is_some_and(|approver| credential_ids.contains(&approver)) {
                     return Err(WalletError::NotAuthorized);
                 }
-
-                // Verify Ed25519 signature over the 32-byte auth-entry hash.
-                let pk: BytesN<32> = signer
-                    .public_key
-                    .try_into()
-                    .map_err(|_| WalletError::InvalidPublicKey)?;
-                let payload_bytes =
-                    Bytes::from_slice(&env, signature_payload.to_array().as_slice());
-                env.crypto().ed25519_verify(&pk, &payload_bytes, &sig.signature);
-
-                extend_signer_ttl(&env, &sig.id, &signer.kind, signer.ttl_ledgers);
             }
+            _ => {}
         }
-
-        Ok(())
     }
+    Ok(())
 }
 
-// ────────────────────────────────────────────────────────
-//  Internal helpers
-// ────────────────────────────────────────────────────────
-
-/// Resolve a signer from persistent (admin) or temporary (session) storage.
-fn get_signer(env: &Env, credential_id: &Bytes) -> Result<Signer, WalletError> {
-    let key = WalletDataKey::Signer(credential_id.clone());
+/// Credential IDs in the signer index, in registration order.
+fn signer_index(env: &Env) -> Vec<Bytes> {
+    env.storage()
+        .instance()
+        .get(&WalletDataKey::SignerIndex)
+        .unwrap_or_else(|| Vec::new(env))
+}
 
-    if let Some(signer) = env.storage().persistent().get::<_, Signer>(&key) {
-        return Ok(signer);
+/// Append `credential_id` to the signer index, dropping IDs whose session
+/// signer has expired so the index stays bounded by the live signers.
+fn index_signer(env: &Env, credential_id: &Bytes) {
+    let mut index = Vec::new(env);
+    for id in signer_index(env).iter() {
+        if id != *credential_id && get_signer(env, &id).is_ok() {
+            index.push_back(id);
+        }
     }
-    if let Some(signer) = env.storage().temporary().get::<_, Signer>(&key) {
-        return Ok(signer);
+    index.push_back(credential_id.clone());
+    env.storage()
+        .instance()
+        .set(&WalletDataKey::SignerIndex, &index);
+}
+
+/// Fail with `TooManySigners` if the wallet has no room for another signer.
+fn check_signer_capacity(env: &Env) -> Result<(), WalletError> {
+    let live = signer_index(env)
+        .iter()
+        .filter(|id| get_signer(env, id).is_ok())
+        .count() as u32;
+    if live >= MAX_SIGNERS {
+        return Err(WalletError::TooManySigners);
     }
+    Ok(())
+}
 
-    Err(WalletError::SignerNotFound)
+/// Remove `credential_id` from the signer index.
+fn unindex_signer(env: &Env, credential_id: &Bytes) {
+    let mut index = signer_index(env);
+    if let Some(position) = index.first_index_of(credential_id) {
+        index.remove(position);
+        env.storage()
+            .instance()
+            .set(&WalletDataKey::SignerIndex, &index);
+    }
 }
 
 /// Extend a signer's TTL after a successful `__check_auth`.
 ///
-/// - Admin signers: always use the fixed constants.
+/// - Admin and operator signers: always use the fixed constants.
 /// - Session signers: extend by the original `ttl_ledgers` so the key stays
 ///   alive as long as it is actively used, capped at the original lifetime.
 ///   The threshold is `ttl_ledgers / 2` (renew when half-way through).
 fn extend_signer_ttl(env: &Env, credential_id: &Bytes, kind: &SignerKind, ttl_ledgers: u32) {
     let key = WalletDataKey::Signer(credential_id.clone());
     match kind {
-        SignerKind::Admin => {
+        SignerKind::Admin | SignerKind::Operator => {
             env.storage()
                 .persistent()
                 .extend_ttl(&key, ADMIN_TTL_THRESHOLD, ADMIN_TTL_EXTEND);
@@ -315,6 +2564,15 @@ fn extend_signer_ttl(env: &Env, credential_id: &Bytes, kind: &SignerKind, ttl_le
     }
 }
 
+/// Validate a WebAuthn credential ID: must be between
+/// `MIN_CREDENTIAL_ID_LEN` and `MAX_CREDENTIAL_ID_LEN` bytes.
+fn validate_credential_id(credential_id: &Bytes) -> Result<(), WalletError> {
+    if !(MIN_CREDENTIAL_ID_LEN..=MAX_CREDENTIAL_ID_LEN).contains(&credential_id.len()) {
+        return Err(WalletError::InvalidCredentialId);
+    }
+    Ok(())
+}
+
 /// Validate an admin (P-256) public key: must be 65 bytes starting with `0x04`
 /// (SEC-1 uncompressed point).
 fn validate_admin_public_key(public_key: &BytesN<65>) -> Result<(), WalletError> {
@@ -325,18 +2583,133 @@ fn validate_admin_public_key(public_key: &BytesN<65>) -> Result<(), WalletError>
     Ok(())
 }
 
-/// Scan `client_data_json` for the `"challenge":"<base64url>"` field and
-/// confirm it matches `base64url(signature_payload)`.
+/// Verify a `webauthn.create` response registering `credential_id` and
+/// return the attested public key.
+fn verify_registration(
+    env: &Env,
+    credential_id: &Bytes,
+    attestation_object: &Bytes,
+    client_data_json: &Bytes,
+) -> Result<BytesN<65>, WalletError> {
+    let client_data_type = client_data_field(client_data_json, b"\"type\":\"")
+        .ok_or(WalletError::InvalidClientData)?;
+    if client_data_type != Bytes::from_slice(env, b"webauthn.create") {
+        return Err(WalletError::InvalidClientDataType);
+    }
+    verify_origin(env, client_data_json)?;
+    let registration =
+        attestation::verify(env, credential_id, attestation_object, client_data_json)?;
+    verify_rp_id(env, &registration.authenticator_data)?;
+    verify_user_flags(env, &registration.authenticator_data)?;
+    Ok(registration.public_key)
+}
+
+/// Confirm `client_data_json` is an assertion (`"type":"webauthn.get"`) whose
+/// `"challenge":"<base64url>"` field matches `base64url(signature_payload)`,
+/// either unpadded as browsers send it or with its `=` padding, as some
+/// client libraries produce.
 fn verify_challenge(
     env: &Env,
     client_data_json: &Bytes,
     signature_payload: &Hash<32>,
 ) -> Result<(), WalletError> {
-    let needle = b"\"challenge\":\"";
+    let client_data_type = client_data_field(client_data_json, b"\"type\":\"")
+        .ok_or(WalletError::InvalidClientData)?;
+    if client_data_type != Bytes::from_slice(env, b"webauthn.get") {
+        return Err(WalletError::InvalidClientDataType);
+    }
+
+    let challenge_bytes = client_data_field(client_data_json, b"\"challenge\":\"")
+        .ok_or(WalletError::InvalidClientData)?;
+    let mut expected = base64url_encode(env, signature_payload.to_array().as_slice());
+    if challenge_bytes == expected {
+        return Ok(());
+    }
+
+    while !expected.len().is_multiple_of(4) {
+        expected.push_back(b'=');
+    }
+    if challenge_bytes != expected {
+        return Err(WalletError::ChallengeMismatch);
+    }
+
+    Ok(())
+}
+
+/// Check the `"origin"` field of `client_data_json` against the wallet's
+/// origin allowlist; an empty allowlist accepts any origin.
+fn verify_origin(env: &Env, client_data_json: &Bytes) -> Result<(), WalletError> {
+    let origins = allowed_origins(env);
+    if origins.is_empty() {
+        return Ok(());
+    }
+
+    let origin = client_data_field(client_data_json, b"\"origin\":\"")
+        .ok_or(WalletError::InvalidClientData)?;
+    if !origins.contains(&origin) {
+        return Err(WalletError::OriginNotAllowed);
+    }
+    Ok(())
+}
+
+/// Check `client_data_json` came from the origin `signer` is bound to, if
+/// any.
+fn verify_signer_origin(signer: &Signer, client_data_json: &Bytes) -> Result<(), WalletError> {
+    let Some(bound) = &signer.origin else {
+        return Ok(());
+    };
+    let origin = client_data_field(client_data_json, b"\"origin\":\"")
+        .ok_or(WalletError::InvalidClientData)?;
+    if origin != *bound {
+        return Err(WalletError::OriginNotAllowed);
+    }
+    Ok(())
+}
+
+/// Reject `authenticator_data` scoped to a relying party the wallet has not
+/// approved, once it has approved any.
+fn verify_rp_id(env: &Env, authenticator_data: &Bytes) -> Result<(), WalletError> {
+    let hashes = allowed_rp_id_hashes(env);
+    if hashes.is_empty() {
+        return Ok(());
+    }
+
+    if authenticator_data.len() < 32 {
+        return Err(WalletError::InvalidAuthenticatorData);
+    }
+    let rp_id_hash: BytesN<32> = authenticator_data.slice(..32).try_into().unwrap();
+    if !hashes.contains(&rp_id_hash) {
+        return Err(WalletError::RpIdMismatch);
+    }
+    Ok(())
+}
+
+/// Require the User Present flag in `authenticator_data`, and User Verified
+/// when the wallet asks for it, so silent or background assertions fail.
+fn verify_user_flags(env: &Env, authenticator_data: &Bytes) -> Result<(), WalletError> {
+    // rpIdHash (32 bytes) ‖ flags (1 byte) ‖ signCount (4 bytes), then
+    // extension data of any length when the ED flag is set
+    if authenticator_data.len() < 37 {
+        return Err(WalletError::InvalidAuthenticatorData);
+    }
+    let flags = authenticator_data.get(32).unwrap();
+
+    if flags & FLAG_USER_PRESENT == 0 {
+        return Err(WalletError::UserNotPresent);
+    }
+    if flags & FLAG_USER_VERIFIED == 0 && requires_user_verification(env) {
+        return Err(WalletError::UserNotVerified);
+    }
+    Ok(())
+}
+
+/// The string value following `needle` (`"<key>":"`) in `client_data_json`,
+/// up to the closing quote.
+fn client_data_field(client_data_json: &Bytes, needle: &[u8]) -> Option<Bytes> {
     let json_len = client_data_json.len();
     let needle_len = needle.len() as u32;
 
-    let mut challenge_start: Option<u32> = None;
+    let mut value_start: Option<u32> = None;
     if json_len >= needle_len {
         for i in 0..=(json_len - needle_len) {
             let mut found = true;
@@ -347,31 +2720,24 @@ fn verify_challenge(
                 }
             }
             if found {
-                challenge_start = Some(i + needle_len);
+                value_start = Some(i + needle_len);
                 break;
             }
         }
     }
 
-    let start = challenge_start.ok_or(WalletError::InvalidClientData)?;
+    let start = value_start?;
 
-    let mut challenge_end: Option<u32> = None;
+    let mut value_end: Option<u32> = None;
     for i in start..json_len {
         if client_data_json.get(i).unwrap() == b'"' {
-            challenge_end = Some(i);
+            value_end = Some(i);
             break;
         }
     }
-    let end = challenge_end.ok_or(WalletError::InvalidClientData)?;
-
-    let challenge_bytes = client_data_json.slice(start..end);
-    let expected = base64url_encode(env, signature_payload.to_array().as_slice());
-
-    if challenge_bytes != expected {
-        return Err(WalletError::ChallengeMismatch);
-    }
+    let end = value_end?;
 
-    Ok(())
+    Some(client_data_json.slice(start..end))
 }
 
 pub fn base64url_encode(env: &Env, input: &[u8]) -> Bytes {
@@ -411,3 +2777,6 @@ pub fn base64url_encode(env: &Env, input: &[u8]) -> Bytes {
 
     out
 }
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,86 @@
+//! Daily spending limits.
+//!
+//! Each limited token has a cap on what SEP-41 calls authorized through
+//! `__check_auth` may move per UTC day, whichever signer authorizes them.
+//! The day's running total lives in temporary storage, which only has to
+//! outlast the day it counts.
+
+use soroban_sdk::{auth::Context, Address, Env, Map, Vec};
+
+use smart_wallet_account_common::{DailySpend, WalletDataKey, WalletError};
+
+use crate::session::spend_amount;
+
+const DAY_SECONDS: u64 = 86_400;
+
+/// Spend entries live for about two days (in ledgers).
+pub const SPEND_TTL: u32 = 34_560;
+
+pub fn daily_limit(env: &Env, token: &Address) -> Option<i128> {
+    env.storage()
+        .persistent()
+        .get(&WalletDataKey::DailyLimit(token.clone()))
+}
+
+pub fn set_daily_limit(env: &Env, token: &Address, limit: Option<i128>) {
+    let key = WalletDataKey::DailyLimit(token.clone());
+    match limit {
+        Some(limit) => env.storage().persistent().set(&key, &limit),
+        None => env.storage().persistent().remove(&key),
+    }
+}
+
+/// What `token` calls have moved so far today.
+pub fn spent_today(env: &Env, token: &Address) -> i128 {
+    env.storage()
+        .temporary()
+        .get::<_, DailySpend>(&WalletDataKey::DailySpend(token.clone()))
+        .filter(|spend| spend.day == today(env))
+        .map_or(0, |spend| spend.spent)
+}
+
+/// Add the token calls in `calls` to today's totals, failing with
+/// `DailyLimitExceeded` if any limited token would go over its cap.
+pub fn charge(env: &Env, calls: &Vec<Context>) -> Result<(), WalletError> {
+    let mut amounts: Map<Address, i128> = Map::new(env);
+    for context in calls.iter() {
+        let Context::Contract(call) = context else {
+            continue;
+        };
+        let Some(amount) = spend_amount(env, &call.fn_name, &call.args) else {
+            continue;
+        };
+        let total = amounts
+            .get(call.contract.clone())
+            .unwrap_or(0)
+            .checked_add(amount.max(0))
+            .ok_or(WalletError::DailyLimitExceeded)?;
+        amounts.set(call.contract, total);
+    }
+
+    for (token, amount) in amounts.iter() {
+        let Some(limit) = daily_limit(env, &token) else {
+            continue;
+        };
+        let spent = spent_today(env, &token)
+            .checked_add(amount)
+            .filter(|spent| *spent <= limit)
+            .ok_or(WalletError::DailyLimitExceeded)?;
+
+        let key = WalletDataKey::DailySpend(token);
+        let spend = DailySpend {
+            day: today(env),
+            spent,
+        };
+        env.storage().temporary().set(&key, &spend);
+        env.storage()
+            .temporary()
+            .extend_ttl(&key, SPEND_TTL, SPEND_TTL);
+    }
+    Ok(())
+}
+
+/// The current UTC day number.
+pub fn today(env: &Env) -> u64 {
+    env.ledger().timestamp() / DAY_SECONDS
+}
@@ -0,0 +1,86 @@
+//! Storage migrations run by `migrate`.
+//!
+//! Contract types are stored as maps of their field names, and an entry
+//! written with fewer fields than the current struct no longer decodes.
+//! Migrations therefore read old entries as raw field maps and write them
+//! back in the current layout.
+
+use soroban_sdk::{Bytes, Env, Map, String, Symbol, TryFromVal, Val, Vec};
+
+use smart_wallet_account_common::{Signer, SignerKind, WalletDataKey, WalletError};
+
+use crate::{admin_count, signer_index, unrestricted_policy};
+
+/// Versions 2 to 4: signers gained `uses_left`, `label`, `added_at` and
+/// `last_used_at`, then `sub_account`, then `origin`.  Rewrite every signer
+/// with all of them, using their defaults wherever an older layout lacks
+/// them; `added_at` stays 0 as the registration time was not recorded.
+///
+/// Wallets from before the signer index have no way to enumerate their
+/// signers, so the caller names them in `credential_ids`.  The index is
+/// rebuilt from the indexed and named signers that still exist, and this
+/// fails with `SignerNotFound` if an admin signer is in neither.
+pub fn signer_fields(env: &Env, credential_ids: &Vec<Bytes>) -> Result<(), WalletError> {
+    let mut candidates = signer_index(env);
+    for credential_id in credential_ids.iter() {
+        if !candidates.contains(&credential_id) {
+            candidates.push_back(credential_id);
+        }
+    }
+
+    let mut index = Vec::new(env);
+    let mut admins = 0u32;
+    for credential_id in candidates.iter() {
+        let key = WalletDataKey::Signer(credential_id.clone());
+        let mut signer = None;
+        if let Some(fields) = env.storage().persistent().get(&key) {
+            signer = upgrade_signer(env, &fields);
+            if let Some(signer) = &signer {
+                env.storage().persistent().set(&key, signer);
+            }
+        } else if let Some(fields) = env.storage().temporary().get(&key) {
+            signer = upgrade_signer(env, &fields);
+            if let Some(signer) = &signer {
+                env.storage().temporary().set(&key, signer);
+            }
+        }
+        if let Some(signer) = signer {
+            if signer.kind == SignerKind::Admin {
+                admins += 1;
+            }
+            index.push_back(credential_id);
+        }
+    }
+    if admins < admin_count(env) {
+        return Err(WalletError::SignerNotFound);
+    }
+    env.storage()
+        .instance()
+        .set(&WalletDataKey::SignerIndex, &index);
+    Ok(())
+}
+
+/// `fields` of a stored signer as a current `Signer`, or `None` if they
+/// lack the key or kind every layout has had.
+fn upgrade_signer(env: &Env, fields: &Map<Symbol, Val>) -> Option<Signer> {
+    Some(Signer {
+        public_key: field(env, fields, "public_key")?,
+        kind: field(env, fields, "kind")?,
+        ttl_ledgers: field(env, fields, "ttl_ledgers").unwrap_or(0),
+        expires_at: field(env, fields, "expires_at").unwrap_or(0),
+        policy: field(env, fields, "policy").unwrap_or_else(|| unrestricted_policy(env)),
+        spent: field(env, fields, "spent").unwrap_or(0),
+        allowed_contracts: field(env, fields, "allowed_contracts").unwrap_or_else(|| Vec::new(env)),
+        uses_left: field(env, fields, "uses_left").unwrap_or(0),
+        label: field(env, fields, "label").unwrap_or_else(|| String::from_str(env, "")),
+        added_at: field(env, fields, "added_at").unwrap_or(0),
+        last_used_at: field(env, fields, "last_used_at").unwrap_or(0),
+        sub_account: field(env, fields, "sub_account").unwrap_or(None),
+        origin: field(env, fields, "origin").unwrap_or(None),
+    })
+}
+
+fn field<T: TryFromVal<Env, Val>>(env: &Env, fields: &Map<Symbol, Val>, name: &str) -> Option<T> {
+    let value = fields.get(Symbol::new(env, name))?;
+    T::try_from_val(env, &value).ok()
+}
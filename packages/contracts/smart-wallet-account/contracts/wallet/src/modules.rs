@@ -0,0 +1,105 @@
+//! Module contracts.
+//!
+//! Admins install modules per `ModuleHook` to extend the wallet without
+//! upgrading it.  A module is called, never given the wallet's authority:
+//! the wallet is not the direct invoker of anything the module calls, so
+//! any auth it needs from the wallet goes through `__check_auth` like any
+//! other call.  `BeforeAuth` modules only see calls on other contracts, so
+//! no module can block the admin calls that uninstall it.  Module lists
+//! live in instance storage.
+
+use soroban_sdk::{auth::Context, Address, Env, IntoVal, Symbol, Val, Vec};
+
+use smart_wallet_account_common::{Invocation, ModuleHook, WalletDataKey, WalletError};
+
+use crate::events;
+
+/// Most modules one hook can have, bounding what each call costs.
+pub const MAX_MODULES: u32 = 5;
+
+pub fn modules(env: &Env, hook: &ModuleHook) -> Vec<Address> {
+    env.storage()
+        .instance()
+        .get(&WalletDataKey::Modules(hook.clone()))
+        .unwrap_or_else(|| Vec::new(env))
+}
+
+fn set_modules(env: &Env, hook: &ModuleHook, modules: &Vec<Address>) {
+    env.storage()
+        .instance()
+        .set(&WalletDataKey::Modules(hook.clone()), modules);
+}
+
+/// Add `module` to `hook` unless it is already installed there.
+pub fn install(env: &Env, module: &Address, hook: &ModuleHook) -> Result<(), WalletError> {
+    if *module == env.current_contract_address() {
+        return Err(WalletError::NotAuthorized);
+    }
+    let mut installed = modules(env, hook);
+    if installed.contains(module) {
+        return Ok(());
+    }
+    if installed.len() >= MAX_MODULES {
+        return Err(WalletError::TooManyModules);
+    }
+    installed.push_back(module.clone());
+    set_modules(env, hook, &installed);
+    events::module_installed(env, module, hook);
+    Ok(())
+}
+
+pub fn uninstall(env: &Env, module: &Address, hook: &ModuleHook) -> Result<(), WalletError> {
+    let mut installed = modules(env, hook);
+    let index = installed
+        .first_index_of(module)
+        .ok_or(WalletError::ModuleNotInstalled)?;
+    installed.remove(index);
+    set_modules(env, hook, &installed);
+    events::module_uninstalled(env, module, hook);
+    Ok(())
+}
+
+/// Run the `BeforeAuth` modules over the calls in `calls` that are not on
+/// the wallet itself, failing with `ModuleRejected` if any module fails.
+pub fn before_auth(env: &Env, calls: &Vec<Context>) -> Result<(), WalletError> {
+    let installed = modules(env, &ModuleHook::BeforeAuth);
+    if installed.is_empty() {
+        return Ok(());
+    }
+    let wallet = env.current_contract_address();
+    let mut external = Vec::new(env);
+    for context in calls.iter() {
+        match &context {
+            Context::Contract(call) if call.contract == wallet => {}
+            _ => external.push_back(context),
+        }
+    }
+    if external.is_empty() {
+        return Ok(());
+    }
+
+    let function = Symbol::new(env, "before_auth");
+    let args: Vec<Val> = (wallet, external).into_val(env);
+    for module in installed.iter() {
+        let result =
+            env.try_invoke_contract::<Val, soroban_sdk::Error>(&module, &function, args.clone());
+        if !matches!(result, Ok(Ok(_))) {
+            return Err(WalletError::ModuleRejected);
+        }
+    }
+    Ok(())
+}
+
+/// Report a batch that has run to the `AfterExecute` modules.
+pub fn after_execute(env: &Env, invocations: &Vec<Invocation>, results: &Vec<Val>) {
+    let function = Symbol::new(env, "after_execute");
+    let args: Vec<Val> = (
+        env.current_contract_address(),
+        invocations.clone(),
+        results.clone(),
+    )
+        .into_val(env);
+    for module in modules(env, &ModuleHook::AfterExecute).iter() {
+        env.invoke_contract::<Val>(&module, &function, args.clone());
+    }
+}
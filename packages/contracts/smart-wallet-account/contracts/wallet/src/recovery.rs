@@ -0,0 +1,128 @@
+//! Guardian recovery storage.
+//!
+//! Guardians and the `RecoveryConfig` are kept in instance storage next to
+//! the signer index.  The single pending `RecoveryRequest` lives in
+//! persistent storage so it outlasts the recovery delay even if the wallet
+//! is otherwise idle.
+
+use soroban_sdk::{Address, Env, Vec};
+
+use smart_wallet_account_common::{RecoveryConfig, RecoveryRequest, WalletDataKey, WalletError};
+
+use crate::{events, ADMIN_TTL_EXTEND, ADMIN_TTL_THRESHOLD};
+
+/// Default and minimum ledgers a recovery waits before it can be finalized
+/// (~1 day).
+pub const RECOVERY_DELAY: u32 = 17_280;
+
+pub fn config(env: &Env) -> RecoveryConfig {
+    env.storage()
+        .instance()
+        .get(&WalletDataKey::RecoveryConfig)
+        .unwrap_or(RecoveryConfig {
+            threshold: 1,
+            delay_ledgers: RECOVERY_DELAY,
+        })
+}
+
+pub fn set_config(env: &Env, config: &RecoveryConfig) {
+    env.storage()
+        .instance()
+        .set(&WalletDataKey::RecoveryConfig, config);
+}
+
+/// Approvals on `request` from guardians that are still guardians.
+pub fn current_approvals(env: &Env, request: &RecoveryRequest) -> u32 {
+    let guardians = guardians(env);
+    request
+        .approvals
+        .iter()
+        .filter(|guardian| guardians.contains(guardian))
+        .count() as u32
+}
+
+/// Record `guardian`'s approval, starting the delay once the request first
+/// reaches the threshold.
+pub fn add_approval(env: &Env, request: &mut RecoveryRequest, guardian: &Address) {
+    request.approvals.push_back(guardian.clone());
+
+    let config = config(env);
+    if request.executable_ledger == 0 && current_approvals(env, request) >= config.threshold {
+        request.executable_ledger = env.ledger().sequence() + config.delay_ledgers;
+    }
+}
+
+/// Validate and store a new threshold and delay.
+pub fn configure(env: &Env, threshold: u32, delay_ledgers: u32) -> Result<(), WalletError> {
+    if threshold == 0 || threshold > guardians(env).len() {
+        return Err(WalletError::InvalidThreshold);
+    }
+    if delay_ledgers < RECOVERY_DELAY {
+        return Err(WalletError::InvalidDelay);
+    }
+    let config = RecoveryConfig {
+        threshold,
+        delay_ledgers,
+    };
+    set_config(env, &config);
+    events::recovery_config_set(env, &config);
+    Ok(())
+}
+
+/// Add `guardian` unless it already is one.
+pub fn add_guardian(env: &Env, guardian: &Address) {
+    let mut guardians = guardians(env);
+    if !guardians.contains(guardian) {
+        guardians.push_back(guardian.clone());
+        set_guardians(env, &guardians);
+        events::guardian_added(env, guardian);
+    }
+}
+
+/// Remove `guardian`, keeping at least `threshold` guardians unless none
+/// are left.
+pub fn remove_guardian(env: &Env, guardian: &Address) -> Result<(), WalletError> {
+    let mut guardians = guardians(env);
+    let index = guardians
+        .first_index_of(guardian)
+        .ok_or(WalletError::NotGuardian)?;
+    guardians.remove(index);
+    if !guardians.is_empty() && guardians.len() < config(env).threshold {
+        return Err(WalletError::InvalidThreshold);
+    }
+    set_guardians(env, &guardians);
+    events::guardian_removed(env, guardian);
+    Ok(())
+}
+
+pub fn guardians(env: &Env) -> Vec<Address> {
+    env.storage()
+        .instance()
+        .get(&WalletDataKey::Guardians)
+        .unwrap_or_else(|| Vec::new(env))
+}
+
+pub fn set_guardians(env: &Env, guardians: &Vec<Address>) {
+    env.storage()
+        .instance()
+        .set(&WalletDataKey::Guardians, guardians);
+}
+
+pub fn load_request(env: &Env) -> Result<RecoveryRequest, WalletError> {
+    env.storage()
+        .persistent()
+        .get(&WalletDataKey::Recovery)
+        .ok_or(WalletError::RecoveryNotFound)
+}
+
+pub fn save_request(env: &Env, request: &RecoveryRequest) {
+    let key = WalletDataKey::Recovery;
+    env.storage().persistent().set(&key, request);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, ADMIN_TTL_THRESHOLD, ADMIN_TTL_EXTEND);
+}
+
+pub fn clear_request(env: &Env) {
+    env.storage().persistent().remove(&WalletDataKey::Recovery);
+}
@@ -0,0 +1,134 @@
+//! Session signer restrictions.
+//!
+//! A session key's contract allowlist and spending policy are checked
+//! against the calls it authorizes, with the wallet's own `execute` replaced
+//! by the calls in its batch.  Only SEP-41 calls that move or allow moving
+//! the wallet's funds count towards the policy; other invocations only need
+//! to be on the allowlist.  Operators can be held to a token list as well.
+
+use soroban_sdk::{auth::Context, Address, Env, Symbol, TryFromVal, Vec};
+
+use smart_wallet_account_common::{SessionPolicy, Signer, WalletError};
+
+/// Reject contexts outside the signer's contract allowlist, if it has one.
+/// Deploying contracts is never allowed to a scoped session.
+pub fn check_contracts(signer: &Signer, auth_contexts: &Vec<Context>) -> Result<(), WalletError> {
+    if signer.allowed_contracts.is_empty() {
+        return Ok(());
+    }
+    for context in auth_contexts.iter() {
+        let allowed = match context {
+            Context::Contract(call) => signer.allowed_contracts.contains(&call.contract),
+            _ => false,
+        };
+        if !allowed {
+            return Err(WalletError::ContractNotAllowed);
+        }
+    }
+    Ok(())
+}
+
+/// Reject SEP-41 calls that move funds of a token outside `allowed_tokens`,
+/// if it is not empty.
+pub fn check_tokens(
+    env: &Env,
+    allowed_tokens: &Vec<Address>,
+    auth_contexts: &Vec<Context>,
+) -> Result<(), WalletError> {
+    if allowed_tokens.is_empty() {
+        return Ok(());
+    }
+    for context in auth_contexts.iter() {
+        let Context::Contract(call) = context else {
+            continue;
+        };
+        if spend_amount(env, &call.fn_name, &call.args).is_some()
+            && !allowed_tokens.contains(&call.contract)
+        {
+            return Err(WalletError::TokenNotAllowed);
+        }
+    }
+    Ok(())
+}
+
+/// Count one auth against a session's usage cap, returning whether it was
+/// the last one.  Sessions without a cap (`uses_left == 0`) are unchanged.
+pub fn use_once(signer: &mut Signer) -> bool {
+    if signer.uses_left == 0 {
+        return false;
+    }
+    signer.uses_left -= 1;
+    signer.uses_left == 0
+}
+
+/// Reject policies whose caps could never be met.
+pub fn validate(policy: &SessionPolicy) -> Result<(), WalletError> {
+    let positive = |cap: Option<i128>| cap.is_none_or(|cap| cap > 0);
+    if !positive(policy.max_per_tx) || !positive(policy.max_total) {
+        return Err(WalletError::InvalidPolicy);
+    }
+    Ok(())
+}
+
+/// Check the token calls in `auth_contexts` against the signer's policy and
+/// add them to its `spent` total.
+pub fn charge(
+    env: &Env,
+    signer: &mut Signer,
+    auth_contexts: &Vec<Context>,
+) -> Result<(), WalletError> {
+    let policy = &signer.policy;
+    check_tokens(env, &policy.allowed_tokens, auth_contexts)?;
+    let mut total = 0i128;
+    for context in auth_contexts.iter() {
+        let Context::Contract(call) = context else {
+            continue;
+        };
+        let Some(amount) = spend_amount(env, &call.fn_name, &call.args) else {
+            continue;
+        };
+        if amount < 0 || policy.max_per_tx.is_some_and(|cap| amount > cap) {
+            return Err(WalletError::SessionLimitExceeded);
+        }
+        total = total
+            .checked_add(amount)
+            .ok_or(WalletError::SessionLimitExceeded)?;
+    }
+    if total == 0 {
+        return Ok(());
+    }
+
+    let spent = signer
+        .spent
+        .checked_add(total)
+        .ok_or(WalletError::SessionLimitExceeded)?;
+    if policy.max_total.is_some_and(|cap| spent > cap) {
+        return Err(WalletError::SessionLimitExceeded);
+    }
+    signer.spent = spent;
+    Ok(())
+}
+
+/// Amount moved by a SEP-41 token call, or `None` for any other function.
+pub fn spend_amount(env: &Env, fn_name: &Symbol, args: &Vec<soroban_sdk::Val>) -> Option<i128> {
+    // Position of the amount argument in each spending function
+    let index = if *fn_name == Symbol::new(env, "transfer") {
+        2
+    } else if *fn_name == Symbol::new(env, "transfer_from") {
+        3
+    } else if *fn_name == Symbol::new(env, "approve") {
+        2
+    } else if *fn_name == Symbol::new(env, "burn") {
+        1
+    } else if *fn_name == Symbol::new(env, "burn_from") {
+        2
+    } else {
+        return None;
+    };
+    // A malformed call cannot be priced, so it counts as unbounded
+    Some(
+        args.get(index)
+            .and_then(|arg| i128::try_from_val(env, &arg).ok())
+            .unwrap_or(i128::MAX),
+    )
+}
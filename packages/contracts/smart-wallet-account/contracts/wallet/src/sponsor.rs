@@ -0,0 +1,129 @@
+//! Sponsored execution.
+//!
+//! Relayers submit `execute_sponsored` transactions and pay their network
+//! fees; the wallet pays them back in a token from the same batch.  The
+//! relayer allowlist lives in instance storage, like the guardians.
+//!
+//! A relayer can also be held to a number of sponsored operations per UTC
+//! day, counted in temporary storage the way daily spending is, so that
+//! one relayer cannot run up fee reimbursements with a flood of small
+//! batches.
+
+use soroban_sdk::{Address, Env, IntoVal, Symbol, TryFromVal, Val, Vec};
+
+use smart_wallet_account_common::{Invocation, RelayerUsage, WalletDataKey, WalletError};
+
+use crate::{
+    events,
+    limits::{today, SPEND_TTL},
+};
+
+pub fn relayers(env: &Env) -> Vec<Address> {
+    env.storage()
+        .instance()
+        .get(&WalletDataKey::Relayers)
+        .unwrap_or_else(|| Vec::new(env))
+}
+
+fn set_relayers(env: &Env, relayers: &Vec<Address>) {
+    env.storage()
+        .instance()
+        .set(&WalletDataKey::Relayers, relayers);
+}
+
+/// Add `relayer` unless it already is one.
+pub fn add_relayer(env: &Env, relayer: &Address) {
+    let mut relayers = relayers(env);
+    if !relayers.contains(relayer) {
+        relayers.push_back(relayer.clone());
+        set_relayers(env, &relayers);
+        events::relayer_added(env, relayer);
+    }
+}
+
+pub fn remove_relayer(env: &Env, relayer: &Address) -> Result<(), WalletError> {
+    let mut relayers = relayers(env);
+    let index = relayers
+        .first_index_of(relayer)
+        .ok_or(WalletError::RelayerNotAllowed)?;
+    relayers.remove(index);
+    set_relayers(env, &relayers);
+    set_rate_limit(env, relayer, None);
+    events::relayer_removed(env, relayer);
+    Ok(())
+}
+
+pub fn rate_limit(env: &Env, relayer: &Address) -> Option<u32> {
+    env.storage()
+        .persistent()
+        .get(&WalletDataKey::RelayerRateLimit(relayer.clone()))
+}
+
+pub fn set_rate_limit(env: &Env, relayer: &Address, limit: Option<u32>) {
+    let key = WalletDataKey::RelayerRateLimit(relayer.clone());
+    match limit {
+        Some(limit) => env.storage().persistent().set(&key, &limit),
+        None => env.storage().persistent().remove(&key),
+    }
+}
+
+/// Sponsored operations `relayer` has submitted so far today, if it has a
+/// rate limit.
+pub fn used_today(env: &Env, relayer: &Address) -> u32 {
+    env.storage()
+        .temporary()
+        .get::<_, RelayerUsage>(&WalletDataKey::RelayerUsage(relayer.clone()))
+        .filter(|usage| usage.day == today(env))
+        .map_or(0, |usage| usage.count)
+}
+
+/// Count one sponsored operation by `relayer`, failing with
+/// `RelayerRateLimited` once it has used up today's limit.
+pub fn count_use(env: &Env, relayer: &Address) -> Result<(), WalletError> {
+    let Some(limit) = rate_limit(env, relayer) else {
+        return Ok(());
+    };
+    let count = used_today(env, relayer);
+    if count >= limit {
+        return Err(WalletError::RelayerRateLimited);
+    }
+
+    let key = WalletDataKey::RelayerUsage(relayer.clone());
+    let usage = RelayerUsage {
+        day: today(env),
+        count: count + 1,
+    };
+    env.storage().temporary().set(&key, &usage);
+    env.storage()
+        .temporary()
+        .extend_ttl(&key, SPEND_TTL, SPEND_TTL);
+    Ok(())
+}
+
+/// The SEP-41 `transfer` that pays `relayer` back, if `fee` is non-zero.
+pub fn fee_invocation(
+    env: &Env,
+    relayer: &Address,
+    fee_token: &Address,
+    fee: i128,
+) -> Option<Invocation> {
+    (fee != 0).then(|| Invocation {
+        contract: fee_token.clone(),
+        function: Symbol::new(env, "transfer"),
+        args: (env.current_contract_address(), relayer.clone(), fee).into_val(env),
+    })
+}
+
+/// The calls an `execute_sponsored(relayer, invocations, fee_token, fee)`
+/// call with `args` makes, fee transfer last.
+pub fn batch(env: &Env, args: &Vec<Val>) -> Option<Vec<Invocation>> {
+    let arg = |index| args.get(index);
+    let relayer = Address::try_from_val(env, &arg(0)?).ok()?;
+    let mut invocations = Vec::<Invocation>::try_from_val(env, &arg(1)?).ok()?;
+    let fee_token = Address::try_from_val(env, &arg(2)?).ok()?;
+    let fee = i128::try_from_val(env, &arg(3)?).ok()?;
+    if let Some(fee) = fee_invocation(env, &relayer, &fee_token, fee) {
+        invocations.push_back(fee);
+    }
+    Some(invocations)
+}
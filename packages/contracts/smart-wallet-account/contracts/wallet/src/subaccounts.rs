@@ -0,0 +1,124 @@
+//! Sub-accounts.
+//!
+//! A sub-account is a named set of token budgets inside the wallet.
+//! Operators and session signers assigned to one can only move the tokens
+//! it has a budget for, and each SEP-41 call they authorize is taken off
+//! that budget, so e.g. a "trading" sub-account run by session keys is
+//! isolated from the rest of the wallet's funds.  Budgets are accounting,
+//! not separate balances: all funds stay at the wallet's address, and
+//! admins spend them under the wallet's own rules.  Sub-accounts live in
+//! instance storage, like the module lists.
+
+use soroban_sdk::{auth::Context, Address, Env, Map, Symbol, Vec};
+
+use smart_wallet_account_common::{WalletDataKey, WalletError};
+
+use crate::{events, session::spend_amount};
+
+/// Most sub-accounts a wallet can have.
+pub const MAX_SUB_ACCOUNTS: u32 = 10;
+
+pub fn names(env: &Env) -> Vec<Symbol> {
+    env.storage()
+        .instance()
+        .get(&WalletDataKey::SubAccounts)
+        .unwrap_or_else(|| Vec::new(env))
+}
+
+fn set_names(env: &Env, names: &Vec<Symbol>) {
+    env.storage()
+        .instance()
+        .set(&WalletDataKey::SubAccounts, names);
+}
+
+/// Token budgets left in sub-account `name`.
+pub fn budgets(env: &Env, name: &Symbol) -> Result<Map<Address, i128>, WalletError> {
+    env.storage()
+        .instance()
+        .get(&WalletDataKey::SubAccount(name.clone()))
+        .ok_or(WalletError::SubAccountNotFound)
+}
+
+fn set_budgets(env: &Env, name: &Symbol, budgets: &Map<Address, i128>) {
+    env.storage()
+        .instance()
+        .set(&WalletDataKey::SubAccount(name.clone()), budgets);
+}
+
+pub fn create(env: &Env, name: &Symbol) -> Result<(), WalletError> {
+    let mut names = names(env);
+    if names.contains(name) {
+        return Err(WalletError::SubAccountExists);
+    }
+    if names.len() >= MAX_SUB_ACCOUNTS {
+        return Err(WalletError::TooManySubAccounts);
+    }
+    names.push_back(name.clone());
+    set_names(env, &names);
+    set_budgets(env, name, &Map::new(env));
+    events::sub_account_created(env, name);
+    Ok(())
+}
+
+pub fn remove(env: &Env, name: &Symbol) -> Result<(), WalletError> {
+    let mut names = names(env);
+    let index = names
+        .first_index_of(name)
+        .ok_or(WalletError::SubAccountNotFound)?;
+    names.remove(index);
+    set_names(env, &names);
+    env.storage()
+        .instance()
+        .remove(&WalletDataKey::SubAccount(name.clone()));
+    events::sub_account_removed(env, name);
+    Ok(())
+}
+
+/// Set what sub-account `name` may still move of `token`; 0 removes the
+/// budget.
+pub fn set_budget(
+    env: &Env,
+    name: &Symbol,
+    token: &Address,
+    amount: i128,
+) -> Result<(), WalletError> {
+    if amount < 0 {
+        return Err(WalletError::InvalidPolicy);
+    }
+    let mut budgets = budgets(env, name)?;
+    if amount == 0 {
+        budgets.remove(token.clone());
+    } else {
+        budgets.set(token.clone(), amount);
+    }
+    set_budgets(env, name, &budgets);
+    events::sub_account_budget_set(env, name, token, amount);
+    Ok(())
+}
+
+/// Take the token calls in `calls` off sub-account `name`'s budgets,
+/// failing with `BudgetExceeded` if any token has too little left.
+pub fn charge(env: &Env, name: &Symbol, calls: &Vec<Context>) -> Result<(), WalletError> {
+    let mut budgets = budgets(env, name)?;
+    let mut charged = false;
+    for context in calls.iter() {
+        let Context::Contract(call) = context else {
+            continue;
+        };
+        let Some(amount) = spend_amount(env, &call.fn_name, &call.args) else {
+            continue;
+        };
+        let left = budgets
+            .get(call.contract.clone())
+            .unwrap_or(0)
+            .checked_sub(amount.max(0))
+            .filter(|left| *left >= 0)
+            .ok_or(WalletError::BudgetExceeded)?;
+        budgets.set(call.contract, left);
+        charged = true;
+    }
+    if charged {
+        set_budgets(env, name, &budgets);
+    }
+    Ok(())
+}
@@ -0,0 +1,4296 @@
+extern crate std;
+
+use ed25519_dalek::{Signer as _, SigningKey};
+use p256::ecdsa::signature::hazmat::PrehashSigner;
+use soroban_sdk::{
+    auth::{Context, ContractContext},
+    contract, contractimpl, contracttype, symbol_short,
+    testutils::{Address as _, AuthorizedFunction, AuthorizedInvocation, Events, Ledger},
+    token, vec,
+    xdr::{self, ToXdr},
+    Address, Bytes, BytesN, Env, IntoVal, InvokeError, String, Symbol, TryFromVal, Val, Vec,
+};
+
+use smart_wallet_account_common::{
+    AccountSignature, AdminOperation, AuditAction, AuditActor, AuditEntry, GasTank, InitialSigner,
+    Intent, Invocation, KeeperTask, ModuleHook, QueuedAction, RecoveryConfig, RecoveryRequest,
+    SessionPolicy, SessionSig, Signature, Signer, SignerInfo, SignerKind, TimelockOperation,
+    WalletDataKey, WalletError, WalletInfo, WalletProfile,
+};
+
+use crate::{
+    audit, base64url_encode, ecdsa, modules, recovery::RECOVERY_DELAY, subaccounts,
+    unrestricted_policy, SmartWallet, SmartWalletClient,
+};
+
+mod vectors;
+
+// ────────────────────────────────────────────────────────
+//  Test helpers
+// ────────────────────────────────────────────────────────
+
+/// Generate a dummy 65-byte uncompressed public key (0x04 ‖ X ‖ Y).
+fn dummy_public_key(env: &Env, seed: u8) -> BytesN<65> {
+    let mut bytes = [0u8; 65];
+    bytes[0] = 0x04;
+    for (i, byte) in bytes.iter_mut().enumerate().skip(1) {
+        *byte = seed.wrapping_add(i as u8);
+    }
+    BytesN::from_array(env, &bytes)
+}
+
+/// Create a credential ID from `name`, long enough to be a valid one.
+fn cred_id(env: &Env, name: &str) -> Bytes {
+    let mut id = Bytes::from_slice(env, b"test-credential-");
+    id.append(&Bytes::from_slice(env, name.as_bytes()));
+    id
+}
+
+/// Deploy and initialize a wallet contract, returning the client and initial cred.
+fn setup_wallet(env: &Env) -> (SmartWalletClient<'_>, Bytes) {
+    let credential_id = cred_id(env, "admin-0");
+    let contract_id = env.register(
+        SmartWallet,
+        (
+            credential_id.clone(),
+            dummy_public_key(env, 1),
+            Vec::<InitialSigner>::new(env),
+        ),
+    );
+    let client = SmartWalletClient::new(env, &contract_id);
+
+    (client, credential_id)
+}
+
+/// Read the stored admin signer count.
+fn admin_count(env: &Env, client: &SmartWalletClient<'_>) -> u32 {
+    env.as_contract(&client.address, || {
+        env.storage()
+            .instance()
+            .get(&WalletDataKey::AdminSignerCount)
+            .unwrap()
+    })
+}
+
+#[test]
+fn test_constructor_initializes_wallet() {
+    let env = Env::default();
+    let (client, admin) = setup_wallet(&env);
+    assert_eq!(admin_count(&env, &client), 1);
+    assert_eq!(client.version(), 4);
+    assert_eq!(client.get_signer(&admin).kind, SignerKind::Admin);
+}
+
+#[test]
+fn test_constructor_adds_initial_signers() {
+    let env = Env::default();
+    let (phone, backup, operator) = (
+        cred_id(&env, "admin-0"),
+        cred_id(&env, "admin-1"),
+        cred_id(&env, "operator-0"),
+    );
+    let initial_signer = |credential_id: &Bytes, seed: u8, kind: SignerKind| InitialSigner {
+        credential_id: credential_id.clone(),
+        public_key: dummy_public_key(&env, seed),
+        kind,
+    };
+    let contract_id = env.register(
+        SmartWallet,
+        (
+            phone.clone(),
+            dummy_public_key(&env, 1),
+            vec![
+                &env,
+                initial_signer(&backup, 2, SignerKind::Admin),
+                initial_signer(&operator, 3, SignerKind::Operator),
+            ],
+        ),
+    );
+    let client = SmartWalletClient::new(&env, &contract_id);
+
+    assert_eq!(admin_count(&env, &client), 2);
+    assert_eq!(client.get_signer(&backup).kind, SignerKind::Admin);
+    assert_eq!(client.get_signer(&operator).kind, SignerKind::Operator);
+    assert_eq!(
+        client
+            .list_signers(&0, &10)
+            .iter()
+            .map(|signer| signer.credential_id)
+            .collect::<std::vec::Vec<_>>(),
+        [phone, backup, operator]
+    );
+}
+
+// ────────────────────────────────────────────────────────
+//  Last admin protection
+// ────────────────────────────────────────────────────────
+
+#[test]
+fn test_admin_count_tracks_add_and_remove() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, admin) = setup_wallet(&env);
+    assert_eq!(admin_count(&env, &client), 1);
+
+    let second = cred_id(&env, "admin-1");
+    client.add_signer(&second, &dummy_public_key(&env, 2));
+    assert_eq!(admin_count(&env, &client), 2);
+
+    // Session signers do not count towards the admins
+    let session = cred_id(&env, "session-0");
+    client.add_session_signer(
+        &session,
+        &BytesN::from_array(&env, &[7u8; 32]),
+        &100,
+        &Vec::new(&env),
+        &0,
+    );
+    client.remove_signer(&session);
+    assert_eq!(admin_count(&env, &client), 2);
+
+    client.remove_signer(&admin);
+    assert_eq!(admin_count(&env, &client), 1);
+}
+
+#[test]
+fn test_remove_last_admin_signer_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, admin) = setup_wallet(&env);
+
+    assert_eq!(
+        client.try_remove_signer(&admin),
+        Err(Ok(WalletError::LastAdminSigner))
+    );
+
+    // Once another admin is added the first can go, but not the second
+    let second = cred_id(&env, "admin-1");
+    client.add_signer(&second, &dummy_public_key(&env, 2));
+    client.remove_signer(&admin);
+    assert_eq!(
+        client.try_remove_signer(&second),
+        Err(Ok(WalletError::LastAdminSigner))
+    );
+    assert_eq!(admin_count(&env, &client), 1);
+}
+
+#[test]
+fn test_rejected_admin_add_leaves_count_unchanged() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, admin) = setup_wallet(&env);
+
+    assert_eq!(
+        client.try_add_signer(&admin, &dummy_public_key(&env, 3)),
+        Err(Ok(WalletError::SignerAlreadyExists))
+    );
+    assert_eq!(admin_count(&env, &client), 1);
+    assert_eq!(
+        client.try_remove_signer(&admin),
+        Err(Ok(WalletError::LastAdminSigner))
+    );
+}
+
+// ────────────────────────────────────────────────────────
+//  Signer enumeration
+// ────────────────────────────────────────────────────────
+
+#[test]
+fn test_list_signers_pages_live_signers() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, admin) = setup_wallet(&env);
+
+    let second = cred_id(&env, "admin-1");
+    client.add_signer(&second, &dummy_public_key(&env, 2));
+    let session = cred_id(&env, "session-0");
+    let session_key = BytesN::from_array(&env, &[7u8; 32]);
+    client.add_session_signer(&session, &session_key, &100, &Vec::new(&env), &0);
+
+    let signers = client.list_signers(&0, &10);
+    assert_eq!(signers.len(), 3);
+    assert_eq!(
+        signers.get(0).unwrap(),
+        SignerInfo {
+            credential_id: admin.clone(),
+            kind: SignerKind::Admin,
+            public_key: dummy_public_key(&env, 1).into(),
+        }
+    );
+    assert_eq!(
+        signers.get(2).unwrap(),
+        SignerInfo {
+            credential_id: session.clone(),
+            kind: SignerKind::Session,
+            public_key: session_key.into(),
+        }
+    );
+
+    let page = client.list_signers(&1, &1);
+    assert_eq!(page.len(), 1);
+    assert_eq!(page.get(0).unwrap().credential_id, second);
+
+    client.remove_signer(&admin);
+    let signers = client.list_signers(&0, &10);
+    assert_eq!(signers.len(), 2);
+    assert_eq!(signers.get(0).unwrap().credential_id, second);
+}
+
+#[test]
+fn test_list_signers_skips_expired_sessions() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, admin) = setup_wallet(&env);
+
+    let session = cred_id(&env, "session-0");
+    client.add_session_signer(
+        &session,
+        &BytesN::from_array(&env, &[7u8; 32]),
+        &100,
+        &Vec::new(&env),
+        &0,
+    );
+    assert_eq!(client.list_signers(&0, &10).len(), 2);
+
+    env.ledger().with_mut(|li| li.sequence_number += 200);
+    let signers = client.list_signers(&0, &10);
+    assert_eq!(signers.len(), 1);
+    assert_eq!(signers.get(0).unwrap().credential_id, admin);
+
+    // The same credential can be registered again without duplicating it
+    client.add_session_signer(
+        &session,
+        &BytesN::from_array(&env, &[8u8; 32]),
+        &100,
+        &Vec::new(&env),
+        &0,
+    );
+    assert_eq!(client.list_signers(&0, &10).len(), 2);
+}
+
+#[test]
+fn test_revoke_all_session_signers() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, admin) = setup_wallet(&env);
+    let operator = cred_id(&env, "operator-0");
+    client.add_operator(&operator, &dummy_public_key(&env, 2));
+    let sessions = [cred_id(&env, "session-0"), cred_id(&env, "session-1")];
+    for session in &sessions {
+        add_session(&env, &client, session);
+    }
+
+    assert_eq!(client.revoke_all_session_signers(), 2);
+    let events = env.events().all();
+    assert_eq!(events.events().len(), 2);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("sgn_rem"), sessions[1].clone()).into_val(&env),
+        SignerKind::Session.into_val(&env),
+    );
+    for session in &sessions {
+        assert!(matches!(
+            client.try_get_signer(session),
+            Err(Ok(WalletError::SignerNotFound))
+        ));
+    }
+    let signers = client.list_signers(&0, &10);
+    assert_eq!(signers.len(), 2);
+    assert_eq!(signers.get(0).unwrap().credential_id, admin);
+    assert_eq!(signers.get(1).unwrap().credential_id, operator);
+
+    assert_eq!(client.revoke_all_session_signers(), 0);
+}
+
+#[test]
+fn test_signer_limits() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup_wallet(&env);
+
+    for len in [15, 1024] {
+        let id = Bytes::from_slice(&env, &std::vec![1u8; len]);
+        assert_eq!(
+            client.try_add_signer(&id, &dummy_public_key(&env, 2)),
+            Err(Ok(WalletError::InvalidCredentialId))
+        );
+        assert_eq!(
+            client.try_add_session_signer(
+                &id,
+                &BytesN::from_array(&env, &[7u8; 32]),
+                &100,
+                &Vec::new(&env),
+                &0
+            ),
+            Err(Ok(WalletError::InvalidCredentialId))
+        );
+    }
+    let long = Bytes::from_slice(&env, &[1u8; 128]);
+    client.add_operator(&long, &dummy_public_key(&env, 2));
+
+    // Fill the wallet, the last slot with a session key
+    for i in 2..19 {
+        client.add_signer(
+            &cred_id(&env, &std::format!("admin-{i}")),
+            &dummy_public_key(&env, 2),
+        );
+    }
+    let session = cred_id(&env, "session-0");
+    client.add_session_signer(
+        &session,
+        &BytesN::from_array(&env, &[7u8; 32]),
+        &100,
+        &Vec::new(&env),
+        &0,
+    );
+    assert_eq!(client.get_wallet_info().admin_signers, 18);
+    assert_eq!(
+        client.try_add_operator(&cred_id(&env, "operator-0"), &dummy_public_key(&env, 3)),
+        Err(Ok(WalletError::TooManySigners))
+    );
+    assert_eq!(
+        client.try_add_session_signer(
+            &cred_id(&env, "session-1"),
+            &BytesN::from_array(&env, &[8u8; 32]),
+            &100,
+            &Vec::new(&env),
+            &0
+        ),
+        Err(Ok(WalletError::TooManySigners))
+    );
+
+    // An expired session frees its slot
+    env.ledger().with_mut(|li| li.sequence_number += 200);
+    client.add_operator(&cred_id(&env, "operator-0"), &dummy_public_key(&env, 3));
+
+    // Guardian recovery is not held to the cap
+    let guardian = Address::generate(&env);
+    client.add_guardian(&guardian);
+    assert_eq!(
+        client.try_propose_recovery(&guardian, &Bytes::new(&env), &dummy_public_key(&env, 9)),
+        Err(Ok(WalletError::InvalidCredentialId))
+    );
+    let recovered = cred_id(&env, "admin-recovered");
+    client.propose_recovery(&guardian, &recovered, &dummy_public_key(&env, 9));
+    env.ledger()
+        .with_mut(|li| li.sequence_number += RECOVERY_DELAY);
+    client.finalize_recovery();
+    assert_eq!(client.get_wallet_info().admin_signers, 19);
+}
+
+#[test]
+fn test_audit_log() {
+    let env = long_lived_env();
+    let key = passkey(1);
+    let (client, admin) = setup_passkey_wallet(&env, &key);
+    let signed_by_admin = AuditActor::Signers(vec![&env, admin.clone()]);
+    let entry = |id: u64, action: AuditAction| AuditEntry {
+        id,
+        action,
+        actor: signed_by_admin.clone(),
+        ledger: env.ledger().sequence(),
+        timestamp: env.ledger().timestamp(),
+    };
+
+    // Mocked auths skip `__check_auth`, so run it first as the host would
+    let payload = BytesN::from_array(&env, &[9u8; 32]);
+    let signature = webauthn_signature(&env, &key, &admin, &payload);
+    check_auth(&env, &client, signature, "freeze", Vec::new(&env)).unwrap();
+    client.freeze();
+    client.unfreeze();
+    assert_eq!(
+        client.get_audit_log(&0, &10),
+        vec![
+            &env,
+            entry(0, AuditAction::Freeze),
+            entry(1, AuditAction::Unfreeze)
+        ]
+    );
+
+    // Proposals record their approvals, recoveries their guardians
+    env.ledger()
+        .with_mut(|li| li.sequence_number += RECOVERY_DELAY);
+    let (second, third) = (cred_id(&env, "admin-1"), cred_id(&env, "admin-2"));
+    client.add_signer(&second, &dummy_public_key(&env, 2));
+    client.add_signer(&third, &dummy_public_key(&env, 3));
+    let guardian = Address::generate(&env);
+    client.add_guardian(&guardian);
+    client.set_threshold(&2);
+    let removal = client.propose(&AdminOperation::RemoveSigner(third.clone()));
+    client.approve(&removal, &admin);
+    client.approve(&removal, &second);
+    client.execute_proposal(&removal);
+    let recovered = cred_id(&env, "admin-recovered");
+    client.propose_recovery(&guardian, &recovered, &dummy_public_key(&env, 9));
+    env.ledger()
+        .with_mut(|li| li.sequence_number += RECOVERY_DELAY);
+    client.finalize_recovery();
+    let log = client.get_audit_log(&4, &2);
+    assert_eq!(log.len(), 2);
+    let removed = log.get(0).unwrap();
+    assert_eq!(
+        (removed.id, removed.action, removed.actor),
+        (
+            4,
+            AuditAction::RemoveSigner(third, SignerKind::Admin),
+            AuditActor::Approvals(vec![&env, admin, second])
+        )
+    );
+    let recovery = log.get(1).unwrap();
+    assert_eq!(
+        (recovery.id, recovery.action, recovery.actor),
+        (
+            5,
+            AuditAction::AddSigner(recovered, SignerKind::Admin),
+            AuditActor::Guardians(vec![&env, guardian])
+        )
+    );
+
+    // Only the latest entries are kept, and pages are capped
+    let session = cred_id(&env, "session-0");
+    for _ in 0..audit::MAX_AUDIT_ENTRIES / 2 {
+        add_session(&env, &client, &session);
+        client.remove_signer(&session);
+    }
+    let log = client.get_audit_log(&0, &100);
+    assert_eq!(log.len(), audit::MAX_AUDIT_PAGE);
+    assert_eq!(log.get(0).unwrap().id, 6);
+    let last = client.get_audit_log(&(audit::MAX_AUDIT_ENTRIES + 5), &10);
+    assert_eq!(last.len(), 1);
+    assert_eq!(
+        last.get(0).unwrap().action,
+        AuditAction::RemoveSigner(session, SignerKind::Session)
+    );
+}
+
+#[test]
+fn test_wallet_info() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup_wallet(&env);
+    let empty = env.crypto().sha256(&Vec::<Address>::new(&env).to_xdr(&env));
+
+    let info = client.get_wallet_info();
+    assert_eq!(
+        info,
+        WalletInfo {
+            version: client.version(),
+            admin_signers: 1,
+            operator_signers: 0,
+            session_signers: 0,
+            frozen: false,
+            threshold: 1,
+            auth_threshold: 1,
+            timelock_delay: 0,
+            user_verification: false,
+            attestation_required: false,
+            guardians: 0,
+            recovery_config: client.get_recovery_config(),
+            recovery_pending: false,
+            origins_digest: empty.to_bytes(),
+            rp_id_hashes_digest: empty.to_bytes(),
+            relayers_digest: empty.to_bytes(),
+            modules_digest: info.modules_digest.clone(),
+        }
+    );
+
+    client.add_signer(&cred_id(&env, "admin-1"), &dummy_public_key(&env, 2));
+    client.add_operator(&cred_id(&env, "operator-0"), &dummy_public_key(&env, 3));
+    client.add_session_signer(
+        &cred_id(&env, "session-0"),
+        &BytesN::from_array(&env, &[7u8; 32]),
+        &100,
+        &Vec::new(&env),
+        &0,
+    );
+    let guardian = Address::generate(&env);
+    client.add_guardian(&guardian);
+    client.set_threshold(&2);
+    client.propose_recovery(
+        &guardian,
+        &cred_id(&env, "recovered"),
+        &dummy_public_key(&env, 9),
+    );
+    let relayer = Address::generate(&env);
+    client.add_relayer(&relayer);
+    client.freeze();
+
+    let changed = client.get_wallet_info();
+    assert_eq!(
+        (
+            changed.admin_signers,
+            changed.operator_signers,
+            changed.session_signers
+        ),
+        (2, 1, 1)
+    );
+    assert!(changed.frozen);
+    assert_eq!(changed.threshold, 2);
+    assert_eq!(changed.guardians, 1);
+    assert!(changed.recovery_pending);
+    assert_eq!(
+        changed.relayers_digest,
+        env.crypto()
+            .sha256(&vec![&env, relayer].to_xdr(&env))
+            .to_bytes()
+    );
+    assert_eq!(changed.origins_digest, info.origins_digest);
+    assert_eq!(changed.modules_digest, info.modules_digest);
+
+    // Expired sessions are not counted
+    env.ledger().with_mut(|li| li.sequence_number += 200);
+    assert_eq!(client.get_wallet_info().session_signers, 0);
+}
+
+// ────────────────────────────────────────────────────────
+//  Events
+// ────────────────────────────────────────────────────────
+
+/// Assert the most recent event published by the wallet.
+fn assert_last_event(env: &Env, client: &SmartWalletClient<'_>, topics: Vec<Val>, data: Val) {
+    let events = env.events().all();
+    let last = events.events().last().expect("no events");
+    assert_eq!(
+        events.filter_by_contract(&client.address).events().last(),
+        Some(last),
+        "last event is not the wallet's"
+    );
+    let xdr::ContractEventBody::V0(body) = &last.body;
+    let topics: std::vec::Vec<xdr::ScVal> = topics
+        .iter()
+        .map(|topic| xdr::ScVal::try_from_val(env, &topic).unwrap())
+        .collect();
+    assert_eq!(body.topics.to_vec(), topics);
+    assert_eq!(body.data, xdr::ScVal::try_from_val(env, &data).unwrap());
+}
+
+#[test]
+fn test_signer_management_emits_events() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, admin) = setup_wallet(&env);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("init"), admin.clone()).into_val(&env),
+        ().into_val(&env),
+    );
+
+    let second = cred_id(&env, "admin-1");
+    client.add_signer(&second, &dummy_public_key(&env, 2));
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("sgn_add"), second.clone()).into_val(&env),
+        (SignerKind::Admin, 0u32).into_val(&env),
+    );
+
+    let session = cred_id(&env, "session-0");
+    client.add_session_signer(
+        &session,
+        &BytesN::from_array(&env, &[7u8; 32]),
+        &100,
+        &Vec::new(&env),
+        &0,
+    );
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("sgn_add"), session.clone()).into_val(&env),
+        (SignerKind::Session, 100u32).into_val(&env),
+    );
+
+    client.remove_signer(&session);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("sgn_rem"), session).into_val(&env),
+        SignerKind::Session.into_val(&env),
+    );
+    client.remove_signer(&admin);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("sgn_rem"), admin).into_val(&env),
+        SignerKind::Admin.into_val(&env),
+    );
+}
+
+#[test]
+fn test_session_auth_emits_event() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup_wallet(&env);
+
+    let session = cred_id(&env, "session-0");
+    let key = add_session(&env, &client, &session);
+
+    let signature = session_signature(&env, &key, &session);
+    check_auth_contexts(&env, &client, signature, Vec::new(&env)).unwrap();
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("auth"), session).into_val(&env),
+        SignerKind::Session.into_val(&env),
+    );
+}
+
+// ────────────────────────────────────────────────────────
+//  Multisig admin operations
+// ────────────────────────────────────────────────────────
+
+/// Deterministic P-256 passkey for tests.
+fn passkey(seed: u8) -> p256::ecdsa::SigningKey {
+    p256::ecdsa::SigningKey::from_slice(&[seed; 32]).unwrap()
+}
+
+/// SEC-1 uncompressed public key of a test passkey.
+fn passkey_public_key(env: &Env, key: &p256::ecdsa::SigningKey) -> BytesN<65> {
+    let point = key.verifying_key().to_encoded_point(false);
+    BytesN::from_array(env, point.as_bytes().try_into().unwrap())
+}
+
+/// A WebAuthn assertion by `key` over `payload`, as a browser would build it.
+fn webauthn_signature(
+    env: &Env,
+    key: &p256::ecdsa::SigningKey,
+    credential_id: &Bytes,
+    payload: &BytesN<32>,
+) -> AccountSignature {
+    webauthn_signature_with(env, key, credential_id, payload, "webauthn.get", 0x05)
+}
+
+/// Like [`webauthn_signature`], with `client_data_type` as the
+/// clientDataJSON `type` and `flags` as the authenticator data flags.
+fn webauthn_signature_with(
+    env: &Env,
+    key: &p256::ecdsa::SigningKey,
+    credential_id: &Bytes,
+    payload: &BytesN<32>,
+    client_data_type: &str,
+    flags: u8,
+) -> AccountSignature {
+    let client_data_json = assertion_client_data(env, client_data_type, payload);
+    signed_client_data(env, key, credential_id, client_data_json, flags)
+}
+
+/// A clientDataJSON of `client_data_type` challenging `payload`.
+fn assertion_client_data(env: &Env, client_data_type: &str, payload: &BytesN<32>) -> Bytes {
+    let mut client_data_json = Bytes::from_slice(env, b"{\"type\":\"");
+    client_data_json.append(&Bytes::from_slice(env, client_data_type.as_bytes()));
+    client_data_json.append(&Bytes::from_slice(env, b"\",\"challenge\":\""));
+    client_data_json.append(&base64url_encode(env, &payload.to_array()));
+    client_data_json.append(&Bytes::from_slice(
+        env,
+        b"\",\"origin\":\"https://wallet.galaxy.dev\"}",
+    ));
+    client_data_json
+}
+
+/// A WebAuthn assertion by `key` over `client_data_json` as given.
+fn signed_client_data(
+    env: &Env,
+    key: &p256::ecdsa::SigningKey,
+    credential_id: &Bytes,
+    client_data_json: Bytes,
+    flags: u8,
+) -> AccountSignature {
+    let mut auth_data = [0u8; 37];
+    auth_data[32] = flags;
+    let authenticator_data = Bytes::from_array(env, &auth_data);
+    signed_assertion(
+        env,
+        key,
+        credential_id,
+        client_data_json,
+        authenticator_data,
+    )
+}
+
+/// A WebAuthn assertion by `key` with `authenticator_data` as given.
+fn signed_assertion(
+    env: &Env,
+    key: &p256::ecdsa::SigningKey,
+    credential_id: &Bytes,
+    client_data_json: Bytes,
+    authenticator_data: Bytes,
+) -> AccountSignature {
+    let mut signed_data = authenticator_data.clone();
+    signed_data.append(&Bytes::from_array(
+        env,
+        &env.crypto().sha256(&client_data_json).to_array(),
+    ));
+    let digest = env.crypto().sha256(&signed_data).to_array();
+    let signature: p256::ecdsa::Signature = key.sign_prehash(&digest).unwrap();
+    let signature = signature.normalize_s().unwrap_or(signature);
+
+    AccountSignature::WebAuthn(Signature {
+        authenticator_data,
+        client_data_json,
+        id: credential_id.clone(),
+        signature: Bytes::from_slice(env, &signature.to_bytes()),
+    })
+}
+
+/// A call to `contract.fn_name(args)` as seen by `__check_auth`.
+fn call_context(env: &Env, contract: &Address, fn_name: &str, args: Vec<Val>) -> Context {
+    Context::Contract(ContractContext {
+        contract: contract.clone(),
+        fn_name: Symbol::new(env, fn_name),
+        args,
+    })
+}
+
+/// Run `__check_auth` for a single call to the wallet's `fn_name`.
+fn check_auth(
+    env: &Env,
+    client: &SmartWalletClient<'_>,
+    signature: AccountSignature,
+    fn_name: &str,
+    args: Vec<Val>,
+) -> Result<(), Result<WalletError, InvokeError>> {
+    let context = call_context(env, &client.address, fn_name, args);
+    check_auth_contexts(env, client, signature, vec![env, context])
+}
+
+/// Run `__check_auth` for `contexts`, signed over the fixed test payload.
+fn check_auth_contexts(
+    env: &Env,
+    client: &SmartWalletClient<'_>,
+    signature: AccountSignature,
+    contexts: Vec<Context>,
+) -> Result<(), Result<WalletError, InvokeError>> {
+    let payload = BytesN::from_array(env, &[9u8; 32]);
+    env.try_invoke_contract_check_auth::<WalletError>(
+        &client.address,
+        &payload,
+        signature.into_val(env),
+        &contexts,
+    )
+}
+
+/// A session key signature over the fixed test payload.
+fn session_signature(env: &Env, key: &SigningKey, credential_id: &Bytes) -> AccountSignature {
+    AccountSignature::SessionKey(SessionSig {
+        id: credential_id.clone(),
+        signature: BytesN::from_array(env, &key.sign(&[9u8; 32]).to_bytes()),
+    })
+}
+
+/// Register a session key for `credential_id` and return its signing key.
+fn add_session(env: &Env, client: &SmartWalletClient<'_>, credential_id: &Bytes) -> SigningKey {
+    let key = SigningKey::from_bytes(&[5u8; 32]);
+    client.add_session_signer(
+        credential_id,
+        &BytesN::from_array(env, &key.verifying_key().to_bytes()),
+        &100,
+        &Vec::new(env),
+        &0,
+    );
+    key
+}
+
+#[test]
+fn test_threshold_gates_admin_operations() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, admin) = setup_wallet(&env);
+    let second = cred_id(&env, "admin-1");
+    let third = cred_id(&env, "admin-2");
+    client.add_signer(&second, &dummy_public_key(&env, 2));
+    client.add_signer(&third, &dummy_public_key(&env, 3));
+
+    assert_eq!(client.get_threshold(), 1);
+    assert_eq!(
+        client.try_set_threshold(&0),
+        Err(Ok(WalletError::InvalidThreshold))
+    );
+    assert_eq!(
+        client.try_set_threshold(&4),
+        Err(Ok(WalletError::InvalidThreshold))
+    );
+    client.set_threshold(&2);
+    assert_eq!(client.get_threshold(), 2);
+
+    // Admin changes now need a proposal; session keys do not
+    assert_eq!(
+        client.try_add_signer(&cred_id(&env, "admin-3"), &dummy_public_key(&env, 4)),
+        Err(Ok(WalletError::ApprovalRequired))
+    );
+    assert_eq!(
+        client.try_remove_signer(&third),
+        Err(Ok(WalletError::ApprovalRequired))
+    );
+    assert_eq!(
+        client.try_set_threshold(&1),
+        Err(Ok(WalletError::ApprovalRequired))
+    );
+    let session = cred_id(&env, "session-0");
+    client.add_session_signer(
+        &session,
+        &BytesN::from_array(&env, &[7u8; 32]),
+        &100,
+        &Vec::new(&env),
+        &0,
+    );
+    client.remove_signer(&session);
+
+    let id = client.propose(&AdminOperation::RemoveSigner(third.clone()));
+    assert_eq!(
+        client.try_execute_proposal(&id),
+        Err(Ok(WalletError::InsufficientApprovals))
+    );
+    client.approve(&id, &admin);
+    assert_eq!(
+        client.try_approve(&id, &admin),
+        Err(Ok(WalletError::AlreadyApproved))
+    );
+    assert_eq!(
+        client.try_execute_proposal(&id),
+        Err(Ok(WalletError::InsufficientApprovals))
+    );
+    client.approve(&id, &second);
+    assert_eq!(
+        client.get_proposal(&id).approvals,
+        vec![&env, admin.clone(), second.clone()]
+    );
+    client.execute_proposal(&id);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("executed"), id).into_val(&env),
+        ().into_val(&env),
+    );
+    assert_eq!(admin_count(&env, &client), 2);
+    assert_eq!(
+        client.try_get_proposal(&id),
+        Err(Ok(WalletError::ProposalNotFound))
+    );
+
+    // Removing another admin would leave the threshold unreachable
+    let id = client.propose(&AdminOperation::RemoveSigner(second.clone()));
+    client.approve(&id, &admin);
+    client.approve(&id, &second);
+    assert_eq!(
+        client.try_execute_proposal(&id),
+        Err(Ok(WalletError::InvalidThreshold))
+    );
+}
+
+#[test]
+fn test_approval_must_be_signed_by_the_approving_admin() {
+    let env = Env::default();
+    let (key_a, key_b) = (passkey(1), passkey(2));
+    let (admin_a, admin_b) = (cred_id(&env, "admin-a"), cred_id(&env, "admin-b"));
+    let contract_id = env.register(
+        SmartWallet,
+        (
+            admin_a.clone(),
+            passkey_public_key(&env, &key_a),
+            Vec::<InitialSigner>::new(&env),
+        ),
+    );
+    let client = SmartWalletClient::new(&env, &contract_id);
+    env.mock_all_auths();
+    client.add_signer(&admin_b, &passkey_public_key(&env, &key_b));
+    let session = cred_id(&env, "session-0");
+    let session_key = add_session(&env, &client, &session);
+
+    let approve_as_b: Vec<Val> = (1u64, admin_b.clone()).into_val(&env);
+    let payload = BytesN::from_array(&env, &[9u8; 32]);
+    assert_eq!(
+        check_auth(
+            &env,
+            &client,
+            webauthn_signature(&env, &key_a, &admin_a, &payload),
+            "approve",
+            approve_as_b.clone(),
+        ),
+        Err(Ok(WalletError::NotAuthorized))
+    );
+    assert_eq!(
+        check_auth(
+            &env,
+            &client,
+            session_signature(&env, &session_key, &session),
+            "approve",
+            approve_as_b.clone()
+        ),
+        Err(Ok(WalletError::NotAuthorized))
+    );
+    check_auth(
+        &env,
+        &client,
+        webauthn_signature(&env, &key_b, &admin_b, &payload),
+        "approve",
+        approve_as_b,
+    )
+    .unwrap();
+
+    // Other calls are not bound to a credential
+    check_auth(
+        &env,
+        &client,
+        webauthn_signature(&env, &key_a, &admin_a, &payload),
+        "set_threshold",
+        (2u32,).into_val(&env),
+    )
+    .unwrap();
+}
+
+// ────────────────────────────────────────────────────────
+//  Session spending policies
+// ────────────────────────────────────────────────────────
+
+/// Amount a session signer has spent so far.
+fn session_spent(env: &Env, client: &SmartWalletClient<'_>, credential_id: &Bytes) -> i128 {
+    env.as_contract(&client.address, || {
+        let signer: Signer = env
+            .storage()
+            .temporary()
+            .get(&WalletDataKey::Signer(credential_id.clone()))
+            .unwrap();
+        signer.spent
+    })
+}
+
+#[test]
+fn test_session_policy_caps_token_spending() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, admin) = setup_wallet(&env);
+    let session = cred_id(&env, "session-0");
+    let key = add_session(&env, &client, &session);
+    let (token, other_token, router) = (
+        Address::generate(&env),
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+    let recipient = Address::generate(&env);
+    let transfer = |token: &Address, amount: i128| {
+        call_context(
+            &env,
+            token,
+            "transfer",
+            (client.address.clone(), recipient.clone(), amount).into_val(&env),
+        )
+    };
+
+    let policy = SessionPolicy {
+        max_per_tx: Some(100),
+        max_total: Some(250),
+        allowed_tokens: vec![&env, token.clone()],
+    };
+    assert_eq!(
+        client.try_set_session_policy(&admin, &policy),
+        Err(Ok(WalletError::SignerNotFound))
+    );
+    assert_eq!(
+        client.try_set_session_policy(
+            &session,
+            &SessionPolicy {
+                max_total: Some(0),
+                ..policy.clone()
+            }
+        ),
+        Err(Ok(WalletError::InvalidPolicy))
+    );
+    client.set_session_policy(&session, &policy);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("sgn_pol"), session.clone()).into_val(&env),
+        policy.into_val(&env),
+    );
+
+    let sign = || session_signature(&env, &key, &session);
+    assert_eq!(
+        check_auth_contexts(&env, &client, sign(), vec![&env, transfer(&token, 101)]),
+        Err(Ok(WalletError::SessionLimitExceeded))
+    );
+    assert_eq!(
+        check_auth_contexts(
+            &env,
+            &client,
+            sign(),
+            vec![&env, transfer(&other_token, 10)]
+        ),
+        Err(Ok(WalletError::TokenNotAllowed))
+    );
+    check_auth_contexts(&env, &client, sign(), vec![&env, transfer(&token, 100)]).unwrap();
+    check_auth_contexts(
+        &env,
+        &client,
+        sign(),
+        vec![&env, transfer(&token, 100), transfer(&token, 50)],
+    )
+    .unwrap();
+    assert_eq!(session_spent(&env, &client, &session), 250);
+
+    // The lifetime cap is used up, but calls that move no tokens still pass
+    assert_eq!(
+        check_auth_contexts(&env, &client, sign(), vec![&env, transfer(&token, 1)]),
+        Err(Ok(WalletError::SessionLimitExceeded))
+    );
+    let swap = call_context(&env, &router, "swap", (1u32,).into_val(&env));
+    check_auth_contexts(&env, &client, sign(), vec![&env, swap]).unwrap();
+}
+
+#[test]
+fn test_session_contract_allowlist() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup_wallet(&env);
+    let (router, token, other) = (
+        Address::generate(&env),
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+
+    let session = cred_id(&env, "session-0");
+    let key = SigningKey::from_bytes(&[5u8; 32]);
+    client.add_session_signer(
+        &session,
+        &BytesN::from_array(&env, &key.verifying_key().to_bytes()),
+        &100,
+        &vec![&env, router.clone(), token.clone()],
+        &0,
+    );
+    let sign = || session_signature(&env, &key, &session);
+    let call = |contract: &Address| call_context(&env, contract, "swap", Vec::new(&env));
+
+    check_auth_contexts(
+        &env,
+        &client,
+        sign(),
+        vec![&env, call(&router), call(&token)],
+    )
+    .unwrap();
+    assert_eq!(
+        check_auth_contexts(
+            &env,
+            &client,
+            sign(),
+            vec![&env, call(&router), call(&other)]
+        ),
+        Err(Ok(WalletError::ContractNotAllowed))
+    );
+    // Managing the wallet is refused before the allowlist is consulted
+    assert_eq!(
+        check_auth(
+            &env,
+            &client,
+            sign(),
+            "remove_signer",
+            (session.clone(),).into_val(&env)
+        ),
+        Err(Ok(WalletError::NotAuthorized))
+    );
+
+    // Unscoped sessions may call anything
+    let unscoped = cred_id(&env, "session-1");
+    let unscoped_key = add_session(&env, &client, &unscoped);
+    check_auth_contexts(
+        &env,
+        &client,
+        session_signature(&env, &unscoped_key, &unscoped),
+        vec![&env, call(&other)],
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_session_rejected_after_expires_at() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup_wallet(&env);
+    let session = cred_id(&env, "session-0");
+    // 100 ledgers ≈ 500 seconds from the current timestamp
+    let key = add_session(&env, &client, &session);
+    let sign = || session_signature(&env, &key, &session);
+    let advance = |ledgers: u32| {
+        env.ledger().with_mut(|li| {
+            li.sequence_number += ledgers;
+            li.timestamp += u64::from(ledgers) * 5;
+        })
+    };
+
+    check_auth_contexts(&env, &client, sign(), Vec::new(&env)).unwrap();
+    advance(60);
+    check_auth_contexts(&env, &client, sign(), Vec::new(&env)).unwrap();
+
+    // Auth renewed the storage entry, but the session is past its expiry
+    advance(60);
+    assert_eq!(client.list_signers(&0, &10).len(), 2);
+    assert_eq!(
+        check_auth_contexts(&env, &client, sign(), Vec::new(&env)),
+        Err(Ok(WalletError::SessionExpired))
+    );
+}
+
+// ────────────────────────────────────────────────────────
+//  Guardian recovery
+// ────────────────────────────────────────────────────────
+
+/// Keep instance storage alive across the recovery delay.
+fn long_lived_env() -> Env {
+    let env = Env::default();
+    env.ledger()
+        .with_mut(|li| li.min_persistent_entry_ttl = 4 * RECOVERY_DELAY);
+    env.mock_all_auths();
+    env
+}
+
+#[test]
+fn test_guardian_recovery_finalizes_after_delay() {
+    let env = long_lived_env();
+    let (client, _) = setup_wallet(&env);
+    let (guardian, stranger) = (Address::generate(&env), Address::generate(&env));
+    client.add_guardian(&guardian);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("grd_add"), guardian.clone()).into_val(&env),
+        ().into_val(&env),
+    );
+    client.add_guardian(&guardian);
+    assert!(env.events().all().events().is_empty());
+    assert_eq!(client.get_guardians(), vec![&env, guardian.clone()]);
+
+    let recovered = cred_id(&env, "admin-recovered");
+    let public_key = dummy_public_key(&env, 9);
+    assert_eq!(
+        client.try_propose_recovery(&stranger, &recovered, &public_key),
+        Err(Ok(WalletError::NotGuardian))
+    );
+    client.propose_recovery(&guardian, &recovered, &public_key);
+    let executable_ledger = env.ledger().sequence() + RECOVERY_DELAY;
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("rec_prop"), guardian.clone()).into_val(&env),
+        (recovered.clone(), executable_ledger).into_val(&env),
+    );
+    assert_eq!(
+        client.get_recovery(),
+        RecoveryRequest {
+            credential_id: recovered.clone(),
+            public_key: public_key.clone(),
+            approvals: vec![&env, guardian.clone()],
+            executable_ledger,
+        }
+    );
+    assert_eq!(
+        client.try_propose_recovery(&guardian, &cred_id(&env, "other"), &public_key),
+        Err(Ok(WalletError::RecoveryPending))
+    );
+
+    env.ledger()
+        .with_mut(|li| li.sequence_number = executable_ledger - 1);
+    assert_eq!(
+        client.try_finalize_recovery(),
+        Err(Ok(WalletError::RecoveryNotReady))
+    );
+
+    env.ledger().with_mut(|li| li.sequence_number += 1);
+    client.finalize_recovery();
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("rec_done"),).into_val(&env),
+        recovered.into_val(&env),
+    );
+    assert_eq!(admin_count(&env, &client), 2);
+    assert_eq!(
+        client.try_get_recovery(),
+        Err(Ok(WalletError::RecoveryNotFound))
+    );
+}
+
+#[test]
+fn test_recovery_resets_multisig_threshold() {
+    let env = long_lived_env();
+    let (client, admin) = setup_wallet(&env);
+    let second = cred_id(&env, "admin-1");
+    client.add_signer(&second, &dummy_public_key(&env, 2));
+    let guardian = Address::generate(&env);
+    client.add_guardian(&guardian);
+    client.set_threshold(&2);
+
+    let recovered = cred_id(&env, "admin-recovered");
+    client.propose_recovery(&guardian, &recovered, &dummy_public_key(&env, 9));
+    env.ledger()
+        .with_mut(|li| li.sequence_number += RECOVERY_DELAY);
+    client.finalize_recovery();
+
+    // Both old admins are lost, so the recovered one must act alone
+    assert_eq!(client.get_threshold(), 1);
+    client.remove_signer(&admin);
+    client.remove_signer(&second);
+    assert_eq!(admin_count(&env, &client), 1);
+}
+
+#[test]
+fn test_guardian_changes_need_approval_above_threshold_1() {
+    let env = long_lived_env();
+    let (client, admin) = setup_wallet(&env);
+    let second = cred_id(&env, "admin-1");
+    client.add_signer(&second, &dummy_public_key(&env, 2));
+    let guardian = Address::generate(&env);
+    client.add_guardian(&guardian);
+    client.set_threshold(&2);
+
+    // One admin can no longer pick guardians or shorten the delay, directly
+    // or through the timelock queue
+    let rogue = Address::generate(&env);
+    assert_eq!(
+        client.try_add_guardian(&rogue),
+        Err(Ok(WalletError::ApprovalRequired))
+    );
+    assert_eq!(
+        client.try_remove_guardian(&guardian),
+        Err(Ok(WalletError::ApprovalRequired))
+    );
+    assert_eq!(
+        client.try_set_recovery_config(&1, &RECOVERY_DELAY),
+        Err(Ok(WalletError::ApprovalRequired))
+    );
+    assert_eq!(
+        client.try_queue_action(&TimelockOperation::AddGuardian(rogue.clone())),
+        Err(Ok(WalletError::ApprovalRequired))
+    );
+    assert_eq!(client.get_guardians(), vec![&env, guardian.clone()]);
+
+    // Two admins can
+    let id = client.propose(&AdminOperation::AddGuardian(rogue.clone()));
+    client.approve(&id, &admin);
+    assert_eq!(
+        client.try_execute_proposal(&id),
+        Err(Ok(WalletError::InsufficientApprovals))
+    );
+    client.approve(&id, &second);
+    client.execute_proposal(&id);
+    assert_eq!(
+        client.get_guardians(),
+        vec![&env, guardian.clone(), rogue.clone()]
+    );
+
+    let id = client.propose(&AdminOperation::SetRecoveryConfig(2, RECOVERY_DELAY));
+    client.approve(&id, &admin);
+    client.approve(&id, &second);
+    client.execute_proposal(&id);
+    assert_eq!(client.get_recovery_config().threshold, 2);
+
+    let id = client.propose(&AdminOperation::RemoveGuardian(rogue));
+    client.approve(&id, &admin);
+    client.approve(&id, &second);
+    assert_eq!(
+        client.try_execute_proposal(&id),
+        Err(Ok(WalletError::InvalidThreshold))
+    );
+}
+
+#[test]
+fn test_removed_guardian_cannot_finalize_recovery() {
+    let env = long_lived_env();
+    let (client, _) = setup_wallet(&env);
+    let guardian = Address::generate(&env);
+    client.add_guardian(&guardian);
+    client.propose_recovery(
+        &guardian,
+        &cred_id(&env, "admin-recovered"),
+        &dummy_public_key(&env, 9),
+    );
+
+    client.remove_guardian(&guardian);
+    assert!(client.get_guardians().is_empty());
+    assert_eq!(
+        client.try_remove_guardian(&guardian),
+        Err(Ok(WalletError::NotGuardian))
+    );
+    env.ledger()
+        .with_mut(|li| li.sequence_number += RECOVERY_DELAY);
+    assert_eq!(
+        client.try_finalize_recovery(),
+        Err(Ok(WalletError::InsufficientApprovals))
+    );
+    assert_eq!(admin_count(&env, &client), 1);
+}
+
+#[test]
+fn test_admin_can_veto_recovery() {
+    let env = Env::default();
+    let key = passkey(1);
+    let admin = cred_id(&env, "admin-0");
+    let contract_id = env.register(
+        SmartWallet,
+        (
+            admin.clone(),
+            passkey_public_key(&env, &key),
+            Vec::<InitialSigner>::new(&env),
+        ),
+    );
+    let client = SmartWalletClient::new(&env, &contract_id);
+    env.mock_all_auths();
+    let session = cred_id(&env, "session-0");
+    let session_key = add_session(&env, &client, &session);
+
+    let guardian = Address::generate(&env);
+    client.add_guardian(&guardian);
+    let recovered = cred_id(&env, "admin-recovered");
+    client.propose_recovery(&guardian, &recovered, &dummy_public_key(&env, 9));
+
+    // A session key cannot veto, an admin passkey can
+    assert_eq!(
+        check_auth(
+            &env,
+            &client,
+            session_signature(&env, &session_key, &session),
+            "veto_recovery",
+            Vec::new(&env)
+        ),
+        Err(Ok(WalletError::NotAuthorized))
+    );
+    let payload = BytesN::from_array(&env, &[9u8; 32]);
+    check_auth(
+        &env,
+        &client,
+        webauthn_signature(&env, &key, &admin, &payload),
+        "veto_recovery",
+        Vec::new(&env),
+    )
+    .unwrap();
+
+    client.veto_recovery();
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("rec_veto"),).into_val(&env),
+        recovered.into_val(&env),
+    );
+    assert_eq!(
+        client.try_finalize_recovery(),
+        Err(Ok(WalletError::RecoveryNotFound))
+    );
+    assert_eq!(
+        client.try_veto_recovery(),
+        Err(Ok(WalletError::RecoveryNotFound))
+    );
+}
+
+#[test]
+fn test_classic_account_guardian() {
+    let env = long_lived_env();
+    let (client, _) = setup_wallet(&env);
+    let classic = Address::from_str(
+        &env,
+        "GA7QYNF7SOWQ3GLR2BGMZEHXAVIRZA4KVWLTJJFC7MGXUA74P7UJVSGZ",
+    );
+    let contract_guardian = Address::generate(&env);
+    client.add_guardian(&contract_guardian);
+    client.add_guardian(&classic);
+    client.set_recovery_config(&2, &RECOVERY_DELAY);
+
+    // The classic key co-approves with its own ed25519 signature
+    let recovered = cred_id(&env, "admin-recovered");
+    client.propose_recovery(&contract_guardian, &recovered, &dummy_public_key(&env, 9));
+    client.approve_recovery(&classic);
+    assert_eq!(
+        env.auths(),
+        std::vec![(
+            classic.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    client.address.clone(),
+                    Symbol::new(&env, "approve_recovery"),
+                    (classic.clone(),).into_val(&env),
+                )),
+                sub_invocations: std::vec![],
+            }
+        )]
+    );
+    assert_eq!(
+        client.get_recovery().approvals,
+        vec![&env, contract_guardian, classic]
+    );
+
+    env.ledger()
+        .with_mut(|li| li.sequence_number += RECOVERY_DELAY);
+    client.finalize_recovery();
+    assert_eq!(client.get_signer(&recovered).kind, SignerKind::Admin);
+}
+
+#[test]
+fn test_recovery_needs_guardian_threshold() {
+    let env = long_lived_env();
+    let (client, _) = setup_wallet(&env);
+    let guardians = [
+        Address::generate(&env),
+        Address::generate(&env),
+        Address::generate(&env),
+    ];
+    for guardian in &guardians {
+        client.add_guardian(guardian);
+    }
+
+    assert_eq!(
+        client.try_set_recovery_config(&4, &RECOVERY_DELAY),
+        Err(Ok(WalletError::InvalidThreshold))
+    );
+    // The delay cannot be cut below a day
+    assert_eq!(
+        client.try_set_recovery_config(&2, &0),
+        Err(Ok(WalletError::InvalidDelay))
+    );
+    assert_eq!(
+        client.try_set_recovery_config(&2, &(RECOVERY_DELAY - 1)),
+        Err(Ok(WalletError::InvalidDelay))
+    );
+    client.set_recovery_config(&2, &(2 * RECOVERY_DELAY));
+    assert_eq!(
+        client.get_recovery_config(),
+        RecoveryConfig {
+            threshold: 2,
+            delay_ledgers: 2 * RECOVERY_DELAY,
+        }
+    );
+
+    let recovered = cred_id(&env, "admin-recovered");
+    client.propose_recovery(&guardians[0], &recovered, &dummy_public_key(&env, 9));
+    assert_eq!(client.get_recovery().executable_ledger, 0);
+    assert_eq!(
+        client.try_finalize_recovery(),
+        Err(Ok(WalletError::RecoveryNotReady))
+    );
+    assert_eq!(
+        client.try_approve_recovery(&guardians[0]),
+        Err(Ok(WalletError::AlreadyApproved))
+    );
+    assert_eq!(
+        client.try_approve_recovery(&Address::generate(&env)),
+        Err(Ok(WalletError::NotGuardian))
+    );
+
+    // The second approval starts the delay
+    env.ledger().with_mut(|li| li.sequence_number += 10);
+    client.approve_recovery(&guardians[1]);
+    let executable_ledger = env.ledger().sequence() + 2 * RECOVERY_DELAY;
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("rec_appr"), guardians[1].clone()).into_val(&env),
+        (2u32, executable_ledger).into_val(&env),
+    );
+    assert_eq!(client.get_recovery().executable_ledger, executable_ledger);
+
+    // Guardians cannot drop below the threshold
+    client.remove_guardian(&guardians[2]);
+    assert_eq!(
+        client.try_remove_guardian(&guardians[1]),
+        Err(Ok(WalletError::InvalidThreshold))
+    );
+
+    env.ledger()
+        .with_mut(|li| li.sequence_number = executable_ledger);
+    client.finalize_recovery();
+    assert_eq!(admin_count(&env, &client), 2);
+}
+
+// ────────────────────────────────────────────────────────
+//  Upgrades
+// ────────────────────────────────────────────────────────
+
+/// `Signer` as wallets deployed before versioning stored it.
+#[contracttype]
+struct SignerV0 {
+    public_key: Bytes,
+    kind: SignerKind,
+    ttl_ledgers: u32,
+}
+
+#[test]
+fn test_migrate_rebuilds_baseline_wallets() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, admin) = setup_wallet(&env);
+    assert_eq!(client.version(), 4);
+    client.migrate(&vec![&env]);
+    assert!(env.events().all().events().is_empty());
+
+    // A baseline wallet has no version, no signer index and three-field
+    // signers
+    let session = cred_id(&env, "session-0");
+    add_session(&env, &client, &session);
+    let admin_key = client.get_signer(&admin).public_key;
+    env.as_contract(&client.address, || {
+        env.storage().persistent().set(
+            &WalletDataKey::Signer(admin.clone()),
+            &SignerV0 {
+                public_key: admin_key.clone(),
+                kind: SignerKind::Admin,
+                ttl_ledgers: 0,
+            },
+        );
+        env.storage().temporary().set(
+            &WalletDataKey::Signer(session.clone()),
+            &SignerV0 {
+                public_key: Bytes::from_array(&env, &[7u8; 32]),
+                kind: SignerKind::Session,
+                ttl_ledgers: 100,
+            },
+        );
+        env.storage().instance().remove(&WalletDataKey::SignerIndex);
+        env.storage().instance().remove(&WalletDataKey::Version);
+    });
+    assert_eq!(client.version(), 0);
+    assert!(client.try_get_signer(&admin).is_err());
+
+    // Its signers must be named, admins at least
+    assert_eq!(
+        client.try_migrate(&vec![&env, session.clone()]),
+        Err(Ok(WalletError::SignerNotFound))
+    );
+    let unknown = cred_id(&env, "never-added");
+    client.migrate(&vec![&env, admin.clone(), session.clone(), unknown]);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("migrated"),).into_val(&env),
+        (0u32, 4u32).into_val(&env),
+    );
+    assert_eq!(client.version(), 4);
+    let signer = client.get_signer(&admin);
+    assert_eq!(signer.public_key, admin_key);
+    assert_eq!(signer.kind, SignerKind::Admin);
+    let signer = client.get_signer(&session);
+    assert_eq!(signer.kind, SignerKind::Session);
+    assert_eq!(signer.ttl_ledgers, 100);
+    let listed = client.list_signers(&0, &10);
+    assert_eq!(listed.len(), 2);
+    assert_eq!(listed.get(0).unwrap().credential_id, admin);
+}
+
+/// `Signer` as version 1 wallets stored it.
+#[contracttype]
+struct SignerV1 {
+    public_key: Bytes,
+    kind: SignerKind,
+    ttl_ledgers: u32,
+    expires_at: u64,
+    policy: SessionPolicy,
+    spent: i128,
+    allowed_contracts: Vec<Address>,
+}
+
+#[test]
+fn test_migrate_rewrites_v1_signers() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, admin) = setup_wallet(&env);
+    let session = cred_id(&env, "session-0");
+    add_session(&env, &client, &session);
+    let policy = SessionPolicy {
+        max_per_tx: Some(5),
+        max_total: None,
+        allowed_tokens: Vec::new(&env),
+    };
+    let admin_key = client.get_signer(&admin).public_key;
+    env.as_contract(&client.address, || {
+        env.storage().persistent().set(
+            &WalletDataKey::Signer(admin.clone()),
+            &SignerV1 {
+                public_key: admin_key.clone(),
+                kind: SignerKind::Admin,
+                ttl_ledgers: 0,
+                expires_at: 0,
+                policy: unrestricted_policy(&env),
+                spent: 0,
+                allowed_contracts: Vec::new(&env),
+            },
+        );
+        env.storage().temporary().set(
+            &WalletDataKey::Signer(session.clone()),
+            &SignerV1 {
+                public_key: Bytes::from_array(&env, &[7u8; 32]),
+                kind: SignerKind::Session,
+                ttl_ledgers: 100,
+                expires_at: 500,
+                policy: policy.clone(),
+                spent: 3,
+                allowed_contracts: Vec::new(&env),
+            },
+        );
+        env.storage().instance().set(&WalletDataKey::Version, &1u32);
+    });
+    assert!(client.try_get_signer(&admin).is_err());
+
+    client.migrate(&vec![&env]);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("migrated"),).into_val(&env),
+        (1u32, 4u32).into_val(&env),
+    );
+    let signer = client.get_signer(&admin);
+    assert_eq!(signer.public_key, admin_key);
+    assert_eq!(signer.kind, SignerKind::Admin);
+    assert_eq!(signer.label, String::from_str(&env, ""));
+    assert_eq!((signer.added_at, signer.last_used_at), (0, 0));
+    let signer = client.get_signer(&session);
+    assert_eq!(signer.kind, SignerKind::Session);
+    assert_eq!((signer.expires_at, signer.spent), (500, 3));
+    assert_eq!(signer.policy, policy);
+    assert_eq!(signer.uses_left, 0);
+    assert_eq!(client.list_signers(&0, &10).len(), 2);
+}
+
+#[test]
+fn test_upgrade_needs_admin_and_threshold() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup_wallet(&env);
+    let session = cred_id(&env, "session-0");
+    let session_key = add_session(&env, &client, &session);
+    let wasm_hash = BytesN::from_array(&env, &[1u8; 32]);
+
+    assert_eq!(
+        check_auth(
+            &env,
+            &client,
+            session_signature(&env, &session_key, &session),
+            "upgrade",
+            (wasm_hash.clone(),).into_val(&env)
+        ),
+        Err(Ok(WalletError::NotAuthorized))
+    );
+
+    client.add_signer(&cred_id(&env, "admin-1"), &dummy_public_key(&env, 2));
+    client.set_threshold(&2);
+    assert_eq!(
+        client.try_upgrade(&wasm_hash),
+        Err(Ok(WalletError::ApprovalRequired))
+    );
+}
+
+// ────────────────────────────────────────────────────────
+//  WebAuthn checks
+// ────────────────────────────────────────────────────────
+
+/// Deploy a wallet whose first admin is the test passkey `key`.
+fn setup_passkey_wallet<'a>(
+    env: &'a Env,
+    key: &p256::ecdsa::SigningKey,
+) -> (SmartWalletClient<'a>, Bytes) {
+    let admin = cred_id(env, "admin-0");
+    let contract_id = env.register(
+        SmartWallet,
+        (
+            admin.clone(),
+            passkey_public_key(env, key),
+            Vec::<InitialSigner>::new(env),
+        ),
+    );
+    let client = SmartWalletClient::new(env, &contract_id);
+    (client, admin)
+}
+
+#[test]
+fn test_origin_allowlist() {
+    let env = Env::default();
+    let key = passkey(1);
+    let (client, admin) = setup_passkey_wallet(&env, &key);
+    env.mock_all_auths();
+    let payload = BytesN::from_array(&env, &[9u8; 32]);
+    let sign = || {
+        check_auth(
+            &env,
+            &client,
+            webauthn_signature(&env, &key, &admin, &payload),
+            "remove_signer",
+            (admin.clone(),).into_val(&env),
+        )
+    };
+    let (ours, other) = (
+        Bytes::from_slice(&env, b"https://wallet.galaxy.dev"),
+        Bytes::from_slice(&env, b"https://wallet.galaxy.dev.example"),
+    );
+
+    // Any origin is accepted until the allowlist has entries
+    sign().unwrap();
+    client.add_origin(&other);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("org_add"),).into_val(&env),
+        other.into_val(&env),
+    );
+    assert_eq!(sign(), Err(Ok(WalletError::OriginNotAllowed)));
+
+    client.add_origin(&ours);
+    client.add_origin(&ours);
+    assert_eq!(
+        client.get_origins(),
+        vec![&env, other.clone(), ours.clone()]
+    );
+    sign().unwrap();
+
+    client.remove_origin(&ours);
+    assert_eq!(sign(), Err(Ok(WalletError::OriginNotAllowed)));
+    assert_eq!(
+        client.try_remove_origin(&ours),
+        Err(Ok(WalletError::OriginNotFound))
+    );
+    client.remove_origin(&other);
+    sign().unwrap();
+}
+
+#[test]
+fn test_signer_origin_binding() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup_wallet(&env);
+    let operator_key = passkey(2);
+    let operator = cred_id(&env, "operator-0");
+    client.add_operator(&operator, &passkey_public_key(&env, &operator_key));
+    let payload = BytesN::from_array(&env, &[9u8; 32]);
+    let sign = || {
+        check_auth_contexts(
+            &env,
+            &client,
+            webauthn_signature(&env, &operator_key, &operator, &payload),
+            Vec::new(&env),
+        )
+    };
+    let (ours, other) = (
+        Bytes::from_slice(&env, b"https://wallet.galaxy.dev"),
+        Bytes::from_slice(&env, b"https://dapp.example"),
+    );
+
+    client.set_signer_origin(&operator, &Some(other.clone()));
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("sgn_org"), operator.clone()).into_val(&env),
+        Some(other.clone()).into_val(&env),
+    );
+    assert_eq!(client.get_signer(&operator).origin, Some(other));
+    assert_eq!(sign(), Err(Ok(WalletError::OriginNotAllowed)));
+
+    client.set_signer_origin(&operator, &Some(ours));
+    sign().unwrap();
+    client.set_signer_origin(&operator, &None);
+    sign().unwrap();
+
+    // Session keys sign without clientDataJSON, so cannot be bound
+    let session = cred_id(&env, "session-0");
+    add_session(&env, &client, &session);
+    assert_eq!(
+        client.try_set_signer_origin(&session, &Some(Bytes::from_slice(&env, b"https://x"))),
+        Err(Ok(WalletError::InvalidPolicy))
+    );
+}
+
+#[test]
+fn test_rp_id_hash_allowlist() {
+    let env = Env::default();
+    let key = passkey(1);
+    let (client, admin) = setup_passkey_wallet(&env, &key);
+    env.mock_all_auths();
+    let payload = BytesN::from_array(&env, &[9u8; 32]);
+    let sign = || {
+        check_auth(
+            &env,
+            &client,
+            webauthn_signature(&env, &key, &admin, &payload),
+            "remove_signer",
+            (admin.clone(),).into_val(&env),
+        )
+    };
+    // The test authenticator data carries an all-zero RP ID hash
+    let (ours, other) = (
+        BytesN::from_array(&env, &[0u8; 32]),
+        BytesN::from_array(&env, &[7u8; 32]),
+    );
+
+    sign().unwrap();
+    client.add_rp_id_hash(&other);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("rp_add"),).into_val(&env),
+        other.into_val(&env),
+    );
+    assert_eq!(sign(), Err(Ok(WalletError::RpIdMismatch)));
+
+    // Registrations are held to the same list
+    let client_data = registration_client_data(&env, "webauthn.create");
+    let new_key = passkey(2);
+    let second = cred_id(&env, "admin-1");
+    let object = attestation_object(&env, "packed", &new_key, &second, &client_data, None);
+    assert_eq!(
+        client.try_add_signer_with_attestation(&second, &object, &client_data),
+        Err(Ok(WalletError::RpIdMismatch))
+    );
+
+    client.add_rp_id_hash(&ours);
+    client.add_rp_id_hash(&ours);
+    assert_eq!(
+        client.get_rp_id_hashes(),
+        vec![&env, other.clone(), ours.clone()]
+    );
+    sign().unwrap();
+    client.add_signer_with_attestation(&second, &object, &client_data);
+
+    client.remove_rp_id_hash(&ours);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("rp_rem"),).into_val(&env),
+        ours.into_val(&env),
+    );
+    assert_eq!(sign(), Err(Ok(WalletError::RpIdMismatch)));
+    assert_eq!(
+        client.try_remove_rp_id_hash(&ours),
+        Err(Ok(WalletError::RpIdHashNotFound))
+    );
+    client.remove_rp_id_hash(&other);
+    sign().unwrap();
+}
+
+#[test]
+fn test_registration_client_data_is_rejected() {
+    let env = Env::default();
+    let key = passkey(1);
+    let (client, admin) = setup_passkey_wallet(&env, &key);
+    let payload = BytesN::from_array(&env, &[9u8; 32]);
+    let args: Vec<Val> = (admin.clone(),).into_val(&env);
+
+    assert_eq!(
+        check_auth(
+            &env,
+            &client,
+            webauthn_signature_with(&env, &key, &admin, &payload, "webauthn.create", 0x05),
+            "remove_signer",
+            args.clone()
+        ),
+        Err(Ok(WalletError::InvalidClientDataType))
+    );
+    // The same assertion with the `webauthn.get` type passes
+    assert_eq!(
+        check_auth(
+            &env,
+            &client,
+            webauthn_signature(&env, &key, &admin, &payload),
+            "remove_signer",
+            args
+        ),
+        Ok(())
+    );
+}
+
+#[test]
+fn test_user_presence_and_verification_flags() {
+    let env = Env::default();
+    let key = passkey(1);
+    let (client, admin) = setup_passkey_wallet(&env, &key);
+    env.mock_all_auths();
+    let payload = BytesN::from_array(&env, &[9u8; 32]);
+    let sign = |flags: u8| {
+        check_auth(
+            &env,
+            &client,
+            webauthn_signature_with(&env, &key, &admin, &payload, "webauthn.get", flags),
+            "remove_signer",
+            (admin.clone(),).into_val(&env),
+        )
+    };
+
+    // User Present is always required, User Verified only on request
+    assert_eq!(sign(0x04), Err(Ok(WalletError::UserNotPresent)));
+    sign(0x01).unwrap();
+
+    assert!(!client.get_user_verification());
+    client.set_user_verification(&true);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("uv_req"),).into_val(&env),
+        true.into_val(&env),
+    );
+    assert!(client.get_user_verification());
+    assert_eq!(sign(0x01), Err(Ok(WalletError::UserNotVerified)));
+    sign(0x05).unwrap();
+}
+
+/// Authenticator data shaped like what devices send: flags, a big-endian
+/// signature counter and, with the ED flag, a CBOR extensions map after it.
+fn device_authenticator_data(env: &Env, flags: u8, counter: u32, extensions: &[u8]) -> Bytes {
+    let mut auth_data = std::vec![0u8; 32];
+    auth_data.push(flags);
+    auth_data.extend(counter.to_be_bytes());
+    auth_data.extend(extensions);
+    Bytes::from_slice(env, &auth_data)
+}
+
+#[test]
+fn test_authenticator_data_extensions() {
+    let env = Env::default();
+    let key = passkey(1);
+    let (client, admin) = setup_passkey_wallet(&env, &key);
+    env.mock_all_auths();
+    client.set_user_verification(&true);
+    client.add_rp_id_hash(&BytesN::from_array(&env, &[0u8; 32]));
+    let payload = BytesN::from_array(&env, &[9u8; 32]);
+    let client_data = assertion_client_data(&env, "webauthn.get", &payload);
+    let sign = |signature: AccountSignature| {
+        check_auth(
+            &env,
+            &client,
+            signature,
+            "remove_signer",
+            (admin.clone(),).into_val(&env),
+        )
+    };
+
+    // A security key returning hmac-secret output: UP | UV | ED
+    let mut extensions = std::vec![0xa1];
+    cbor_text(&mut extensions, "hmac-secret");
+    cbor_bytes(&mut extensions, &[5u8; 32]);
+    let security_key = device_authenticator_data(&env, 0x85, 42, &extensions);
+    // A synced platform passkey: UP | UV | BE | BS, counter always 0
+    let platform = device_authenticator_data(&env, 0x1d, 0, &[]);
+    // A platform authenticator reporting credProtect: UP | UV | BE | ED
+    let mut extensions = std::vec![0xa1];
+    cbor_text(&mut extensions, "credProtect");
+    extensions.push(0x02);
+    let cred_protect = device_authenticator_data(&env, 0x8d, 7, &extensions);
+
+    for auth_data in [&security_key, &platform, &cred_protect] {
+        sign(signed_assertion(
+            &env,
+            &key,
+            &admin,
+            client_data.clone(),
+            auth_data.clone(),
+        ))
+        .unwrap();
+    }
+
+    // Extension data is covered by the signature
+    let AccountSignature::WebAuthn(mut signature) =
+        signed_assertion(&env, &key, &admin, client_data.clone(), security_key)
+    else {
+        unreachable!();
+    };
+    signature.authenticator_data.set(45, 6);
+    assert!(sign(AccountSignature::WebAuthn(signature)).is_err());
+    // Flags are still read at their fixed offset
+    let no_uv = device_authenticator_data(&env, 0x81, 42, &extensions);
+    assert_eq!(
+        sign(signed_assertion(&env, &key, &admin, client_data, no_uv)),
+        Err(Ok(WalletError::UserNotVerified))
+    );
+
+    // A registration with credProtect after the credential public key
+    let client_data = registration_client_data(&env, "webauthn.create");
+    let new_key = passkey(2);
+    let second = cred_id(&env, "admin-1");
+    let object = attestation_object_with(
+        &env,
+        "packed",
+        &new_key,
+        &second,
+        &client_data,
+        Some(&passkey(9)),
+        &extensions,
+    );
+    client.add_signer_with_attestation(&second, &object, &client_data);
+    assert_eq!(
+        client.get_signer(&second).public_key,
+        Bytes::from(passkey_public_key(&env, &new_key))
+    );
+}
+
+// ────────────────────────────────────────────────────────
+//  Batched calls
+// ────────────────────────────────────────────────────────
+
+fn invocation(env: &Env, contract: &Address, function: &str, args: Vec<Val>) -> Invocation {
+    Invocation {
+        contract: contract.clone(),
+        function: Symbol::new(env, function),
+        args,
+    }
+}
+
+#[test]
+fn test_execute_runs_batch_from_wallet() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup_wallet(&env);
+    let token = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    token::StellarAssetClient::new(&env, &token).mint(&client.address, &1_000);
+    let (recipient, spender) = (Address::generate(&env), Address::generate(&env));
+    let wallet = client.address.clone();
+
+    let results = client.execute(&vec![
+        &env,
+        invocation(
+            &env,
+            &token,
+            "approve",
+            (wallet.clone(), spender.clone(), 50i128, 1_000u32).into_val(&env),
+        ),
+        invocation(
+            &env,
+            &token,
+            "transfer",
+            (wallet.clone(), recipient.clone(), 100i128).into_val(&env),
+        ),
+        invocation(&env, &token, "balance", (wallet.clone(),).into_val(&env)),
+    ]);
+    assert_eq!(results.len(), 3);
+    assert_eq!(i128::try_from_val(&env, &results.get(2).unwrap()), Ok(900));
+
+    let token_client = token::Client::new(&env, &token);
+    assert_eq!(token_client.balance(&recipient), 100);
+    assert_eq!(token_client.allowance(&wallet, &spender), 50);
+
+    // The wallet cannot call itself through a batch
+    assert_eq!(
+        client.try_execute(&vec![
+            &env,
+            invocation(&env, &wallet, "get_threshold", Vec::new(&env)),
+        ]),
+        Err(Ok(WalletError::NotAuthorized))
+    );
+}
+
+#[test]
+fn test_session_restrictions_apply_inside_execute() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup_wallet(&env);
+    let (token, other) = (Address::generate(&env), Address::generate(&env));
+    let session = cred_id(&env, "session-0");
+    let key = SigningKey::from_bytes(&[5u8; 32]);
+    client.add_session_signer(
+        &session,
+        &BytesN::from_array(&env, &key.verifying_key().to_bytes()),
+        &100,
+        &vec![&env, token.clone()],
+        &0,
+    );
+    client.set_session_policy(
+        &session,
+        &SessionPolicy {
+            max_per_tx: Some(100),
+            max_total: None,
+            allowed_tokens: Vec::new(&env),
+        },
+    );
+
+    let recipient = Address::generate(&env);
+    let transfer = |contract: &Address, amount: i128| {
+        invocation(
+            &env,
+            contract,
+            "transfer",
+            (client.address.clone(), recipient.clone(), amount).into_val(&env),
+        )
+    };
+    let execute = |batch: Vec<Invocation>| {
+        check_auth(
+            &env,
+            &client,
+            session_signature(&env, &key, &session),
+            "execute",
+            (batch,).into_val(&env),
+        )
+    };
+
+    execute(vec![&env, transfer(&token, 60), transfer(&token, 40)]).unwrap();
+    assert_eq!(session_spent(&env, &client, &session), 100);
+    assert_eq!(
+        execute(vec![&env, transfer(&token, 101)]),
+        Err(Ok(WalletError::SessionLimitExceeded))
+    );
+    assert_eq!(
+        execute(vec![&env, transfer(&token, 10), transfer(&other, 10)]),
+        Err(Ok(WalletError::ContractNotAllowed))
+    );
+}
+
+// ────────────────────────────────────────────────────────
+//  Daily spending limits
+// ────────────────────────────────────────────────────────
+
+#[test]
+fn test_daily_limit_covers_admins_and_sessions() {
+    let env = Env::default();
+    let key = passkey(1);
+    let (client, admin) = setup_passkey_wallet(&env, &key);
+    env.mock_all_auths();
+    let session = cred_id(&env, "session-0");
+    let session_key = add_session(&env, &client, &session);
+    let (token, other_token) = (Address::generate(&env), Address::generate(&env));
+    let recipient = Address::generate(&env);
+    let transfer = |token: &Address, amount: i128| {
+        call_context(
+            &env,
+            token,
+            "transfer",
+            (client.address.clone(), recipient.clone(), amount).into_val(&env),
+        )
+    };
+    let payload = BytesN::from_array(&env, &[9u8; 32]);
+    let by_admin = || webauthn_signature(&env, &key, &admin, &payload);
+    let by_session = || session_signature(&env, &session_key, &session);
+
+    assert_eq!(
+        client.try_set_daily_limit(&token, &Some(0)),
+        Err(Ok(WalletError::InvalidPolicy))
+    );
+    client.set_daily_limit(&token, &Some(100));
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("day_lim"), token.clone()).into_val(&env),
+        Some(100i128).into_val(&env),
+    );
+    assert_eq!(client.get_daily_limit(&token), Some(100));
+
+    check_auth_contexts(&env, &client, by_admin(), vec![&env, transfer(&token, 60)]).unwrap();
+    check_auth_contexts(
+        &env,
+        &client,
+        by_session(),
+        vec![&env, transfer(&token, 30)],
+    )
+    .unwrap();
+    assert_eq!(client.get_daily_spent(&token), 90);
+    assert_eq!(
+        check_auth_contexts(&env, &client, by_admin(), vec![&env, transfer(&token, 11)]),
+        Err(Ok(WalletError::DailyLimitExceeded))
+    );
+    check_auth_contexts(
+        &env,
+        &client,
+        by_session(),
+        vec![&env, transfer(&other_token, 1_000)],
+    )
+    .unwrap();
+
+    // The next UTC day starts from zero
+    env.ledger().with_mut(|li| li.timestamp += 86_400);
+    assert_eq!(client.get_daily_spent(&token), 0);
+    check_auth_contexts(&env, &client, by_admin(), vec![&env, transfer(&token, 100)]).unwrap();
+}
+
+#[test]
+fn test_daily_limit_raise_needs_second_admin() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, admin) = setup_wallet(&env);
+    let second = cred_id(&env, "admin-1");
+    client.add_signer(&second, &dummy_public_key(&env, 2));
+    let token = Address::generate(&env);
+    client.set_daily_limit(&token, &Some(100));
+
+    // Tightening needs no approval
+    client.set_daily_limit(&token, &Some(50));
+    assert_eq!(client.get_daily_limit(&token), Some(50));
+
+    // Loosening does, even at a threshold of 1
+    assert_eq!(
+        client.try_set_daily_limit(&token, &Some(51)),
+        Err(Ok(WalletError::ApprovalRequired))
+    );
+    assert_eq!(
+        client.try_set_daily_limit(&token, &None),
+        Err(Ok(WalletError::ApprovalRequired))
+    );
+    assert_eq!(client.get_daily_limit(&token), Some(50));
+
+    let id = client.propose(&AdminOperation::SetDailyLimit(token.clone(), Some(200)));
+    client.approve(&id, &admin);
+    assert_eq!(
+        client.try_execute_proposal(&id),
+        Err(Ok(WalletError::InsufficientApprovals))
+    );
+    client.approve(&id, &second);
+    client.execute_proposal(&id);
+    assert_eq!(client.get_daily_limit(&token), Some(200));
+
+    let id = client.propose(&AdminOperation::SetDailyLimit(token.clone(), None));
+    client.approve(&id, &admin);
+    client.approve(&id, &second);
+    client.execute_proposal(&id);
+    assert_eq!(client.get_daily_limit(&token), None);
+}
+
+#[test]
+fn test_invoke_proposal_needs_second_admin() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, admin) = setup_wallet(&env);
+    let second = cred_id(&env, "admin-1");
+    client.add_signer(&second, &dummy_public_key(&env, 2));
+    let token = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    token::StellarAssetClient::new(&env, &token).mint(&client.address, &1_000);
+    let recipient = Address::generate(&env);
+
+    let id = client.propose(&AdminOperation::Invoke(vec![
+        &env,
+        invocation(
+            &env,
+            &token,
+            "transfer",
+            (client.address.clone(), recipient.clone(), 500i128).into_val(&env),
+        ),
+    ]));
+    client.approve(&id, &admin);
+    assert_eq!(
+        client.try_execute_proposal(&id),
+        Err(Ok(WalletError::InsufficientApprovals))
+    );
+
+    client.approve(&id, &second);
+    client.execute_proposal(&id);
+    assert_eq!(token::Client::new(&env, &token).balance(&recipient), 500);
+}
+
+#[test]
+fn test_fast_track_needs_supermajority() {
+    let env = long_lived_env();
+    let (client, admin) = setup_wallet(&env);
+    let (first, second) = (Address::generate(&env), Address::generate(&env));
+    client.add_guardian(&first);
+    client.add_guardian(&second);
+    client.set_recovery_config(&2, &RECOVERY_DELAY);
+    client.set_timelock_delay(&100);
+    let third = Address::generate(&env);
+    let action = client.queue_action(&TimelockOperation::AddGuardian(third.clone()));
+
+    // A lone admin cannot skip its own timelock
+    let id = client.propose(&AdminOperation::FastTrackAction(action));
+    client.approve(&id, &admin);
+    assert_eq!(
+        client.try_execute_proposal(&id),
+        Err(Ok(WalletError::InsufficientApprovals))
+    );
+
+    // Two thirds of three admins is two
+    let admin_1 = cred_id(&env, "admin-1");
+    let admin_2 = cred_id(&env, "admin-2");
+    let add_1 = client.queue_action(&TimelockOperation::AddSigner(
+        admin_1.clone(),
+        dummy_public_key(&env, 2),
+    ));
+    let add_2 = client.queue_action(&TimelockOperation::AddSigner(
+        admin_2.clone(),
+        dummy_public_key(&env, 3),
+    ));
+    env.ledger().with_mut(|li| li.sequence_number += 100);
+    client.execute_action(&add_1);
+    client.execute_action(&add_2);
+    assert_eq!(admin_count(&env, &client), 3);
+    client.approve(&id, &admin_1);
+    client.execute_proposal(&id);
+    assert_eq!(
+        client.get_guardians(),
+        vec![&env, first.clone(), second.clone(), third]
+    );
+    assert_eq!(
+        client.try_get_action(&action),
+        Err(Ok(WalletError::ActionNotFound))
+    );
+
+    // A recovery still needs its guardians before admins can fast-track it
+    let id = client.propose(&AdminOperation::FastTrackRecovery);
+    client.approve(&id, &admin);
+    client.approve(&id, &admin_2);
+    assert_eq!(
+        client.try_execute_proposal(&id),
+        Err(Ok(WalletError::RecoveryNotFound))
+    );
+    let recovered = cred_id(&env, "admin-recovered");
+    client.propose_recovery(&first, &recovered, &dummy_public_key(&env, 9));
+    assert_eq!(
+        client.try_execute_proposal(&id),
+        Err(Ok(WalletError::RecoveryNotReady))
+    );
+    client.approve_recovery(&second);
+    client.execute_proposal(&id);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("executed"), id).into_val(&env),
+        ().into_val(&env),
+    );
+    assert_eq!(admin_count(&env, &client), 4);
+    assert_eq!(
+        client.try_get_recovery(),
+        Err(Ok(WalletError::RecoveryNotFound))
+    );
+}
+
+#[test]
+fn test_session_removed_after_max_uses() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup_wallet(&env);
+    let session = cred_id(&env, "session-0");
+    let key = SigningKey::from_bytes(&[5u8; 32]);
+    client.add_session_signer(
+        &session,
+        &BytesN::from_array(&env, &key.verifying_key().to_bytes()),
+        &100,
+        &Vec::new(&env),
+        &2,
+    );
+    let sign = || session_signature(&env, &key, &session);
+
+    check_auth_contexts(&env, &client, sign(), Vec::new(&env)).unwrap();
+    let uses_left = env.as_contract(&client.address, || {
+        env.storage()
+            .temporary()
+            .get::<_, Signer>(&WalletDataKey::Signer(session.clone()))
+            .unwrap()
+            .uses_left
+    });
+    assert_eq!(uses_left, 1);
+
+    check_auth_contexts(&env, &client, sign(), Vec::new(&env)).unwrap();
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("sgn_rem"), session.clone()).into_val(&env),
+        SignerKind::Session.into_val(&env),
+    );
+    assert_eq!(client.list_signers(&0, &10).len(), 1);
+    assert_eq!(
+        check_auth_contexts(&env, &client, sign(), Vec::new(&env)),
+        Err(Ok(WalletError::SignerNotFound))
+    );
+}
+
+#[test]
+fn test_signer_metadata() {
+    let env = Env::default();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+    let key = passkey(1);
+    let (client, admin) = setup_passkey_wallet(&env, &key);
+    env.mock_all_auths();
+
+    let signer = client.get_signer(&admin);
+    assert_eq!(signer.label, String::from_str(&env, ""));
+    assert_eq!((signer.added_at, signer.last_used_at), (1_000, 0));
+
+    let label = String::from_str(&env, "iPhone passkey");
+    client.set_signer_label(&admin, &label);
+    assert_eq!(client.get_signer(&admin).label, label);
+    assert_eq!(
+        client.try_set_signer_label(&admin, &String::from_str(&env, &"x".repeat(65))),
+        Err(Ok(WalletError::InvalidLabel))
+    );
+    assert_eq!(
+        client.try_get_signer(&cred_id(&env, "missing")).err(),
+        Some(Ok(WalletError::SignerNotFound))
+    );
+
+    // Successful auth records when each signer was last used
+    env.ledger().with_mut(|li| li.timestamp = 5_000);
+    let payload = BytesN::from_array(&env, &[9u8; 32]);
+    check_auth_contexts(
+        &env,
+        &client,
+        webauthn_signature(&env, &key, &admin, &payload),
+        Vec::new(&env),
+    )
+    .unwrap();
+    let signer = client.get_signer(&admin);
+    assert_eq!((signer.label, signer.last_used_at), (label, 5_000));
+
+    let session = cred_id(&env, "session-0");
+    let session_key = add_session(&env, &client, &session);
+    assert_eq!(client.get_signer(&session).added_at, 5_000);
+    env.ledger().with_mut(|li| li.timestamp = 5_060);
+    check_auth_contexts(
+        &env,
+        &client,
+        session_signature(&env, &session_key, &session),
+        Vec::new(&env),
+    )
+    .unwrap();
+    assert_eq!(client.get_signer(&session).last_used_at, 5_060);
+}
+
+#[test]
+fn test_rotate_signer_swaps_key_in_place() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, admin) = setup_wallet(&env);
+    let second = cred_id(&env, "admin-1");
+    client.add_signer(&second, &dummy_public_key(&env, 2));
+    client.set_signer_label(&admin, &String::from_str(&env, "laptop"));
+
+    let rotated = cred_id(&env, "admin-0b");
+    client.rotate_signer(&admin, &rotated, &dummy_public_key(&env, 3).into());
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("sgn_rot"), admin.clone()).into_val(&env),
+        (rotated.clone(), SignerKind::Admin).into_val(&env),
+    );
+    assert_eq!(admin_count(&env, &client), 2);
+    let signer = client.get_signer(&rotated);
+    assert_eq!(signer.kind, SignerKind::Admin);
+    assert_eq!(signer.label, String::from_str(&env, "laptop"));
+    assert_eq!(signer.public_key, Bytes::from(dummy_public_key(&env, 3)));
+    assert_eq!(
+        client.try_get_signer(&admin).err(),
+        Some(Ok(WalletError::SignerNotFound))
+    );
+    assert_eq!(
+        client.list_signers(&0, &10).get(0).unwrap().credential_id,
+        rotated
+    );
+
+    // The new key must suit the signer's kind, and the new ID be free
+    assert_eq!(
+        client.try_rotate_signer(&rotated, &admin, &Bytes::from_array(&env, &[7u8; 32])),
+        Err(Ok(WalletError::InvalidPublicKey))
+    );
+    assert_eq!(
+        client.try_rotate_signer(&rotated, &second, &dummy_public_key(&env, 4).into()),
+        Err(Ok(WalletError::SignerAlreadyExists))
+    );
+    assert_eq!(
+        client.try_rotate_signer(
+            &rotated,
+            &Bytes::from_array(&env, &[1u8; 8]),
+            &dummy_public_key(&env, 4).into()
+        ),
+        Err(Ok(WalletError::InvalidCredentialId))
+    );
+
+    // Session keys keep their policy and allowlist
+    let session = cred_id(&env, "session-0");
+    let allowed = vec![&env, Address::generate(&env)];
+    client.add_session_signer(
+        &session,
+        &BytesN::from_array(&env, &[7u8; 32]),
+        &100,
+        &allowed,
+        &3,
+    );
+    let new_session = cred_id(&env, "session-1");
+    client.rotate_signer(&session, &new_session, &Bytes::from_array(&env, &[8u8; 32]));
+    let signer = client.get_signer(&new_session);
+    assert_eq!(signer.kind, SignerKind::Session);
+    assert_eq!((signer.allowed_contracts, signer.uses_left), (allowed, 3));
+
+    // With a threshold above 1, admins rotate through a proposal
+    client.set_threshold(&2);
+    let replacement = cred_id(&env, "admin-1b");
+    assert_eq!(
+        client.try_rotate_signer(&second, &replacement, &dummy_public_key(&env, 5).into()),
+        Err(Ok(WalletError::ApprovalRequired))
+    );
+    let id = client.propose(&AdminOperation::RotateSigner(
+        second.clone(),
+        replacement.clone(),
+        dummy_public_key(&env, 5),
+    ));
+    client.approve(&id, &rotated);
+    client.approve(&id, &second);
+    client.execute_proposal(&id);
+    assert_eq!(client.get_signer(&replacement).kind, SignerKind::Admin);
+    assert_eq!(admin_count(&env, &client), 2);
+}
+
+#[test]
+fn test_operator_can_transact_but_not_manage() {
+    let env = Env::default();
+    let (admin_key, operator_key) = (passkey(1), passkey(2));
+    let (client, admin) = setup_passkey_wallet(&env, &admin_key);
+    env.mock_all_auths();
+    let operator = cred_id(&env, "operator-0");
+    client.add_operator(&operator, &passkey_public_key(&env, &operator_key));
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("sgn_add"), operator.clone()).into_val(&env),
+        (SignerKind::Operator, 0u32).into_val(&env),
+    );
+    assert_eq!(admin_count(&env, &client), 1);
+    assert_eq!(client.get_signer(&operator).kind, SignerKind::Operator);
+
+    let payload = BytesN::from_array(&env, &[9u8; 32]);
+    let sign = || webauthn_signature(&env, &operator_key, &operator, &payload);
+    let token = Address::generate(&env);
+    let transfer: Vec<Val> =
+        (client.address.clone(), Address::generate(&env), 10i128).into_val(&env);
+    check_auth_contexts(
+        &env,
+        &client,
+        sign(),
+        vec![
+            &env,
+            call_context(&env, &token, "transfer", transfer.clone()),
+        ],
+    )
+    .unwrap();
+    let batch = vec![&env, invocation(&env, &token, "transfer", transfer)];
+    check_auth(&env, &client, sign(), "execute", (batch,).into_val(&env)).unwrap();
+
+    let new_key: Vec<Val> = (cred_id(&env, "admin-1"), dummy_public_key(&env, 2)).into_val(&env);
+    for (function, args) in [
+        ("add_signer", new_key.clone()),
+        ("add_operator", new_key),
+        ("remove_signer", (admin.clone(),).into_val(&env)),
+        (
+            "upgrade",
+            (BytesN::from_array(&env, &[1u8; 32]),).into_val(&env),
+        ),
+        ("set_threshold", (1u32,).into_val(&env)),
+        ("approve", (1u64, operator.clone()).into_val(&env)),
+    ] {
+        assert_eq!(
+            check_auth(&env, &client, sign(), function, args),
+            Err(Ok(WalletError::NotAuthorized))
+        );
+    }
+
+    // Admins still manage signers, operators included
+    check_auth(
+        &env,
+        &client,
+        webauthn_signature(&env, &admin_key, &admin, &payload),
+        "remove_signer",
+        (operator.clone(),).into_val(&env),
+    )
+    .unwrap();
+    client.remove_signer(&operator);
+    assert_eq!(admin_count(&env, &client), 1);
+    assert_eq!(client.list_signers(&0, &10).len(), 1);
+}
+
+#[test]
+fn test_allowed_tokens_per_signer() {
+    let env = Env::default();
+    let (admin_key, operator_key) = (passkey(1), passkey(2));
+    let (client, admin) = setup_passkey_wallet(&env, &admin_key);
+    env.mock_all_auths();
+    let operator = cred_id(&env, "operator-0");
+    client.add_operator(&operator, &passkey_public_key(&env, &operator_key));
+    let (usdc, governance) = (Address::generate(&env), Address::generate(&env));
+    let transfer: Vec<Val> =
+        (client.address.clone(), Address::generate(&env), 10i128).into_val(&env);
+
+    assert_eq!(
+        client.try_set_allowed_tokens(&admin, &vec![&env, usdc.clone()]),
+        Err(Ok(WalletError::InvalidPolicy))
+    );
+    client.set_allowed_tokens(&operator, &vec![&env, usdc.clone()]);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("sgn_tok"), operator.clone()).into_val(&env),
+        vec![&env, usdc.clone()].into_val(&env),
+    );
+
+    let payload = BytesN::from_array(&env, &[9u8; 32]);
+    let sign = || webauthn_signature(&env, &operator_key, &operator, &payload);
+    let spend = |token: &Address| {
+        check_auth_contexts(
+            &env,
+            &client,
+            sign(),
+            vec![
+                &env,
+                call_context(&env, token, "transfer", transfer.clone()),
+            ],
+        )
+    };
+    spend(&usdc).unwrap();
+    assert_eq!(spend(&governance), Err(Ok(WalletError::TokenNotAllowed)));
+    let batch = vec![
+        &env,
+        invocation(&env, &usdc, "transfer", transfer.clone()),
+        invocation(&env, &governance, "transfer", transfer.clone()),
+    ];
+    assert_eq!(
+        check_auth(&env, &client, sign(), "execute", (batch,).into_val(&env)),
+        Err(Ok(WalletError::TokenNotAllowed))
+    );
+
+    // Other calls on the token, and the admin, are not limited
+    check_auth_contexts(
+        &env,
+        &client,
+        sign(),
+        vec![
+            &env,
+            call_context(
+                &env,
+                &governance,
+                "balance",
+                (client.address.clone(),).into_val(&env),
+            ),
+        ],
+    )
+    .unwrap();
+    check_auth_contexts(
+        &env,
+        &client,
+        webauthn_signature(&env, &admin_key, &admin, &payload),
+        vec![
+            &env,
+            call_context(&env, &governance, "transfer", transfer.clone()),
+        ],
+    )
+    .unwrap();
+
+    // Sessions share their policy's token list
+    let session = cred_id(&env, "session-0");
+    let session_key = add_session(&env, &client, &session);
+    client.set_allowed_tokens(&session, &vec![&env, usdc.clone()]);
+    assert_eq!(
+        client.get_signer(&session).policy.allowed_tokens,
+        vec![&env, usdc.clone()]
+    );
+    assert_eq!(
+        check_auth_contexts(
+            &env,
+            &client,
+            session_signature(&env, &session_key, &session),
+            vec![
+                &env,
+                call_context(&env, &governance, "transfer", transfer.clone())
+            ],
+        ),
+        Err(Ok(WalletError::TokenNotAllowed))
+    );
+
+    client.set_allowed_tokens(&operator, &Vec::new(&env));
+    spend(&governance).unwrap();
+}
+
+#[test]
+fn test_timelocked_actions() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup_wallet(&env);
+    client.set_timelock_delay(&100);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("tl_delay"),).into_val(&env),
+        100u32.into_val(&env),
+    );
+
+    // Sensitive changes can no longer be made directly
+    let second = cred_id(&env, "admin-1");
+    let guardian = Address::generate(&env);
+    assert_eq!(
+        client.try_add_signer(&second, &dummy_public_key(&env, 2)),
+        Err(Ok(WalletError::TimelockRequired))
+    );
+    assert_eq!(
+        client.try_add_guardian(&guardian),
+        Err(Ok(WalletError::TimelockRequired))
+    );
+    assert_eq!(
+        client.try_set_recovery_config(&1, &10),
+        Err(Ok(WalletError::TimelockRequired))
+    );
+    assert_eq!(
+        client.try_set_timelock_delay(&0),
+        Err(Ok(WalletError::TimelockRequired))
+    );
+
+    let id = client.queue_action(&TimelockOperation::AddSigner(
+        second.clone(),
+        dummy_public_key(&env, 2),
+    ));
+    let action = QueuedAction {
+        operation: TimelockOperation::AddSigner(second.clone(), dummy_public_key(&env, 2)),
+        executable_ledger: env.ledger().sequence() + 100,
+    };
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("tl_queue"), id).into_val(&env),
+        action.clone().into_val(&env),
+    );
+    assert_eq!(client.get_action(&id), action);
+    assert_eq!(
+        client.try_execute_action(&id),
+        Err(Ok(WalletError::ActionNotReady))
+    );
+
+    // Any admin can cancel while the action waits; a session key cannot
+    let guardian_id = client.queue_action(&TimelockOperation::AddGuardian(guardian));
+    let session = cred_id(&env, "session-0");
+    let session_key = add_session(&env, &client, &session);
+    assert_eq!(
+        check_auth(
+            &env,
+            &client,
+            session_signature(&env, &session_key, &session),
+            "cancel_action",
+            (guardian_id,).into_val(&env),
+        ),
+        Err(Ok(WalletError::NotAuthorized))
+    );
+    client.cancel_action(&guardian_id);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("tl_cancel"), guardian_id).into_val(&env),
+        ().into_val(&env),
+    );
+
+    env.ledger().with_mut(|li| li.sequence_number += 100);
+    client.execute_action(&id);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("tl_exec"), id).into_val(&env),
+        ().into_val(&env),
+    );
+    assert_eq!(admin_count(&env, &client), 2);
+    assert_eq!(
+        client.try_execute_action(&guardian_id),
+        Err(Ok(WalletError::ActionNotFound))
+    );
+    assert!(client.get_guardians().is_empty());
+
+    // Lowering the delay waits out the current one
+    let id = client.queue_action(&TimelockOperation::SetDelay(0));
+    env.ledger().with_mut(|li| li.sequence_number += 100);
+    client.execute_action(&id);
+    assert_eq!(client.get_timelock_delay(), 0);
+    client.add_signer(&cred_id(&env, "admin-2"), &dummy_public_key(&env, 3));
+    assert_eq!(admin_count(&env, &client), 3);
+}
+
+#[test]
+fn test_freeze_blocks_auth_until_unfrozen() {
+    let env = long_lived_env();
+    let key = passkey(1);
+    let (client, admin) = setup_passkey_wallet(&env, &key);
+    let session = cred_id(&env, "session-0");
+    let session_key = add_session(&env, &client, &session);
+    let payload = BytesN::from_array(&env, &[9u8; 32]);
+    let sign = || webauthn_signature(&env, &key, &admin, &payload);
+    let token = Address::generate(&env);
+    let transfer = || {
+        vec![
+            &env,
+            call_context(
+                &env,
+                &token,
+                "transfer",
+                (client.address.clone(), 5i128).into_val(&env),
+            ),
+        ]
+    };
+
+    client.freeze();
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("frozen"),).into_val(&env),
+        ().into_val(&env),
+    );
+    assert!(client.is_frozen());
+    assert_eq!(
+        check_auth_contexts(&env, &client, sign(), transfer()),
+        Err(Ok(WalletError::WalletFrozen))
+    );
+    assert_eq!(
+        check_auth_contexts(
+            &env,
+            &client,
+            session_signature(&env, &session_key, &session),
+            transfer()
+        ),
+        Err(Ok(WalletError::WalletFrozen))
+    );
+    assert_eq!(
+        check_auth(
+            &env,
+            &client,
+            sign(),
+            "add_guardian",
+            (token.clone(),).into_val(&env)
+        ),
+        Err(Ok(WalletError::WalletFrozen))
+    );
+    check_auth(&env, &client, sign(), "unfreeze", Vec::new(&env)).unwrap();
+
+    // A single admin's unfreeze only takes effect after the delay, and
+    // freezing again cancels it
+    client.unfreeze();
+    env.ledger()
+        .with_mut(|li| li.sequence_number += RECOVERY_DELAY - 1);
+    assert!(client.is_frozen());
+    client.freeze();
+    env.ledger().with_mut(|li| li.sequence_number += 1);
+    assert!(client.is_frozen());
+
+    client.unfreeze();
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("unfreeze"),).into_val(&env),
+        (env.ledger().sequence() + RECOVERY_DELAY).into_val(&env),
+    );
+    env.ledger()
+        .with_mut(|li| li.sequence_number += RECOVERY_DELAY);
+    assert!(!client.is_frozen());
+    check_auth_contexts(&env, &client, sign(), transfer()).unwrap();
+    assert_eq!(client.try_unfreeze(), Err(Ok(WalletError::NotFrozen)));
+
+    // With a higher threshold, unfreezing takes a proposal
+    client.add_signer(&cred_id(&env, "admin-1"), &dummy_public_key(&env, 2));
+    client.set_threshold(&2);
+    client.freeze();
+    assert_eq!(
+        client.try_unfreeze(),
+        Err(Ok(WalletError::ApprovalRequired))
+    );
+    let remove = client.propose(&AdminOperation::RemoveSigner(cred_id(&env, "admin-1")));
+    let id = client.propose(&AdminOperation::Unfreeze);
+    for proposal in [remove, id] {
+        client.approve(&proposal, &admin);
+        client.approve(&proposal, &cred_id(&env, "admin-1"));
+    }
+    assert_eq!(
+        client.try_execute_proposal(&remove),
+        Err(Ok(WalletError::WalletFrozen))
+    );
+    client.execute_proposal(&id);
+    assert!(!client.is_frozen());
+}
+
+#[test]
+fn test_session_cannot_manage_signers() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, admin) = setup_wallet(&env);
+    let session = cred_id(&env, "session-0");
+    let session_key = add_session(&env, &client, &session);
+    let sign = || session_signature(&env, &session_key, &session);
+    let policy = SessionPolicy {
+        max_per_tx: None,
+        max_total: None,
+        allowed_tokens: Vec::new(&env),
+    };
+
+    let new_admin: Vec<Val> = (cred_id(&env, "admin-1"), dummy_public_key(&env, 2)).into_val(&env);
+    let new_session: Vec<Val> = (
+        cred_id(&env, "session-1"),
+        BytesN::from_array(&env, &[8u8; 32]),
+        100u32,
+        Vec::<Address>::new(&env),
+        0u32,
+    )
+        .into_val(&env);
+    for (function, args) in [
+        ("add_signer", new_admin.clone()),
+        ("add_operator", new_admin),
+        ("add_session_signer", new_session),
+        ("remove_signer", (admin.clone(),).into_val(&env)),
+        (
+            "rotate_signer",
+            (
+                session.clone(),
+                cred_id(&env, "session-1"),
+                Bytes::from_array(&env, &[8u8; 32]),
+            )
+                .into_val(&env),
+        ),
+        (
+            "set_session_policy",
+            (session.clone(), policy).into_val(&env),
+        ),
+        (
+            "upgrade",
+            (BytesN::from_array(&env, &[1u8; 32]),).into_val(&env),
+        ),
+        ("add_guardian", (Address::generate(&env),).into_val(&env)),
+    ] {
+        assert_eq!(
+            check_auth(&env, &client, sign(), function, args),
+            Err(Ok(WalletError::NotAuthorized))
+        );
+    }
+
+    // Batched calls to other contracts are still fine
+    let batch = vec![
+        &env,
+        invocation(&env, &Address::generate(&env), "swap", Vec::new(&env)),
+    ];
+    check_auth(&env, &client, sign(), "execute", (batch,).into_val(&env)).unwrap();
+}
+
+// ────────────────────────────────────────────────────────
+//  Attestation
+// ────────────────────────────────────────────────────────
+
+/// Append a CBOR item head of `major` type with argument `len`.
+fn cbor_head(out: &mut std::vec::Vec<u8>, major: u8, len: usize) {
+    match len {
+        0..=23 => out.push(major << 5 | len as u8),
+        24..=255 => out.extend([major << 5 | 24, len as u8]),
+        _ => {
+            out.push(major << 5 | 25);
+            out.extend((len as u16).to_be_bytes());
+        }
+    }
+}
+
+fn cbor_bytes(out: &mut std::vec::Vec<u8>, bytes: &[u8]) {
+    cbor_head(out, 2, bytes.len());
+    out.extend_from_slice(bytes);
+}
+
+fn cbor_text(out: &mut std::vec::Vec<u8>, text: &str) {
+    cbor_head(out, 3, text.len());
+    out.extend_from_slice(text.as_bytes());
+}
+
+/// A registration clientDataJSON of `client_data_type`.
+fn registration_client_data(env: &Env, client_data_type: &str) -> Bytes {
+    let json = std::format!(
+        "{{\"type\":\"{client_data_type}\",\"challenge\":\"AAAA\",\"origin\":\"https://wallet.galaxy.dev\"}}"
+    );
+    Bytes::from_slice(env, json.as_bytes())
+}
+
+/// A `fmt` attestation object registering `key` as `credential_id`,
+/// signed by `attestation_key` (with a certificate for it) or, for self
+/// attestation, by `key` itself.  The signature uses a high S value, as
+/// authenticators may.
+fn attestation_object(
+    env: &Env,
+    fmt: &str,
+    key: &p256::ecdsa::SigningKey,
+    credential_id: &Bytes,
+    client_data_json: &Bytes,
+    attestation_key: Option<&p256::ecdsa::SigningKey>,
+) -> Bytes {
+    attestation_object_with(
+        env,
+        fmt,
+        key,
+        credential_id,
+        client_data_json,
+        attestation_key,
+        &[],
+    )
+}
+
+/// Like [`attestation_object`], with the CBOR `extensions` map appended to
+/// the authenticator data and its ED flag set, unless it is empty.
+fn attestation_object_with(
+    env: &Env,
+    fmt: &str,
+    key: &p256::ecdsa::SigningKey,
+    credential_id: &Bytes,
+    client_data_json: &Bytes,
+    attestation_key: Option<&p256::ecdsa::SigningKey>,
+    extensions: &[u8],
+) -> Bytes {
+    let point = key.verifying_key().to_encoded_point(false);
+    let mut auth_data = std::vec![0u8; 32];
+    auth_data.push(if extensions.is_empty() { 0x45 } else { 0xc5 }); // UP | UV | AT (| ED)
+    auth_data.extend([0u8; 4 + 16]);
+    auth_data.extend((credential_id.len() as u16).to_be_bytes());
+    let mut id = std::vec![0u8; credential_id.len() as usize];
+    credential_id.copy_into_slice(&mut id);
+    auth_data.extend(&id);
+    auth_data.extend([0xa5, 0x01, 0x02, 0x03, 0x26, 0x20, 0x01, 0x21, 0x58, 0x20]);
+    auth_data.extend(point.x().unwrap());
+    auth_data.extend([0x22, 0x58, 0x20]);
+    auth_data.extend(point.y().unwrap());
+    auth_data.extend(extensions);
+
+    let mut signed_data = auth_data.clone();
+    signed_data.extend(env.crypto().sha256(client_data_json).to_array());
+    let digest = env
+        .crypto()
+        .sha256(&Bytes::from_slice(env, &signed_data))
+        .to_array();
+    let signer = attestation_key.unwrap_or(key);
+    let signature: p256::ecdsa::Signature = signer.sign_prehash(&digest).unwrap();
+    let signature = signature.normalize_s().unwrap_or(signature);
+    let (r, s) = signature.split_scalars();
+    let high_s =
+        p256::ecdsa::Signature::from_scalars(r.to_bytes(), (-*s.as_ref()).to_bytes()).unwrap();
+
+    let mut object = std::vec::Vec::new();
+    cbor_head(&mut object, 5, 3);
+    cbor_text(&mut object, "fmt");
+    cbor_text(&mut object, fmt);
+    cbor_text(&mut object, "attStmt");
+    cbor_head(
+        &mut object,
+        5,
+        if attestation_key.is_some() { 3 } else { 2 },
+    );
+    cbor_text(&mut object, "alg");
+    object.push(0x26); // -7, ES256
+    cbor_text(&mut object, "sig");
+    cbor_bytes(&mut object, &high_s.to_der().to_bytes());
+    if let Some(attestation_key) = attestation_key {
+        // A stand-in certificate: only its subject public key info is read
+        let mut certificate = std::vec![0x30, 0x82, 0x01, 0x00];
+        certificate.extend([
+            0x30, 0x59, 0x30, 0x13, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, 0x06,
+            0x08, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07, 0x03, 0x42, 0x00,
+        ]);
+        certificate.extend(
+            attestation_key
+                .verifying_key()
+                .to_encoded_point(false)
+                .as_bytes(),
+        );
+        certificate.extend([0xa3, 0x00]);
+        cbor_text(&mut object, "x5c");
+        cbor_head(&mut object, 4, 1);
+        cbor_bytes(&mut object, &certificate);
+    }
+    cbor_text(&mut object, "authData");
+    cbor_bytes(&mut object, &auth_data);
+    Bytes::from_slice(env, &object)
+}
+
+#[test]
+fn test_add_signer_with_attestation() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup_wallet(&env);
+    let client_data = registration_client_data(&env, "webauthn.create");
+
+    // Self attestation
+    let key = passkey(2);
+    let second = cred_id(&env, "admin-1");
+    client.add_signer_with_attestation(
+        &second,
+        &attestation_object(&env, "packed", &key, &second, &client_data, None),
+        &client_data,
+    );
+    assert_eq!(
+        client.get_signer(&second).public_key,
+        Bytes::from(passkey_public_key(&env, &key))
+    );
+
+    // Attestation certificate
+    let key = passkey(3);
+    let third = cred_id(&env, "admin-2");
+    let object = attestation_object(
+        &env,
+        "packed",
+        &key,
+        &third,
+        &client_data,
+        Some(&passkey(9)),
+    );
+    client.add_signer_with_attestation(&third, &object, &client_data);
+    assert_eq!(admin_count(&env, &client), 3);
+
+    let fourth = cred_id(&env, "admin-3");
+    let object = attestation_object(&env, "packed", &key, &fourth, &client_data, None);
+    assert_eq!(
+        client.try_add_signer_with_attestation(&cred_id(&env, "other"), &object, &client_data),
+        Err(Ok(WalletError::InvalidAttestation))
+    );
+    assert_eq!(
+        client.try_add_signer_with_attestation(
+            &fourth,
+            &object,
+            &registration_client_data(&env, "webauthn.get")
+        ),
+        Err(Ok(WalletError::InvalidClientDataType))
+    );
+    assert_eq!(
+        client.try_add_signer_with_attestation(
+            &fourth,
+            &attestation_object(&env, "none", &key, &fourth, &client_data, None),
+            &client_data
+        ),
+        Err(Ok(WalletError::UnsupportedAttestation))
+    );
+    assert_eq!(
+        client.try_add_signer_with_attestation(
+            &fourth,
+            &Bytes::from_array(&env, &[0xa1, 0x63]),
+            &client_data
+        ),
+        Err(Ok(WalletError::InvalidAttestation))
+    );
+    // Signed over different client data
+    assert!(client
+        .try_add_signer_with_attestation(
+            &fourth,
+            &object,
+            &registration_client_data(&env, "webauthn.create\",\"x\":\"")
+        )
+        .is_err());
+
+    client.set_attestation_required(&true);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("att_req"),).into_val(&env),
+        true.into_val(&env),
+    );
+    assert_eq!(
+        client.try_add_signer(&fourth, &dummy_public_key(&env, 4)),
+        Err(Ok(WalletError::AttestationRequired))
+    );
+    client.add_signer_with_attestation(&fourth, &object, &client_data);
+    assert_eq!(admin_count(&env, &client), 4);
+}
+
+#[test]
+fn test_prove_registration() {
+    let env = Env::default();
+    let key = passkey(1);
+    let (client, admin) = setup_passkey_wallet(&env, &key);
+    let client_data = registration_client_data(&env, "webauthn.create");
+    let object = attestation_object(&env, "packed", &key, &admin, &client_data, None);
+
+    // Needs no auth
+    client.prove_registration(&admin, &object, &client_data);
+    assert_last_event(
+        &env,
+        &client,
+        (Symbol::new(&env, "sgn_prov"), admin.clone()).into_val(&env),
+        ().into_val(&env),
+    );
+
+    // The attested key must be the stored one
+    let other = attestation_object(&env, "packed", &passkey(2), &admin, &client_data, None);
+    assert_eq!(
+        client.try_prove_registration(&admin, &other, &client_data),
+        Err(Ok(WalletError::InvalidAttestation))
+    );
+    assert_eq!(
+        client.try_prove_registration(&cred_id(&env, "unknown"), &object, &client_data),
+        Err(Ok(WalletError::SignerNotFound))
+    );
+    assert_eq!(
+        client.try_prove_registration(
+            &admin,
+            &object,
+            &registration_client_data(&env, "webauthn.get")
+        ),
+        Err(Ok(WalletError::InvalidClientDataType))
+    );
+}
+
+#[test]
+fn test_challenge_accepts_padded_base64url() {
+    let env = Env::default();
+    let key = passkey(1);
+    let (client, admin) = setup_passkey_wallet(&env, &key);
+    let payload = BytesN::from_array(&env, &[9u8; 32]);
+    let challenge = base64url_encode(&env, &payload.to_array());
+    let with_challenge = |suffix: &str| {
+        let mut json = Bytes::from_slice(&env, b"{\"type\":\"webauthn.get\",\"challenge\":\"");
+        json.append(&challenge);
+        json.append(&Bytes::from_slice(&env, suffix.as_bytes()));
+        json.append(&Bytes::from_slice(
+            &env,
+            b"\",\"origin\":\"https://wallet.galaxy.dev\"}",
+        ));
+        signed_client_data(&env, &key, &admin, json, 0x05)
+    };
+
+    check_auth_contexts(&env, &client, with_challenge(""), Vec::new(&env)).unwrap();
+    check_auth_contexts(&env, &client, with_challenge("="), Vec::new(&env)).unwrap();
+    for suffix in ["==", "A", "=A"] {
+        assert_eq!(
+            check_auth_contexts(&env, &client, with_challenge(suffix), Vec::new(&env)),
+            Err(Ok(WalletError::ChallengeMismatch))
+        );
+    }
+}
+
+#[test]
+fn test_der_signatures_are_decoded_on_chain() {
+    let env = Env::default();
+    let key = passkey(1);
+    let (client, admin) = setup_passkey_wallet(&env, &key);
+    let payload = BytesN::from_array(&env, &[9u8; 32]);
+    let with_signature = |encode: &dyn Fn(p256::ecdsa::Signature) -> std::vec::Vec<u8>| {
+        let AccountSignature::WebAuthn(mut sig) = webauthn_signature(&env, &key, &admin, &payload)
+        else {
+            unreachable!()
+        };
+        let mut compact = [0u8; 64];
+        sig.signature.copy_into_slice(&mut compact);
+        let signature = p256::ecdsa::Signature::from_slice(&compact).unwrap();
+        sig.signature = Bytes::from_slice(&env, &encode(signature));
+        AccountSignature::WebAuthn(sig)
+    };
+
+    // DER as authenticators return it, with either half of S
+    check_auth_contexts(
+        &env,
+        &client,
+        with_signature(&|signature| signature.to_der().to_bytes().to_vec()),
+        Vec::new(&env),
+    )
+    .unwrap();
+    let high_s = |signature: p256::ecdsa::Signature| {
+        let (r, s) = signature.split_scalars();
+        p256::ecdsa::Signature::from_scalars(r.to_bytes(), (-*s.as_ref()).to_bytes()).unwrap()
+    };
+    check_auth_contexts(
+        &env,
+        &client,
+        with_signature(&|signature| high_s(signature).to_der().to_bytes().to_vec()),
+        Vec::new(&env),
+    )
+    .unwrap();
+
+    for malformed in [
+        &|signature: p256::ecdsa::Signature| signature.to_bytes()[..63].to_vec(),
+        &|signature: p256::ecdsa::Signature| {
+            let mut der = signature.to_der().to_bytes().to_vec();
+            der[1] += 1;
+            der
+        },
+    ] as [&dyn Fn(p256::ecdsa::Signature) -> std::vec::Vec<u8>; 2]
+    {
+        assert_eq!(
+            check_auth_contexts(&env, &client, with_signature(malformed), Vec::new(&env)),
+            Err(Ok(WalletError::InvalidSignature))
+        );
+    }
+}
+
+#[test]
+fn test_high_s_compact_signature_is_rejected() {
+    let env = Env::default();
+    let key = passkey(1);
+    let (client, admin) = setup_passkey_wallet(&env, &key);
+    let payload = BytesN::from_array(&env, &[9u8; 32]);
+    let AccountSignature::WebAuthn(mut sig) = webauthn_signature(&env, &key, &admin, &payload)
+    else {
+        unreachable!()
+    };
+
+    let mut compact = [0u8; 64];
+    sig.signature.copy_into_slice(&mut compact);
+    let (r, s) = p256::ecdsa::Signature::from_slice(&compact)
+        .unwrap()
+        .split_scalars();
+    let mirrored =
+        p256::ecdsa::Signature::from_scalars(r.to_bytes(), (-*s.as_ref()).to_bytes()).unwrap();
+    sig.signature = Bytes::from_slice(&env, &mirrored.to_bytes());
+    assert_eq!(
+        check_auth_contexts(
+            &env,
+            &client,
+            AccountSignature::WebAuthn(sig),
+            Vec::new(&env)
+        ),
+        Err(Ok(WalletError::NonCanonicalSignature))
+    );
+}
+
+#[test]
+fn test_verify_message() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let key = passkey(1);
+    let (client, admin) = setup_passkey_wallet(&env, &key);
+    let operator_key = passkey(2);
+    let operator = cred_id(&env, "operator-0");
+    client.add_operator(&operator, &passkey_public_key(&env, &operator_key));
+    let message = Bytes::from_slice(&env, b"Sign in to galaxy.dev, nonce 42");
+    let mut prefixed = Bytes::from_slice(&env, b"Stellar Signed Message:\n");
+    prefixed.append(&message);
+    let message_hash = env.crypto().sha256(&prefixed).to_bytes();
+    let sign = |key: &p256::ecdsa::SigningKey, credential_id: &Bytes, payload: &BytesN<32>| {
+        let AccountSignature::WebAuthn(sig) = webauthn_signature(&env, key, credential_id, payload)
+        else {
+            unreachable!()
+        };
+        sig
+    };
+
+    client.verify_message(&message, &sign(&key, &admin, &message_hash));
+    client.verify_message(&message, &sign(&operator_key, &operator, &message_hash));
+    assert_eq!(client.get_signer(&admin).last_used_at, 0);
+
+    // Another message, an unprefixed hash, or an unknown passkey
+    let other = Bytes::from_slice(&env, b"Sign in to galaxy.dev, nonce 43");
+    assert_eq!(
+        client.try_verify_message(&other, &sign(&key, &admin, &message_hash)),
+        Err(Ok(WalletError::ChallengeMismatch))
+    );
+    let unprefixed = env.crypto().sha256(&message).to_bytes();
+    assert_eq!(
+        client.try_verify_message(&message, &sign(&key, &admin, &unprefixed)),
+        Err(Ok(WalletError::ChallengeMismatch))
+    );
+    let stranger = cred_id(&env, "admin-1");
+    assert_eq!(
+        client.try_verify_message(&message, &sign(&key, &stranger, &message_hash)),
+        Err(Ok(WalletError::SignerNotFound))
+    );
+    let session = cred_id(&env, "session-0");
+    add_session(&env, &client, &session);
+    assert_eq!(
+        client.try_verify_message(&message, &sign(&key, &session, &message_hash)),
+        Err(Ok(WalletError::NotAuthorized))
+    );
+
+    // Another passkey's signature under the admin's credential ID aborts
+    // in the host
+    assert!(matches!(
+        client.try_verify_message(&message, &sign(&passkey(3), &admin, &message_hash)),
+        Err(Err(_))
+    ));
+
+    client.add_rp_id_hash(&BytesN::from_array(&env, &[7u8; 32]));
+    assert_eq!(
+        client.try_verify_message(&message, &sign(&key, &admin, &message_hash)),
+        Err(Ok(WalletError::RpIdMismatch))
+    );
+    client.remove_rp_id_hash(&BytesN::from_array(&env, &[7u8; 32]));
+
+    client.freeze();
+    assert_eq!(
+        client.try_verify_message(&message, &sign(&key, &admin, &message_hash)),
+        Err(Ok(WalletError::WalletFrozen))
+    );
+}
+
+#[test]
+fn test_wallet_profile() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup_wallet(&env);
+    assert_eq!(
+        client.get_profile(),
+        WalletProfile {
+            display_name: String::from_str(&env, ""),
+            avatar_hash: None,
+            currency: None,
+        }
+    );
+
+    let profile = WalletProfile {
+        display_name: String::from_str(&env, "Ada's wallet"),
+        avatar_hash: Some(BytesN::from_array(&env, &[7u8; 32])),
+        currency: Some(symbol_short!("USD")),
+    };
+    client.set_profile(&profile);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("profile"),).into_val(&env),
+        profile.clone().into_val(&env),
+    );
+    assert_eq!(client.get_profile(), profile);
+
+    let long_name = WalletProfile {
+        display_name: String::from_str(&env, &"x".repeat(65)),
+        ..profile.clone()
+    };
+    assert_eq!(
+        client.try_set_profile(&long_name),
+        Err(Ok(WalletError::InvalidLabel))
+    );
+    assert_eq!(client.get_profile(), profile);
+
+    // Session keys cannot change the profile
+    let session = cred_id(&env, "session-0");
+    let session_key = add_session(&env, &client, &session);
+    let update = call_context(
+        &env,
+        &client.address,
+        "set_profile",
+        (profile,).into_val(&env),
+    );
+    assert_eq!(
+        check_auth_contexts(
+            &env,
+            &client,
+            session_signature(&env, &session_key, &session),
+            vec![&env, update],
+        ),
+        Err(Ok(WalletError::NotAuthorized))
+    );
+}
+
+#[test]
+fn test_corrupted_signature_scalars() {
+    let env = Env::default();
+    let key = passkey(1);
+    let (client, admin) = setup_passkey_wallet(&env, &key);
+    let payload = BytesN::from_array(&env, &[9u8; 32]);
+    let AccountSignature::WebAuthn(sig) = webauthn_signature(&env, &key, &admin, &payload) else {
+        unreachable!()
+    };
+    let mut valid = [0u8; 64];
+    sig.signature.copy_into_slice(&mut valid);
+    let check = |signature: &[u8]| {
+        let mut sig = sig.clone();
+        sig.signature = Bytes::from_slice(&env, signature);
+        check_auth_contexts(
+            &env,
+            &client,
+            AccountSignature::WebAuthn(sig),
+            Vec::new(&env),
+        )
+    };
+    let order = ecdsa::P256_ORDER;
+
+    // R or S of zero, or not below the group order
+    for (offset, scalar) in [(0, [0u8; 32]), (32, [0u8; 32]), (0, order), (0, [0xff; 32])] {
+        let mut corrupted = valid;
+        corrupted[offset..offset + 32].copy_from_slice(&scalar);
+        assert_eq!(check(&corrupted), Err(Ok(WalletError::InvalidSignature)));
+    }
+    let mut der = std::vec![0x30, 0x45, 0x02, 0x21, 0x00];
+    der.extend(order);
+    der.extend([0x02, 0x20]);
+    der.extend(&valid[32..]);
+    assert_eq!(check(&der), Err(Ok(WalletError::InvalidSignature)));
+
+    // A well-formed signature that does not verify still fails in the host
+    let mut corrupted = valid;
+    corrupted[31] ^= 1;
+    assert!(matches!(check(&corrupted), Err(Err(_))));
+    check(&valid).unwrap();
+}
+
+#[test]
+fn test_auth_threshold_needs_several_passkeys() {
+    let env = long_lived_env();
+    let keys = [passkey(1), passkey(2), passkey(3)];
+    let (client, admin) = setup_passkey_wallet(&env, &keys[0]);
+    let (second, operator) = (cred_id(&env, "admin-1"), cred_id(&env, "operator-0"));
+    client.add_signer(&second, &passkey_public_key(&env, &keys[1]));
+    client.add_operator(&operator, &passkey_public_key(&env, &keys[2]));
+
+    assert_eq!(
+        client.try_set_auth_threshold(&3),
+        Err(Ok(WalletError::InvalidThreshold))
+    );
+    client.set_auth_threshold(&2);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("auth_thr"),).into_val(&env),
+        2u32.into_val(&env),
+    );
+    assert_eq!(client.get_auth_threshold(), 2);
+
+    let payload = BytesN::from_array(&env, &[9u8; 32]);
+    let sign = |key: &p256::ecdsa::SigningKey, credential_id: &Bytes| {
+        let AccountSignature::WebAuthn(sig) =
+            webauthn_signature(&env, key, credential_id, &payload)
+        else {
+            unreachable!()
+        };
+        sig
+    };
+    let token = Address::generate(&env);
+    let transfer = vec![
+        &env,
+        call_context(
+            &env,
+            &token,
+            "transfer",
+            (client.address.clone(), Address::generate(&env), 10i128).into_val(&env),
+        ),
+    ];
+
+    assert_eq!(
+        check_auth_contexts(
+            &env,
+            &client,
+            webauthn_signature(&env, &keys[0], &admin, &payload),
+            transfer.clone()
+        ),
+        Err(Ok(WalletError::InsufficientSignatures))
+    );
+    let duplicated = vec![&env, sign(&keys[0], &admin), sign(&keys[0], &admin)];
+    assert_eq!(
+        check_auth_contexts(
+            &env,
+            &client,
+            AccountSignature::MultiWebAuthn(duplicated),
+            transfer.clone()
+        ),
+        Err(Ok(WalletError::DuplicateSignature))
+    );
+    let admins = vec![&env, sign(&keys[0], &admin), sign(&keys[1], &second)];
+    check_auth_contexts(
+        &env,
+        &client,
+        AccountSignature::MultiWebAuthn(admins.clone()),
+        transfer.clone(),
+    )
+    .unwrap();
+
+    // An operator co-signer counts, but only for calls it may make itself
+    let with_operator = vec![&env, sign(&keys[0], &admin), sign(&keys[2], &operator)];
+    check_auth_contexts(
+        &env,
+        &client,
+        AccountSignature::MultiWebAuthn(with_operator.clone()),
+        transfer.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        check_auth(
+            &env,
+            &client,
+            AccountSignature::MultiWebAuthn(with_operator),
+            "remove_signer",
+            (operator.clone(),).into_val(&env),
+        ),
+        Err(Ok(WalletError::NotAuthorized))
+    );
+
+    // Co-signing admins may approve as either of themselves
+    check_auth(
+        &env,
+        &client,
+        AccountSignature::MultiWebAuthn(admins.clone()),
+        "approve",
+        (1u64, second.clone()).into_val(&env),
+    )
+    .unwrap();
+    assert_eq!(
+        check_auth(
+            &env,
+            &client,
+            AccountSignature::MultiWebAuthn(admins),
+            "approve",
+            (1u64, cred_id(&env, "admin-2")).into_val(&env),
+        ),
+        Err(Ok(WalletError::NotAuthorized))
+    );
+
+    // Session keys are not affected
+    let session = cred_id(&env, "session-0");
+    let session_key = add_session(&env, &client, &session);
+    check_auth_contexts(
+        &env,
+        &client,
+        session_signature(&env, &session_key, &session),
+        transfer,
+    )
+    .unwrap();
+
+    // Admins can never drop below the auth threshold, until a recovery
+    // resets it for the recovered passkey
+    assert_eq!(
+        client.try_remove_signer(&second),
+        Err(Ok(WalletError::InvalidThreshold))
+    );
+    let guardian = Address::generate(&env);
+    client.add_guardian(&guardian);
+    client.propose_recovery(
+        &guardian,
+        &cred_id(&env, "admin-recovered"),
+        &dummy_public_key(&env, 9),
+    );
+    env.ledger()
+        .with_mut(|li| li.sequence_number += RECOVERY_DELAY);
+    client.finalize_recovery();
+    assert_eq!(client.get_auth_threshold(), 1);
+}
+
+#[test]
+fn test_execute_sponsored_reimburses_relayer() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup_wallet(&env);
+    let token = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    token::StellarAssetClient::new(&env, &token).mint(&client.address, &1_000);
+    let (relayer, recipient) = (Address::generate(&env), Address::generate(&env));
+    let wallet = client.address.clone();
+    let batch = vec![
+        &env,
+        invocation(
+            &env,
+            &token,
+            "transfer",
+            (wallet.clone(), recipient.clone(), 100i128).into_val(&env),
+        ),
+    ];
+
+    assert_eq!(
+        client.try_execute_sponsored(&relayer, &batch, &token, &5),
+        Err(Ok(WalletError::RelayerNotAllowed))
+    );
+    client.add_relayer(&relayer);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("rly_add"), relayer.clone()).into_val(&env),
+        ().into_val(&env),
+    );
+    assert_eq!(client.get_relayers(), vec![&env, relayer.clone()]);
+    assert_eq!(
+        client.try_execute_sponsored(&relayer, &batch, &token, &-1),
+        Err(Ok(WalletError::InvalidFee))
+    );
+
+    client.execute_sponsored(&relayer, &batch, &token, &5);
+    assert_last_event(
+        &env,
+        &client,
+        (Symbol::new(&env, "sponsored"), relayer.clone()).into_val(&env),
+        (token.clone(), 5i128).into_val(&env),
+    );
+    let token_client = token::Client::new(&env, &token);
+    assert_eq!(token_client.balance(&recipient), 100);
+    assert_eq!(token_client.balance(&relayer), 5);
+    assert_eq!(token_client.balance(&wallet), 895);
+
+    // Session keys may sponsor, with the fee held to their allowlist
+    let session = cred_id(&env, "session-0");
+    let session_key = SigningKey::from_bytes(&[5u8; 32]);
+    client.add_session_signer(
+        &session,
+        &BytesN::from_array(&env, &session_key.verifying_key().to_bytes()),
+        &100,
+        &vec![&env, token.clone()],
+        &0,
+    );
+    let sponsored = |fee_token: &Address| {
+        check_auth(
+            &env,
+            &client,
+            session_signature(&env, &session_key, &session),
+            "execute_sponsored",
+            (relayer.clone(), batch.clone(), fee_token.clone(), 5i128).into_val(&env),
+        )
+    };
+    sponsored(&token).unwrap();
+    assert_eq!(
+        sponsored(&Address::generate(&env)),
+        Err(Ok(WalletError::ContractNotAllowed))
+    );
+
+    client.remove_relayer(&relayer);
+    assert_eq!(
+        client.try_remove_relayer(&relayer),
+        Err(Ok(WalletError::RelayerNotAllowed))
+    );
+    assert_eq!(
+        client.try_execute_sponsored(&relayer, &batch, &token, &5),
+        Err(Ok(WalletError::RelayerNotAllowed))
+    );
+}
+
+#[test]
+fn test_sub_account_budgets() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, admin) = setup_wallet(&env);
+    let (token, other_token) = (Address::generate(&env), Address::generate(&env));
+    let trading = symbol_short!("trading");
+    let session = cred_id(&env, "session-0");
+    let session_key = add_session(&env, &client, &session);
+    let operator_key = passkey(2);
+    let operator = cred_id(&env, "operator-0");
+    client.add_operator(&operator, &passkey_public_key(&env, &operator_key));
+    let transfer = |token: &Address, amount: i128| {
+        vec![
+            &env,
+            call_context(
+                &env,
+                token,
+                "transfer",
+                (client.address.clone(), Address::generate(&env), amount).into_val(&env),
+            ),
+        ]
+    };
+    let by_session = |calls| {
+        check_auth_contexts(
+            &env,
+            &client,
+            session_signature(&env, &session_key, &session),
+            calls,
+        )
+    };
+
+    assert_eq!(
+        client.try_assign_sub_account(&session, &Some(trading.clone())),
+        Err(Ok(WalletError::SubAccountNotFound))
+    );
+    client.create_sub_account(&trading);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("sub_add"), trading.clone()).into_val(&env),
+        ().into_val(&env),
+    );
+    assert_eq!(
+        client.try_create_sub_account(&trading),
+        Err(Ok(WalletError::SubAccountExists))
+    );
+    assert_eq!(
+        client.try_set_sub_account_budget(&trading, &token, &-1),
+        Err(Ok(WalletError::InvalidPolicy))
+    );
+    client.set_sub_account_budget(&trading, &token, &100);
+    assert_eq!(
+        client.try_assign_sub_account(&admin, &Some(trading.clone())),
+        Err(Ok(WalletError::InvalidPolicy))
+    );
+    client.assign_sub_account(&session, &Some(trading.clone()));
+    client.assign_sub_account(&operator, &Some(trading.clone()));
+    assert_eq!(
+        client.get_signer(&session).sub_account,
+        Some(trading.clone())
+    );
+
+    // Assigned signers spend from the budget, and only tokens it has
+    by_session(transfer(&token, 60)).unwrap();
+    assert_eq!(
+        client.get_sub_account_budgets(&trading).get(token.clone()),
+        Some(40)
+    );
+    assert_eq!(
+        by_session(transfer(&token, 50)),
+        Err(Ok(WalletError::BudgetExceeded))
+    );
+    assert_eq!(
+        by_session(transfer(&other_token, 1)),
+        Err(Ok(WalletError::BudgetExceeded))
+    );
+    let payload = BytesN::from_array(&env, &[9u8; 32]);
+    let by_operator = |calls| {
+        check_auth_contexts(
+            &env,
+            &client,
+            webauthn_signature(&env, &operator_key, &operator, &payload),
+            calls,
+        )
+    };
+    by_operator(transfer(&token, 40)).unwrap();
+    assert_eq!(
+        by_operator(transfer(&token, 1)),
+        Err(Ok(WalletError::BudgetExceeded))
+    );
+
+    // Unassigned signers are not held to it
+    client.assign_sub_account(&session, &None);
+    by_session(transfer(&other_token, 500)).unwrap();
+
+    client.assign_sub_account(&session, &Some(trading.clone()));
+    client.remove_sub_account(&trading);
+    assert!(client.get_sub_accounts().is_empty());
+    assert_eq!(
+        by_session(transfer(&token, 1)),
+        Err(Ok(WalletError::SubAccountNotFound))
+    );
+
+    for i in 0..subaccounts::MAX_SUB_ACCOUNTS {
+        client.create_sub_account(&Symbol::new(&env, &std::format!("account_{i}")));
+    }
+    assert_eq!(
+        client.try_create_sub_account(&trading),
+        Err(Ok(WalletError::TooManySubAccounts))
+    );
+}
+
+#[test]
+fn test_relayer_rate_limit() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup_wallet(&env);
+    let token = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    token::StellarAssetClient::new(&env, &token).mint(&client.address, &1_000);
+    let (relayer, other) = (Address::generate(&env), Address::generate(&env));
+    let batch = Vec::<Invocation>::new(&env);
+
+    assert_eq!(
+        client.try_set_relayer_rate_limit(&relayer, &Some(2)),
+        Err(Ok(WalletError::RelayerNotAllowed))
+    );
+    client.add_relayer(&relayer);
+    client.add_relayer(&other);
+    assert_eq!(
+        client.try_set_relayer_rate_limit(&relayer, &Some(0)),
+        Err(Ok(WalletError::InvalidPolicy))
+    );
+    client.set_relayer_rate_limit(&relayer, &Some(2));
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("rly_lim"), relayer.clone()).into_val(&env),
+        Some(2u32).into_val(&env),
+    );
+    assert_eq!(client.get_relayer_rate_limit(&relayer), Some(2));
+
+    client.execute_sponsored(&relayer, &batch, &token, &5);
+    client.execute_sponsored(&relayer, &batch, &token, &5);
+    assert_eq!(client.get_relayer_usage(&relayer), 2);
+    assert_eq!(
+        client.try_execute_sponsored(&relayer, &batch, &token, &5),
+        Err(Ok(WalletError::RelayerRateLimited))
+    );
+    assert_eq!(token::Client::new(&env, &token).balance(&relayer), 10);
+
+    // Other relayers are not affected, and the count resets daily
+    client.execute_sponsored(&other, &batch, &token, &5);
+    env.ledger().with_mut(|li| li.timestamp += 86_400);
+    assert_eq!(client.get_relayer_usage(&relayer), 0);
+    client.execute_sponsored(&relayer, &batch, &token, &5);
+
+    // Removing a relayer drops its limit
+    client.remove_relayer(&relayer);
+    assert_eq!(client.get_relayer_rate_limit(&relayer), None);
+    client.add_relayer(&relayer);
+    client.set_relayer_rate_limit(&other, &Some(1));
+    client.set_relayer_rate_limit(&other, &None);
+    for _ in 0..3 {
+        client.execute_sponsored(&relayer, &batch, &token, &5);
+        client.execute_sponsored(&other, &batch, &token, &5);
+    }
+}
+
+#[test]
+fn test_spending_intents() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup_wallet(&env);
+    let token = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    token::StellarAssetClient::new(&env, &token).mint(&client.address, &1_000);
+    let balance = |address: &Address| token::Client::new(&env, &token).balance(address);
+    let payee = Address::generate(&env);
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    assert_eq!(
+        client.try_create_intent(&token, &payee, &0, &2_000),
+        Err(Ok(WalletError::InvalidIntent))
+    );
+    assert_eq!(
+        client.try_create_intent(&token, &payee, &100, &999),
+        Err(Ok(WalletError::IntentExpired))
+    );
+    let id = client.create_intent(&token, &payee, &100, &2_000);
+    let intent = Intent {
+        token: token.clone(),
+        to: payee.clone(),
+        amount: 100,
+        deadline: 2_000,
+    };
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("int_add"), id).into_val(&env),
+        intent.clone().into_val(&env),
+    );
+    assert_eq!(client.get_intent(&id), intent);
+
+    // Session keys cannot create intents
+    let session = cred_id(&env, "session-0");
+    let session_key = add_session(&env, &client, &session);
+    let create = call_context(
+        &env,
+        &client.address,
+        "create_intent",
+        (token.clone(), payee.clone(), 100i128, 2_000u64).into_val(&env),
+    );
+    assert_eq!(
+        check_auth_contexts(
+            &env,
+            &client,
+            session_signature(&env, &session_key, &session),
+            vec![&env, create],
+        ),
+        Err(Ok(WalletError::NotAuthorized))
+    );
+
+    // Anyone can make the transfer, exactly once
+    env.set_auths(&[]);
+    client.execute_intent(&id);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("int_exec"), id).into_val(&env),
+        ().into_val(&env),
+    );
+    assert_eq!(balance(&payee), 100);
+    assert_eq!(
+        client.try_execute_intent(&id),
+        Err(Ok(WalletError::IntentNotFound))
+    );
+
+    // Daily limits, deadlines and freezes still apply
+    env.mock_all_auths();
+    client.set_daily_limit(&token, &Some(50));
+    let over_limit = client.create_intent(&token, &payee, &100, &2_000);
+    assert_eq!(
+        client.try_execute_intent(&over_limit),
+        Err(Ok(WalletError::DailyLimitExceeded))
+    );
+    env.ledger().with_mut(|li| li.timestamp = 2_001);
+    assert_eq!(
+        client.try_execute_intent(&over_limit),
+        Err(Ok(WalletError::IntentExpired))
+    );
+    let frozen = client.create_intent(&token, &payee, &100, &3_000);
+    client.freeze();
+    assert_eq!(
+        client.try_execute_intent(&frozen),
+        Err(Ok(WalletError::WalletFrozen))
+    );
+
+    client.cancel_intent(&over_limit);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("int_rem"), over_limit).into_val(&env),
+        ().into_val(&env),
+    );
+    assert_eq!(
+        client.try_get_intent(&over_limit),
+        Err(Ok(WalletError::IntentNotFound))
+    );
+    assert_eq!(balance(&payee), 100);
+}
+
+#[test]
+fn test_gas_tank_reimburses_executors() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup_wallet(&env);
+    let token = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    token::StellarAssetClient::new(&env, &token).mint(&client.address, &1_000);
+    let balance = |address: &Address| token::Client::new(&env, &token).balance(address);
+    let (executor, stranger, payee) = (
+        Address::generate(&env),
+        Address::generate(&env),
+        Address::generate(&env),
+    );
+    let intent = || client.create_intent(&token, &payee, &100, &u64::MAX);
+
+    let tank = GasTank {
+        token: token.clone(),
+        budget: 25,
+        reimbursement: 10,
+        daily_cap: 15,
+    };
+    assert_eq!(
+        client.try_set_gas_tank(&Some(GasTank {
+            budget: -1,
+            ..tank.clone()
+        })),
+        Err(Ok(WalletError::InvalidPolicy))
+    );
+    client.set_gas_tank(&Some(tank.clone()));
+    client.add_executor(&executor);
+    assert_eq!(client.get_executors(), vec![&env, executor.clone()]);
+
+    let id = intent();
+    assert_eq!(
+        client.try_run_task(&stranger, &KeeperTask::ExecuteIntent(id)),
+        Err(Ok(WalletError::ExecutorNotAllowed))
+    );
+    client.run_task(&executor, &KeeperTask::ExecuteIntent(id));
+    assert_eq!((balance(&payee), balance(&executor)), (100, 10));
+    assert_eq!(client.get_gas_tank().unwrap().budget, 15);
+    assert_eq!(
+        client.try_run_task(&executor, &KeeperTask::ExecuteIntent(id)),
+        Err(Ok(WalletError::IntentNotFound))
+    );
+
+    // The daily cap cuts reimbursements down, then stops them
+    let id = intent();
+    client.run_task(&executor, &KeeperTask::ExecuteIntent(id));
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("gas_paid"), executor.clone()).into_val(&env),
+        (token.clone(), 5i128).into_val(&env),
+    );
+    assert_eq!(client.get_executor_reimbursed(&executor), 15);
+    let id = intent();
+    client.run_task(&executor, &KeeperTask::ExecuteIntent(id));
+    assert_eq!((balance(&payee), balance(&executor)), (300, 15));
+
+    // A new day pays again, up to what is left of the budget
+    env.ledger().with_mut(|li| li.timestamp += 86_400);
+    let id = intent();
+    client.run_task(&executor, &KeeperTask::ExecuteIntent(id));
+    let id = intent();
+    client.run_task(&executor, &KeeperTask::ExecuteIntent(id));
+    assert_eq!(balance(&executor), 25);
+    assert_eq!(client.get_gas_tank().unwrap().budget, 0);
+
+    client.remove_executor(&executor);
+    assert_eq!(
+        client.try_remove_executor(&executor),
+        Err(Ok(WalletError::ExecutorNotAllowed))
+    );
+}
+
+#[test]
+fn test_gas_tank_reimbursement_counts_towards_daily_limit() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup_wallet(&env);
+    let token = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    token::StellarAssetClient::new(&env, &token).mint(&client.address, &1_000);
+    let (executor, payee) = (Address::generate(&env), Address::generate(&env));
+    client.set_gas_tank(&Some(GasTank {
+        token: token.clone(),
+        budget: 100,
+        reimbursement: 10,
+        daily_cap: 100,
+    }));
+    client.add_executor(&executor);
+    client.set_daily_limit(&token, &Some(105));
+
+    // The payment fits under the limit, the payment and payout do not
+    let id = client.create_intent(&token, &payee, &100, &u64::MAX);
+    assert_eq!(
+        client.try_run_task(&executor, &KeeperTask::ExecuteIntent(id)),
+        Err(Ok(WalletError::DailyLimitExceeded))
+    );
+    assert_eq!(client.get_gas_tank().unwrap().budget, 100);
+    client.execute_intent(&id);
+    assert_eq!(client.get_daily_spent(&token), 100);
+
+    env.ledger().with_mut(|li| li.timestamp += 86_400);
+    let id = client.create_intent(&token, &payee, &90, &u64::MAX);
+    client.run_task(&executor, &KeeperTask::ExecuteIntent(id));
+    assert_eq!(client.get_daily_spent(&token), 100);
+    assert_eq!(token::Client::new(&env, &token).balance(&executor), 10);
+}
+
+#[test]
+fn test_batched_actions_and_intents() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _) = setup_wallet(&env);
+    client.set_timelock_delay(&100);
+    let (token, payee) = (Address::generate(&env), Address::generate(&env));
+    let (first, second) = (Address::generate(&env), Address::generate(&env));
+
+    // One wallet authorization covers the whole batch
+    let operations = vec![
+        &env,
+        TimelockOperation::AddGuardian(first.clone()),
+        TimelockOperation::AddGuardian(second.clone()),
+    ];
+    assert_eq!(client.queue_actions(&operations), vec![&env, 1, 2]);
+    assert_eq!(
+        env.auths(),
+        std::vec![(
+            client.address.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    client.address.clone(),
+                    Symbol::new(&env, "queue_actions"),
+                    (operations.clone(),).into_val(&env),
+                )),
+                sub_invocations: std::vec![],
+            }
+        )]
+    );
+    assert_eq!(
+        client.get_action(&2).operation,
+        TimelockOperation::AddGuardian(second.clone())
+    );
+    env.ledger().with_mut(|li| li.sequence_number += 100);
+    client.execute_action(&1);
+    client.execute_action(&2);
+    assert_eq!(client.get_guardians(), vec![&env, first, second]);
+
+    let intent = |amount: i128| Intent {
+        token: token.clone(),
+        to: payee.clone(),
+        amount,
+        deadline: 1_000,
+    };
+    let ids = client.create_intents(&vec![&env, intent(10), intent(20)]);
+    assert_eq!(ids, vec![&env, 1, 2]);
+    assert_eq!(client.get_intent(&2), intent(20));
+
+    // One bad item fails the whole batch
+    assert_eq!(
+        client.try_create_intents(&vec![&env, intent(30), intent(0)]),
+        Err(Ok(WalletError::InvalidIntent))
+    );
+    assert_eq!(
+        client.try_get_intent(&3),
+        Err(Ok(WalletError::IntentNotFound))
+    );
+    let mut too_many = Vec::new(&env);
+    for _ in 0..21 {
+        too_many.push_back(intent(1));
+    }
+    assert_eq!(
+        client.try_create_intents(&too_many),
+        Err(Ok(WalletError::BatchTooLarge))
+    );
+}
+
+/// A module that rejects calls on its blocked contract and counts the
+/// invocations it is told about.
+#[contract]
+pub struct TestModule;
+
+#[contractimpl]
+impl TestModule {
+    pub fn block(env: Env, contract: Address) {
+        env.storage()
+            .instance()
+            .set(&symbol_short!("blocked"), &contract);
+    }
+
+    pub fn before_auth(env: Env, _wallet: Address, calls: Vec<Context>) {
+        let blocked: Option<Address> = env.storage().instance().get(&symbol_short!("blocked"));
+        for context in calls.iter() {
+            if let Context::Contract(call) = context {
+                assert!(Some(call.contract) != blocked, "blocked contract");
+            }
+        }
+    }
+
+    pub fn after_execute(
+        env: Env,
+        _wallet: Address,
+        invocations: Vec<Invocation>,
+        _results: Vec<Val>,
+    ) {
+        let seen = Self::seen(env.clone()) + invocations.len();
+        env.storage().instance().set(&symbol_short!("seen"), &seen);
+    }
+
+    pub fn seen(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&symbol_short!("seen"))
+            .unwrap_or(0)
+    }
+}
+
+#[test]
+fn test_modules_hook_into_auth_and_execute() {
+    let env = Env::default();
+    let key = passkey(1);
+    let (client, admin) = setup_passkey_wallet(&env, &key);
+    env.mock_all_auths();
+    let module = TestModuleClient::new(&env, &env.register(TestModule, ()));
+    client.install_module(&module.address, &ModuleHook::BeforeAuth);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("mod_add"), module.address.clone()).into_val(&env),
+        ModuleHook::BeforeAuth.into_val(&env),
+    );
+    client.install_module(&module.address, &ModuleHook::AfterExecute);
+    client.install_module(&module.address, &ModuleHook::AfterExecute);
+    assert_eq!(
+        client.get_modules(&ModuleHook::AfterExecute),
+        vec![&env, module.address.clone()]
+    );
+    assert_eq!(
+        client.try_install_module(&client.address, &ModuleHook::BeforeAuth),
+        Err(Ok(WalletError::NotAuthorized))
+    );
+
+    // Before auth: the module sees calls on other contracts only
+    let payload = BytesN::from_array(&env, &[9u8; 32]);
+    let sign = || webauthn_signature(&env, &key, &admin, &payload);
+    let (token, blocked) = (Address::generate(&env), Address::generate(&env));
+    module.block(&blocked);
+    let transfer = |token: &Address| {
+        vec![
+            &env,
+            call_context(
+                &env,
+                token,
+                "transfer",
+                (client.address.clone(), Address::generate(&env), 10i128).into_val(&env),
+            ),
+        ]
+    };
+    check_auth_contexts(&env, &client, sign(), transfer(&token)).unwrap();
+    assert_eq!(
+        check_auth_contexts(&env, &client, sign(), transfer(&blocked)),
+        Err(Ok(WalletError::ModuleRejected))
+    );
+    let batch = vec![&env, invocation(&env, &blocked, "transfer", Vec::new(&env))];
+    assert_eq!(
+        check_auth(&env, &client, sign(), "execute", (batch,).into_val(&env)),
+        Err(Ok(WalletError::ModuleRejected))
+    );
+    module.block(&client.address);
+    let uninstall: Vec<Val> = (module.address.clone(), ModuleHook::BeforeAuth).into_val(&env);
+    check_auth(&env, &client, sign(), "uninstall_module", uninstall).unwrap();
+
+    // After execute: the module is told about each batch
+    let token = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    token::StellarAssetClient::new(&env, &token).mint(&client.address, &1_000);
+    let balance = invocation(
+        &env,
+        &token,
+        "balance",
+        (client.address.clone(),).into_val(&env),
+    );
+    client.execute(&vec![&env, balance.clone(), balance]);
+    assert_eq!(module.seen(), 2);
+
+    client.uninstall_module(&module.address, &ModuleHook::BeforeAuth);
+    assert_last_event(
+        &env,
+        &client,
+        (symbol_short!("mod_rem"), module.address.clone()).into_val(&env),
+        ModuleHook::BeforeAuth.into_val(&env),
+    );
+    assert_eq!(
+        client.try_uninstall_module(&module.address, &ModuleHook::BeforeAuth),
+        Err(Ok(WalletError::ModuleNotInstalled))
+    );
+    module.block(&blocked);
+    check_auth_contexts(&env, &client, sign(), transfer(&blocked)).unwrap();
+
+    for _ in 1..modules::MAX_MODULES {
+        client.install_module(&env.register(TestModule, ()), &ModuleHook::AfterExecute);
+    }
+    assert_eq!(
+        client.try_install_module(&env.register(TestModule, ()), &ModuleHook::AfterExecute),
+        Err(Ok(WalletError::TooManyModules))
+    );
+}
@@ -0,0 +1,169 @@
+//! WebAuthn assertion vectors covering the shapes relying parties receive,
+//! run through `__check_auth` end to end.
+//!
+//! Each vector is a complete assertion: clientDataJSON byte for byte
+//! (field order, `crossOrigin`, Chrome's `other_keys_can_be_added_here`
+//! padding), authenticator data with a given set of flags and signature
+//! counter, and the DER signature with whichever S half it came out with.
+//! They are fixed bytes, not built by the test helpers, so a change to
+//! parsing or verification that breaks one of these layouts fails here.
+//!
+//! All of them were produced with a software P-256 key and are named after
+//! the shape they exercise, not after any authenticator.  Assertions
+//! captured from hardware belong in the same table, with the wallet's
+//! origin and RP ID swapped for their own.
+
+use super::*;
+
+/// One recorded assertion; byte fields are hex.
+struct Vector {
+    shape: &'static str,
+    credential_id: &'static str,
+    public_key: &'static str,
+    /// Soroban signature payload the challenge encodes
+    payload: &'static str,
+    client_data_json: &'static str,
+    authenticator_data: &'static str,
+    signature: &'static str,
+}
+
+const ORIGIN: &str = "https://wallet.galaxy.dev";
+const RP_ID: &str = "wallet.galaxy.dev";
+
+const VECTORS: &[Vector] = &[
+    Vector {
+        shape: "backed-up credential, zero counter, minimal clientDataJSON, low S",
+        credential_id: "79c9ecef4edbada5fef0cd136be48145b56a29d8",
+        public_key: "042faa142bebc2868cc4ad77d92d9f832b3bb2634a9f5b21df8c761a767ea0c1aa2e9b83ba424db96d1da9d37e16ccf0f21efb5b8f86466a2cfb250b38b4a540f8",
+        payload: "1b45c3c00ceae9c23c1051c546422a341188f5b9cda77bacdcf275f32dd8ba20",
+        client_data_json: r#"{"type":"webauthn.get","challenge":"G0XDwAzq6cI8EFHFRkIqNBGI9bnNp3us3PJ18y3YuiA","origin":"https://wallet.galaxy.dev"}"#,
+        authenticator_data: "13323ed1ed8688264696ec5b59d6008e3843f6932825ed2faf02e7e3fe7ed1d81d00000000",
+        signature: "304402207e8dfbd10d72c30a7b6e297312858a24adcedea42989024e9490abcfe53302dd02205f19bb6c10e00489d640097a66d6b2cca4e2bd6d49b5cce55a5b593fca4a6c66",
+    },
+    Vector {
+        shape: "backed-up credential, zero counter, crossOrigin, high S",
+        credential_id: "b964140f7ac70da753f271c63743086e",
+        public_key: "04d04f19742b26c12ff837e8ae9693b0670db8a2747e88966007c02a57514f9e0b010d705fcafe90d7431b390f2fe9a4be9bd4b090abc431c3bf9dc723d40fb2a6",
+        payload: "9b301983739f32c52ad399ef6a6f3b1019f6e67b1b43b9c7233f154ae70cf723",
+        client_data_json: r#"{"type":"webauthn.get","challenge":"mzAZg3OfMsUq05nvam87EBn25nsbQ7nHIz8VSucM9yM","origin":"https://wallet.galaxy.dev","crossOrigin":false}"#,
+        authenticator_data: "13323ed1ed8688264696ec5b59d6008e3843f6932825ed2faf02e7e3fe7ed1d81d00000000",
+        signature: "3045022003cb8bf71416eee2825bbc12306613b3ee443afc51f3fd103771c3fe7f4fc7df0221009b25f3aa81f84b5d6c93f8d76aeb71a8d010ebc896f66e1e3c1c458d953f32bb",
+    },
+    Vector {
+        shape: "device-bound credential, counter 47, padded clientDataJSON, high S",
+        credential_id: "a2e6213a36153a1a40730918a3be78d486cfccc5902f0da6136780c7a62dab6baed3d26a4c93e55e1696fd6af7d1e667ba8bc8efd35471117ac1aab1bf1f47d0",
+        public_key: "04f46bb53c64488d79c8d3a2f16efeb12211ccb8bbff7ead6a93a6d550477299efd66765d7f4205cdd38a2a213560cd51d19240d116795ff0c604c9f468f2e978e",
+        payload: "e4f6c72c8724c287abd481fee553f97c3c50288bbf25ebb00e5d72e4263307e5",
+        client_data_json: r#"{"type":"webauthn.get","challenge":"5PbHLIckwoer1IH-5VP5fDxQKIu_JeuwDl1y5CYzB-U","origin":"https://wallet.galaxy.dev","crossOrigin":false,"other_keys_can_be_added_here":"do not compare clientDataJSON against a template. See https://goo.gl/yabPex"}"#,
+        authenticator_data: "13323ed1ed8688264696ec5b59d6008e3843f6932825ed2faf02e7e3fe7ed1d8050000002f",
+        signature: "304502205c8388d53f04346cb057fc07547a8035d1bfad239112c1c91dda6f3257f83dea022100d12c1e8c1f8c4c226de424825841f6dfea60e19db70dd02fe87b2021bff50d54",
+    },
+    Vector {
+        shape: "device-bound credential, counter 17, type field last, low S",
+        credential_id: "cfcdf1b5da1a490673a9e17ab0e067149b32e2ad0b83a023ee4657cc2ebf57d8",
+        public_key: "044799a24dca59c8d5e7a36ee6f596cd5a60aebe97962e36a73987c010aa912e5849aa867f148f4f30eda6d509e3c09d4f6c1b4e318f03b0428c564436cdde3323",
+        payload: "5b48a82cf7d71675ee60415994a28ef2ef971905212ba1fb8e3db537aa2d380f",
+        client_data_json: r#"{"challenge":"W0ioLPfXFnXuYEFZlKKO8u-XGQUhK6H7jj21N6otOA8","origin":"https://wallet.galaxy.dev","type":"webauthn.get"}"#,
+        authenticator_data: "13323ed1ed8688264696ec5b59d6008e3843f6932825ed2faf02e7e3fe7ed1d80500000011",
+        signature: "30440220284f31d0a6eb4db073a045e1eb9e2c2014958cb299375fae58dd3c9759bada5502206106aa19448b2d902a2207653b2fd68ad858e1d5acfa4cfe51780106f57e4e74",
+    },
+];
+
+fn hex(env: &Env, hex: &str) -> Bytes {
+    let bytes: std::vec::Vec<u8> = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect();
+    Bytes::from_slice(env, &bytes)
+}
+
+/// A wallet whose only admin is `vector`'s credential, holding assertions
+/// to the vectors' origin and RP ID and requiring user verification.
+fn setup_vector_wallet<'a>(env: &'a Env, vector: &Vector) -> SmartWalletClient<'a> {
+    let public_key: BytesN<65> = hex(env, vector.public_key).try_into().unwrap();
+    let contract_id = env.register(
+        SmartWallet,
+        (
+            hex(env, vector.credential_id),
+            public_key,
+            Vec::<InitialSigner>::new(env),
+        ),
+    );
+    let client = SmartWalletClient::new(env, &contract_id);
+    client.add_origin(&Bytes::from_slice(env, ORIGIN.as_bytes()));
+    client.add_rp_id_hash(
+        &env.crypto()
+            .sha256(&Bytes::from_slice(env, RP_ID.as_bytes()))
+            .into(),
+    );
+    client.set_user_verification(&true);
+    client
+}
+
+fn vector_signature(env: &Env, vector: &Vector) -> Signature {
+    Signature {
+        authenticator_data: hex(env, vector.authenticator_data),
+        client_data_json: Bytes::from_slice(env, vector.client_data_json.as_bytes()),
+        id: hex(env, vector.credential_id),
+        signature: hex(env, vector.signature),
+    }
+}
+
+fn check_vector(
+    env: &Env,
+    client: &SmartWalletClient<'_>,
+    payload: &BytesN<32>,
+    signature: Signature,
+) -> Result<(), Result<WalletError, InvokeError>> {
+    let context = call_context(
+        env,
+        &client.address,
+        "remove_signer",
+        (signature.id.clone(),).into_val(env),
+    );
+    env.try_invoke_contract_check_auth::<WalletError>(
+        &client.address,
+        payload,
+        AccountSignature::WebAuthn(signature).into_val(env),
+        &vec![env, context],
+    )
+}
+
+#[test]
+fn test_assertion_vectors() {
+    for vector in VECTORS {
+        let env = Env::default();
+        env.mock_all_auths();
+        let client = setup_vector_wallet(&env, vector);
+        let payload: BytesN<32> = hex(&env, vector.payload).try_into().unwrap();
+
+        assert_eq!(
+            check_vector(&env, &client, &payload, vector_signature(&env, vector)),
+            Ok(()),
+            "{}",
+            vector.shape
+        );
+        // Bound to the payload its challenge encodes
+        assert_eq!(
+            check_vector(
+                &env,
+                &client,
+                &BytesN::from_array(&env, &[9u8; 32]),
+                vector_signature(&env, vector)
+            ),
+            Err(Ok(WalletError::ChallengeMismatch)),
+            "{}",
+            vector.shape
+        );
+        // And to the signed authenticator data, counter included
+        let mut signature = vector_signature(&env, vector);
+        let last = signature.authenticator_data.len() - 1;
+        let counter = signature.authenticator_data.get(last).unwrap();
+        signature.authenticator_data.set(last, counter ^ 1);
+        assert!(
+            check_vector(&env, &client, &payload, signature).is_err(),
+            "{}",
+            vector.shape
+        );
+    }
+}
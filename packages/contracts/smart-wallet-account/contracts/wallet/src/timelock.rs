@@ -0,0 +1,66 @@
+//! Timelocked action storage.
+//!
+//! The delay and the next action ID live in instance storage.  Queued
+//! actions are kept in persistent storage, like multisig proposals, so they
+//! outlast the delay on an otherwise idle wallet.
+
+use soroban_sdk::Env;
+
+use smart_wallet_account_common::{QueuedAction, WalletDataKey, WalletError};
+
+use crate::{ADMIN_TTL_EXTEND, ADMIN_TTL_THRESHOLD};
+
+pub fn delay(env: &Env) -> u32 {
+    env.storage()
+        .instance()
+        .get(&WalletDataKey::TimelockDelay)
+        .unwrap_or(0)
+}
+
+pub fn set_delay(env: &Env, delay_ledgers: u32) {
+    env.storage()
+        .instance()
+        .set(&WalletDataKey::TimelockDelay, &delay_ledgers);
+}
+
+/// Fail with `TimelockRequired` if changes must be queued.
+pub fn check_unlocked(env: &Env) -> Result<(), WalletError> {
+    if delay(env) > 0 {
+        return Err(WalletError::TimelockRequired);
+    }
+    Ok(())
+}
+
+/// Reserve the next action ID.
+pub fn next_id(env: &Env) -> u64 {
+    let id: u64 = env
+        .storage()
+        .instance()
+        .get(&WalletDataKey::NextActionId)
+        .unwrap_or(1);
+    env.storage()
+        .instance()
+        .set(&WalletDataKey::NextActionId, &(id + 1));
+    id
+}
+
+pub fn load(env: &Env, action_id: u64) -> Result<QueuedAction, WalletError> {
+    env.storage()
+        .persistent()
+        .get(&WalletDataKey::QueuedAction(action_id))
+        .ok_or(WalletError::ActionNotFound)
+}
+
+pub fn save(env: &Env, action_id: u64, action: &QueuedAction) {
+    let key = WalletDataKey::QueuedAction(action_id);
+    env.storage().persistent().set(&key, action);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, ADMIN_TTL_THRESHOLD, ADMIN_TTL_EXTEND);
+}
+
+pub fn remove(env: &Env, action_id: u64) {
+    env.storage()
+        .persistent()
+        .remove(&WalletDataKey::QueuedAction(action_id));
+}
@@ -28,22 +28,17 @@ echo "Wallet WASM Hash: $WALLET_WASM_HASH"
 
 echo "-----------------------------------"
 echo "Deploying Factory contract..."
+# The deployer (as factory admin) and the wallet WASM hash go to the
+# factory's constructor
 FACTORY_ID=$(stellar contract deploy \
   --wasm "$FACTORY_WASM" \
   --source "$SOURCE" \
   --network "$NETWORK" \
-  --ignore-checks)
+  --ignore-checks \
+  -- --admin "$SOURCE" --wallet_wasm_hash "$WALLET_WASM_HASH")
 
 echo "Factory Contract ID: $FACTORY_ID"
 
-echo "-----------------------------------"
-echo "Initializing Factory..."
-stellar contract invoke \
-  --id "$FACTORY_ID" \
-  --source "$SOURCE" \
-  --network "$NETWORK" \
-  -- init --wallet_wasm_hash "$WALLET_WASM_HASH"
-
 echo "-----------------------------------"
 echo "Deployment Complete!"
 echo "FACTORY_CONTRACT_ID=$FACTORY_ID"
@@ -335,6 +335,10 @@ export interface AddSessionSignerParams {
   walletAddress: string;
   sessionPublicKey: string;
   ttlSeconds: number;
+  /** Contract IDs the session key may authorize calls to; omit for any. */
+  allowedContracts?: string[];
+  /** Successful auths before the session key is removed; omit for no cap. */
+  maxUses?: number;
   credentialId?: string;
   webAuthnAssertion?: PublicKeyCredential;
 }
@@ -575,6 +579,8 @@ export class SmartWalletService {
       walletAddress,
       sessionPublicKey,
       ttlSeconds,
+      allowedContracts = [],
+      maxUses = 0,
       credentialId,
       webAuthnAssertion,
     } = params;
@@ -620,7 +626,11 @@ export class SmartWalletService {
           'add_session_signer',
           xdr.ScVal.scvBytes(credentialBytes),
           xdr.ScVal.scvBytes(Buffer.from(sessionPublicKeyBytes)),
-          nativeToScVal(ttlLedgers, { type: 'u32' })
+          nativeToScVal(ttlLedgers, { type: 'u32' }),
+          xdr.ScVal.scvVec(
+            allowedContracts.map((id) => Address.fromString(id).toScVal())
+          ),
+          nativeToScVal(maxUses, { type: 'u32' })
         )
       )
       .setTimeout(300)
@@ -1151,7 +1161,9 @@ export class SmartWalletService {
           'deploy',
           buildAccountAddressScVal(SIMULATION_DEPLOYER_ADDRESS),
           xdr.ScVal.scvBytes(Buffer.from(base64UrlToUint8Array(credentialId))),
-          xdr.ScVal.scvBytes(Buffer.from(publicKey65Bytes))
+          xdr.ScVal.scvBytes(Buffer.from(publicKey65Bytes)),
+          // No further initial signers
+          xdr.ScVal.scvVec([])
         )
       )
       .setTimeout(300)
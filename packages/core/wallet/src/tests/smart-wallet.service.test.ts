@@ -556,7 +556,9 @@ describe('SmartWalletService', () => {
         'add_session_signer',
         expect.anything(), // credential_id bytes
         expect.anything(), // session_public_key bytes
-        expect.anything() // ttl_ledgers u32
+        expect.anything(), // ttl_ledgers u32
+        expect.anything(), // allowed_contracts vec
+        expect.anything() // max_uses u32
       );
     });
 
@@ -858,6 +860,7 @@ describe('SmartWalletService', () => {
         'deploy',
         expect.anything(),
         expect.anything(),
+        expect.anything(),
         expect.anything()
       );
       expect(mockServer.simulateTransaction).toHaveBeenCalledWith(
@@ -94,7 +94,7 @@ main() {
   wallet_hash="$(read_field "smart_wallet_wallet" "wasmHash")"
   if [[ -n "$wallet_hash" ]]; then
     invoke_init "smart_wallet_factory" \
-      init --wallet_wasm_hash "$wallet_hash"
+      init --admin "$DEPLOYER_IDENTITY" --wallet_wasm_hash "$wallet_hash"
   else
     warn "smart_wallet_wallet wasmHash not in manifest — skipping factory init"
   fi